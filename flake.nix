@@ -60,6 +60,8 @@
             libpulseaudio
 
             libxkbcommon
+
+            linux-pam
           ];
           LD_LIBRARY_PATH = with pkgs;
             pkgs.lib.makeLibraryPath [
@@ -105,6 +107,7 @@
               cairo
 
               alsa-lib
+              linux-pam
             ];
           };
 
@@ -1,27 +1,27 @@
-use ballad_config::theme::{Theme, ThemeColors};
+use ballad_config::theme::{Color, Theme, ThemeColors};
 
 fn main() {
     let theme = Theme {
         colors: ThemeColors {
-            pink: "#d3869b".to_string(),
-            orange: "#d65d0e".to_string(),
-            red: "#cc241d".to_string(),
-            yellow: "#d79921".to_string(),
-            green: "#98971a".to_string(),
-            blue: "#458588".to_string(),
-            purple: "#b16286".to_string(),
-            text: "#ebdbb2".to_string(),
-            subtext_1: "#ebdbb2".to_string(),
-            subtext_0: "#d5c4a1".to_string(),
-            overlay_2: "#665c54".to_string(),
-            overlay_1: "#7c6f64".to_string(),
-            overlay_0: "#928374".to_string(),
-            surface_2: "#3c3836".to_string(),
-            surface_1: "#282828".to_string(),
-            surface_0: "#282828".to_string(),
-            bg_0: "#1d2021".to_string(),
-            bg_1: "#282828".to_string(),
-            bg_2: "#32302f".to_string(),
+            pink: Color::hex(0xd3869b),
+            orange: Color::hex(0xd65d0e),
+            red: Color::hex(0xcc241d),
+            yellow: Color::hex(0xd79921),
+            green: Color::hex(0x98971a),
+            blue: Color::hex(0x458588),
+            purple: Color::hex(0xb16286),
+            text: Color::hex(0xebdbb2),
+            subtext_1: Color::hex(0xebdbb2),
+            subtext_0: Color::hex(0xd5c4a1),
+            overlay_2: Color::hex(0x665c54),
+            overlay_1: Color::hex(0x7c6f64),
+            overlay_0: Color::hex(0x928374),
+            surface_2: Color::hex(0x3c3836),
+            surface_1: Color::hex(0x282828),
+            surface_0: Color::hex(0x282828),
+            bg_0: Color::hex(0x1d2021),
+            bg_1: Color::hex(0x282828),
+            bg_2: Color::hex(0x32302f),
         },
         name: "Gruvbox".to_string(),
         variant: ballad_config::theme::ThemeVariant::Dark,
@@ -0,0 +1,104 @@
+//! Works with themes from the command line.
+//!
+//! - `theme import <file>` installs the themes in a base16/base24 YAML, catppuccin JSON or
+//!   Ballad TOML file.
+//! - `theme export <theme name> <file> [ballad|base16|base24|catppuccin]` writes a theme to a
+//!   file, in the format its extension suggests by default.
+//! - `theme render <template> [theme name]` prints a template rendered with a theme, the
+//!   selected one by default.
+//! - `theme palette <image> [name] [--light|--dark] [--save]` prints a theme generated from an
+//!   image as TOML, and `--save` adds it to the custom themes. Needs the `palette` feature.
+
+use std::path::{Path, PathBuf};
+
+use ballad_config::{
+    get_or_init_shell_config,
+    theme::{
+        Theme,
+        files::{ThemeFileFormat, export_theme, import_themes, install_theme},
+        get_or_init_all_theme_selections,
+        templates::{load_template, render},
+    },
+};
+
+const USAGE: &str = "Usage: theme [import <file> | export <theme name> <file> [format] | render <template> [theme name] | palette <image> [name] [--light|--dark] [--save]]";
+
+fn find_theme(name: &str) -> Theme {
+    get_or_init_all_theme_selections()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|selection| selection.theme())
+        .find(|theme| theme.name == name)
+        .unwrap_or_else(|| panic!("There is no theme called {name}"))
+}
+
+#[cfg(feature = "palette")]
+fn palette(args: &[&str]) {
+    use ballad_config::theme::{ThemeVariant, palette::Palette};
+
+    let (flags, positional): (Vec<&str>, Vec<&str>) =
+        args.iter().partition(|arg| arg.starts_with("--"));
+    let [path, rest @ ..] = &positional[..] else {
+        eprintln!("{USAGE}");
+        return;
+    };
+    let name = rest.first().copied().unwrap_or("Wallpaper");
+    let variant = if flags.contains(&"--light") {
+        Some(ThemeVariant::Light)
+    } else if flags.contains(&"--dark") {
+        Some(ThemeVariant::Dark)
+    } else {
+        None
+    };
+
+    let palette = Palette::load(Path::new(path)).unwrap_or_else(|err| panic!("{err}"));
+    let theme = palette.theme(name, variant);
+    println!("{}", toml::to_string_pretty(&theme).unwrap());
+
+    if flags.contains(&"--save") {
+        let mut config = get_or_init_shell_config().unwrap();
+        config.theme.save_custom_theme(theme);
+        ballad_config::set_shell_config(&config).unwrap();
+    }
+}
+
+#[cfg(not(feature = "palette"))]
+fn palette(_: &[&str]) {
+    eprintln!("Generating themes from images needs the palette feature");
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["import", path] => {
+            for theme in import_themes(Path::new(path)).unwrap_or_else(|err| panic!("{err}")) {
+                let installed = install_theme(&theme).unwrap_or_else(|err| panic!("{err}"));
+                println!("Installed {} to {}", theme.name, installed.display());
+            }
+        }
+        ["export", name, path, ref format @ ..] => {
+            let path = PathBuf::from(path);
+            let format = match format {
+                [format] => ThemeFileFormat::ALL
+                    .into_iter()
+                    .find(|option| option.name().eq_ignore_ascii_case(format))
+                    .unwrap_or_else(|| panic!("Unknown format {format}")),
+                _ => ThemeFileFormat::from_path(&path)
+                    .expect("Can't tell the format from the file name"),
+            };
+            export_theme(&find_theme(name), &path, format).unwrap_or_else(|err| panic!("{err}"));
+        }
+        ["render", template, ref name @ ..] => {
+            let theme = match name {
+                [name] => find_theme(name),
+                _ => get_or_init_shell_config().unwrap_or_default().theme.theme(),
+            };
+            let rendered = load_template(template)
+                .and_then(|source| render(template, &source, &theme))
+                .unwrap_or_else(|err| panic!("{err}"));
+            print!("{rendered}");
+        }
+        ["palette", ref args @ ..] => palette(args),
+        _ => eprintln!("{USAGE}"),
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+/// What to do once the battery reaches [`BatteryConfig::critical_level`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Enum, glib::Variant))]
+#[cfg_attr(feature = "gtk", enum_type(name = "BalladConfigCriticalBatteryAction"))]
+pub enum CriticalBatteryAction {
+    Nothing,
+    #[default]
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    PowerOff,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigBatteryConfig"))]
+#[serde(default)]
+pub struct BatteryConfig {
+    /// Percentages at which a low battery warning is shown while discharging.
+    pub warning_levels: Vec<u8>,
+    /// Switch to the power-saver profile below this percentage while discharging. The previous profile is restored
+    /// once the computer is plugged in.
+    pub power_saver_level: Option<u8>,
+    /// The percentage at which [`Self::critical_action`] is performed.
+    pub critical_level: u8,
+    pub critical_action: CriticalBatteryAction,
+    /// How long to wait after warning about the critical action before performing it, giving the user time to plug
+    /// the computer in.
+    pub critical_action_delay_seconds: u32,
+}
+impl BatteryConfig {
+    /// The most severe warning level `percentage` has dropped to, if any.
+    pub fn warning_level_for(&self, percentage: f64) -> Option<u8> {
+        self.warning_levels
+            .iter()
+            .copied()
+            .filter(|level| percentage <= *level as f64)
+            .min()
+    }
+}
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            warning_levels: vec![20, 10],
+            power_saver_level: Some(20),
+            critical_level: 5,
+            critical_action: CriticalBatteryAction::default(),
+            critical_action_delay_seconds: 60,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::theme::{Theme, ThemeSelection};
+
+/// Configuration for ballad-greeter. It runs as the greeter user before anyone logs in, so it is read from the system
+/// config directories (usually `/etc/xdg/ballad/greeter.toml`) and never written.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GreeterConfig {
+    /// An image shown behind the login box.
+    pub background: Option<PathBuf>,
+    /// The user selected when the greeter starts, even if they aren't in the user list.
+    pub default_user: Option<String>,
+    pub theme: ThemeSelection,
+    /// Themes [`ThemeSelection::Custom`] can refer to, since the greeter has no shell config of its own.
+    pub custom_themes: Vec<Theme>,
+    /// The keyboard layout selected when the greeter starts, like `us` or `de(nodeadkeys)`.
+    pub keyboard_layout: Option<String>,
+    pub large_text: bool,
+    pub high_contrast: bool,
+}
+impl GreeterConfig {
+    /// The selected theme, or the default one if a custom theme can't be found.
+    pub fn theme(&self) -> Theme {
+        let theme = match &self.theme {
+            ThemeSelection::Custom(name) => self
+                .custom_themes
+                .iter()
+                .find(|theme| theme.name == *name)
+                .cloned(),
+            selection => selection.theme(),
+        };
+        theme.unwrap_or_else(|| {
+            ThemeSelection::default()
+                .theme()
+                .expect("Builtin themes always exist")
+        })
+    }
+}
+
+pub fn greeter_config_path() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("ballad")
+        .ok()?
+        .find_config_file("greeter.toml")
+}
+
+/// Reads the greeter config, or the default one if there is none.
+pub fn get_greeter_config() -> Result<GreeterConfig, crate::Error> {
+    let Some(path) = greeter_config_path() else {
+        return Ok(GreeterConfig::default());
+    };
+    let content = std::fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+}
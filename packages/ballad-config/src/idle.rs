@@ -0,0 +1,64 @@
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+/// How long the user has to be idle before each stage, in seconds. A stage is skipped if its timeout is unset.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Variant))]
+#[serde(default)]
+pub struct IdleTimeouts {
+    /// Dim the screen to [`IdleConfig::dim_brightness`].
+    pub dim: Option<u32>,
+    /// Lock the session.
+    pub lock: Option<u32>,
+    /// Turn the outputs off. Only niri can do this, so the stage does nothing in other compositors.
+    pub dpms_off: Option<u32>,
+    pub suspend: Option<u32>,
+}
+
+/// Dims, locks, turns off the outputs and suspends after the user has been idle for a while. Off by default, so it
+/// doesn't fight with another idle daemon like swayidle.
+///
+/// Wayland idle inhibitors, like a playing video, keep the timers from starting. logind idle inhibitors are only
+/// checked when a stage is due, so one taken after that doesn't undo the stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigIdleConfig"))]
+#[serde(default)]
+pub struct IdleConfig {
+    pub enabled: bool,
+    /// Timeouts used while plugged in, or when there is no battery.
+    pub ac: IdleTimeouts,
+    /// Timeouts used while running on battery.
+    pub battery: IdleTimeouts,
+    /// The brightness to dim to, as a fraction of the brightness before dimming.
+    pub dim_brightness: f64,
+    /// Lock the session before suspending, even if the lock timeout hasn't passed.
+    pub lock_before_suspend: bool,
+}
+impl IdleConfig {
+    pub fn timeouts(&self, on_battery: bool) -> IdleTimeouts {
+        if on_battery { self.battery } else { self.ac }
+    }
+}
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ac: IdleTimeouts {
+                dim: Some(5 * 60),
+                lock: Some(10 * 60),
+                dpms_off: Some(11 * 60),
+                suspend: None,
+            },
+            battery: IdleTimeouts {
+                dim: Some(2 * 60),
+                lock: Some(5 * 60),
+                dpms_off: Some(5 * 60 + 30),
+                suspend: None,
+            },
+            dim_brightness: 0.3,
+            lock_before_suspend: true,
+        }
+    }
+}
@@ -1,13 +1,23 @@
+pub mod battery;
+pub mod greeter;
+pub mod idle;
+pub mod notifications;
 pub mod theme;
+pub mod wallpaper;
 
 #[cfg(feature = "gtk")]
 use gtk::glib;
 use snafu::Snafu;
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
+pub use battery::BatteryConfig;
+pub use greeter::GreeterConfig;
+pub use idle::IdleConfig;
+pub use notifications::NotificationsConfig;
 pub use theme::{ThemeConfig, ThemeSelection};
+pub use wallpaper::WallpaperConfig;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "gtk", derive(glib::Enum, glib::Variant))]
@@ -18,12 +28,99 @@ pub enum PowerProfile {
     HighPerformance,
     PowerSaver,
 }
+impl PowerProfile {
+    /// The name power-profiles-daemon uses for the profile.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Balanced => "balanced",
+            Self::HighPerformance => "performance",
+            Self::PowerSaver => "power-saver",
+        }
+    }
+}
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Snafu)]
+#[snafu(display("{input:?} isn't a time like 07:30"))]
+pub struct TimeOfDayError {
+    input: String,
+}
+
+/// A time of day in local time, shown like `07:30`.
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigTimeOfDay"))]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+impl TimeOfDay {
+    pub const fn new(hour: u8, minute: u8) -> Self {
+        Self { hour, minute }
+    }
+
+    pub const fn minutes_since_midnight(&self) -> u32 {
+        self.hour as u32 * 60 + self.minute as u32
+    }
+}
+impl FromStr for TimeOfDay {
+    type Err = TimeOfDayError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (hour, minute) = input
+            .trim()
+            .split_once(':')
+            .and_then(|(hour, minute)| Some((hour.parse::<u8>().ok()?, minute.parse::<u8>().ok()?)))
+            .filter(|(hour, minute)| *hour < 24 && *minute < 60)
+            .ok_or_else(|| TimeOfDaySnafu { input }.build())?;
+        Ok(Self::new(hour, minute))
+    }
+}
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Holds a power profile while a window of an application is open.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "ProfileHoldRule"))]
+pub struct ProfileHoldRule {
+    /// The app ID of the windows, ending with `*` to match every app ID with that prefix, like `steam_app_*`.
+    pub app_id: String,
+    /// Only performance and power-saver can be held.
+    pub profile: PowerProfile,
+}
+impl ProfileHoldRule {
+    pub fn matches(&self, app_id: &str) -> bool {
+        match self.app_id.strip_suffix('*') {
+            Some(prefix) => app_id.starts_with(prefix),
+            None => app_id == self.app_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
 #[cfg_attr(feature = "gtk", boxed_type(name = "PowerProfilesConfig"))]
+#[serde(default)]
 pub struct PowerProfilesConfig {
-    pub enabled: bool,
+    /// Show the power profile selector. It is hidden anyway when power-profiles-daemon isn't running. While it is
+    /// off, the battery policy doesn't switch to power-saver either.
+    ///
+    /// This replaces `enabled`, which older versions wrote as `false` into every config without ever reading it.
+    pub show_selector: bool,
+    pub hold_rules: Vec<ProfileHoldRule>,
+}
+impl Default for PowerProfilesConfig {
+    fn default() -> Self {
+        Self {
+            show_selector: true,
+            hold_rules: Vec::new(),
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,6 +129,14 @@ pub struct PowerProfilesConfig {
 pub struct ShellConfig {
     pub theme: ThemeConfig,
     pub power_profiles: PowerProfilesConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub battery: BatteryConfig,
+    #[serde(default)]
+    pub idle: IdleConfig,
+    #[serde(default)]
+    pub wallpaper: WallpaperConfig,
 }
 
 pub fn shell_config_path() -> PathBuf {
@@ -41,6 +146,14 @@ pub fn shell_config_path() -> PathBuf {
         .unwrap()
 }
 
+/// A stylesheet compiled after the shell styles, so users can restyle widgets without rebuilding the shell.
+pub fn user_style_path() -> PathBuf {
+    xdg::BaseDirectories::with_prefix("ballad")
+        .unwrap()
+        .place_config_file("style.scss")
+        .unwrap()
+}
+
 pub fn get_or_init_shell_config() -> Result<ShellConfig, Error> {
     let path = shell_config_path();
     std::fs::create_dir_all(path.parent().unwrap())?;
@@ -108,3 +221,42 @@ pub enum Error {
     #[snafu(transparent)]
     TomlSerialize { source: toml::ser::Error },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The config every install wrote before power profiles could be turned off.
+    const BASELINE_CONFIG: &str = r#"[theme]
+selected_theme = "CatppuccinMacchiato"
+custom_themes = []
+default_dark_gtk_theme = "catppuccin-macchiato-sky-standard"
+default_light_gtk_theme = "catppuccin-latte-sky-standard"
+corner_radius = 16.0
+ui_radius = 8
+transition_length = 0.2
+
+[power_profiles]
+enabled = false
+"#;
+
+    #[test]
+    fn baseline_config() {
+        let config = toml::from_str::<ShellConfig>(BASELINE_CONFIG).unwrap();
+        assert!(config.power_profiles.show_selector);
+        assert_eq!(
+            config.theme.selected_theme,
+            ThemeSelection::CatppuccinMacchiato
+        );
+        assert_eq!(config.idle, IdleConfig::default());
+    }
+
+    #[test]
+    fn parse_time_of_day() {
+        assert_eq!("07:30".parse::<TimeOfDay>().unwrap(), TimeOfDay::new(7, 30));
+        assert_eq!(" 7:05 ".parse::<TimeOfDay>().unwrap().to_string(), "07:05");
+        for invalid in ["24:00", "12:60", "noon", "12", "-1:30"] {
+            assert!(invalid.parse::<TimeOfDay>().is_err(), "{invalid}");
+        }
+    }
+}
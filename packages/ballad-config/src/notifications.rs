@@ -0,0 +1,86 @@
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use crate::TimeOfDay;
+
+/// A daily window of time where do not disturb is automatically enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigQuietHours"))]
+pub struct QuietHours {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+impl QuietHours {
+    /// Whether `time` falls within the quiet hours. Quiet hours may wrap around midnight.
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        let start = self.start.minutes_since_midnight();
+        let end = self.end.minutes_since_midnight();
+        let time = time.minutes_since_midnight();
+
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            start: TimeOfDay::new(22, 0),
+            end: TimeOfDay::new(7, 0),
+        }
+    }
+}
+
+/// Overrides for notifications sent by a single application.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigNotificationAppRule"))]
+#[serde(default)]
+pub struct NotificationAppRule {
+    /// Matched against the notification's app name or desktop entry.
+    pub app: String,
+    /// Never show popups for this application.
+    pub mute: bool,
+    /// Never write this application's notifications to the history.
+    pub never_persist: bool,
+    /// Overrides [`NotificationsConfig::always_show_critical`] for this application.
+    pub always_show_critical: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigNotificationsConfig"))]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Manually enabled do not disturb.
+    pub do_not_disturb: bool,
+    /// Automatically enable do not disturb during these hours.
+    pub quiet_hours: Option<QuietHours>,
+    /// Show popups for critical notifications even when do not disturb is active.
+    pub always_show_critical: bool,
+    pub app_rules: Vec<NotificationAppRule>,
+    /// The maximum number of notifications kept in the history.
+    pub history_limit: u32,
+}
+impl NotificationsConfig {
+    pub fn rule_for(&self, app: &str) -> Option<&NotificationAppRule> {
+        self.app_rules
+            .iter()
+            .find(|rule| rule.app.eq_ignore_ascii_case(app))
+    }
+}
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            do_not_disturb: false,
+            quiet_hours: None,
+            always_show_critical: true,
+            app_rules: Vec::new(),
+            history_limit: 100,
+        }
+    }
+}
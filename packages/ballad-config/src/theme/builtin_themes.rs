@@ -1,27 +1,27 @@
-use super::{Theme, ThemeColors, ThemeVariant};
+use super::{Color, Theme, ThemeColors, ThemeVariant};
 
 pub fn catppuccin_latte() -> Theme {
     Theme {
         colors: ThemeColors {
-            pink: "#ea76cb".to_string(),
-            orange: "#fe640b".to_string(),
-            red: "#d20f39".to_string(),
-            yellow: "#df8e1d".to_string(),
-            green: "#40a02b".to_string(),
-            blue: "#04a5e5".to_string(),
-            purple: "#7287fd".to_string(),
-            text: "#4c4f69".to_string(),
-            subtext_0: "#6c6f85".to_string(),
-            subtext_1: "#5c5f77".to_string(),
-            overlay_2: "#7c7f93".to_string(),
-            overlay_1: "#8c8fa1".to_string(),
-            overlay_0: "#9ca0b0".to_string(),
-            surface_2: "#acb0be".to_string(),
-            surface_1: "#bcc0cc".to_string(),
-            surface_0: "#ccd0da".to_string(),
-            bg_0: "#eff1f5".to_string(),
-            bg_1: "#e6e9ef".to_string(),
-            bg_2: "#dce0e8".to_string(),
+            pink: Color::hex(0xea76cb),
+            orange: Color::hex(0xfe640b),
+            red: Color::hex(0xd20f39),
+            yellow: Color::hex(0xdf8e1d),
+            green: Color::hex(0x40a02b),
+            blue: Color::hex(0x04a5e5),
+            purple: Color::hex(0x7287fd),
+            text: Color::hex(0x4c4f69),
+            subtext_0: Color::hex(0x6c6f85),
+            subtext_1: Color::hex(0x5c5f77),
+            overlay_2: Color::hex(0x7c7f93),
+            overlay_1: Color::hex(0x8c8fa1),
+            overlay_0: Color::hex(0x9ca0b0),
+            surface_2: Color::hex(0xacb0be),
+            surface_1: Color::hex(0xbcc0cc),
+            surface_0: Color::hex(0xccd0da),
+            bg_0: Color::hex(0xeff1f5),
+            bg_1: Color::hex(0xe6e9ef),
+            bg_2: Color::hex(0xdce0e8),
         },
         variant: (ThemeVariant::Light),
         name: "Catppuccin Latte".to_string(),
@@ -32,25 +32,25 @@ pub fn catppuccin_latte() -> Theme {
 pub fn catppuccin_frappe() -> Theme {
     Theme {
         colors: ThemeColors {
-            pink: "#f4b8e4".to_string(),
-            orange: "#ef9f76".to_string(),
-            red: "#e78284".to_string(),
-            yellow: "#e5c890".to_string(),
-            green: "#a6d189".to_string(),
-            blue: "#99d1db".to_string(),
-            purple: "#babbf1".to_string(),
-            text: "#c6d0f5".to_string(),
-            subtext_0: "#a5adce".to_string(),
-            subtext_1: "#b5bfe2".to_string(),
-            overlay_2: "#949cbb".to_string(),
-            overlay_1: "#838ba7".to_string(),
-            overlay_0: "#737994".to_string(),
-            surface_2: "#626880".to_string(),
-            surface_1: "#51576d".to_string(),
-            surface_0: "#414559".to_string(),
-            bg_0: "#303446".to_string(),
-            bg_1: "#292c3c".to_string(),
-            bg_2: "#232634".to_string(),
+            pink: Color::hex(0xf4b8e4),
+            orange: Color::hex(0xef9f76),
+            red: Color::hex(0xe78284),
+            yellow: Color::hex(0xe5c890),
+            green: Color::hex(0xa6d189),
+            blue: Color::hex(0x99d1db),
+            purple: Color::hex(0xbabbf1),
+            text: Color::hex(0xc6d0f5),
+            subtext_0: Color::hex(0xa5adce),
+            subtext_1: Color::hex(0xb5bfe2),
+            overlay_2: Color::hex(0x949cbb),
+            overlay_1: Color::hex(0x838ba7),
+            overlay_0: Color::hex(0x737994),
+            surface_2: Color::hex(0x626880),
+            surface_1: Color::hex(0x51576d),
+            surface_0: Color::hex(0x414559),
+            bg_0: Color::hex(0x303446),
+            bg_1: Color::hex(0x292c3c),
+            bg_2: Color::hex(0x232634),
         },
         variant: (ThemeVariant::Dark),
         name: "Catppuccin Frappé".to_string(),
@@ -61,25 +61,25 @@ pub fn catppuccin_frappe() -> Theme {
 pub fn catppuccin_macchiato() -> Theme {
     Theme {
         colors: ThemeColors {
-            pink: "#f5bde6".to_string(),
-            orange: "#f5a97f".to_string(),
-            red: "#ed8796".to_string(),
-            yellow: "#eed49f".to_string(),
-            green: "#a6da95".to_string(),
-            blue: "#91d7e3".to_string(),
-            purple: "#b7bdf8".to_string(),
-            text: "#cad3f5".to_string(),
-            subtext_0: "#a5adcb".to_string(),
-            subtext_1: "#b8c0e0".to_string(),
-            overlay_2: "#939ab7".to_string(),
-            overlay_1: "#8087a2".to_string(),
-            overlay_0: "#6e738d".to_string(),
-            surface_2: "#5b6078".to_string(),
-            surface_1: "#494d64".to_string(),
-            surface_0: "#363a4f".to_string(),
-            bg_0: "#24273a".to_string(),
-            bg_1: "#1e2030".to_string(),
-            bg_2: "#181926".to_string(),
+            pink: Color::hex(0xf5bde6),
+            orange: Color::hex(0xf5a97f),
+            red: Color::hex(0xed8796),
+            yellow: Color::hex(0xeed49f),
+            green: Color::hex(0xa6da95),
+            blue: Color::hex(0x91d7e3),
+            purple: Color::hex(0xb7bdf8),
+            text: Color::hex(0xcad3f5),
+            subtext_0: Color::hex(0xa5adcb),
+            subtext_1: Color::hex(0xb8c0e0),
+            overlay_2: Color::hex(0x939ab7),
+            overlay_1: Color::hex(0x8087a2),
+            overlay_0: Color::hex(0x6e738d),
+            surface_2: Color::hex(0x5b6078),
+            surface_1: Color::hex(0x494d64),
+            surface_0: Color::hex(0x363a4f),
+            bg_0: Color::hex(0x24273a),
+            bg_1: Color::hex(0x1e2030),
+            bg_2: Color::hex(0x181926),
         },
         variant: (ThemeVariant::Dark),
         name: "Catppuccin Macchiato".to_string(),
@@ -90,25 +90,25 @@ pub fn catppuccin_macchiato() -> Theme {
 pub fn catppuccin_mocha() -> Theme {
     Theme {
         colors: ThemeColors {
-            pink: "#f5c2e7".to_string(),
-            orange: "#fab387".to_string(),
-            red: "#f38ba8".to_string(),
-            yellow: "#f9e2af".to_string(),
-            green: "#a6e3a1".to_string(),
-            blue: "#89dceb".to_string(),
-            purple: "#b4befe".to_string(),
-            text: "#cdd6f4".to_string(),
-            subtext_0: "#a6adc8".to_string(),
-            subtext_1: "#bac2de".to_string(),
-            overlay_2: "#9399b2".to_string(),
-            overlay_1: "#7f849c".to_string(),
-            overlay_0: "#6c7086".to_string(),
-            surface_2: "#585b70".to_string(),
-            surface_1: "#45475a".to_string(),
-            surface_0: "#313244".to_string(),
-            bg_0: "#1e1e2e".to_string(),
-            bg_1: "#181825".to_string(),
-            bg_2: "#11111b".to_string(),
+            pink: Color::hex(0xf5c2e7),
+            orange: Color::hex(0xfab387),
+            red: Color::hex(0xf38ba8),
+            yellow: Color::hex(0xf9e2af),
+            green: Color::hex(0xa6e3a1),
+            blue: Color::hex(0x89dceb),
+            purple: Color::hex(0xb4befe),
+            text: Color::hex(0xcdd6f4),
+            subtext_0: Color::hex(0xa6adc8),
+            subtext_1: Color::hex(0xbac2de),
+            overlay_2: Color::hex(0x9399b2),
+            overlay_1: Color::hex(0x7f849c),
+            overlay_0: Color::hex(0x6c7086),
+            surface_2: Color::hex(0x585b70),
+            surface_1: Color::hex(0x45475a),
+            surface_0: Color::hex(0x313244),
+            bg_0: Color::hex(0x1e1e2e),
+            bg_1: Color::hex(0x181825),
+            bg_2: Color::hex(0x11111b),
         },
         variant: (ThemeVariant::Dark),
         name: "Catppuccin Mocha".to_string(),
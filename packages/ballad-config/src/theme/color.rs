@@ -0,0 +1,319 @@
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum ColorError {
+    #[snafu(display("{input:?} isn't a color: {reason}"))]
+    Invalid { input: String, reason: String },
+}
+
+fn invalid<T>(input: &str, reason: impl Into<String>) -> Result<T, ColorError> {
+    InvalidSnafu {
+        input,
+        reason: reason.into(),
+    }
+    .fail()
+}
+
+/// An sRGB color with an alpha channel.
+///
+/// Colors are parsed from `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, `hsl()` and `hsla()`, and
+/// written as `#rrggbb`, or `#rrggbbaa` if they are translucent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "gtk", derive(glib::Variant))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// An opaque color from a number like `0x24273a`.
+    pub const fn hex(rgb: u32) -> Self {
+        Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.a == 255
+    }
+
+    /// The color as `rrggbb`, without the `#` or the alpha channel, for programs that want it that way.
+    pub fn to_hex_rgb(&self) -> String {
+        format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// The WCAG relative luminance, from 0 for black to 1 for white.
+    pub fn luminance(&self) -> f64 {
+        let linear = |channel: u8| {
+            let channel = channel as f64 / 255.0;
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// The WCAG contrast ratio with another color, from 1 for the same luminance to 21 for black on white.
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let [r, g, b] = [self.r, self.g, self.b].map(|channel| channel as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return Hsl::new(0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        Hsl::new(hue, saturation, lightness)
+    }
+}
+
+/// A color as hue, saturation and lightness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    /// In degrees.
+    pub hue: f64,
+    /// From 0 to 1.
+    pub saturation: f64,
+    /// From 0 to 1.
+    pub lightness: f64,
+}
+impl Hsl {
+    pub fn new(hue: f64, saturation: f64, lightness: f64) -> Self {
+        Self {
+            hue: hue.rem_euclid(360.0),
+            saturation: saturation.clamp(0.0, 1.0),
+            lightness: lightness.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn to_color(self) -> Color {
+        let chroma = (1.0 - (2.0 * self.lightness - 1.0).abs()) * self.saturation;
+        let hue = self.hue / 60.0;
+        let x = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let offset = self.lightness - chroma / 2.0;
+        let channel = |value: f64| ((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
+    }
+}
+
+fn parse_hex(input: &str, hex: &str) -> Result<Color, ColorError> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return invalid(input, "hex colors can only have the digits 0-9 and a-f");
+    }
+    let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).unwrap();
+    let pair = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap();
+    Ok(match hex.len() {
+        3 => Color::rgb(digit(0) * 17, digit(1) * 17, digit(2) * 17),
+        4 => Color::rgba(digit(0) * 17, digit(1) * 17, digit(2) * 17, digit(3) * 17),
+        6 => Color::rgb(pair(0), pair(2), pair(4)),
+        8 => Color::rgba(pair(0), pair(2), pair(4), pair(6)),
+        _ => return invalid(input, "hex colors need 3, 4, 6 or 8 digits"),
+    })
+}
+
+/// Splits the arguments of a function like `rgb(1, 2, 3)` or `rgb(1 2 3 / 50%)` into the color and the alpha.
+fn arguments<'a>(
+    input: &str,
+    arguments: &'a str,
+) -> Result<(Vec<&'a str>, Option<&'a str>), ColorError> {
+    let (color, alpha) = match arguments.split_once('/') {
+        Some((color, alpha)) => (color, Some(alpha.trim())),
+        None => (arguments, None),
+    };
+    let mut values = color
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    let alpha = match (alpha, values.len()) {
+        (Some(alpha), 3) => Some(alpha),
+        (None, 4) => values.pop(),
+        (None, 3) => None,
+        _ => return invalid(input, "expected 3 values and an optional alpha"),
+    };
+    Ok((values, alpha))
+}
+
+/// A number, or a percentage of `max`.
+fn number(input: &str, value: &str, max: f64) -> Result<f64, ColorError> {
+    let (value, percentage) = match value.strip_suffix('%') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && percentage => {
+            Ok((number / 100.0 * max).clamp(0.0, max))
+        }
+        Ok(number) if number.is_finite() => Ok(number.clamp(0.0, max)),
+        _ => invalid(input, format!("{value:?} isn't a number")),
+    }
+}
+
+fn alpha(input: &str, alpha: Option<&str>) -> Result<u8, ColorError> {
+    Ok(match alpha {
+        Some(alpha) => (number(input, alpha, 1.0)? * 255.0).round() as u8,
+        None => 255,
+    })
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let color = input.trim().to_lowercase();
+        if let Some(hex) = color.strip_prefix('#') {
+            return parse_hex(input, hex);
+        }
+
+        let Some((function, rest)) = color.split_once('(') else {
+            return invalid(input, "expected a hex color, rgb() or hsl()");
+        };
+        let Some(rest) = rest.strip_suffix(')') else {
+            return invalid(input, "missing a closing parenthesis");
+        };
+        let (values, alpha_value) = arguments(input, rest)?;
+        let a = alpha(input, alpha_value)?;
+        match function.trim() {
+            "rgb" | "rgba" => {
+                let channel = |value: &str| Ok(number(input, value, 255.0)?.round() as u8);
+                Ok(Color::rgba(
+                    channel(values[0])?,
+                    channel(values[1])?,
+                    channel(values[2])?,
+                    a,
+                ))
+            }
+            "hsl" | "hsla" => {
+                let hue = values[0].strip_suffix("deg").unwrap_or(values[0]);
+                let hue = match hue.parse::<f64>() {
+                    Ok(hue) if hue.is_finite() => hue,
+                    _ => return invalid(input, format!("{hue:?} isn't a hue")),
+                };
+                let percentage = |value: &str| {
+                    if !value.ends_with('%') {
+                        return invalid(
+                            input,
+                            "hsl() saturation and lightness must be percentages",
+                        );
+                    }
+                    number(input, value, 1.0)
+                };
+                let color =
+                    Hsl::new(hue, percentage(values[1])?, percentage(values[2])?).to_color();
+                Ok(Color { a, ..color })
+            }
+            function => invalid(input, format!("unknown color function {function}()")),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.to_hex_rgb())?;
+        if !self.is_opaque() {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let color = String::deserialize(deserializer)?;
+        color.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let parsed = [
+            ("#1e1e2e", Color::hex(0x1e1e2e)),
+            ("#1E1E2E", Color::hex(0x1e1e2e)),
+            ("#fa0", Color::hex(0xffaa00)),
+            ("#ffaa0080", Color::rgba(255, 170, 0, 128)),
+            ("#fa08", Color::rgba(255, 170, 0, 136)),
+            ("rgb(30, 30, 46)", Color::hex(0x1e1e2e)),
+            ("rgb(30 30 46 / 50%)", Color::rgba(30, 30, 46, 128)),
+            ("rgba(255, 0, 0, 0.5)", Color::rgba(255, 0, 0, 128)),
+            ("rgb(100%, 0%, 50%)", Color::rgb(255, 0, 128)),
+            ("hsl(0, 100%, 50%)", Color::hex(0xff0000)),
+            ("hsl(240deg 100% 25%)", Color::hex(0x000080)),
+            ("hsla(120, 100%, 50%, 0)", Color::rgba(0, 255, 0, 0)),
+            ("  #000000  ", Color::hex(0x000000)),
+        ];
+        for (input, color) in parsed {
+            assert_eq!(input.parse::<Color>().unwrap(), color, "{input}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for color in [Color::hex(0x24273a), Color::rgba(1, 2, 3, 4)] {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+        assert_eq!(Color::hex(0x24273a).to_string(), "#24273a");
+        assert_eq!(Color::rgba(1, 2, 3, 4).to_string(), "#01020304");
+    }
+
+    #[test]
+    fn invalid() {
+        for invalid in [
+            "",
+            "black",
+            "#12345",
+            "#gggggg",
+            "#ééé",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3",
+            "rgb(a, b, c)",
+            "hsl(0, 100, 50)",
+            "lab(50 0 0)",
+        ] {
+            assert!(invalid.parse::<Color>().is_err(), "{invalid:?} parsed");
+        }
+    }
+}
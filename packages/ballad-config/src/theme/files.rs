@@ -0,0 +1,746 @@
+//! Reads and writes themes as files, so they can be shared without editing `shell_config.toml`.
+//!
+//! Ballad themes are TOML files holding a single [`Theme`], installed in `$XDG_DATA_HOME/ballad/themes`. Themes can
+//! also be imported from and exported to base16 and base24 YAML schemes and catppuccin's JSON palette.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use super::{Color, ColorError, Theme, ThemeColors, ThemeVariant};
+
+#[derive(Debug, Snafu)]
+pub enum ThemeFileError {
+    #[snafu(display("Failed to access {path}: {source}"))]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to parse {path}: {source}"))]
+    Toml {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[snafu(transparent)]
+    TomlSerialize { source: toml::ser::Error },
+    #[snafu(display("Failed to parse {path}: {source}"))]
+    Yaml {
+        path: String,
+        source: serde_norway::Error,
+    },
+    #[snafu(transparent)]
+    YamlSerialize { source: serde_norway::Error },
+    #[snafu(display("Failed to parse {path}: {source}"))]
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(transparent)]
+    JsonSerialize { source: serde_json::Error },
+    #[snafu(display("{path} has no {color} color"))]
+    MissingColor { path: String, color: String },
+    #[snafu(display("The {color} color in {path} is invalid: {source}"))]
+    InvalidColor {
+        path: String,
+        color: String,
+        source: ColorError,
+    },
+    #[snafu(display("{path} has no catppuccin flavors"))]
+    NoFlavors { path: String },
+    #[snafu(display("Can't tell the format of {path} from its extension"))]
+    UnknownFormat { path: String },
+}
+
+/// The formats themes can be imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFileFormat {
+    /// A [`Theme`] as TOML.
+    Ballad,
+    /// A base16 scheme as YAML.
+    Base16,
+    /// A base24 scheme as YAML, which adds darker backgrounds and bright colors to base16.
+    Base24,
+    /// The JSON palette of catppuccin, with one or more flavors.
+    Catppuccin,
+}
+impl ThemeFileFormat {
+    pub const ALL: [ThemeFileFormat; 4] = [
+        ThemeFileFormat::Ballad,
+        ThemeFileFormat::Base16,
+        ThemeFileFormat::Base24,
+        ThemeFileFormat::Catppuccin,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ballad => "Ballad",
+            Self::Base16 => "base16",
+            Self::Base24 => "base24",
+            Self::Catppuccin => "Catppuccin",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ballad => "toml",
+            Self::Base16 | Self::Base24 => "yaml",
+            Self::Catppuccin => "json",
+        }
+    }
+
+    /// The format of a file, from its extension. YAML files are base16 unless they have base24 colors.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "toml" => Some(Self::Ballad),
+            "yaml" | "yml" => Some(Self::Base16),
+            "json" => Some(Self::Catppuccin),
+            _ => None,
+        }
+    }
+}
+
+/// Where installed themes are kept: `$XDG_DATA_HOME/ballad/themes`.
+pub fn themes_dir() -> PathBuf {
+    xdg::BaseDirectories::with_prefix("ballad")
+        .unwrap()
+        .get_data_home()
+        .join("themes")
+}
+
+/// The `ballad/themes` folders of the XDG data directories, with `$XDG_DATA_HOME` first.
+pub fn theme_dirs() -> Vec<PathBuf> {
+    let Ok(dirs) = xdg::BaseDirectories::with_prefix("ballad") else {
+        return Vec::new();
+    };
+    std::iter::once(dirs.get_data_home())
+        .chain(dirs.get_data_dirs())
+        .map(|dir| dir.join("themes"))
+        .collect()
+}
+
+/// The themes installed in [`theme_dirs`].
+pub fn installed_themes() -> Vec<Theme> {
+    installed_themes_in(&theme_dirs())
+}
+
+/// The themes installed in `dirs`. Themes in earlier folders come first, and files that fail to parse are skipped.
+pub fn installed_themes_in(dirs: &[PathBuf]) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut files = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| ThemeFileFormat::from_path(path) == Some(ThemeFileFormat::Ballad))
+            .collect::<Vec<_>>();
+        files.sort();
+        for path in files {
+            match read_theme(&path) {
+                Ok(theme) if !themes.iter().any(|other| other.name == theme.name) => {
+                    themes.push(theme)
+                }
+                Ok(_) => {}
+                Err(err) => println!("Skipping theme: {err}"),
+            }
+        }
+    }
+    themes
+}
+
+/// Writes `theme` into the themes folder as TOML, replacing an installed theme file with the same name, and returns its
+/// path.
+pub fn install_theme(theme: &Theme) -> Result<PathBuf, ThemeFileError> {
+    let dir = themes_dir();
+    std::fs::create_dir_all(&dir).context(IoSnafu {
+        path: dir.display().to_string(),
+    })?;
+    let path = dir.join(format!("{}.toml", file_stem(&theme.name)));
+    export_theme(theme, &path, ThemeFileFormat::Ballad)?;
+    Ok(path)
+}
+
+/// Removes the installed theme file for the theme called `name`. Returns whether there was one.
+pub fn uninstall_theme(name: &str) -> Result<bool, ThemeFileError> {
+    let path = themes_dir().join(format!("{}.toml", file_stem(name)));
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(&path).context(IoSnafu {
+        path: path.display().to_string(),
+    })?;
+    Ok(true)
+}
+
+/// A file name for a theme, like `gruvbox-dark` for "Gruvbox Dark".
+fn file_stem(name: &str) -> String {
+    let stem = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if stem.is_empty() {
+        "theme".to_string()
+    } else {
+        stem
+    }
+}
+
+fn read(path: &Path) -> Result<String, ThemeFileError> {
+    std::fs::read_to_string(path).context(IoSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+fn read_theme(path: &Path) -> Result<Theme, ThemeFileError> {
+    toml::from_str(&read(path)?).context(TomlSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+/// Reads the themes in a file, in the format its extension suggests. Catppuccin palettes can hold several flavors, so
+/// this returns every theme in the file.
+pub fn import_themes(path: &Path) -> Result<Vec<Theme>, ThemeFileError> {
+    let format = ThemeFileFormat::from_path(path).context(UnknownFormatSnafu {
+        path: path.display().to_string(),
+    })?;
+    import_themes_as(path, format)
+}
+
+pub fn import_themes_as(
+    path: &Path,
+    format: ThemeFileFormat,
+) -> Result<Vec<Theme>, ThemeFileError> {
+    match format {
+        ThemeFileFormat::Ballad => Ok(vec![read_theme(path)?]),
+        ThemeFileFormat::Base16 | ThemeFileFormat::Base24 => Ok(vec![import_base16(path)?]),
+        ThemeFileFormat::Catppuccin => import_catppuccin(path),
+    }
+}
+
+/// Writes `theme` to `path` in `format`.
+pub fn export_theme(
+    theme: &Theme,
+    path: &Path,
+    format: ThemeFileFormat,
+) -> Result<(), ThemeFileError> {
+    let content = match format {
+        ThemeFileFormat::Ballad => toml::to_string_pretty(theme)?,
+        ThemeFileFormat::Base16 => serde_norway::to_string(&export_base16(theme, false))?,
+        ThemeFileFormat::Base24 => serde_norway::to_string(&export_base16(theme, true))?,
+        ThemeFileFormat::Catppuccin => serde_json::to_string_pretty(&export_catppuccin(theme))?,
+    };
+    std::fs::write(path, content).context(IoSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+/// Parses the color called `name` in the file at `path`, adding the `#` that base16 schemes usually leave out.
+fn parse_color(path: &Path, name: &str, color: &str) -> Result<Color, ThemeFileError> {
+    let color = color.trim();
+    let color = if color.starts_with('#') {
+        color.parse()
+    } else {
+        format!("#{color}").parse()
+    };
+    color.context(InvalidColorSnafu {
+        path: path.display().to_string(),
+        color: name,
+    })
+}
+
+fn variant_from_colors(background: Color, text: Color) -> ThemeVariant {
+    if background.luminance() > text.luminance() {
+        ThemeVariant::Light
+    } else {
+        ThemeVariant::Dark
+    }
+}
+
+/// A base16 or base24 scheme, in both the original flat format and the newer one with a `palette` map.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Base16Scheme {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    #[serde(alias = "scheme")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    palette: BTreeMap<String, String>,
+    /// The colors of the flat format, next to the name.
+    #[serde(flatten, skip_serializing)]
+    flat: BTreeMap<String, serde_norway::Value>,
+}
+
+fn import_base16(path: &Path) -> Result<Theme, ThemeFileError> {
+    let scheme: Base16Scheme = serde_norway::from_str(&read(path)?).context(YamlSnafu {
+        path: path.display().to_string(),
+    })?;
+    let mut colors = scheme.palette;
+    for (key, value) in scheme.flat {
+        if key.starts_with("base")
+            && let serde_norway::Value::String(value) = value
+        {
+            colors.insert(key, value);
+        }
+    }
+    let colors = colors
+        .into_iter()
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect::<BTreeMap<_, _>>();
+
+    let color = |key: &str| {
+        let color = colors.get(key).context(MissingColorSnafu {
+            path: path.display().to_string(),
+            color: key.to_string(),
+        })?;
+        parse_color(path, key, color)
+    };
+    // base24 adds darker backgrounds and bright colors, used where base16 has to repeat a color.
+    let base24 = |key: &str, fallback: &str| match colors.get(key) {
+        Some(color) => parse_color(path, key, color),
+        None => color(fallback),
+    };
+
+    let theme_colors = ThemeColors {
+        pink: base24("base17", "base0e")?,
+        orange: color("base09")?,
+        red: color("base08")?,
+        yellow: color("base0a")?,
+        green: color("base0b")?,
+        blue: color("base0d")?,
+        purple: color("base0e")?,
+        text: color("base05")?,
+        subtext_1: color("base05")?,
+        subtext_0: color("base04")?,
+        overlay_2: color("base04")?,
+        overlay_1: color("base03")?,
+        overlay_0: color("base03")?,
+        surface_2: color("base02")?,
+        surface_1: color("base02")?,
+        surface_0: color("base01")?,
+        bg_0: color("base00")?,
+        bg_1: base24("base10", "base00")?,
+        bg_2: base24("base11", "base00")?,
+    };
+    let variant = match scheme.variant.as_deref() {
+        Some("light") => ThemeVariant::Light,
+        Some("dark") => ThemeVariant::Dark,
+        _ => variant_from_colors(theme_colors.bg_0, theme_colors.text),
+    };
+    Ok(Theme {
+        name: scheme.name.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        }),
+        colors: theme_colors,
+        variant,
+        gtk_theme: None,
+    })
+}
+
+fn export_base16(theme: &Theme, base24: bool) -> Base16Scheme {
+    let colors = &theme.colors;
+    let mut palette = [
+        ("base00", &colors.bg_0),
+        ("base01", &colors.surface_0),
+        ("base02", &colors.surface_1),
+        ("base03", &colors.overlay_0),
+        ("base04", &colors.subtext_0),
+        ("base05", &colors.text),
+        ("base06", &colors.subtext_1),
+        ("base07", &colors.text),
+        ("base08", &colors.red),
+        ("base09", &colors.orange),
+        ("base0A", &colors.yellow),
+        ("base0B", &colors.green),
+        ("base0C", &colors.blue),
+        ("base0D", &colors.blue),
+        ("base0E", &colors.purple),
+        ("base0F", &colors.pink),
+    ]
+    .into_iter()
+    .map(|(key, color)| (key.to_string(), color.to_string()))
+    .collect::<BTreeMap<_, _>>();
+    if base24 {
+        palette.extend(
+            [
+                ("base10", &colors.bg_1),
+                ("base11", &colors.bg_2),
+                ("base12", &colors.red),
+                ("base13", &colors.yellow),
+                ("base14", &colors.green),
+                ("base15", &colors.blue),
+                ("base16", &colors.blue),
+                ("base17", &colors.pink),
+            ]
+            .into_iter()
+            .map(|(key, color)| (key.to_string(), color.to_string())),
+        );
+    }
+    Base16Scheme {
+        system: Some(if base24 { "base24" } else { "base16" }.to_string()),
+        name: Some(theme.name.clone()),
+        author: None,
+        variant: Some(if theme.is_light() { "light" } else { "dark" }.to_string()),
+        palette,
+        flat: BTreeMap::new(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CatppuccinColor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    hex: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CatppuccinFlavor {
+    name: String,
+    dark: bool,
+    colors: BTreeMap<String, CatppuccinColor>,
+}
+
+fn catppuccin_theme(path: &Path, flavor: CatppuccinFlavor) -> Result<Theme, ThemeFileError> {
+    let color = |key: &str| {
+        let color = flavor.colors.get(key).context(MissingColorSnafu {
+            path: path.display().to_string(),
+            color: key.to_string(),
+        })?;
+        parse_color(path, key, &color.hex)
+    };
+    Ok(Theme {
+        colors: ThemeColors {
+            pink: color("pink")?,
+            orange: color("peach")?,
+            red: color("red")?,
+            yellow: color("yellow")?,
+            green: color("green")?,
+            blue: color("sky")?,
+            purple: color("lavender")?,
+            text: color("text")?,
+            subtext_1: color("subtext1")?,
+            subtext_0: color("subtext0")?,
+            overlay_2: color("overlay2")?,
+            overlay_1: color("overlay1")?,
+            overlay_0: color("overlay0")?,
+            surface_2: color("surface2")?,
+            surface_1: color("surface1")?,
+            surface_0: color("surface0")?,
+            bg_0: color("base")?,
+            bg_1: color("mantle")?,
+            bg_2: color("crust")?,
+        },
+        name: if flavor.name.to_lowercase().starts_with("catppuccin") {
+            flavor.name
+        } else {
+            format!("Catppuccin {}", flavor.name)
+        },
+        variant: if flavor.dark {
+            ThemeVariant::Dark
+        } else {
+            ThemeVariant::Light
+        },
+        gtk_theme: None,
+    })
+}
+
+/// Reads catppuccin's `palette.json`, which maps flavor ids like `mocha` to flavors, or a file with a single flavor.
+fn import_catppuccin(path: &Path) -> Result<Vec<Theme>, ThemeFileError> {
+    let json: serde_json::Value = serde_json::from_str(&read(path)?).context(JsonSnafu {
+        path: path.display().to_string(),
+    })?;
+    let parse = |value: serde_json::Value| {
+        serde_json::from_value::<CatppuccinFlavor>(value).context(JsonSnafu {
+            path: path.display().to_string(),
+        })
+    };
+
+    if json.get("colors").is_some() {
+        return Ok(vec![catppuccin_theme(path, parse(json)?)?]);
+    }
+    let serde_json::Value::Object(entries) = json else {
+        return NoFlavorsSnafu {
+            path: path.display().to_string(),
+        }
+        .fail();
+    };
+    // Other keys like `version` aren't flavors.
+    let mut flavors = entries
+        .into_iter()
+        .filter(|(_, value)| value.get("colors").is_some())
+        .map(|(_, value)| {
+            let order = value.get("order").and_then(|order| order.as_u64());
+            Ok((order, parse(value)?))
+        })
+        .collect::<Result<Vec<_>, ThemeFileError>>()?;
+    if flavors.is_empty() {
+        return NoFlavorsSnafu {
+            path: path.display().to_string(),
+        }
+        .fail();
+    }
+    flavors.sort_by_key(|(order, _)| *order);
+    flavors
+        .into_iter()
+        .map(|(_, flavor)| catppuccin_theme(path, flavor))
+        .collect()
+}
+
+/// Exports a theme as a catppuccin palette with a single flavor. Catppuccin has more accents than Ballad, so the
+/// missing ones repeat the closest Ballad color.
+fn export_catppuccin(theme: &Theme) -> BTreeMap<String, CatppuccinFlavor> {
+    let colors = &theme.colors;
+    let flavor_colors = [
+        ("pink", &colors.pink),
+        ("peach", &colors.orange),
+        ("red", &colors.red),
+        ("yellow", &colors.yellow),
+        ("green", &colors.green),
+        ("sky", &colors.blue),
+        ("lavender", &colors.purple),
+        ("text", &colors.text),
+        ("subtext1", &colors.subtext_1),
+        ("subtext0", &colors.subtext_0),
+        ("overlay2", &colors.overlay_2),
+        ("overlay1", &colors.overlay_1),
+        ("overlay0", &colors.overlay_0),
+        ("surface2", &colors.surface_2),
+        ("surface1", &colors.surface_1),
+        ("surface0", &colors.surface_0),
+        ("base", &colors.bg_0),
+        ("mantle", &colors.bg_1),
+        ("crust", &colors.bg_2),
+        ("rosewater", &colors.pink),
+        ("flamingo", &colors.pink),
+        ("mauve", &colors.purple),
+        ("maroon", &colors.red),
+        ("teal", &colors.green),
+        ("sapphire", &colors.blue),
+        ("blue", &colors.blue),
+    ];
+
+    let flavor = CatppuccinFlavor {
+        name: theme.name.clone(),
+        dark: theme.is_dark(),
+        colors: flavor_colors
+            .into_iter()
+            .map(|(name, hex)| {
+                let color = CatppuccinColor {
+                    name: None,
+                    hex: hex.to_string(),
+                };
+                (name.to_string(), color)
+            })
+            .collect(),
+    };
+    BTreeMap::from([(file_stem(&theme.name), flavor)])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::ThemeSelection;
+
+    /// Gruvbox Dark Hard in the original flat base16 format, without `#` before the colors.
+    const GRUVBOX_BASE16: &str = r#"scheme: "Gruvbox dark, hard"
+author: "Dawid Kurek (dawikur@gmail.com), morhetz (https://github.com/morhetz/gruvbox)"
+base00: "1d2021"
+base01: "3c3836"
+base02: "504945"
+base03: "665c54"
+base04: "bdae93"
+base05: "d5c4a1"
+base06: "ebdbb2"
+base07: "fbf1c7"
+base08: "fb4934"
+base09: "fe8019"
+base0A: "fabd2f"
+base0B: "b8bb26"
+base0C: "8ec07c"
+base0D: "83a598"
+base0E: "d3869b"
+base0F: "d65d0e"
+"#;
+
+    /// A light scheme in the newer format, with the colors in a `palette` map.
+    const ONE_LIGHT_BASE24: &str = r##"system: "base24"
+name: "One Light"
+author: "Daniel Pfeifer (http://github.com/purpleKarrot)"
+variant: "light"
+palette:
+  base00: "#fafafa"
+  base01: "#f0f0f1"
+  base02: "#e5e5e6"
+  base03: "#a0a1a7"
+  base04: "#696c77"
+  base05: "#383a42"
+  base06: "#202227"
+  base07: "#090a0b"
+  base08: "#ca1243"
+  base09: "#d75f00"
+  base0A: "#c18401"
+  base0B: "#50a14f"
+  base0C: "#0184bc"
+  base0D: "#4078f2"
+  base0E: "#a626a4"
+  base0F: "#986801"
+  base10: "#ececed"
+  base11: "#e0e0e1"
+  base12: "#e45649"
+  base13: "#e5c07b"
+  base14: "#50a14f"
+  base15: "#0997b3"
+  base16: "#61afef"
+  base17: "#c678dd"
+"##;
+
+    fn builtin() -> [Theme; 4] {
+        [
+            ThemeSelection::CatppuccinFrappe,
+            ThemeSelection::CatppuccinMacchiato,
+            ThemeSelection::CatppuccinMocha,
+            ThemeSelection::CatppuccinLatte,
+        ]
+        .map(|selection| selection.theme().unwrap())
+    }
+
+    /// An empty folder for the files of one test, which run at the same time.
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ballad-theme-files-{}-{test}", std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trips() {
+        let dir = temp_dir("round-trips");
+        let builtin = builtin();
+        for theme in &builtin {
+            for format in ThemeFileFormat::ALL {
+                let path = dir.join(format!(
+                    "{}-{}.{}",
+                    theme.name,
+                    format.name(),
+                    format.extension()
+                ));
+                export_theme(theme, &path, format).unwrap();
+                let imported = import_themes(&path).unwrap();
+                assert_eq!(imported.len(), 1, "{}", path.display());
+                let imported = &imported[0];
+                assert_eq!(imported.name, theme.name, "{}", path.display());
+                assert_eq!(imported.variant, theme.variant, "{}", path.display());
+
+                let colors = &imported.colors;
+                match format {
+                    // These keep every color.
+                    ThemeFileFormat::Ballad | ThemeFileFormat::Catppuccin => {
+                        assert_eq!(*colors, theme.colors, "{}", path.display())
+                    }
+                    // base16 has fewer colors, so some are repeated.
+                    ThemeFileFormat::Base16 | ThemeFileFormat::Base24 => {
+                        assert_eq!(colors.bg_0, theme.colors.bg_0);
+                        assert_eq!(colors.text, theme.colors.text);
+                        assert_eq!(colors.red, theme.colors.red);
+                        assert_eq!(colors.blue, theme.colors.blue);
+                        assert_eq!(colors.surface_0, theme.colors.surface_0);
+                        if format == ThemeFileFormat::Base24 {
+                            assert_eq!(colors.pink, theme.colors.pink);
+                            assert_eq!(colors.bg_1, theme.colors.bg_1);
+                            assert_eq!(colors.bg_2, theme.colors.bg_2);
+                        }
+                    }
+                }
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn base16_schemes() {
+        let dir = temp_dir("base16");
+        let gruvbox_path = dir.join("gruvbox-dark-hard.yaml");
+        std::fs::write(&gruvbox_path, GRUVBOX_BASE16).unwrap();
+        let gruvbox = import_themes(&gruvbox_path).unwrap().remove(0);
+        assert_eq!(gruvbox.name, "Gruvbox dark, hard");
+        assert_eq!(gruvbox.variant, ThemeVariant::Dark);
+        assert_eq!(gruvbox.colors.bg_0, Color::hex(0x1d2021));
+        assert_eq!(gruvbox.colors.yellow, Color::hex(0xfabd2f));
+        assert_eq!(gruvbox.colors.bg_2, Color::hex(0x1d2021));
+
+        let one_light_path = dir.join("one-light.yml");
+        std::fs::write(&one_light_path, ONE_LIGHT_BASE24).unwrap();
+        let one_light = import_themes(&one_light_path).unwrap().remove(0);
+        assert_eq!(one_light.variant, ThemeVariant::Light);
+        assert_eq!(one_light.colors.bg_1, Color::hex(0xececed));
+        assert_eq!(one_light.colors.pink, Color::hex(0xc678dd));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn catppuccin_palette() {
+        let dir = temp_dir("catppuccin");
+        let builtin = builtin();
+        // A palette with every flavor, like catppuccin's `palette.json`.
+        let mut palette = serde_json::Map::new();
+        palette.insert("version".to_string(), "1.7.1".into());
+        for (order, theme) in builtin.iter().enumerate() {
+            let path = dir.join("flavor.json");
+            export_theme(theme, &path, ThemeFileFormat::Catppuccin).unwrap();
+            let flavor: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            let (id, mut flavor) = flavor.into_iter().next().unwrap();
+            flavor["order"] = order.into();
+            palette.insert(id, flavor);
+        }
+        let palette_path = dir.join("palette.json");
+        std::fs::write(&palette_path, serde_json::to_string(&palette).unwrap()).unwrap();
+        // Catppuccin palettes don't have a GTK theme.
+        let without_gtk_themes = builtin.clone().map(|theme| Theme {
+            gtk_theme: None,
+            ..theme
+        });
+        assert_eq!(import_themes(&palette_path).unwrap(), without_gtk_themes);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn broken_files() {
+        let dir = temp_dir("broken");
+        let broken_path = dir.join("broken.yaml");
+        std::fs::write(&broken_path, "base00: \"000000\"\n").unwrap();
+        assert!(matches!(
+            import_themes(&broken_path),
+            Err(ThemeFileError::MissingColor { .. })
+        ));
+        std::fs::write(
+            &broken_path,
+            GRUVBOX_BASE16.replace("\"fb4934\"", "\"fb493\""),
+        )
+        .unwrap();
+        assert!(matches!(
+            import_themes(&broken_path),
+            Err(ThemeFileError::InvalidColor { color, .. }) if color == "base08"
+        ));
+        assert!(matches!(
+            import_themes(&dir.join("theme.css")),
+            Err(ThemeFileError::UnknownFormat { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,43 +1,55 @@
 mod builtin_themes;
+pub mod color;
+pub mod files;
+#[cfg(feature = "palette")]
+pub mod palette;
+pub mod schedule;
+pub mod templates;
+
+use std::path::PathBuf;
 
 use builtin_themes::{catppuccin_latte, catppuccin_macchiato};
+#[cfg(feature = "gtk")]
 use gtk::glib;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use snafu::{ResultExt, Snafu};
 
 use crate::get_or_init_shell_config;
+pub use color::{Color, ColorError, Hsl};
 
 /// The color variables for the theme
 /// These variables are designed for interop with catppuccin themes, so do your best with the required colors if you are using a different theme.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawThemeColors")]
 #[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
 #[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigThemeColors"))]
 pub struct ThemeColors {
-    pub pink: String,
-    pub orange: String,
-    pub red: String,
-    pub yellow: String,
-    pub green: String,
-    pub blue: String,
-    pub purple: String,
-
-    pub text: String,
-    pub subtext_1: String,
-    pub subtext_0: String,
-
-    pub overlay_2: String,
-    pub overlay_1: String,
-    pub overlay_0: String,
-
-    pub surface_2: String,
-    pub surface_1: String,
-    pub surface_0: String,
+    pub pink: Color,
+    pub orange: Color,
+    pub red: Color,
+    pub yellow: Color,
+    pub green: Color,
+    pub blue: Color,
+    pub purple: Color,
+
+    pub text: Color,
+    pub subtext_1: Color,
+    pub subtext_0: Color,
+
+    pub overlay_2: Color,
+    pub overlay_1: Color,
+    pub overlay_0: Color,
+
+    pub surface_2: Color,
+    pub surface_1: Color,
+    pub surface_0: Color,
 
     /// Background color for the lowest layer of an application
-    pub bg_0: String,
+    pub bg_0: Color,
     /// Background color for the middle (second) layer of an application
-    pub bg_1: String,
+    pub bg_1: Color,
     /// Background color for the top layer of an application
-    pub bg_2: String,
+    pub bg_2: Color,
 }
 
 impl ThemeColors {
@@ -91,6 +103,122 @@ $bg_1: {bg_1};
 $bg_2: {bg_2};"
         )
     }
+
+    /// Every color with its name, like `("bg_0", "#24273a")`.
+    pub fn named(&self) -> [(&'static str, &Color); 19] {
+        [
+            ("pink", &self.pink),
+            ("orange", &self.orange),
+            ("red", &self.red),
+            ("yellow", &self.yellow),
+            ("green", &self.green),
+            ("blue", &self.blue),
+            ("purple", &self.purple),
+            ("text", &self.text),
+            ("subtext_1", &self.subtext_1),
+            ("subtext_0", &self.subtext_0),
+            ("overlay_2", &self.overlay_2),
+            ("overlay_1", &self.overlay_1),
+            ("overlay_0", &self.overlay_0),
+            ("surface_2", &self.surface_2),
+            ("surface_1", &self.surface_1),
+            ("surface_0", &self.surface_0),
+            ("bg_0", &self.bg_0),
+            ("bg_1", &self.bg_1),
+            ("bg_2", &self.bg_2),
+        ]
+    }
+
+    pub fn named_mut(&mut self) -> [(&'static str, &mut Color); 19] {
+        [
+            ("pink", &mut self.pink),
+            ("orange", &mut self.orange),
+            ("red", &mut self.red),
+            ("yellow", &mut self.yellow),
+            ("green", &mut self.green),
+            ("blue", &mut self.blue),
+            ("purple", &mut self.purple),
+            ("text", &mut self.text),
+            ("subtext_1", &mut self.subtext_1),
+            ("subtext_0", &mut self.subtext_0),
+            ("overlay_2", &mut self.overlay_2),
+            ("overlay_1", &mut self.overlay_1),
+            ("overlay_0", &mut self.overlay_0),
+            ("surface_2", &mut self.surface_2),
+            ("surface_1", &mut self.surface_1),
+            ("surface_0", &mut self.surface_0),
+            ("bg_0", &mut self.bg_0),
+            ("bg_1", &mut self.bg_1),
+            ("bg_2", &mut self.bg_2),
+        ]
+    }
+
+    /// The colors as GTK named colors, like `@define-color bg_0 #24273a;`, for plain CSS.
+    pub fn as_gtk_css(&self) -> String {
+        self.named()
+            .iter()
+            .map(|(name, color)| format!("@define-color {name} {color};\n"))
+            .collect()
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum ThemeColorsError {
+    #[snafu(display("The {name} color is invalid: {source}"))]
+    InvalidColor { name: String, source: ColorError },
+}
+
+/// [`ThemeColors`] as they are written in the config, so an invalid color can be reported with its name.
+#[derive(Deserialize)]
+struct RawThemeColors {
+    pink: String,
+    orange: String,
+    red: String,
+    yellow: String,
+    green: String,
+    blue: String,
+    purple: String,
+    text: String,
+    subtext_1: String,
+    subtext_0: String,
+    overlay_2: String,
+    overlay_1: String,
+    overlay_0: String,
+    surface_2: String,
+    surface_1: String,
+    surface_0: String,
+    bg_0: String,
+    bg_1: String,
+    bg_2: String,
+}
+
+impl TryFrom<RawThemeColors> for ThemeColors {
+    type Error = ThemeColorsError;
+
+    fn try_from(raw: RawThemeColors) -> Result<Self, Self::Error> {
+        let parse = |name: &str, color: String| color.parse().context(InvalidColorSnafu { name });
+        Ok(Self {
+            pink: parse("pink", raw.pink)?,
+            orange: parse("orange", raw.orange)?,
+            red: parse("red", raw.red)?,
+            yellow: parse("yellow", raw.yellow)?,
+            green: parse("green", raw.green)?,
+            blue: parse("blue", raw.blue)?,
+            purple: parse("purple", raw.purple)?,
+            text: parse("text", raw.text)?,
+            subtext_1: parse("subtext_1", raw.subtext_1)?,
+            subtext_0: parse("subtext_0", raw.subtext_0)?,
+            overlay_2: parse("overlay_2", raw.overlay_2)?,
+            overlay_1: parse("overlay_1", raw.overlay_1)?,
+            overlay_0: parse("overlay_0", raw.overlay_0)?,
+            surface_2: parse("surface_2", raw.surface_2)?,
+            surface_1: parse("surface_1", raw.surface_1)?,
+            surface_0: parse("surface_0", raw.surface_0)?,
+            bg_0: parse("bg_0", raw.bg_0)?,
+            bg_1: parse("bg_1", raw.bg_1)?,
+            bg_2: parse("bg_2", raw.bg_2)?,
+        })
+    }
 }
 
 /// The variant of the theme (light or dark)
@@ -142,28 +270,16 @@ pub enum ThemeSelection {
     Custom(String),
 }
 
-impl ThemeSelection {
-    fn try_read_custom_theme(&self) -> Option<Theme> {
-        match self {
-            Self::Custom(theme_name) => {
-                let config = get_or_init_shell_config();
-                let custom_themes = config.ok()?.theme.custom_themes;
-
-                custom_themes
-                    .into_iter()
-                    .find(|theme| theme.name == *theme_name)
-            }
-            _ => panic!("Cannot read custom theme from non-custom theme selection"),
-        }
-    }
+/// The theme of the default [`ThemeSelection`], used when the selected theme can't be read.
+pub fn default_theme() -> Theme {
+    catppuccin_macchiato()
+}
 
+impl ThemeSelection {
     pub fn is_light(&self) -> Option<bool> {
         Some(match self {
             Self::CatppuccinLatte => true,
-            Self::Custom(_) => {
-                let theme = self.try_read_custom_theme()?;
-                theme.variant.is_light()
-            }
+            Self::Custom(_) => self.theme()?.variant.is_light(),
             _ => false,
         })
     }
@@ -171,13 +287,33 @@ impl ThemeSelection {
         self.is_light().map(|light| !light)
     }
 
+    /// Reads the theme, looking custom themes up in the shell config and the installed theme files.
     pub fn theme(&self) -> Option<Theme> {
+        let custom_themes = match self {
+            Self::Custom(_) => get_or_init_shell_config().ok()?.theme.custom_themes,
+            _ => Vec::new(),
+        };
+        self.theme_in(&custom_themes, &files::theme_dirs())
+    }
+
+    /// Reads the theme, looking custom themes up in `custom_themes` and then in the theme files installed in
+    /// `theme_dirs`.
+    pub fn theme_in(&self, custom_themes: &[Theme], theme_dirs: &[PathBuf]) -> Option<Theme> {
         Some(match self {
             ThemeSelection::CatppuccinFrappe => builtin_themes::catppuccin_frappe(),
             ThemeSelection::CatppuccinMacchiato => builtin_themes::catppuccin_macchiato(),
             ThemeSelection::CatppuccinMocha => builtin_themes::catppuccin_mocha(),
             ThemeSelection::CatppuccinLatte => builtin_themes::catppuccin_latte(),
-            ThemeSelection::Custom(_) => return self.try_read_custom_theme(),
+            // Themes in the config take precedence over installed theme files with the same name.
+            ThemeSelection::Custom(theme_name) => custom_themes
+                .iter()
+                .find(|theme| theme.name == *theme_name)
+                .cloned()
+                .or_else(|| {
+                    files::installed_themes_in(theme_dirs)
+                        .into_iter()
+                        .find(|theme| theme.name == *theme_name)
+                })?,
         })
     }
 }
@@ -187,6 +323,8 @@ impl ThemeSelection {
 #[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigThemeConfig"))]
 pub struct ThemeConfig {
     pub selected_theme: ThemeSelection,
+    /// Custom themes that fail to load are skipped, so a typo doesn't lose the rest of the config.
+    #[serde(deserialize_with = "deserialize_custom_themes")]
     pub custom_themes: Vec<Theme>,
     pub default_dark_gtk_theme: String,
     pub default_light_gtk_theme: String,
@@ -194,6 +332,13 @@ pub struct ThemeConfig {
     pub corner_radius: f64,
     pub ui_radius: u32,
     pub transition_length: f64,
+
+    /// Files rendered from templates whenever the theme changes, so other programs use its colors.
+    #[serde(default)]
+    pub outputs: Vec<templates::ThemeOutput>,
+    /// Switches between a light and a dark theme automatically.
+    #[serde(default)]
+    pub auto_switch: schedule::ThemeSwitchConfig,
 }
 
 impl Default for ThemeConfig {
@@ -207,33 +352,95 @@ impl Default for ThemeConfig {
             corner_radius: 16.0,
             ui_radius: 8,
             transition_length: 0.2,
+
+            outputs: Vec::new(),
+            auto_switch: Default::default(),
         }
     }
 }
 
+fn deserialize_custom_themes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Theme>, D::Error> {
+    let themes = Vec::<toml::Value>::deserialize(deserializer)?;
+    Ok(themes
+        .into_iter()
+        .filter_map(|theme| {
+            let name = theme
+                .get("name")
+                .and_then(|name| name.as_str())
+                .unwrap_or("unnamed")
+                .to_string();
+            match theme.try_into::<Theme>() {
+                Ok(theme) => Some(theme),
+                Err(err) => {
+                    println!("Skipping the custom theme {name}: {err}");
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
 impl ThemeConfig {
-    pub fn as_scss(&self) -> Option<String> {
+    /// The selected theme, or the default theme if the selected custom theme is missing or invalid.
+    pub fn theme(&self) -> Theme {
+        self.theme_in(&files::theme_dirs())
+    }
+
+    /// Like [`Self::theme`], with installed theme files read from `theme_dirs`.
+    pub fn theme_in(&self, theme_dirs: &[PathBuf]) -> Theme {
+        self.selected_theme
+            .theme_in(&self.custom_themes, theme_dirs)
+            .unwrap_or_else(|| {
+                println!(
+                    "Failed to read the theme {:?}, falling back to the default theme",
+                    self.selected_theme
+                );
+                default_theme()
+            })
+    }
+
+    /// Adds `theme` to the custom themes, replacing the custom theme with the same name.
+    pub fn save_custom_theme(&mut self, theme: Theme) {
+        match self
+            .custom_themes
+            .iter_mut()
+            .find(|custom| custom.name == theme.name)
+        {
+            Some(custom) => *custom = theme,
+            None => self.custom_themes.push(theme),
+        }
+    }
+
+    pub fn as_scss(&self) -> String {
+        self.as_scss_in(&files::theme_dirs())
+    }
+
+    /// Like [`Self::as_scss`], with installed theme files read from `theme_dirs`.
+    pub fn as_scss_in(&self, theme_dirs: &[PathBuf]) -> String {
         let Self {
-            selected_theme,
+            selected_theme: _,
             custom_themes: _,
             default_dark_gtk_theme: _,
             default_light_gtk_theme: _,
             corner_radius,
             ui_radius,
             transition_length,
+            outputs: _,
+            auto_switch: _,
         } = self;
 
-        let colors = selected_theme.theme()?.colors;
-        let colors_scss = colors.as_scss();
+        let colors_scss = self.theme_in(theme_dirs).colors.as_scss();
 
-        Some(format!(
+        format!(
             "{colors_scss}
 
 $corner-radius: {corner_radius}px;
 $ui-radius: {ui_radius}px;
 $transition-length: {transition_length}s;
 $transition: all $transition-length;",
-        ))
+        )
     }
 }
 
@@ -245,12 +452,49 @@ pub fn get_or_init_all_theme_selections() -> Result<Vec<ThemeSelection>, crate::
         ThemeSelection::CatppuccinMocha,
         ThemeSelection::CatppuccinLatte,
     ];
-    themes.extend(
-        config
-            .theme
-            .custom_themes
-            .into_iter()
-            .map(|theme| ThemeSelection::Custom(theme.name)),
-    );
+    for theme in config
+        .theme
+        .custom_themes
+        .into_iter()
+        .chain(files::installed_themes())
+    {
+        let selection = ThemeSelection::Custom(theme.name);
+        if !themes.contains(&selection) {
+            themes.push(selection);
+        }
+    }
     Ok(themes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShellConfig;
+
+    #[test]
+    fn invalid_color_names_the_field() {
+        let theme = toml::to_string(&ThemeSelection::CatppuccinMocha.theme().unwrap())
+            .unwrap()
+            .replace("bg_0 = \"#1e1e2e\"", "bg_0 = \"#1e1e2g\"");
+        let err = toml::from_str::<Theme>(&theme).unwrap_err().to_string();
+        assert!(err.contains("bg_0"), "{err}");
+    }
+
+    /// An invalid custom theme is skipped instead of failing the whole config, and the shell falls back to the default
+    /// theme if it was selected.
+    #[test]
+    fn invalid_custom_theme_is_skipped() {
+        let mut config = ShellConfig::default();
+        config.theme.custom_themes.push(Theme {
+            name: "Broken".to_string(),
+            ..ThemeSelection::CatppuccinMocha.theme().unwrap()
+        });
+        config.theme.selected_theme = ThemeSelection::Custom("Broken".to_string());
+        let content = toml::to_string(&config)
+            .unwrap()
+            .replace("bg_0 = \"#1e1e2e\"", "bg_0 = \"#1e1e2g\"");
+        let config = toml::from_str::<ShellConfig>(&content).unwrap();
+        assert!(config.theme.custom_themes.is_empty());
+        assert!(config.theme.as_scss_in(&[]).contains("$bg_0: #24273a;"));
+    }
+}
@@ -0,0 +1,419 @@
+//! Generates a theme from the colors of an image, like the wallpaper.
+//!
+//! The image is quantised with median cut into a handful of swatches. The most common swatch tints the backgrounds,
+//! surfaces and text, and each accent takes the hue of a swatch close to it, so red stays red enough for errors. Every
+//! color is then adjusted until it is readable on `bg_0`.
+
+use std::path::Path;
+
+use image::{DynamicImage, RgbaImage};
+use snafu::{ResultExt, Snafu};
+
+use super::{Color, Hsl, Theme, ThemeColors, ThemeVariant};
+
+/// How many swatches median cut splits the image into.
+const SWATCH_COUNT: usize = 16;
+/// Images are sampled down to about this many pixels before they are quantised.
+const SAMPLE_PIXELS: u32 = 128 * 128;
+/// Pixels more transparent than this are ignored.
+const MIN_ALPHA: u8 = 128;
+/// The relative luminance of middle gray. Images brighter than this on average get a light theme.
+const LIGHT_LUMINANCE: f64 = 0.18;
+
+/// The WCAG contrast text and subtext have against `bg_0`.
+const TEXT_CONTRAST: f64 = 7.0;
+const SUBTEXT_CONTRAST: f64 = 4.5;
+/// The WCAG contrast accents have against `bg_0`.
+const ACCENT_CONTRAST: f64 = 4.5;
+
+/// How far the hue of a swatch can be from an accent and still be used for it, in degrees.
+const ACCENT_HUE_RANGE: f64 = 30.0;
+/// Swatches less saturated than this are too gray to be used as an accent.
+const ACCENT_MIN_SATURATION: f64 = 0.25;
+
+#[derive(Debug, Snafu)]
+pub enum PaletteError {
+    #[snafu(display("Failed to read the image at {path}: {source}"))]
+    Image {
+        path: String,
+        source: image::ImageError,
+    },
+    #[snafu(display("The image has no opaque pixels"))]
+    Empty,
+}
+
+fn hue_distance(a: f64, b: f64) -> f64 {
+    let distance = (a - b).rem_euclid(360.0);
+    distance.min(360.0 - distance)
+}
+
+/// How far to turn from `from` to reach `to`, in degrees between -180 and 180.
+fn signed_hue_distance(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Moves the lightness of `color` away from `background` until they have at least `contrast`, or can't get further
+/// apart.
+fn ensure_contrast(color: Hsl, background: Color, contrast: f64, variant: ThemeVariant) -> Color {
+    let step = if variant.is_dark() { 0.01 } else { -0.01 };
+    let mut color = color;
+    loop {
+        let rgb = color.to_color();
+        let at_limit = if variant.is_dark() {
+            color.lightness >= 1.0
+        } else {
+            color.lightness <= 0.0
+        };
+        if at_limit || rgb.contrast_ratio(&background) >= contrast {
+            return rgb;
+        }
+        color = Hsl::new(color.hue, color.saturation, color.lightness + step);
+    }
+}
+
+/// A color that covers part of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swatch {
+    pub color: Color,
+    /// How many of the sampled pixels the swatch stands for.
+    pub population: u32,
+}
+
+/// The colors of an image, the most common first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub swatches: Vec<Swatch>,
+}
+impl Palette {
+    /// Reads the image at `path` and extracts its palette.
+    pub fn load(path: &Path) -> Result<Self, PaletteError> {
+        let image = image::open(path).context(ImageSnafu {
+            path: path.display().to_string(),
+        })?;
+        Self::from_image(&image)
+    }
+
+    pub fn from_image(image: &DynamicImage) -> Result<Self, PaletteError> {
+        Self::from_rgba(&image.to_rgba8())
+    }
+
+    pub fn from_rgba(image: &RgbaImage) -> Result<Self, PaletteError> {
+        // Sample every n-th pixel in both directions instead of resizing, which is faster and just as good for this.
+        let stride = ((image.width() as u64 * image.height() as u64) as f64 / SAMPLE_PIXELS as f64)
+            .sqrt()
+            .ceil()
+            .max(1.0) as usize;
+        let pixels = image
+            .rows()
+            .step_by(stride)
+            .flat_map(|row| row.step_by(stride))
+            .filter(|pixel| pixel[3] >= MIN_ALPHA)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect::<Vec<_>>();
+        if pixels.is_empty() {
+            return EmptySnafu.fail();
+        }
+
+        let mut swatches = median_cut(pixels, SWATCH_COUNT)
+            .into_iter()
+            .map(|pixels| {
+                let sum = pixels.iter().fold([0u64; 3], |sum, pixel| {
+                    [0, 1, 2].map(|channel| sum[channel] + pixel[channel] as u64)
+                });
+                let [r, g, b] = sum.map(|sum| (sum / pixels.len() as u64) as u8);
+                Swatch {
+                    color: Color::rgb(r, g, b),
+                    population: pixels.len() as u32,
+                }
+            })
+            .collect::<Vec<_>>();
+        swatches.sort_by_key(|swatch| (std::cmp::Reverse(swatch.population), swatch.color));
+        Ok(Self { swatches })
+    }
+
+    fn population(&self) -> u32 {
+        self.swatches.iter().map(|swatch| swatch.population).sum()
+    }
+
+    /// The variant that suits the image: light for bright images and dark for everything else.
+    pub fn variant(&self) -> ThemeVariant {
+        let luminance = self
+            .swatches
+            .iter()
+            .map(|swatch| swatch.color.luminance() * swatch.population as f64)
+            .sum::<f64>()
+            / self.population().max(1) as f64;
+        if luminance > LIGHT_LUMINANCE {
+            ThemeVariant::Light
+        } else {
+            ThemeVariant::Dark
+        }
+    }
+
+    /// The hue and saturation that tint the backgrounds, surfaces and text, taken from the most common swatch that
+    /// isn't gray.
+    fn tint(&self) -> (f64, f64) {
+        self.swatches
+            .iter()
+            .map(|swatch| swatch.color.to_hsl())
+            .find(|color| color.saturation >= 0.1)
+            .map(|color| (color.hue, color.saturation.min(0.3)))
+            .unwrap_or((0.0, 0.0))
+    }
+
+    /// The swatch closest to `hue` that is colorful enough to be an accent, weighted by how much of the image it covers.
+    fn accent_near(&self, hue: f64) -> Option<Hsl> {
+        self.swatches
+            .iter()
+            .map(|swatch| (swatch.color.to_hsl(), swatch.population))
+            .filter(|(color, _)| {
+                color.saturation >= ACCENT_MIN_SATURATION
+                    && hue_distance(color.hue, hue) <= ACCENT_HUE_RANGE
+            })
+            .max_by(|(a, a_population), (b, b_population)| {
+                let score = |color: &Hsl, population: u32| {
+                    population as f64 * (1.0 - hue_distance(color.hue, hue) / 180.0)
+                };
+                score(a, *a_population).total_cmp(&score(b, *b_population))
+            })
+            .map(|(color, _)| color)
+    }
+
+    /// Generates a theme from the palette, with `variant` or the one that suits the image.
+    pub fn theme(&self, name: impl Into<String>, variant: Option<ThemeVariant>) -> Theme {
+        let variant = variant.unwrap_or_else(|| self.variant());
+        let (hue, saturation) = self.tint();
+        let neutral = |lightness: f64| Hsl::new(hue, saturation, lightness);
+
+        // Lightness of each neutral, from the lowest background layer up to the text.
+        let (backgrounds, surfaces, overlays, subtexts, text) = match variant {
+            ThemeVariant::Dark => (
+                [0.14, 0.11, 0.08],
+                [0.21, 0.27, 0.33],
+                [0.43, 0.51, 0.59],
+                [0.70, 0.78],
+                0.88,
+            ),
+            ThemeVariant::Light => (
+                [0.95, 0.91, 0.87],
+                [0.84, 0.78, 0.72],
+                [0.64, 0.58, 0.52],
+                [0.40, 0.33],
+                0.25,
+            ),
+        };
+        let [bg_0, bg_1, bg_2] = backgrounds.map(|lightness| neutral(lightness).to_color());
+        let [surface_0, surface_1, surface_2] =
+            surfaces.map(|lightness| neutral(lightness).to_color());
+        let [overlay_0, overlay_1, overlay_2] =
+            overlays.map(|lightness| neutral(lightness).to_color());
+        let [subtext_0, subtext_1] = subtexts
+            .map(|lightness| ensure_contrast(neutral(lightness), bg_0, SUBTEXT_CONTRAST, variant));
+        let text = ensure_contrast(neutral(text), bg_0, TEXT_CONTRAST, variant);
+
+        let accent = |target: f64| {
+            let lightness = if variant.is_dark() { 0.75 } else { 0.45 };
+            let color = match self.accent_near(target) {
+                // Only go halfway to the hue of the swatch, so neighbouring accents like red and pink stay apart.
+                Some(color) => Hsl::new(
+                    target + signed_hue_distance(target, color.hue) / 2.0,
+                    color.saturation.clamp(0.5, 0.9),
+                    lightness,
+                ),
+                None => Hsl::new(target, 0.7, lightness),
+            };
+            ensure_contrast(color, bg_0, ACCENT_CONTRAST, variant)
+        };
+
+        Theme {
+            colors: ThemeColors {
+                pink: accent(330.0),
+                orange: accent(25.0),
+                red: accent(355.0),
+                yellow: accent(45.0),
+                green: accent(115.0),
+                blue: accent(215.0),
+                purple: accent(265.0),
+                text,
+                subtext_1,
+                subtext_0,
+                overlay_2,
+                overlay_1,
+                overlay_0,
+                surface_2,
+                surface_1,
+                surface_0,
+                bg_0,
+                bg_1,
+                bg_2,
+            },
+            name: name.into(),
+            variant,
+            gtk_theme: None,
+        }
+    }
+}
+
+/// Splits `pixels` into up to `count` boxes, each time halving the box with the widest range of one channel at its
+/// median.
+fn median_cut(pixels: Vec<[u8; 3]>, count: usize) -> Vec<Vec<[u8; 3]>> {
+    let range = |pixels: &[[u8; 3]]| {
+        (0..3)
+            .map(|channel| {
+                let values = pixels.iter().map(|pixel| pixel[channel]);
+                let max = values.clone().max().unwrap_or(0);
+                let min = values.min().unwrap_or(0);
+                (max - min, channel)
+            })
+            .max()
+            .unwrap_or((0, 0))
+    };
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let Some((index, (_, channel))) = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, pixels)| (index, range(pixels)))
+            .filter(|(_, (range, _))| *range > 0)
+            .max_by_key(|(index, (range, _))| (*range, std::cmp::Reverse(*index)))
+        else {
+            break;
+        };
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|pixel| (pixel[channel], *pixel));
+        let upper = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+    boxes
+}
+
+#[cfg(test)]
+mod tests {
+    //! Checks the palette extraction against sample images drawn here, so the results don't depend on any files.
+
+    use image::{Rgba, RgbaImage};
+
+    use super::{Palette, PaletteError};
+    use crate::ThemeConfig;
+    use crate::theme::{Color, Theme, ThemeVariant};
+
+    /// A sunset: an orange and purple sky above dark blue water.
+    fn sunset() -> RgbaImage {
+        RgbaImage::from_fn(320, 200, |x, y| {
+            let t = y as f64 / 200.0;
+            if y < 120 {
+                let r = (250.0 - 120.0 * t) as u8;
+                let g = (140.0 - 100.0 * t) as u8;
+                let b = (60.0 + 120.0 * t + (x % 7) as f64) as u8;
+                Rgba([r, g, b, 255])
+            } else {
+                Rgba([15, 25, (60 + x % 11) as u8, 255])
+            }
+        })
+    }
+
+    /// A snowy field under a pale blue sky, with a few dark green trees.
+    fn snow() -> RgbaImage {
+        RgbaImage::from_fn(300, 300, |x, y| {
+            if y < 100 {
+                Rgba([190, 215, 245, 255])
+            } else if x % 60 < 8 && y < 180 {
+                Rgba([30, 80, 40, 255])
+            } else {
+                Rgba([240, 242, 248, 255])
+            }
+        })
+    }
+
+    /// A gray gradient with no color at all.
+    fn gray() -> RgbaImage {
+        RgbaImage::from_fn(256, 16, |x, _| {
+            Rgba([x as u8 / 3, x as u8 / 3, x as u8 / 3, 255])
+        })
+    }
+
+    fn assert_readable(theme: &Theme) {
+        let colors = &theme.colors;
+        let bg_0 = colors.bg_0;
+        let checks = [
+            ("text", &colors.text, 7.0),
+            ("subtext_1", &colors.subtext_1, 4.5),
+            ("subtext_0", &colors.subtext_0, 4.5),
+            ("pink", &colors.pink, 4.5),
+            ("orange", &colors.orange, 4.5),
+            ("red", &colors.red, 4.5),
+            ("yellow", &colors.yellow, 4.5),
+            ("green", &colors.green, 4.5),
+            ("blue", &colors.blue, 4.5),
+            ("purple", &colors.purple, 4.5),
+        ];
+        for (name, color, contrast) in checks {
+            let ratio = color.contrast_ratio(&bg_0);
+            assert!(
+                ratio >= contrast,
+                "{name} {color} on {} has a contrast of {ratio:.2} in {}",
+                colors.bg_0,
+                theme.name
+            );
+        }
+    }
+
+    #[test]
+    fn sample_themes() {
+        let samples = [
+            ("Sunset", sunset(), ThemeVariant::Dark),
+            ("Snow", snow(), ThemeVariant::Light),
+            ("Gray", gray(), ThemeVariant::Dark),
+        ];
+        for (name, image, variant) in samples {
+            let palette = Palette::from_rgba(&image).unwrap();
+            assert_eq!(palette, Palette::from_rgba(&image).unwrap());
+            assert!(palette.swatches.len() <= 16);
+            assert_eq!(palette.variant(), variant, "{name}");
+
+            let theme = palette.theme(name, None);
+            assert_eq!(theme, palette.theme(name, None));
+            assert_eq!(theme.variant, variant);
+            assert_readable(&theme);
+
+            let other = match variant {
+                ThemeVariant::Dark => ThemeVariant::Light,
+                ThemeVariant::Light => ThemeVariant::Dark,
+            };
+            let forced = palette.theme(name, Some(other));
+            assert_eq!(forced.variant, other);
+            assert_readable(&forced);
+        }
+    }
+
+    #[test]
+    fn sample_colors() {
+        let sunset = Palette::from_rgba(&sunset()).unwrap().theme("Sunset", None);
+        assert_eq!(sunset.colors.bg_0, Color::hex(0x191e2e));
+        assert_eq!(sunset.colors.blue, Color::hex(0x98b1e6));
+        let gray = Palette::from_rgba(&gray()).unwrap().theme("Gray", None);
+        assert_eq!(gray.colors.bg_0, Color::hex(0x242424));
+    }
+
+    #[test]
+    fn transparent_image() {
+        let transparent = RgbaImage::from_pixel(8, 8, Rgba([255, 0, 0, 0]));
+        assert!(matches!(
+            Palette::from_rgba(&transparent),
+            Err(PaletteError::Empty)
+        ));
+    }
+
+    /// Saving a theme with the name of a custom theme replaces it.
+    #[test]
+    fn save_generated_theme() {
+        let sunset = Palette::from_rgba(&sunset()).unwrap().theme("Sunset", None);
+        let mut config = ThemeConfig::default();
+        config.save_custom_theme(sunset);
+        config.save_custom_theme(Palette::from_rgba(&snow()).unwrap().theme("Sunset", None));
+        assert_eq!(config.custom_themes.len(), 1);
+        assert!(config.custom_themes[0].is_light());
+    }
+}
@@ -0,0 +1,339 @@
+//! Switches between a light and a dark theme automatically, on a schedule, at sunrise and sunset, or when the
+//! `org.freedesktop.appearance` color scheme changes.
+//!
+//! This only works out which variant should be used. `ballad-services` checks it against the clock and switches the
+//! theme.
+
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use super::{ThemeSelection, ThemeVariant};
+use crate::TimeOfDay;
+
+const MINUTES_PER_DAY: f64 = 24.0 * 60.0;
+/// The sun is up while its center is above this angle from the zenith, in degrees. It is a bit more than 90 for the
+/// radius of the sun and the refraction of the atmosphere.
+const SUNRISE_ZENITH: f64 = 90.833;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Enum, glib::Variant))]
+#[cfg_attr(feature = "gtk", enum_type(name = "BalladConfigThemeSwitchMode"))]
+pub enum ThemeSwitchMode {
+    /// The theme only changes when it is picked.
+    #[default]
+    Manual,
+    /// Switch at [`ThemeSwitchConfig::light_at`] and [`ThemeSwitchConfig::dark_at`].
+    Schedule,
+    /// Switch at sunrise and sunset at [`ThemeSwitchConfig::latitude`] and [`ThemeSwitchConfig::longitude`].
+    Sun,
+    /// Follow the `color-scheme` of `org.freedesktop.appearance`, as the settings portal reports it.
+    Portal,
+}
+
+/// When the sun rises and sets on a day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SunTimes {
+    /// In minutes after midnight UTC. Sunset can be past the end of the day, or sunrise before its start, far from
+    /// Greenwich.
+    Rises { sunrise: f64, sunset: f64 },
+    /// The midnight sun.
+    AlwaysUp,
+    /// The polar night.
+    AlwaysDown,
+}
+
+/// Sunrise and sunset on `day_of_year`, 1 for the 1st of January, at `latitude` and `longitude` in degrees, with north
+/// and east positive. Uses NOAA's approximation, which is within a few minutes away from the poles.
+pub fn sun_times(day_of_year: u32, latitude: f64, longitude: f64) -> SunTimes {
+    let year_angle = 2.0 * std::f64::consts::PI / 365.0 * (day_of_year as f64 - 1.0);
+    let (sin_1, cos_1) = year_angle.sin_cos();
+    let (sin_2, cos_2) = (2.0 * year_angle).sin_cos();
+    let (sin_3, cos_3) = (3.0 * year_angle).sin_cos();
+    // How far solar noon is from noon at the meridian, in minutes.
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * cos_1 - 0.032077 * sin_1 - 0.014615 * cos_2 - 0.040849 * sin_2);
+    let declination = 0.006918 - 0.399912 * cos_1 + 0.070257 * sin_1 - 0.006758 * cos_2
+        + 0.000907 * sin_2
+        - 0.002697 * cos_3
+        + 0.00148 * sin_3;
+
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = SUNRISE_ZENITH.to_radians().cos() / (latitude.cos() * declination.cos())
+        - latitude.tan() * declination.tan();
+    if cos_hour_angle > 1.0 {
+        return SunTimes::AlwaysDown;
+    }
+    if cos_hour_angle < -1.0 {
+        return SunTimes::AlwaysUp;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    SunTimes::Rises {
+        sunrise: 720.0 - 4.0 * (longitude + hour_angle) - equation_of_time,
+        sunset: 720.0 - 4.0 * (longitude - hour_angle) - equation_of_time,
+    }
+}
+
+/// Whether `minute` is in the light part of a day that turns light at `light_at` and dark at `dark_at`, all in minutes
+/// after midnight.
+fn variant_between(light_at: f64, dark_at: f64, minute: f64) -> ThemeVariant {
+    let [light_at, dark_at, minute] =
+        [light_at, dark_at, minute].map(|m| m.rem_euclid(MINUTES_PER_DAY));
+    let light = if light_at <= dark_at {
+        light_at <= minute && minute < dark_at
+    } else {
+        minute >= light_at || minute < dark_at
+    };
+    if light {
+        ThemeVariant::Light
+    } else {
+        ThemeVariant::Dark
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Variant))]
+#[serde(default)]
+pub struct ThemeSwitchConfig {
+    pub mode: ThemeSwitchMode,
+    /// The theme switched to when it gets light. The quick settings keep it up to date with the light theme picked
+    /// there.
+    pub light_theme: ThemeSelection,
+    pub dark_theme: ThemeSelection,
+    /// When the light theme starts with [`ThemeSwitchMode::Schedule`].
+    pub light_at: TimeOfDay,
+    /// When the dark theme starts with [`ThemeSwitchMode::Schedule`].
+    pub dark_at: TimeOfDay,
+    /// Where sunrise and sunset are taken for [`ThemeSwitchMode::Sun`], in degrees. North and east are positive.
+    pub latitude: f64,
+    pub longitude: f64,
+}
+impl Default for ThemeSwitchConfig {
+    fn default() -> Self {
+        Self {
+            mode: ThemeSwitchMode::Manual,
+            light_theme: ThemeSelection::CatppuccinLatte,
+            dark_theme: ThemeSelection::default(),
+            light_at: TimeOfDay::new(7, 0),
+            dark_at: TimeOfDay::new(19, 0),
+            latitude: 0.0,
+            longitude: 0.0,
+        }
+    }
+}
+impl ThemeSwitchConfig {
+    pub fn theme(&self, variant: ThemeVariant) -> &ThemeSelection {
+        match variant {
+            ThemeVariant::Light => &self.light_theme,
+            ThemeVariant::Dark => &self.dark_theme,
+        }
+    }
+
+    /// The variant the schedule or the sun asks for at `minute` minutes after local midnight on `day_of_year`, in a
+    /// time zone `utc_offset` minutes ahead of UTC. `None` if the theme isn't switched by the clock.
+    pub fn scheduled_variant(
+        &self,
+        day_of_year: u32,
+        utc_offset: i32,
+        minute: f64,
+    ) -> Option<ThemeVariant> {
+        match self.mode {
+            ThemeSwitchMode::Manual | ThemeSwitchMode::Portal => None,
+            ThemeSwitchMode::Schedule => Some(variant_between(
+                self.light_at.minutes_since_midnight() as f64,
+                self.dark_at.minutes_since_midnight() as f64,
+                minute,
+            )),
+            ThemeSwitchMode::Sun => Some(
+                match sun_times(day_of_year, self.latitude, self.longitude) {
+                    SunTimes::Rises { sunrise, sunset } => variant_between(
+                        sunrise + utc_offset as f64,
+                        sunset + utc_offset as f64,
+                        minute,
+                    ),
+                    SunTimes::AlwaysUp => ThemeVariant::Light,
+                    SunTimes::AlwaysDown => ThemeVariant::Dark,
+                },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShellConfig;
+
+    /// Sunrise and sunset are allowed to be this many minutes from the published times.
+    const TOLERANCE: f64 = 5.0;
+
+    fn format_minutes(minutes: f64) -> String {
+        let minutes = minutes.rem_euclid(24.0 * 60.0).round() as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    fn assert_rises(times: SunTimes, expected_sunrise: f64, expected_sunset: f64) {
+        let SunTimes::Rises { sunrise, sunset } = times else {
+            panic!("Expected the sun to rise, got {times:?}");
+        };
+        assert!(
+            (sunrise - expected_sunrise).abs() <= TOLERANCE,
+            "sunrise at {}",
+            format_minutes(sunrise)
+        );
+        assert!(
+            (sunset - expected_sunset).abs() <= TOLERANCE,
+            "sunset at {}",
+            format_minutes(sunset)
+        );
+    }
+
+    #[test]
+    fn sunrise_and_sunset() {
+        // London at the summer solstice: 03:43 to 20:21 UTC.
+        assert_rises(
+            sun_times(172, 51.51, -0.13),
+            3.0 * 60.0 + 43.0,
+            20.0 * 60.0 + 21.0,
+        );
+        // New York on new year's day: 12:20 to 21:39 UTC.
+        assert_rises(
+            sun_times(1, 40.71, -74.01),
+            12.0 * 60.0 + 20.0,
+            21.0 * 60.0 + 39.0,
+        );
+        // Tromsø has the polar night in December and the midnight sun in June.
+        assert_eq!(sun_times(355, 69.65, 18.96), SunTimes::AlwaysDown);
+        assert_eq!(sun_times(172, 69.65, 18.96), SunTimes::AlwaysUp);
+    }
+
+    #[test]
+    fn light_between() {
+        assert_eq!(
+            variant_between(7.0 * 60.0, 19.0 * 60.0, 12.0 * 60.0),
+            ThemeVariant::Light
+        );
+        assert_eq!(
+            variant_between(7.0 * 60.0, 19.0 * 60.0, 20.0 * 60.0),
+            ThemeVariant::Dark
+        );
+        // Sunset after midnight UTC, like in the Americas.
+        assert_eq!(
+            variant_between(12.0 * 60.0, 25.0 * 60.0, 30.0),
+            ThemeVariant::Light
+        );
+        assert_eq!(
+            variant_between(12.0 * 60.0, 25.0 * 60.0, 2.0 * 60.0),
+            ThemeVariant::Dark
+        );
+    }
+
+    #[test]
+    fn schedule() {
+        let manual = ThemeSwitchConfig::default();
+        assert_eq!(manual.scheduled_variant(1, 0, 12.0 * 60.0), None);
+        let portal = ThemeSwitchConfig {
+            mode: ThemeSwitchMode::Portal,
+            ..Default::default()
+        };
+        assert_eq!(portal.scheduled_variant(1, 0, 12.0 * 60.0), None);
+
+        let schedule = ThemeSwitchConfig {
+            mode: ThemeSwitchMode::Schedule,
+            ..Default::default()
+        };
+        for (minute, variant) in [
+            (0.0, ThemeVariant::Dark),
+            (6.0 * 60.0 + 59.0, ThemeVariant::Dark),
+            (7.0 * 60.0, ThemeVariant::Light),
+            (18.0 * 60.0 + 59.0, ThemeVariant::Light),
+            (19.0 * 60.0, ThemeVariant::Dark),
+        ] {
+            assert_eq!(
+                schedule.scheduled_variant(1, 0, minute),
+                Some(variant),
+                "{minute}"
+            );
+        }
+        // A light part of the day that goes past midnight, like for night shifts.
+        let night_shift = ThemeSwitchConfig {
+            light_at: TimeOfDay::new(22, 0),
+            dark_at: TimeOfDay::new(6, 0),
+            ..schedule.clone()
+        };
+        for (minute, variant) in [
+            (23.0 * 60.0, ThemeVariant::Light),
+            (3.0 * 60.0, ThemeVariant::Light),
+            (12.0 * 60.0, ThemeVariant::Dark),
+        ] {
+            assert_eq!(
+                night_shift.scheduled_variant(1, 0, minute),
+                Some(variant),
+                "{minute}"
+            );
+        }
+    }
+
+    #[test]
+    fn sun() {
+        // London in British Summer Time, an hour ahead of UTC.
+        let london = ThemeSwitchConfig {
+            mode: ThemeSwitchMode::Sun,
+            latitude: 51.51,
+            longitude: -0.13,
+            ..Default::default()
+        };
+        assert_eq!(
+            london.scheduled_variant(172, 60, 4.0 * 60.0),
+            Some(ThemeVariant::Dark)
+        );
+        assert_eq!(
+            london.scheduled_variant(172, 60, 5.0 * 60.0),
+            Some(ThemeVariant::Light)
+        );
+        assert_eq!(
+            london.scheduled_variant(172, 60, 21.0 * 60.0),
+            Some(ThemeVariant::Light)
+        );
+        assert_eq!(
+            london.scheduled_variant(172, 60, 22.0 * 60.0),
+            Some(ThemeVariant::Dark)
+        );
+        let tromso = ThemeSwitchConfig {
+            latitude: 69.65,
+            longitude: 18.96,
+            ..london.clone()
+        };
+        assert_eq!(
+            tromso.scheduled_variant(355, 60, 12.0 * 60.0),
+            Some(ThemeVariant::Dark)
+        );
+        assert_eq!(
+            tromso.scheduled_variant(172, 120, 0.0),
+            Some(ThemeVariant::Light)
+        );
+    }
+
+    #[test]
+    fn config() {
+        let london = ThemeSwitchConfig {
+            mode: ThemeSwitchMode::Sun,
+            latitude: 51.51,
+            longitude: -0.13,
+            ..Default::default()
+        };
+        let mut config = ShellConfig::default();
+        config.theme.auto_switch = ThemeSwitchConfig {
+            dark_theme: ThemeSelection::CatppuccinMocha,
+            light_at: TimeOfDay::new(6, 45),
+            ..london
+        };
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<ShellConfig>(&toml).unwrap(), config);
+        // Configs from before automatic switching keep switching by hand.
+        let mut old = toml::Table::try_from(&config).unwrap();
+        old["theme"].as_table_mut().unwrap().remove("auto_switch");
+        let old = old.try_into::<ShellConfig>().unwrap();
+        assert_eq!(old.theme.auto_switch, ThemeSwitchConfig::default());
+    }
+}
@@ -0,0 +1,241 @@
+//! Renders the theme into config files for other programs, so GTK, Qt, terminals and niri match the shell.
+//!
+//! Templates are plain text with `{{ variable }}` placeholders. The variables are the color names of
+//! [`ThemeColors`](super::ThemeColors) like `bg_0` or `blue`, which render as `#rrggbb`, plus `name` and `variant`.
+//! A color can be followed by `.strip` for `rrggbb` without the `#`, or `.rgb` for `r, g, b`.
+
+use std::path::PathBuf;
+
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use super::Theme;
+
+/// The templates that come with Ballad, by name.
+pub const BUILTIN_TEMPLATES: [(&str, &str); 7] = [
+    ("gtk4", include_str!("../../templates/gtk4.css")),
+    ("qt-colors", include_str!("../../templates/qt-colors.conf")),
+    ("kvantum", include_str!("../../templates/kvantum.kvconfig")),
+    ("foot", include_str!("../../templates/foot.ini")),
+    ("alacritty", include_str!("../../templates/alacritty.toml")),
+    ("kitty", include_str!("../../templates/kitty.conf")),
+    ("niri", include_str!("../../templates/niri.kdl")),
+];
+
+#[derive(Debug, Snafu)]
+pub enum TemplateError {
+    #[snafu(display("There is no template called {name}"))]
+    UnknownTemplate { name: String },
+    #[snafu(display("Failed to access {path}: {source}"))]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unknown variable {variable} in the {template} template"))]
+    UnknownVariable { template: String, variable: String },
+    #[snafu(display("A {{{{ isn't closed in the {template} template"))]
+    Unclosed { template: String },
+}
+
+/// A file rendered from a template whenever the theme changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Variant))]
+pub struct ThemeOutput {
+    /// A file in `$XDG_CONFIG_HOME/ballad/templates`, or one of the [`BUILTIN_TEMPLATES`]. A file with the name of a
+    /// built-in template replaces it.
+    pub template: String,
+    /// Where the rendered file is written. A leading `~` is the home directory.
+    pub path: String,
+    /// A shell command run after the file is written, so the program picks up the new colors.
+    pub reload: Option<String>,
+}
+impl ThemeOutput {
+    pub fn output_path(&self) -> PathBuf {
+        match (self.path.strip_prefix("~/"), std::env::var_os("HOME")) {
+            (Some(path), Some(home)) => PathBuf::from(home).join(path),
+            _ => PathBuf::from(&self.path),
+        }
+    }
+
+    pub fn render(&self, theme: &Theme) -> Result<String, TemplateError> {
+        render(&self.template, &load_template(&self.template)?, theme)
+    }
+
+    /// Renders the template and writes it to [`Self::output_path`], unless the file already has the same content.
+    /// Returns whether the file changed.
+    pub fn write(&self, theme: &Theme) -> Result<bool, TemplateError> {
+        let content = self.render(theme)?;
+        let path = self.output_path();
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == content) {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(IoSnafu {
+                path: parent.display().to_string(),
+            })?;
+        }
+        std::fs::write(&path, content).context(IoSnafu {
+            path: path.display().to_string(),
+        })?;
+        Ok(true)
+    }
+}
+
+/// Where user templates are kept: `$XDG_CONFIG_HOME/ballad/templates`.
+pub fn templates_dir() -> PathBuf {
+    xdg::BaseDirectories::with_prefix("ballad")
+        .unwrap()
+        .get_config_home()
+        .join("templates")
+}
+
+/// The template called `name`, from the templates folder or else the built-in ones.
+pub fn load_template(name: &str) -> Result<String, TemplateError> {
+    let path = templates_dir().join(name);
+    if path.is_file() {
+        return std::fs::read_to_string(&path).context(IoSnafu {
+            path: path.display().to_string(),
+        });
+    }
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, template)| template.to_string())
+        .context(UnknownTemplateSnafu { name })
+}
+
+fn variable(name: &str, variable: &str, theme: &Theme) -> Result<String, TemplateError> {
+    match variable {
+        "name" => return Ok(theme.name.clone()),
+        "variant" => return Ok(if theme.is_light() { "light" } else { "dark" }.to_string()),
+        _ => {}
+    }
+
+    let (color_name, format) = variable.split_once('.').unwrap_or((variable, ""));
+    let color = theme
+        .colors
+        .named()
+        .into_iter()
+        .find(|(name, _)| *name == color_name)
+        .map(|(_, color)| *color)
+        .context(UnknownVariableSnafu {
+            template: name,
+            variable,
+        })?;
+    match format {
+        "" => Ok(color.to_string()),
+        "strip" => Ok(color.to_hex_rgb()),
+        "rgb" => Ok(format!("{}, {}, {}", color.r, color.g, color.b)),
+        _ => UnknownVariableSnafu {
+            template: name,
+            variable,
+        }
+        .fail(),
+    }
+}
+
+/// Replaces the `{{ variable }}` placeholders in `template` with the colors of `theme`. `name` is only used in errors.
+pub fn render(name: &str, template: &str, theme: &Theme) -> Result<String, TemplateError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("}}")
+            .context(UnclosedSnafu { template: name })?;
+        rendered.push_str(&variable(name, rest[start + 2..start + end].trim(), theme)?);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ThemeSelection,
+        theme::{Color, ThemeColors},
+    };
+
+    fn mocha() -> Theme {
+        ThemeSelection::CatppuccinMocha.theme().unwrap()
+    }
+
+    /// Every built-in template renders with every built-in theme.
+    #[test]
+    fn builtin_templates() {
+        let themes = [
+            ThemeSelection::CatppuccinFrappe,
+            ThemeSelection::CatppuccinMacchiato,
+            ThemeSelection::CatppuccinMocha,
+            ThemeSelection::CatppuccinLatte,
+        ]
+        .map(|selection| selection.theme().unwrap());
+
+        for theme in &themes {
+            for (name, template) in BUILTIN_TEMPLATES {
+                let rendered = render(name, template, theme).unwrap();
+                assert!(!rendered.contains("{{"), "{name} has a placeholder left");
+                assert!(
+                    rendered.contains(&theme.name),
+                    "{name} doesn't name the theme"
+                );
+                assert!(
+                    rendered.contains(&theme.colors.blue.to_hex_rgb()),
+                    "{name} doesn't use blue"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn variables() {
+        let mocha = &mocha();
+
+        assert_eq!(
+            render(
+                "test",
+                "{{ bg_0 }} {{bg_0.strip}} {{bg_0.rgb}} {{variant}}",
+                mocha
+            )
+            .unwrap(),
+            "#1e1e2e 1e1e2e 30, 30, 46 dark"
+        );
+    }
+
+    #[test]
+    fn invalid_placeholders() {
+        let mocha = &mocha();
+        assert!(matches!(
+            render("test", "{{ bg_3 }}", mocha),
+            Err(TemplateError::UnknownVariable { .. })
+        ));
+        assert!(matches!(
+            render("test", "{{ bg_0.hsl }}", mocha),
+            Err(TemplateError::UnknownVariable { .. })
+        ));
+        assert!(matches!(
+            render("test", "{{ bg_0", mocha),
+            Err(TemplateError::Unclosed { .. })
+        ));
+    }
+
+    #[test]
+    fn translucent_colors() {
+        let mocha = &mocha();
+        let translucent = Theme {
+            colors: ThemeColors {
+                bg_0: Color::rgba(30, 30, 46, 128),
+                ..mocha.colors.clone()
+            },
+            ..mocha.clone()
+        };
+        assert_eq!(
+            render("test", "{{bg_0}} {{bg_0.strip}}", &translucent).unwrap(),
+            "#1e1e2e80 1e1e2e"
+        );
+    }
+}
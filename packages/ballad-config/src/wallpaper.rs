@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "gtk")]
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+/// How the image is fitted to the monitor.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Enum, glib::Variant))]
+#[cfg_attr(feature = "gtk", enum_type(name = "BalladConfigWallpaperMode"))]
+pub enum WallpaperMode {
+    /// Cover the monitor, cropping the image.
+    #[default]
+    Fill,
+    /// Show the whole image, leaving bars on the sides.
+    Fit,
+    /// Stretch the image to the size of the monitor.
+    Stretch,
+    /// Show the image at its own size in the middle.
+    Center,
+    /// Repeat the image at its own size.
+    Tile,
+}
+impl WallpaperMode {
+    pub const ALL: [WallpaperMode; 5] = [
+        WallpaperMode::Fill,
+        WallpaperMode::Fit,
+        WallpaperMode::Stretch,
+        WallpaperMode::Center,
+        WallpaperMode::Tile,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fill => "Fill",
+            Self::Fit => "Fit",
+            Self::Stretch => "Stretch",
+            Self::Center => "Center",
+            Self::Tile => "Tile",
+        }
+    }
+}
+
+/// How the wallpaper changes to the next image.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Enum, glib::Variant))]
+#[cfg_attr(feature = "gtk", enum_type(name = "BalladConfigWallpaperTransition"))]
+pub enum WallpaperTransition {
+    None,
+    #[default]
+    Fade,
+}
+
+/// Overrides the wallpaper on one monitor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Variant))]
+pub struct MonitorWallpaper {
+    /// The connector name of the monitor, like `DP-1` or `eDP-1`.
+    pub connector: String,
+    pub path: Option<PathBuf>,
+    pub mode: Option<WallpaperMode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gtk", derive(glib::Boxed, glib::Variant))]
+#[cfg_attr(feature = "gtk", boxed_type(name = "BalladConfigWallpaperConfig"))]
+#[serde(default)]
+pub struct WallpaperConfig {
+    /// Draw the wallpaper. Turn this off to use another program like swaybg.
+    pub enabled: bool,
+    /// An image, or a folder of images to cycle through.
+    pub path: Option<PathBuf>,
+    pub mode: WallpaperMode,
+    /// How long each image of a folder is shown, in seconds.
+    pub slideshow_interval: u32,
+    /// Show the images of a folder in a random order.
+    pub shuffle: bool,
+    pub transition: WallpaperTransition,
+    /// How long the transition takes, in seconds.
+    pub transition_duration: f64,
+    /// A CSS color shown where the image doesn't cover the monitor.
+    pub background_color: String,
+    pub monitors: Vec<MonitorWallpaper>,
+}
+impl WallpaperConfig {
+    /// The path and mode used on the monitor with `connector`.
+    pub fn for_monitor(&self, connector: &str) -> (Option<PathBuf>, WallpaperMode) {
+        let monitor = self
+            .monitors
+            .iter()
+            .find(|monitor| monitor.connector == connector);
+        (
+            monitor
+                .and_then(|monitor| monitor.path.clone())
+                .or_else(|| self.path.clone()),
+            monitor
+                .and_then(|monitor| monitor.mode)
+                .unwrap_or(self.mode),
+        )
+    }
+}
+impl Default for WallpaperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            mode: WallpaperMode::default(),
+            slideshow_interval: 10 * 60,
+            shuffle: false,
+            transition: WallpaperTransition::default(),
+            transition_duration: 1.0,
+            background_color: "black".to_string(),
+            monitors: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::{cell::Cell, path::Path};
+
+use ballad_config::{
+    GreeterConfig,
+    theme::{Color, Theme, ThemeColors},
+};
+use gtk::{
+    CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION, Settings, gdk::Display, gio, glib,
+    prelude::*, style_context_add_provider_for_display,
+};
+
+/// GTK's built-in high contrast themes.
+const HIGH_CONTRAST_THEME: &str = "HighContrast";
+const HIGH_CONTRAST_INVERSE_THEME: &str = "HighContrastInverse";
+/// How much large text scales the font DPI.
+const LARGE_TEXT_SCALE: f64 = 1.5;
+/// The DPI GTK uses when it isn't set, in 1024ths of a dot per inch.
+const DEFAULT_XFT_DPI: i32 = 96 * 1024;
+
+fn high_contrast_colors(dark: bool) -> ThemeColors {
+    let (fg, bg) = if dark {
+        (Color::hex(0xffffff), Color::hex(0x000000))
+    } else {
+        (Color::hex(0x000000), Color::hex(0xffffff))
+    };
+    let accent = Color::hex(if dark { 0xffff00 } else { 0x0000c0 });
+    let error = Color::hex(if dark { 0xff6060 } else { 0xc00000 });
+    ThemeColors {
+        pink: accent,
+        orange: accent,
+        red: error,
+        yellow: accent,
+        green: accent,
+        blue: accent,
+        purple: accent,
+        text: fg,
+        subtext_1: fg,
+        subtext_0: fg,
+        overlay_2: fg,
+        overlay_1: fg,
+        overlay_0: fg,
+        surface_2: bg,
+        surface_1: bg,
+        surface_0: bg,
+        bg_0: bg,
+        bg_1: bg,
+        bg_2: bg,
+    }
+}
+
+fn background_css(background: &Path) -> String {
+    let uri = gio::File::for_path(background).uri();
+    format!(
+        "window.greeter {{ background-image: url(\"{uri}\"); background-size: cover; background-position: center; }}\n"
+    )
+}
+
+/// The theme, background and accessibility settings of the greeter.
+pub struct Appearance {
+    provider: CssProvider,
+    theme: Theme,
+    background: Option<String>,
+    default_gtk_theme: Option<glib::GString>,
+    default_xft_dpi: i32,
+    high_contrast: Cell<bool>,
+}
+impl Appearance {
+    pub fn new(config: &GreeterConfig) -> Self {
+        let provider = CssProvider::new();
+        style_context_add_provider_for_display(
+            &Display::default().unwrap(),
+            &provider,
+            STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+
+        let settings = Settings::default().unwrap();
+        let theme = config.theme();
+        if let Some(gtk_theme) = &theme.gtk_theme {
+            settings.set_gtk_theme_name(Some(gtk_theme));
+        }
+        settings.set_gtk_application_prefer_dark_theme(theme.is_dark());
+
+        let this = Self {
+            provider,
+            background: config.background.as_deref().map(background_css),
+            default_gtk_theme: settings.gtk_theme_name(),
+            default_xft_dpi: Some(settings.gtk_xft_dpi())
+                .filter(|dpi| *dpi > 0)
+                .unwrap_or(DEFAULT_XFT_DPI),
+            high_contrast: Cell::new(config.high_contrast),
+            theme,
+        };
+        this.set_high_contrast(config.high_contrast);
+        this.set_large_text(config.large_text);
+        this
+    }
+
+    fn load_css(&self) {
+        let colors = if self.high_contrast.get() {
+            high_contrast_colors(self.theme.is_dark())
+        } else {
+            self.theme.colors.clone()
+        };
+        let mut css = colors.as_gtk_css();
+        css.push_str(include_str!("../style/style.css"));
+        // The background would make text harder to read.
+        if let Some(background) = self
+            .background
+            .as_ref()
+            .filter(|_| !self.high_contrast.get())
+        {
+            css.push_str(background);
+        }
+        self.provider.load_from_string(&css);
+    }
+
+    pub fn set_high_contrast(&self, high_contrast: bool) {
+        self.high_contrast.set(high_contrast);
+        let settings = Settings::default().unwrap();
+        if high_contrast {
+            settings.set_gtk_theme_name(Some(if self.theme.is_dark() {
+                HIGH_CONTRAST_INVERSE_THEME
+            } else {
+                HIGH_CONTRAST_THEME
+            }));
+        } else {
+            settings.set_gtk_theme_name(self.default_gtk_theme.as_deref());
+        }
+        self.load_css();
+    }
+
+    pub fn set_large_text(&self, large_text: bool) {
+        let dpi = if large_text {
+            (self.default_xft_dpi as f64 * LARGE_TEXT_SCALE) as i32
+        } else {
+            self.default_xft_dpi
+        };
+        Settings::default().unwrap().set_gtk_xft_dpi(dpi);
+    }
+}
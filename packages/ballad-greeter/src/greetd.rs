@@ -1,18 +1,17 @@
-use std::{io, net::Shutdown, path::Path};
-
-use smol::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::unix::UnixStream,
-};
+use greetd_ipc::{AuthMessageType, ErrorType, Response};
 use snafu::Snafu;
 
+use crate::ipc::{GreetdClient, IpcError};
+use crate::sessions::SessionCommand;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AuthFlowState {
     Uninitialized,
-    WaitForResponse,
     SendAuthResponse,
     SendEmptyResponse,
     Authenticated,
+    Started,
+    /// greetd refused a request and keeps the session around until it is cancelled.
     Failed,
 }
 
@@ -24,132 +23,149 @@ pub enum RequestedAction {
     ExitApplication,
 }
 
-#[derive(Debug, Clone)]
+/// Drives the greetd login flow for one user at a time.
+#[derive(Debug)]
 pub struct GreetdSession {
-    socket: UnixStream,
+    client: GreetdClient,
 
     pub failed_attempts: u32,
     state: AuthFlowState,
 }
 impl GreetdSession {
-    pub async fn new(socket_path: impl AsRef<Path>) -> io::Result<Self> {
-        let socket = UnixStream::connect(socket_path).await?;
-        Ok(Self {
-            socket,
+    pub fn with_client(client: GreetdClient) -> Self {
+        Self {
+            client,
             failed_attempts: 0,
             state: AuthFlowState::Uninitialized,
-        })
+        }
     }
 
-    async fn read_response_packet(&mut self) -> io::Result<greetd_ipc::Response> {
-        let mut packet_size = [0u8; 4];
-        self.socket.read_exact(&mut packet_size).await?;
-        let packet_size = u32::from_le_bytes(packet_size) as usize;
-
-        let mut packet = vec![0u8; packet_size];
-        self.socket.read_exact(&mut packet).await?;
-
-        let packet = serde_json::from_slice(&packet)
-            .map_err(|de| io::Error::new(io::ErrorKind::InvalidData, de));
-        dbg!(&packet);
-        packet
+    /// Cancels the session being authenticated, if any, so a different user can log in.
+    pub async fn cancel(&mut self) -> Result<(), GreeterError> {
+        if matches!(
+            self.state,
+            AuthFlowState::Uninitialized | AuthFlowState::Started
+        ) {
+            return Ok(());
+        }
+        self.state = AuthFlowState::Uninitialized;
+        match self.client.cancel_session().await? {
+            Response::Error { description, .. } => Err(GreeterError::GreetdError {
+                message: description,
+            }),
+            _ => Ok(()),
+        }
     }
-    async fn send_packet(&mut self, packet: &greetd_ipc::Request) -> io::Result<()> {
-        dbg!(packet);
-        let packet = serde_json::to_vec(packet)
-            .map_err(|se| io::Error::new(io::ErrorKind::InvalidData, se))?;
-        let packet_size = (packet.len() as u32).to_le_bytes();
 
-        self.socket.write_all(&packet_size).await?;
-        self.socket.write_all(&packet).await?;
-        Ok(())
+    fn handle_response(&mut self, response: Response) -> Result<RequestedAction, GreeterError> {
+        match response {
+            Response::AuthMessage {
+                auth_message_type,
+                auth_message,
+            } => match auth_message_type {
+                AuthMessageType::Visible | AuthMessageType::Secret => {
+                    self.state = AuthFlowState::SendAuthResponse;
+                    Ok(RequestedAction::SendDataFromPrompt {
+                        prompt: auth_message,
+                        visible: matches!(auth_message_type, AuthMessageType::Visible),
+                    })
+                }
+                AuthMessageType::Info | AuthMessageType::Error => {
+                    self.state = AuthFlowState::SendEmptyResponse;
+                    Ok(RequestedAction::DisplayMessage(auth_message))
+                }
+            },
+            Response::Error {
+                error_type: ErrorType::AuthError,
+                ..
+            } => {
+                self.failed_attempts += 1;
+                self.state = AuthFlowState::Failed;
+                Err(GreeterError::FailedToAuthenticate)
+            }
+            Response::Error {
+                error_type,
+                description,
+            } => {
+                self.state = AuthFlowState::Failed;
+                Err(GreeterError::GreetdError {
+                    message: format!("{:?}: {}", error_type, description),
+                })
+            }
+            Response::Success => {
+                self.state = AuthFlowState::Authenticated;
+                Ok(RequestedAction::None)
+            }
+        }
     }
 
+    /// Makes the next request of the login flow for `user`. `data` answers the last prompt and `command` is started
+    /// once the user is authenticated.
+    ///
+    /// A wrong answer returns [`GreeterError::FailedToAuthenticate`], and any other request greetd refuses, like
+    /// starting a session with a bad command, returns [`GreeterError::GreetdError`]. Both cancel the session, so the
+    /// next step starts over. [`GreeterError::SessionShutdown`] and [`GreeterError::Ipc`] mean greetd is gone and the
+    /// greeter should exit.
     pub async fn step_statemachine(
         &mut self,
         user: &str,
         data: Option<&str>,
-        command: &str,
+        command: &SessionCommand,
+    ) -> Result<RequestedAction, GreeterError> {
+        let result = self.step(user, data, command).await;
+        if self.state == AuthFlowState::Failed {
+            // The session is forgotten even if greetd refuses to cancel it, so the next step starts over. Losing greetd
+            // is worse than the error that got here though.
+            if let Err(err @ (GreeterError::SessionShutdown | GreeterError::Ipc { .. })) =
+                self.cancel().await
+            {
+                return Err(err);
+            }
+        }
+        result
+    }
+
+    async fn step(
+        &mut self,
+        user: &str,
+        data: Option<&str>,
+        command: &SessionCommand,
     ) -> Result<RequestedAction, GreeterError> {
         match self.state {
             AuthFlowState::Uninitialized => {
-                let packet = greetd_ipc::Request::CreateSession {
-                    username: user.to_string(),
-                };
-                self.send_packet(&packet).await?;
-                self.state = AuthFlowState::WaitForResponse;
-                Ok(RequestedAction::None)
-            }
-            AuthFlowState::WaitForResponse => {
-                let response = self.read_response_packet().await?;
-                match response {
-                    greetd_ipc::Response::AuthMessage {
-                        auth_message_type,
-                        auth_message,
-                    } => match auth_message_type {
-                        greetd_ipc::AuthMessageType::Visible => {
-                            self.state = AuthFlowState::SendAuthResponse;
-                            Ok(RequestedAction::SendDataFromPrompt {
-                                prompt: auth_message,
-                                visible: true,
-                            })
-                        }
-                        greetd_ipc::AuthMessageType::Secret => {
-                            self.state = AuthFlowState::SendAuthResponse;
-                            Ok(RequestedAction::SendDataFromPrompt {
-                                prompt: auth_message,
-                                visible: false,
-                            })
-                        }
-                        greetd_ipc::AuthMessageType::Info | greetd_ipc::AuthMessageType::Error => {
-                            self.state = AuthFlowState::SendEmptyResponse;
-                            Ok(RequestedAction::DisplayMessage(auth_message))
-                        }
-                    },
-                    greetd_ipc::Response::Error {
-                        error_type,
-                        description,
-                    } => {
-                        self.state = AuthFlowState::Failed;
-                        Err(GreeterError::GreetdError {
-                            message: format!("{:?}: {}", error_type, description),
-                        })
-                    }
-                    greetd_ipc::Response::Success => {
-                        self.state = AuthFlowState::Authenticated;
-                        Ok(RequestedAction::None)
-                    }
-                }
+                let response = self.client.create_session(user).await?;
+                self.handle_response(response)
             }
             AuthFlowState::SendEmptyResponse => {
-                let packet = greetd_ipc::Request::PostAuthMessageResponse { response: None };
-                self.send_packet(&packet).await?;
-                self.state = AuthFlowState::WaitForResponse;
-                Ok(RequestedAction::None)
+                let response = self.client.post_auth_message_response(None).await?;
+                self.handle_response(response)
             }
             AuthFlowState::SendAuthResponse => {
                 let response = data.ok_or(GreeterError::MissingData)?;
-                let packet = greetd_ipc::Request::PostAuthMessageResponse {
-                    response: Some(response.to_string()),
-                };
-                self.send_packet(&packet).await?;
-                self.state = AuthFlowState::WaitForResponse;
-                Ok(RequestedAction::None)
+                let response = self
+                    .client
+                    .post_auth_message_response(Some(response))
+                    .await?;
+                self.handle_response(response)
             }
-
             AuthFlowState::Authenticated => {
-                let packet = greetd_ipc::Request::StartSession {
-                    cmd: vec![command.to_string()],
-                    env: vec![],
-                };
-                self.send_packet(&packet).await?;
-                Ok(RequestedAction::ExitApplication)
+                let response = self
+                    .client
+                    .start_session(command.cmd.clone(), command.env.clone())
+                    .await?;
+                match response {
+                    Response::Success => {
+                        self.state = AuthFlowState::Started;
+                        Ok(RequestedAction::ExitApplication)
+                    }
+                    response => self.handle_response(response),
+                }
             }
+            AuthFlowState::Started => Ok(RequestedAction::ExitApplication),
+            // Only left here if the last step was dropped before it could cancel the session.
             AuthFlowState::Failed => {
-                let packet = greetd_ipc::Request::CancelSession;
-                _ = self.send_packet(&packet).await;
-                _ = self.socket.shutdown(Shutdown::Both);
-                Err(GreeterError::FailedToAuthenticate)
+                self.cancel().await?;
+                Ok(RequestedAction::None)
             }
         }
     }
@@ -157,14 +173,339 @@ impl GreetdSession {
 
 #[derive(Debug, Snafu)]
 pub enum GreeterError {
-    #[snafu(transparent)]
-    Io { source: io::Error },
+    /// Failed to talk to greetd
+    #[snafu(display("{source}"))]
+    Ipc { source: IpcError },
     /// Did not provide requested data
     MissingData,
     /// Non fatal authentication error
     FailedToAuthenticate,
-    /// Possible fatal error
+    /// greetd refused a request. The session is cancelled, so the next attempt starts over.
     GreetdError { message: String },
     /// Fatal error
+    #[snafu(display("The connection to greetd was closed"))]
     SessionShutdown,
 }
+impl From<IpcError> for GreeterError {
+    fn from(source: IpcError) -> Self {
+        match source {
+            IpcError::Closed | IpcError::Desynchronized => GreeterError::SessionShutdown,
+            source => GreeterError::Ipc { source },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Runs the login flow against an in-process fake greetd.
+    //!
+    //! The fake knows three users: `alice` with the password `hunter2`, `bob` who is asked for a one-time code before
+    //! his password `correct horse`, and `carol` who gets an info message before her password `swordfish`. It refuses
+    //! to start [`BROKEN_COMMAND`], like greetd does for a session that can't be started.
+
+    use std::{io, time::Duration};
+
+    use greetd_ipc::{AuthMessageType, ErrorType, Request, Response};
+    use smol::{
+        channel::{Receiver, Sender},
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::unix::UnixStream,
+    };
+
+    use super::{GreetdSession, GreeterError, RequestedAction};
+    use crate::ipc::GreetdClient;
+    use crate::sessions::SessionCommand;
+
+    const BROKEN_COMMAND: &str = "no-such-session";
+
+    /// The questions each user is asked, in order, with the expected answer. `None` is an info message.
+    fn questions(
+        username: &str,
+    ) -> Option<Vec<(AuthMessageType, &'static str, Option<&'static str>)>> {
+        match username {
+            "alice" => Some(vec![(
+                AuthMessageType::Secret,
+                "Password:",
+                Some("hunter2"),
+            )]),
+            "bob" => Some(vec![
+                (AuthMessageType::Visible, "One-time code:", Some("123456")),
+                (AuthMessageType::Secret, "Password:", Some("correct horse")),
+            ]),
+            "carol" => Some(vec![
+                (
+                    AuthMessageType::Info,
+                    "Your password expires in 3 days",
+                    None,
+                ),
+                (AuthMessageType::Secret, "Password:", Some("swordfish")),
+            ]),
+            _ => None,
+        }
+    }
+
+    /// What the fake reports back after each started session.
+    #[derive(Debug)]
+    struct StartedSession {
+        username: String,
+        cmd: Vec<String>,
+        env: Vec<String>,
+    }
+
+    enum FakeState {
+        Idle,
+        Authenticating { username: String, step: usize },
+        Authenticated { username: String },
+    }
+
+    async fn read_request(stream: &mut UnixStream) -> io::Result<Request> {
+        let mut size = [0u8; 4];
+        stream.read_exact(&mut size).await?;
+        let mut packet = vec![0u8; u32::from_ne_bytes(size) as usize];
+        stream.read_exact(&mut packet).await?;
+        serde_json::from_slice(&packet)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    async fn write_response(stream: &mut UnixStream, response: &Response) -> io::Result<()> {
+        let packet = serde_json::to_vec(response)?;
+        stream
+            .write_all(&(packet.len() as u32).to_ne_bytes())
+            .await?;
+        stream.write_all(&packet).await
+    }
+
+    fn error(error_type: ErrorType, description: &str) -> Response {
+        Response::Error {
+            error_type,
+            description: description.to_string(),
+        }
+    }
+
+    fn ask(username: &str, step: usize) -> Response {
+        let question = questions(username)
+            .unwrap_or_default()
+            .into_iter()
+            .nth(step);
+        match question {
+            Some((auth_message_type, auth_message, _)) => Response::AuthMessage {
+                auth_message_type,
+                auth_message: auth_message.to_string(),
+            },
+            None => Response::Success,
+        }
+    }
+
+    /// Answers requests like greetd would until the greeter hangs up or `drop_after` requests were answered.
+    async fn serve(
+        mut stream: UnixStream,
+        started: Sender<StartedSession>,
+        drop_after: Option<usize>,
+    ) {
+        let mut state = FakeState::Idle;
+        let mut answered = 0;
+
+        while let Ok(request) = read_request(&mut stream).await {
+            if drop_after.is_some_and(|limit| answered >= limit) {
+                return;
+            }
+            let (next, response) = match (state, request) {
+                (FakeState::Idle, Request::CreateSession { username }) => {
+                    match questions(&username) {
+                        Some(_) => {
+                            let response = ask(&username, 0);
+                            (FakeState::Authenticating { username, step: 0 }, response)
+                        }
+                        // PAM asks unknown users for a password too, so it doesn't give away who exists.
+                        None => (
+                            FakeState::Authenticating { username, step: 0 },
+                            Response::AuthMessage {
+                                auth_message_type: AuthMessageType::Secret,
+                                auth_message: "Password:".to_string(),
+                            },
+                        ),
+                    }
+                }
+                (
+                    FakeState::Authenticating { username, step },
+                    Request::PostAuthMessageResponse { response },
+                ) => {
+                    let expected =
+                        questions(&username).and_then(|questions| questions.get(step).map(|q| q.2));
+                    match expected {
+                        Some(expected) if expected == response.as_deref() => {
+                            let response = ask(&username, step + 1);
+                            match response {
+                                Response::Success => {
+                                    (FakeState::Authenticated { username }, response)
+                                }
+                                _ => (
+                                    FakeState::Authenticating {
+                                        username,
+                                        step: step + 1,
+                                    },
+                                    response,
+                                ),
+                            }
+                        }
+                        // Like greetd, the session stays around until it is cancelled.
+                        _ => (
+                            FakeState::Authenticating { username, step },
+                            error(ErrorType::AuthError, "pam_authenticate: AUTH_ERR"),
+                        ),
+                    }
+                }
+                (FakeState::Authenticated { username }, Request::StartSession { cmd, .. })
+                    if cmd.first().is_some_and(|cmd| cmd == BROKEN_COMMAND) =>
+                {
+                    (
+                        FakeState::Authenticated { username },
+                        error(ErrorType::Error, "session command not found"),
+                    )
+                }
+                (FakeState::Authenticated { username }, Request::StartSession { cmd, env }) => {
+                    _ = started.send(StartedSession { username, cmd, env }).await;
+                    (FakeState::Idle, Response::Success)
+                }
+                (_, Request::CancelSession) => (FakeState::Idle, Response::Success),
+                (state, _) => (
+                    state,
+                    error(ErrorType::Error, "a session is already being configured"),
+                ),
+            };
+            state = next;
+            if write_response(&mut stream, &response).await.is_err() {
+                return;
+            }
+            answered += 1;
+        }
+    }
+
+    /// Connects a session to a new fake greetd, which hangs up after `drop_after` requests.
+    fn connect(drop_after: Option<usize>) -> (GreetdSession, Receiver<StartedSession>) {
+        let (started_sender, started) = smol::channel::unbounded();
+        let (greeter, greetd) = UnixStream::pair().expect("Failed to create a socket pair");
+        smol::spawn(serve(greetd, started_sender, drop_after)).detach();
+        let client = GreetdClient::from_stream(greeter).with_timeout(Duration::from_secs(5));
+        (GreetdSession::with_client(client), started)
+    }
+
+    fn command(cmd: &str) -> SessionCommand {
+        SessionCommand {
+            cmd: vec![cmd.to_string()],
+            env: vec!["XDG_SESSION_TYPE=wayland".to_string()],
+        }
+    }
+
+    /// Steps the state machine like the greeter UI does, answering prompts from `answers` in order. Returns the
+    /// messages that were shown.
+    async fn log_in(
+        session: &mut GreetdSession,
+        username: &str,
+        answers: &[&str],
+        command: &SessionCommand,
+    ) -> Result<Vec<String>, GreeterError> {
+        let mut answers = answers.iter();
+        let mut messages = Vec::new();
+        let mut data = None;
+        loop {
+            match session
+                .step_statemachine(username, data.take(), command)
+                .await?
+            {
+                RequestedAction::None => {}
+                RequestedAction::DisplayMessage(message) => messages.push(message),
+                RequestedAction::SendDataFromPrompt { .. } => {
+                    let Some(answer) = answers.next() else {
+                        // Out of answers, so give up on this user.
+                        session.cancel().await?;
+                        return Ok(messages);
+                    };
+                    data = Some(*answer);
+                }
+                RequestedAction::ExitApplication => return Ok(messages),
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_password_then_right_one() {
+        smol::block_on(async {
+            let (mut session, started) = connect(None);
+            let command = command("niri-session");
+
+            let result = log_in(&mut session, "alice", &["hunter3"], &command).await;
+            assert!(matches!(result, Err(GreeterError::FailedToAuthenticate)));
+            assert_eq!(session.failed_attempts, 1);
+
+            log_in(&mut session, "alice", &["hunter2"], &command)
+                .await
+                .unwrap();
+            let alice = started.recv().await.unwrap();
+            assert_eq!(alice.username, "alice");
+            assert_eq!(alice.cmd, command.cmd);
+            assert_eq!(alice.env, command.env);
+        });
+    }
+
+    #[test]
+    fn cancelled_login_then_another_user() {
+        smol::block_on(async {
+            let (mut session, started) = connect(None);
+            let command = command("niri-session");
+
+            // bob gives up after the one-time code.
+            log_in(&mut session, "bob", &["123456"], &command)
+                .await
+                .unwrap();
+            let messages = log_in(&mut session, "carol", &["swordfish"], &command)
+                .await
+                .unwrap();
+            assert_eq!(messages, ["Your password expires in 3 days"]);
+            assert_eq!(started.recv().await.unwrap().username, "carol");
+        });
+    }
+
+    #[test]
+    fn refused_session_can_be_retried() {
+        smol::block_on(async {
+            let (mut session, started) = connect(None);
+
+            let result = log_in(
+                &mut session,
+                "alice",
+                &["hunter2"],
+                &command(BROKEN_COMMAND),
+            )
+            .await;
+            assert!(matches!(result, Err(GreeterError::GreetdError { .. })));
+            // That wasn't the password's fault.
+            assert_eq!(session.failed_attempts, 0);
+
+            log_in(
+                &mut session,
+                "alice",
+                &["hunter2"],
+                &command("niri-session"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(started.recv().await.unwrap().cmd, ["niri-session"]);
+        });
+    }
+
+    #[test]
+    fn lost_connection_is_fatal() {
+        smol::block_on(async {
+            let (mut session, _started) = connect(Some(1));
+            let result = log_in(
+                &mut session,
+                "mallory",
+                &["guess"],
+                &command("niri-session"),
+            )
+            .await;
+            assert!(matches!(result, Err(GreeterError::SessionShutdown)));
+        });
+    }
+}
@@ -0,0 +1,207 @@
+//! A client for the greetd IPC protocol.
+//!
+//! greetd speaks JSON over a unix socket, with every packet prefixed by its length as a native endian `u32`. Each
+//! request gets exactly one response, so [`GreetdClient`] only allows one request in flight and refuses to be used
+//! again if a request timed out or was dropped halfway through, since the next response would belong to the wrong
+//! request.
+
+use std::{fmt, io, net::Shutdown, path::Path, time::Duration};
+
+use greetd_ipc::{Request, Response};
+use smol::{
+    Timer,
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::unix::UnixStream,
+};
+use snafu::{ResultExt, Snafu};
+
+/// How long greetd gets to answer a request. PAM modules may take a while, e.g. after a wrong password.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Packets larger than this are not from greetd.
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Snafu)]
+pub enum IpcError {
+    /// Failed to talk to greetd
+    #[snafu(display("Failed to talk to greetd: {source}"))]
+    Io { source: io::Error },
+    /// greetd closed the connection
+    #[snafu(display("greetd closed the connection"))]
+    Closed,
+    /// greetd did not answer in time
+    #[snafu(display("greetd did not answer within {}s", timeout.as_secs()))]
+    Timeout { timeout: Duration },
+    /// greetd sent something that isn't a response
+    #[snafu(display("greetd sent an invalid packet: {source}"))]
+    Malformed { source: serde_json::Error },
+    /// A previous request was dropped before its response arrived
+    #[snafu(display("a previous request to greetd was interrupted"))]
+    Desynchronized,
+}
+
+/// Formats a request for logs without the answer to an authentication prompt, which is usually a password.
+pub struct Redacted<'a>(pub &'a Request);
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Request::PostAuthMessageResponse { response } => f
+                .debug_struct("PostAuthMessageResponse")
+                .field("response", &response.as_ref().map(|_| "<redacted>"))
+                .finish(),
+            request => request.fmt(f),
+        }
+    }
+}
+
+/// A connection to greetd.
+#[derive(Debug)]
+pub struct GreetdClient {
+    socket: UnixStream,
+    timeout: Duration,
+    /// Prints every packet, with secrets redacted.
+    log_packets: bool,
+    in_flight: bool,
+    closed: bool,
+}
+impl GreetdClient {
+    pub async fn connect(socket_path: impl AsRef<Path>) -> Result<Self, IpcError> {
+        let socket = UnixStream::connect(socket_path).await.context(IoSnafu)?;
+        Ok(Self::from_stream(socket))
+    }
+
+    /// Uses an already connected socket, e.g. one half of a [`UnixStream::pair`].
+    pub fn from_stream(socket: UnixStream) -> Self {
+        Self {
+            socket,
+            timeout: DEFAULT_TIMEOUT,
+            log_packets: false,
+            in_flight: false,
+            closed: false,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_packet_logging(mut self, log_packets: bool) -> Self {
+        self.log_packets = log_packets;
+        self
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Sends `request` and waits for its response.
+    ///
+    /// If this future is dropped before it finishes, the client can't tell which response belongs to which request
+    /// anymore, so every later request fails with [`IpcError::Desynchronized`].
+    pub async fn request(&mut self, request: &Request) -> Result<Response, IpcError> {
+        if self.closed {
+            return Err(IpcError::Closed);
+        }
+        if self.in_flight {
+            return Err(IpcError::Desynchronized);
+        }
+        if self.log_packets {
+            println!("greetd <- {:?}", Redacted(request));
+        }
+
+        self.in_flight = true;
+        let timeout = self.timeout;
+        let result = smol::future::or(self.exchange(request), async move {
+            Timer::after(timeout).await;
+            Err(IpcError::Timeout { timeout })
+        })
+        .await;
+
+        match &result {
+            Ok(response) => {
+                self.in_flight = false;
+                if self.log_packets {
+                    println!("greetd -> {response:?}");
+                }
+            }
+            // The response may still arrive later and be mistaken for the answer to the next request.
+            Err(IpcError::Timeout { .. }) => self.shutdown(),
+            Err(IpcError::Closed | IpcError::Io { .. }) => self.closed = true,
+            Err(_) => self.in_flight = false,
+        }
+        result
+    }
+
+    async fn exchange(&mut self, request: &Request) -> Result<Response, IpcError> {
+        let packet = serde_json::to_vec(request).context(MalformedSnafu)?;
+        self.socket
+            .write_all(&(packet.len() as u32).to_ne_bytes())
+            .await
+            .map_err(map_io)?;
+        self.socket.write_all(&packet).await.map_err(map_io)?;
+
+        let mut packet_size = [0u8; 4];
+        self.socket
+            .read_exact(&mut packet_size)
+            .await
+            .map_err(map_io)?;
+        let packet_size = u32::from_ne_bytes(packet_size) as usize;
+        if packet_size > MAX_PACKET_SIZE {
+            return Err(IpcError::Io {
+                source: io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("packet of {packet_size} bytes is too large"),
+                ),
+            });
+        }
+
+        let mut packet = vec![0u8; packet_size];
+        self.socket.read_exact(&mut packet).await.map_err(map_io)?;
+        serde_json::from_slice(&packet).context(MalformedSnafu)
+    }
+
+    pub async fn create_session(&mut self, username: &str) -> Result<Response, IpcError> {
+        self.request(&Request::CreateSession {
+            username: username.to_string(),
+        })
+        .await
+    }
+
+    /// Answers the last authentication prompt. `None` acknowledges an info or error message.
+    pub async fn post_auth_message_response(
+        &mut self,
+        response: Option<&str>,
+    ) -> Result<Response, IpcError> {
+        self.request(&Request::PostAuthMessageResponse {
+            response: response.map(str::to_string),
+        })
+        .await
+    }
+
+    pub async fn start_session(
+        &mut self,
+        cmd: Vec<String>,
+        env: Vec<String>,
+    ) -> Result<Response, IpcError> {
+        self.request(&Request::StartSession { cmd, env }).await
+    }
+
+    pub async fn cancel_session(&mut self) -> Result<Response, IpcError> {
+        self.request(&Request::CancelSession).await
+    }
+
+    /// Closes the connection. greetd cancels whatever session it was still authenticating.
+    pub fn shutdown(&mut self) {
+        self.closed = true;
+        _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+fn map_io(err: io::Error) -> IpcError {
+    match err.kind() {
+        io::ErrorKind::UnexpectedEof
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::ConnectionReset => IpcError::Closed,
+        _ => IpcError::Io { source: err },
+    }
+}
@@ -0,0 +1,146 @@
+use std::{io, path::PathBuf, process::Command};
+
+/// Where xkeyboard-config is installed when `XKB_CONFIG_ROOT` isn't set.
+pub const DEFAULT_XKB_CONFIG_ROOT: &str = "/usr/share/X11/xkb";
+
+/// A keyboard layout, or one of its variants, from xkb's `evdev.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardLayout {
+    pub layout: String,
+    pub variant: Option<String>,
+    pub description: String,
+}
+impl KeyboardLayout {
+    /// The layout in the `layout(variant)` form xkb uses, like `us` or `de(nodeadkeys)`.
+    pub fn id(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{}({variant})", self.layout),
+            None => self.layout.clone(),
+        }
+    }
+
+    /// The environment that makes compositors started by the session use this layout.
+    pub fn session_env(&self) -> Vec<String> {
+        let mut env = vec![format!("XKB_DEFAULT_LAYOUT={}", self.layout)];
+        if let Some(variant) = &self.variant {
+            env.push(format!("XKB_DEFAULT_VARIANT={variant}"));
+        }
+        env
+    }
+
+    /// Switches the keyboard of the compositor the greeter runs in to this layout.
+    ///
+    /// Only sway and Hyprland can change the layout at runtime. Returns `false` for other compositors, where the layout
+    /// only applies to the session that is started.
+    pub fn apply(&self) -> io::Result<bool> {
+        let variant = self.variant.as_deref().unwrap_or("");
+        let mut command = if std::env::var_os("SWAYSOCK").is_some() {
+            let mut command = Command::new("swaymsg");
+            command.args([
+                &format!("input type:keyboard xkb_layout {}", self.layout),
+                ",",
+                &format!("input type:keyboard xkb_variant \"{variant}\""),
+            ]);
+            command
+        } else if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            let mut command = Command::new("hyprctl");
+            command.args([
+                "--batch",
+                &format!(
+                    "keyword input:kb_layout {} ; keyword input:kb_variant {variant}",
+                    self.layout
+                ),
+            ]);
+            command
+        } else {
+            return Ok(false);
+        };
+        let status = command.status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{:?} exited with {status}",
+                command.get_program()
+            )));
+        }
+        Ok(true)
+    }
+}
+
+fn evdev_rules_path() -> PathBuf {
+    std::env::var_os("XKB_CONFIG_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_XKB_CONFIG_ROOT))
+        .join("rules/evdev.xml")
+}
+
+fn config_item(node: roxmltree::Node) -> Option<(String, String)> {
+    let item = node
+        .children()
+        .find(|child| child.has_tag_name("configItem"))?;
+    let text = |tag: &str| {
+        item.children()
+            .find(|child| child.has_tag_name(tag))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+    };
+    Some((text("name")?, text("description")?))
+}
+
+/// Reads every layout and variant from the `layoutList` of an xkb rules file.
+pub fn parse_evdev_rules(xml: &str) -> Result<Vec<KeyboardLayout>, roxmltree::Error> {
+    // The rules files start with a DOCTYPE pointing to `xkb.dtd`.
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document = roxmltree::Document::parse_with_options(xml, options)?;
+    let mut layouts = Vec::new();
+
+    let layout_nodes = document
+        .descendants()
+        .filter(|node| node.has_tag_name("layout"))
+        .filter(|node| {
+            node.parent()
+                .is_some_and(|parent| parent.has_tag_name("layoutList"))
+        });
+    for node in layout_nodes {
+        let Some((layout, description)) = config_item(node) else {
+            continue;
+        };
+        let variants = node
+            .descendants()
+            .filter(|child| child.has_tag_name("variant"))
+            .filter_map(config_item)
+            .map(|(variant, description)| KeyboardLayout {
+                layout: layout.clone(),
+                variant: Some(variant),
+                description,
+            })
+            .collect::<Vec<_>>();
+        layouts.push(KeyboardLayout {
+            layout,
+            variant: None,
+            description,
+        });
+        layouts.extend(variants);
+    }
+
+    Ok(layouts)
+}
+
+/// The layouts installed on this system, sorted by description.
+pub fn xkb_layouts() -> Vec<KeyboardLayout> {
+    let path = evdev_rules_path();
+    let mut layouts = match std::fs::read_to_string(&path) {
+        Ok(xml) => parse_evdev_rules(&xml).unwrap_or_else(|err| {
+            println!("Failed to parse {}: {err}", path.display());
+            Vec::new()
+        }),
+        Err(err) => {
+            println!("Failed to read {}: {err}", path.display());
+            Vec::new()
+        }
+    };
+    layouts.sort_by_key(|layout| layout.description.to_lowercase());
+    layouts
+}
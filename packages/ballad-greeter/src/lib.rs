@@ -0,0 +1,4 @@
+pub mod greetd;
+pub mod ipc;
+pub mod keyboard;
+pub mod sessions;
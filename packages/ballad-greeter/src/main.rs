@@ -1,58 +1,57 @@
-pub mod greetd;
+mod appearance;
+mod ui;
 
-use std::{thread::sleep, time::Duration};
+use std::rc::Rc;
 
-use greetd::{GreetdSession, GreeterError, RequestedAction};
 use gtk::{Application, glib, prelude::*};
 
+use ballad_greeter::greetd::GreetdSession;
+use ballad_greeter::ipc::GreetdClient;
+
+use appearance::Appearance;
+
+/// Set to print the packets exchanged with greetd. Answers to prompts are redacted.
+const LOG_IPC_ENV: &str = "BALLAD_GREETER_LOG_IPC";
+
 fn main() {
     let app = Application::builder()
-        .application_id("com.gavinniederman.ballad-shell")
+        .application_id("com.gavinniederman.ballad-greeter")
         .build();
 
     app.connect_activate(activate);
-    app.connect_startup(startup);
 
     app.run();
 }
 
 fn activate(app: &Application) {
-    let window = gtk::ApplicationWindow::new(app);
-    window.set_title(Some("Ballad Shell"));
-    window.set_default_size(800, 600);
-    window.present();
-}
-
-fn startup(app: &Application) {
     let app = app.clone();
     glib::spawn_future_local(async move {
-        let greetd_socket = std::env::var("GREETD_SOCK").expect("Cannot find GREETD_SOCK in environment. Is this running as your greeter? Use a program like `greetd-stub` if you are developing this program.");
-        let mut session = GreetdSession::new(greetd_socket).await.unwrap();
-        loop {
-            match session
-                .step_statemachine("real", Some("3636"), "firefox")
-                .await
-            {
-                Ok(RequestedAction::DisplayMessage(message)) => println!("message: {message}"),
-                Ok(RequestedAction::SendDataFromPrompt { prompt, visible }) => {
-                    println!("prompt: {prompt}, visible: {visible}")
-                }
-                Ok(RequestedAction::ExitApplication) => {
-                    println!("Exiting");
-                    app.quit();
-                    break;
-                }
-                Ok(RequestedAction::None) => {}
-                Err(GreeterError::FailedToAuthenticate) => {
-                    println!("Failed to authenticate");
-                    break;
-                }
-                Err(e) => {
-                    println!("Error: {e}");
-                    break;
-                }
+        let Ok(greetd_socket) = std::env::var("GREETD_SOCK") else {
+            println!(
+                "Cannot find GREETD_SOCK in environment. Is this running as your greeter? Use a program like `greetd-stub` if you are developing this program."
+            );
+            app.quit();
+            return;
+        };
+        let client = match GreetdClient::connect(greetd_socket).await {
+            Ok(client) => client.with_packet_logging(std::env::var_os(LOG_IPC_ENV).is_some()),
+            Err(err) => {
+                println!("Failed to connect to greetd: {err}");
+                app.quit();
+                return;
             }
-            sleep(Duration::from_secs(1));
-        }
+        };
+        let config = ballad_config::greeter::get_greeter_config().unwrap_or_else(|err| {
+            println!("Failed to read the greeter config, using the defaults: {err}");
+            Default::default()
+        });
+        let appearance = Rc::new(Appearance::new(&config));
+        ui::greeter_window(
+            &app,
+            GreetdSession::with_client(client),
+            &config,
+            appearance,
+        )
+        .present();
     });
 }
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Wraps the command of X11 sessions, since greetd only starts the session and not a display server.
+pub const XSESSION_WRAPPER: &[&str] = &["startx", "/usr/bin/env"];
+/// Used when no session files are installed at all.
+pub const FALLBACK_SESSION_COMMAND: &str = "niri-session";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionKind {
+    Wayland,
+    X11,
+}
+impl SessionKind {
+    fn directory(self) -> &'static str {
+        match self {
+            SessionKind::Wayland => "wayland-sessions",
+            SessionKind::X11 => "xsessions",
+        }
+    }
+    fn session_type(self) -> &'static str {
+        match self {
+            SessionKind::Wayland => "wayland",
+            SessionKind::X11 => "x11",
+        }
+    }
+}
+
+/// A session from a `.desktop` file in `wayland-sessions` or `xsessions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopSession {
+    /// The file name without `.desktop`, which is what AccountsService remembers.
+    pub id: String,
+    pub name: String,
+    pub comment: Option<String>,
+    pub exec: Vec<String>,
+    pub desktop_names: Vec<String>,
+    pub kind: SessionKind,
+}
+
+/// The command and environment greetd starts the session with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionCommand {
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+}
+
+impl DesktopSession {
+    /// Parses the `[Desktop Entry]` group. Returns `None` for hidden entries and entries without a command.
+    pub fn parse(id: &str, contents: &str, kind: SessionKind) -> Option<Self> {
+        let mut in_entry = false;
+        let mut name = None;
+        let mut comment = None;
+        let mut exec = None;
+        let mut try_exec = None;
+        let mut desktop_names = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            // Localised keys like `Name[de]` are ignored.
+            match key.trim() {
+                "Name" => name = Some(value.to_string()),
+                "Comment" => comment = Some(value.to_string()),
+                "Exec" => exec = Some(value.to_string()),
+                "TryExec" => try_exec = Some(value.to_string()),
+                "DesktopNames" => {
+                    desktop_names = value
+                        .split(';')
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                "Hidden" | "NoDisplay" if value == "true" => return None,
+                _ => {}
+            }
+        }
+
+        if try_exec.is_some_and(|program| !program_exists(&program)) {
+            return None;
+        }
+        let exec = split_exec(&exec?);
+        if exec.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            id: id.to_string(),
+            name: name.unwrap_or_else(|| id.to_string()),
+            comment,
+            exec,
+            desktop_names,
+            kind,
+        })
+    }
+
+    /// The session started when nothing is installed in the session directories.
+    pub fn fallback() -> Self {
+        Self {
+            id: FALLBACK_SESSION_COMMAND.to_string(),
+            name: "Niri".to_string(),
+            comment: None,
+            exec: vec![FALLBACK_SESSION_COMMAND.to_string()],
+            desktop_names: vec!["niri".to_string()],
+            kind: SessionKind::Wayland,
+        }
+    }
+
+    pub fn command(&self) -> SessionCommand {
+        let cmd = match self.kind {
+            SessionKind::Wayland => self.exec.clone(),
+            SessionKind::X11 => XSESSION_WRAPPER
+                .iter()
+                .map(|arg| arg.to_string())
+                .chain(self.exec.iter().cloned())
+                .collect(),
+        };
+
+        let mut env = vec![
+            format!("XDG_SESSION_TYPE={}", self.kind.session_type()),
+            format!("XDG_SESSION_DESKTOP={}", self.id),
+        ];
+        if !self.desktop_names.is_empty() {
+            env.push(format!(
+                "XDG_CURRENT_DESKTOP={}",
+                self.desktop_names.join(":")
+            ));
+        }
+
+        SessionCommand { cmd, env }
+    }
+}
+
+/// Splits an `Exec` value into arguments following the desktop entry quoting rules, dropping field codes.
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            // Sessions are never started with files or URLs, so every other field code expands to nothing.
+            '%' => {
+                if chars.next() == Some('%') {
+                    current.push('%');
+                    in_arg = true;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg && !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+fn program_exists(program: &str) -> bool {
+    let path = Path::new(program);
+    if path.is_absolute() {
+        return path.exists();
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).exists()))
+        .unwrap_or(false)
+}
+
+/// The data directories to look for session files in, most important first.
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string())
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    let usr_share = PathBuf::from("/usr/share");
+    if !dirs.contains(&usr_share) {
+        dirs.push(usr_share);
+    }
+    dirs
+}
+
+/// Finds every installed session, Wayland sessions first. Earlier data directories shadow later ones.
+pub fn discover_sessions() -> Vec<DesktopSession> {
+    let dirs = data_dirs();
+    let mut seen = HashSet::new();
+    let mut sessions = Vec::new();
+
+    for kind in [SessionKind::Wayland, SessionKind::X11] {
+        let mut found = Vec::new();
+        for dir in &dirs {
+            let Ok(entries) = fs::read_dir(dir.join(kind.directory())) else {
+                continue;
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path
+                    .extension()
+                    .is_none_or(|extension| extension != "desktop")
+                {
+                    continue;
+                }
+                let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                if !seen.insert((kind, id.to_string())) {
+                    continue;
+                }
+                let Ok(contents) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                if let Some(session) = DesktopSession::parse(id, &contents, kind) {
+                    found.push(session);
+                }
+            }
+        }
+        found.sort_by_key(|session| session.name.to_lowercase());
+        sessions.extend(found);
+    }
+
+    if sessions.is_empty() {
+        sessions.push(DesktopSession::fallback());
+    }
+    sessions
+}
@@ -0,0 +1,681 @@
+use std::{cell::LazyCell, rc::Rc};
+
+use ballad_config::GreeterConfig;
+use ballad_services::accounts::{ACCOUNTS_SERVICE, AccountsService, User};
+use ballad_services::logind::{LOGIND_SERVICE, PowerAction};
+use gtk::{
+    Align, Application, ApplicationWindow, Button, CheckButton, DropDown, Entry,
+    EventControllerKey, Image, InputPurpose, Label, ListBox, ListBoxRow, MenuButton, Orientation,
+    Overflow, Popover, PropertyExpression, SelectionMode, StringList, StringObject,
+    gdk::Key,
+    gio,
+    glib::{self, clone, closure_local},
+    prelude::*,
+};
+use smol::lock::Mutex;
+
+use ballad_greeter::greetd::{GreetdSession, GreeterError, RequestedAction};
+use ballad_greeter::keyboard::{KeyboardLayout, xkb_layouts};
+use ballad_greeter::sessions::{DesktopSession, SessionKind, discover_sessions};
+
+use crate::appearance::Appearance;
+
+const AVATAR_SIZE: i32 = 48;
+/// The widget name of the row that uses the typed username. Other rows are named after their user.
+const OTHER_USER_ROW: &str = "other-user";
+
+/// The widgets the greetd state machine drives.
+#[derive(Clone)]
+struct Greeter {
+    application: Application,
+    session: Rc<Mutex<GreetdSession>>,
+    desktop_sessions: Rc<Vec<DesktopSession>>,
+    session_picker: DropDown,
+    layouts: Rc<Vec<KeyboardLayout>>,
+    layout_picker: DropDown,
+    users: ListBox,
+    username: Entry,
+    prompt: Label,
+    response: Entry,
+    message: Label,
+    failed_attempts: Label,
+}
+
+impl Greeter {
+    /// The user picked in the list, or the typed username for "Other user".
+    fn selected_user(&self) -> Option<String> {
+        let row = self.users.selected_row()?;
+        let name = row.widget_name();
+        if name == OTHER_USER_ROW {
+            Some(self.username.text().to_string()).filter(|name| !name.is_empty())
+        } else {
+            Some(name.to_string())
+        }
+    }
+
+    /// The session picked in the dropdown.
+    fn selected_session(&self) -> &DesktopSession {
+        let index = self.session_picker.selected() as usize;
+        self.desktop_sessions
+            .get(index)
+            .unwrap_or(&self.desktop_sessions[0])
+    }
+
+    fn selected_layout(&self) -> Option<&KeyboardLayout> {
+        self.layouts.get(self.layout_picker.selected() as usize)
+    }
+
+    /// Picks the session `user` last logged into, if it is still installed.
+    fn select_last_session(&self, user: &User) {
+        let last = [
+            (SessionKind::Wayland, user.session()),
+            (SessionKind::X11, user.xsession()),
+        ];
+        let index = last.into_iter().find_map(|(kind, id)| {
+            let id = id?;
+            self.desktop_sessions
+                .iter()
+                .position(|session| session.kind == kind && session.id == id)
+        });
+        if let Some(index) = index {
+            self.session_picker.set_selected(index as u32);
+        }
+    }
+
+    /// Stores the picked session in AccountsService so it is preselected next time.
+    async fn remember_session(&self, user_name: &str) {
+        let service = ACCOUNTS_SERVICE.with(|service| LazyCell::force(service).clone());
+        let Some(user) = service.find_user_by_name(user_name).await else {
+            return;
+        };
+        let session = self.selected_session();
+        let result = match session.kind {
+            SessionKind::Wayland if user.session().as_ref() != Some(&session.id) => {
+                user.set_session(&session.id).await
+            }
+            SessionKind::X11 if user.xsession().as_ref() != Some(&session.id) => {
+                user.set_xsession(&session.id).await
+            }
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            println!("Failed to remember the session of {user_name}: {err}");
+        }
+    }
+
+    fn show_message(&self, message: &str, error: bool) {
+        self.message.set_label(message);
+        self.message.set_visible(!message.is_empty());
+        if error {
+            self.message.add_css_class("error");
+        } else {
+            self.message.remove_css_class("error");
+        }
+    }
+
+    fn wait_for_input(&self) {
+        self.prompt.set_visible(false);
+        self.response.set_visible(false);
+    }
+
+    /// Cancels whatever is being authenticated and starts over for the selected user.
+    async fn begin(&self) {
+        self.wait_for_input();
+        self.show_message("", false);
+        let Some(user) = self.selected_user() else {
+            self.username.grab_focus();
+            return;
+        };
+        if let Err(err) = self.session.lock().await.cancel().await {
+            self.show_message(&err.to_string(), true);
+            return;
+        }
+        self.advance(&user, None).await;
+    }
+
+    /// Steps the state machine until it needs something from the user.
+    async fn advance(&self, user: &str, mut data: Option<String>) {
+        let mut command = self.selected_session().command();
+        if let Some(layout) = self.selected_layout() {
+            command.env.extend(layout.session_env());
+        }
+        let mut session = self.session.lock().await;
+        loop {
+            let result = session
+                .step_statemachine(user, data.take().as_deref(), &command)
+                .await;
+            match result {
+                Ok(RequestedAction::None) => {}
+                Ok(RequestedAction::DisplayMessage(message)) => self.show_message(&message, false),
+                Ok(RequestedAction::SendDataFromPrompt { prompt, visible }) => {
+                    self.prompt.set_label(&prompt);
+                    self.prompt.set_visible(true);
+                    self.response.set_text("");
+                    self.response.set_visibility(visible);
+                    self.response.set_input_purpose(if visible {
+                        InputPurpose::FreeForm
+                    } else {
+                        InputPurpose::Password
+                    });
+                    self.response.set_visible(true);
+                    self.response.set_sensitive(true);
+                    self.response.grab_focus();
+                    return;
+                }
+                Ok(RequestedAction::ExitApplication) => {
+                    self.remember_session(user).await;
+                    self.application.quit();
+                    return;
+                }
+                Err(GreeterError::FailedToAuthenticate) => {
+                    self.show_message("Authentication failed", true);
+                    self.failed_attempts
+                        .set_label(&match session.failed_attempts {
+                            1 => "1 failed attempt".to_string(),
+                            attempts => format!("{attempts} failed attempts"),
+                        });
+                    self.failed_attempts.set_visible(true);
+                    // The session was cancelled, so the loop starts a new one and asks again.
+                }
+                Err(err @ (GreeterError::SessionShutdown | GreeterError::Ipc { .. })) => {
+                    // greetd starts the greeter again, which reconnects.
+                    println!("Lost the connection to greetd: {err}");
+                    self.application.quit();
+                    return;
+                }
+                Err(err) => {
+                    self.wait_for_input();
+                    self.show_message(&err.to_string(), true);
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn respond(&self) {
+        let Some(user) = self.selected_user() else {
+            return;
+        };
+        let response = self.response.text().to_string();
+        self.response.set_text("");
+        self.response.set_sensitive(false);
+        self.advance(&user, Some(response)).await;
+    }
+}
+
+fn cached_user(user_name: &str) -> Option<User> {
+    ACCOUNTS_SERVICE.with(|service| {
+        service
+            .cached_users()
+            .iter::<User>()
+            .flatten()
+            .find(|user| user.user_name() == user_name)
+    })
+}
+
+fn user_avatar(user: &User) -> Image {
+    let image = Image::builder()
+        .icon_name("avatar-default-symbolic")
+        .pixel_size(AVATAR_SIZE)
+        .build();
+    if let Some(icon_file) = user
+        .icon_file()
+        .filter(|path| std::fs::exists(path).unwrap_or(false))
+    {
+        image.set_from_file(Some(icon_file));
+    }
+    image
+}
+
+fn user_row(user: &User) -> ListBoxRow {
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .build();
+    let avatar = gtk::Box::builder()
+        .css_classes(["user-avatar"])
+        .overflow(Overflow::Hidden)
+        .build();
+    avatar.append(&user_avatar(user));
+    content.append(&avatar);
+
+    let names = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .valign(Align::Center)
+        .build();
+    let user_name = user.user_name();
+    let real_name = user.real_name().filter(|name| !name.is_empty());
+    names.append(
+        &Label::builder()
+            .label(real_name.as_deref().unwrap_or(&user_name))
+            .halign(Align::Start)
+            .css_classes(["user-real-name"])
+            .build(),
+    );
+    if real_name.is_some() {
+        names.append(
+            &Label::builder()
+                .label(&user_name)
+                .halign(Align::Start)
+                .css_classes(["user-name"])
+                .build(),
+        );
+    }
+    content.append(&names);
+
+    ListBoxRow::builder()
+        .name(&user_name)
+        .css_classes(["user-row"])
+        .child(&content)
+        .build()
+}
+
+fn other_user_row() -> ListBoxRow {
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .build();
+    content.append(
+        &Image::builder()
+            .icon_name("system-users-symbolic")
+            .pixel_size(AVATAR_SIZE)
+            .build(),
+    );
+    content.append(
+        &Label::builder()
+            .label("Other user")
+            .valign(Align::Center)
+            .css_classes(["user-real-name"])
+            .build(),
+    );
+    ListBoxRow::builder()
+        .name(OTHER_USER_ROW)
+        .css_classes(["user-row"])
+        .child(&content)
+        .build()
+}
+
+fn fill_users(users: &ListBox, service: &AccountsService) {
+    users.remove_all();
+    for user in service.cached_users().iter::<User>().flatten() {
+        users.append(&user_row(&user));
+    }
+    users.append(&other_user_row());
+}
+
+fn power_button(greeter: &Greeter, action: PowerAction, icon_name: &str) -> Button {
+    let button = Button::builder()
+        .icon_name(icon_name)
+        .tooltip_text(action.name())
+        .css_classes(["power-button"])
+        .build();
+    LOGIND_SERVICE.with(|service| {
+        let update = move |button: &Button, service: &ballad_services::logind::LogindService| {
+            button.set_visible(service.can(action));
+        };
+        update(&button, service);
+        service.connect_notify_local(
+            None,
+            clone!(
+                #[weak]
+                button,
+                move |service, _| update(&button, service)
+            ),
+        );
+    });
+    button.connect_clicked(clone!(
+        #[strong]
+        greeter,
+        move |_| {
+            glib::spawn_future_local(clone!(
+                #[strong]
+                greeter,
+                async move {
+                    let service = LOGIND_SERVICE.with(|service| LazyCell::force(service).clone());
+                    if let Err(err) = service.perform(action).await {
+                        greeter.show_message(
+                            &format!("Failed to {}: {err}", action.name().to_lowercase()),
+                            true,
+                        );
+                    }
+                }
+            ));
+        }
+    ));
+    button
+}
+
+fn accessibility_menu(appearance: Rc<Appearance>, config: &GreeterConfig) -> MenuButton {
+    let options = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .build();
+    let large_text = CheckButton::builder()
+        .label("Large text")
+        .active(config.large_text)
+        .build();
+    let high_contrast = CheckButton::builder()
+        .label("High contrast")
+        .active(config.high_contrast)
+        .build();
+    large_text.connect_toggled(clone!(
+        #[strong]
+        appearance,
+        move |button| appearance.set_large_text(button.is_active())
+    ));
+    high_contrast.connect_toggled(move |button| appearance.set_high_contrast(button.is_active()));
+    options.append(&large_text);
+    options.append(&high_contrast);
+
+    MenuButton::builder()
+        .icon_name("preferences-desktop-accessibility-symbolic")
+        .tooltip_text("Accessibility")
+        .popover(&Popover::builder().child(&options).build())
+        .build()
+}
+
+fn layout_picker(layouts: &[KeyboardLayout], config: &GreeterConfig) -> DropDown {
+    let descriptions = layouts
+        .iter()
+        .map(|layout| layout.description.as_str())
+        .collect::<Vec<_>>();
+    let picker = DropDown::builder()
+        .model(&StringList::new(&descriptions))
+        .expression(PropertyExpression::new(
+            StringObject::static_type(),
+            None::<gtk::Expression>,
+            "string",
+        ))
+        .enable_search(true)
+        .tooltip_text("Keyboard layout")
+        .css_classes(["layout-picker"])
+        .build();
+    let initial = config.keyboard_layout.as_deref().unwrap_or("us");
+    if let Some(index) = layouts.iter().position(|layout| layout.id() == initial) {
+        picker.set_selected(index as u32);
+    }
+    picker.set_visible(!layouts.is_empty());
+    picker
+}
+
+fn apply_layout(layout: KeyboardLayout) {
+    glib::spawn_future_local(async move {
+        match gio::spawn_blocking(move || layout.apply()).await {
+            Ok(Ok(true)) => {}
+            Ok(Ok(false)) => {
+                println!(
+                    "The compositor can't change the keyboard layout, it only applies to the session"
+                )
+            }
+            Ok(Err(err)) => println!("Failed to change the keyboard layout: {err}"),
+            Err(_) => println!("Failed to change the keyboard layout"),
+        }
+    });
+}
+
+/// Accessibility and keyboard layout on the left, power actions on the right.
+fn greeter_bar(
+    greeter: &Greeter,
+    appearance: Rc<Appearance>,
+    config: &GreeterConfig,
+) -> gtk::CenterBox {
+    let start = gtk::Box::builder().spacing(6).build();
+    start.append(&accessibility_menu(appearance, config));
+    start.append(&greeter.layout_picker);
+
+    let end = gtk::Box::builder().spacing(6).build();
+    end.append(&power_button(
+        greeter,
+        PowerAction::Suspend,
+        "weather-clear-night-symbolic",
+    ));
+    end.append(&power_button(
+        greeter,
+        PowerAction::Reboot,
+        "system-reboot-symbolic",
+    ));
+    end.append(&power_button(
+        greeter,
+        PowerAction::PowerOff,
+        "system-shutdown-symbolic",
+    ));
+
+    gtk::CenterBox::builder()
+        .start_widget(&start)
+        .end_widget(&end)
+        .css_classes(["greeter-bar"])
+        .build()
+}
+
+/// Selects the configured default user, typing their name into "Other user" if they aren't listed.
+fn select_default_user(greeter: &Greeter, default_user: &str) -> bool {
+    let mut index = 0;
+    while let Some(row) = greeter.users.row_at_index(index) {
+        let name = row.widget_name();
+        if name == default_user || name == OTHER_USER_ROW {
+            if name == OTHER_USER_ROW {
+                greeter.username.set_text(default_user);
+                greeter.username.set_visible(true);
+            }
+            greeter.users.select_row(Some(&row));
+            row.grab_focus();
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+pub fn greeter_window(
+    application: &Application,
+    session: GreetdSession,
+    config: &GreeterConfig,
+    appearance: Rc<Appearance>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(application)
+        .title("Ballad Greeter")
+        .css_classes(["greeter"])
+        .build();
+    window.fullscreen();
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .halign(Align::Center)
+        .valign(Align::Center)
+        .css_classes(["greeter-content"])
+        .build();
+
+    let users = ListBox::builder()
+        .selection_mode(SelectionMode::Single)
+        .css_classes(["user-list"])
+        .build();
+    let username = Entry::builder()
+        .placeholder_text("Username")
+        .visible(false)
+        .css_classes(["username-entry"])
+        .build();
+    let prompt = Label::builder()
+        .halign(Align::Start)
+        .visible(false)
+        .css_classes(["prompt"])
+        .build();
+    let response = Entry::builder()
+        .visible(false)
+        .css_classes(["prompt-entry"])
+        .build();
+    let message = Label::builder()
+        .wrap(true)
+        .visible(false)
+        .css_classes(["message"])
+        .build();
+    let failed_attempts = Label::builder()
+        .visible(false)
+        .css_classes(["failed-attempts"])
+        .build();
+
+    let desktop_sessions = discover_sessions();
+    let session_names = desktop_sessions
+        .iter()
+        .map(|session| session.name.as_str())
+        .collect::<Vec<_>>();
+    let session_picker = DropDown::builder()
+        .model(&StringList::new(&session_names))
+        .tooltip_text("Session")
+        .halign(Align::Center)
+        .css_classes(["session-picker"])
+        .build();
+
+    content.append(&users);
+    content.append(&username);
+    content.append(&prompt);
+    content.append(&response);
+    content.append(&message);
+    content.append(&failed_attempts);
+    content.append(&session_picker);
+
+    let layouts = xkb_layouts();
+    let layout_picker = layout_picker(&layouts, config);
+
+    let greeter = Greeter {
+        application: application.clone(),
+        session: Rc::new(Mutex::new(session)),
+        desktop_sessions: Rc::new(desktop_sessions),
+        session_picker,
+        layouts: Rc::new(layouts),
+        layout_picker: layout_picker.clone(),
+        users: users.clone(),
+        username: username.clone(),
+        prompt,
+        response: response.clone(),
+        message,
+        failed_attempts,
+    };
+
+    let root = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    content.set_vexpand(true);
+    root.append(&content);
+    root.append(&greeter_bar(&greeter, appearance, config));
+    window.set_child(Some(&root));
+
+    if config.keyboard_layout.is_some()
+        && let Some(layout) = greeter.selected_layout()
+    {
+        apply_layout(layout.clone());
+    }
+    layout_picker.connect_selected_notify(clone!(
+        #[strong]
+        greeter,
+        move |_| {
+            if let Some(layout) = greeter.selected_layout() {
+                apply_layout(layout.clone());
+            }
+        }
+    ));
+
+    ACCOUNTS_SERVICE.with(|service| {
+        fill_users(&users, service);
+        service.connect_closure(
+            "cached-users-changed",
+            false,
+            closure_local!(
+                #[weak]
+                users,
+                move |service: AccountsService| fill_users(&users, &service)
+            ),
+        );
+    });
+
+    users.connect_row_selected(clone!(
+        #[strong]
+        greeter,
+        move |_, row| {
+            if let Some(user) = row.and_then(|row| cached_user(&row.widget_name())) {
+                greeter.select_last_session(&user);
+            }
+        }
+    ));
+    users.connect_row_activated(clone!(
+        #[strong]
+        greeter,
+        move |users, row| {
+            users.select_row(Some(row));
+            let other_user = row.widget_name() == OTHER_USER_ROW;
+            greeter.username.set_visible(other_user);
+            if other_user && greeter.username.text().is_empty() {
+                greeter.wait_for_input();
+                greeter.username.grab_focus();
+                return;
+            }
+            glib::spawn_future_local(clone!(
+                #[strong]
+                greeter,
+                async move { greeter.begin().await }
+            ));
+        }
+    ));
+    username.connect_activate(clone!(
+        #[strong]
+        greeter,
+        move |_| {
+            glib::spawn_future_local(clone!(
+                #[strong]
+                greeter,
+                async move { greeter.begin().await }
+            ));
+        }
+    ));
+    response.connect_activate(clone!(
+        #[strong]
+        greeter,
+        move |_| {
+            glib::spawn_future_local(clone!(
+                #[strong]
+                greeter,
+                async move { greeter.respond().await }
+            ));
+        }
+    ));
+
+    // Escape goes back to the user list to pick someone else.
+    let keys = EventControllerKey::new();
+    keys.connect_key_pressed(clone!(
+        #[strong]
+        greeter,
+        move |_, key, _, _| {
+            if key != Key::Escape {
+                return glib::Propagation::Proceed;
+            }
+            greeter.wait_for_input();
+            greeter.show_message("", false);
+            if let Some(row) = greeter.users.selected_row() {
+                row.grab_focus();
+            }
+            glib::spawn_future_local(clone!(
+                #[strong]
+                greeter,
+                async move {
+                    if let Err(err) = greeter.session.lock().await.cancel().await {
+                        greeter.show_message(&err.to_string(), true);
+                    }
+                }
+            ));
+            glib::Propagation::Stop
+        }
+    ));
+    window.add_controller(keys);
+
+    let default_user_selected = config
+        .default_user
+        .as_deref()
+        .is_some_and(|default_user| select_default_user(&greeter, default_user));
+    if !default_user_selected && let Some(first) = users.row_at_index(0) {
+        users.select_row(Some(&first));
+        first.grab_focus();
+    }
+
+    window
+}
@@ -0,0 +1,321 @@
+//! Exposes a fake MPRIS player on the session bus and watches it through the media service.
+//!
+//! The media service toggles playback every five seconds, so the player's state should be printed going back and
+//! forth between playing and paused. Use a private session bus to keep your real players out of the way:
+//! ```sh
+//! dbus-run-session -- cargo run --example fake_mpris_player
+//! ```
+//! While it is running, `playerctl` can be used to control the fake player as well.
+
+use std::{collections::HashMap, time::Instant};
+
+use ballad_services::mpris::{MPRIS_OBJECT_PATH, MediaPlayer, MprisService};
+use gtk::glib::{self, ControlFlow, clone};
+use gtk::prelude::*;
+use zbus::{
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedValue, Value},
+};
+
+const TRACK_LENGTH: i64 = 180_000_000;
+
+struct FakeMediaPlayer2;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl FakeMediaPlayer2 {
+    fn raise(&self) {
+        println!("[fake player] Raise");
+    }
+    fn quit(&self) {
+        println!("[fake player] Quit");
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Ballad Fake Player"
+    }
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        "ballad-fake-player"
+    }
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<&str> {
+        Vec::new()
+    }
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+struct FakePlayer {
+    playing: bool,
+    track: u32,
+    volume: f64,
+    shuffle: bool,
+    loop_status: String,
+    /// Position when playback was last started or paused, and when that was.
+    position: (i64, Instant),
+}
+impl FakePlayer {
+    fn current_position(&self) -> i64 {
+        let (position, since) = self.position;
+        if self.playing {
+            (position + since.elapsed().as_micros() as i64).min(TRACK_LENGTH)
+        } else {
+            position
+        }
+    }
+
+    async fn set_playing(&mut self, playing: bool, emitter: &SignalEmitter<'_>) {
+        self.position = (self.current_position(), Instant::now());
+        self.playing = playing;
+        self.playback_status_changed(emitter).await.ok();
+    }
+
+    async fn change_track(&mut self, offset: i32, emitter: &SignalEmitter<'_>) {
+        self.track = self.track.saturating_add_signed(offset);
+        self.position = (0, Instant::now());
+        self.metadata_changed(emitter).await.ok();
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl FakePlayer {
+    async fn play(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] Play");
+        self.set_playing(true, &emitter).await;
+    }
+    async fn pause(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] Pause");
+        self.set_playing(false, &emitter).await;
+    }
+    async fn play_pause(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] PlayPause");
+        self.set_playing(!self.playing, &emitter).await;
+    }
+    async fn stop(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] Stop");
+        self.set_playing(false, &emitter).await;
+        self.position = (0, Instant::now());
+    }
+    async fn next(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] Next");
+        self.change_track(1, &emitter).await;
+    }
+    async fn previous(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        println!("[fake player] Previous");
+        self.change_track(-1, &emitter).await;
+    }
+    async fn seek(&mut self, offset: i64, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        let position = (self.current_position() + offset).clamp(0, TRACK_LENGTH);
+        println!("[fake player] Seek to {position}");
+        self.position = (position, Instant::now());
+        Self::seeked(&emitter, position).await.ok();
+    }
+    async fn set_position(
+        &mut self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        println!("[fake player] SetPosition {track_id} {position}");
+        if track_id.as_str() != self.track_id() || !(0..=TRACK_LENGTH).contains(&position) {
+            return;
+        }
+        self.position = (position, Instant::now());
+        Self::seeked(&emitter, position).await.ok();
+    }
+    fn open_uri(&self, uri: &str) {
+        println!("[fake player] OpenUri {uri}");
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        if self.playing { "Playing" } else { "Paused" }
+    }
+    #[zbus(property)]
+    fn loop_status(&self) -> &str {
+        &self.loop_status
+    }
+    #[zbus(property)]
+    fn set_loop_status(&mut self, loop_status: String) {
+        self.loop_status = loop_status;
+    }
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+    #[zbus(property)]
+    fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+    }
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut metadata = HashMap::new();
+        let mut insert = |key: &str, value: Value| {
+            metadata.insert(key.to_string(), value.try_to_owned().unwrap());
+        };
+        insert(
+            "mpris:trackid",
+            ObjectPath::try_from(self.track_id()).unwrap().into(),
+        );
+        insert("mpris:length", TRACK_LENGTH.into());
+        insert("xesam:title", format!("Track {}", self.track).into());
+        insert("xesam:artist", vec!["The Fakes", "Ballad"].into());
+        insert("xesam:album", "Greatest Hits".into());
+        metadata
+    }
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.volume
+    }
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.current_position()
+    }
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.track > 1
+    }
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        true
+    }
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+impl FakePlayer {
+    fn track_id(&self) -> String {
+        format!("/com/gavinniederman/ballad/FakePlayer/Track/{}", self.track)
+    }
+}
+
+fn print_player(player: &MediaPlayer) {
+    println!(
+        "{} ({}): {:?} \"{}\" by {} on {} [{}s / {}s] volume {:.2}",
+        player.identity(),
+        player.bus_name(),
+        player.playback_status(),
+        player.title(),
+        player.artist(),
+        player.album(),
+        player.position() / 1_000_000,
+        player.length() / 1_000_000,
+        player.volume(),
+    );
+}
+
+fn main() {
+    let main_loop = glib::MainLoop::new(None, false);
+
+    glib::spawn_future_local(async {
+        let player_connection = zbus::connection::Builder::session()
+            .unwrap()
+            .name("org.mpris.MediaPlayer2.ballad_fake")
+            .unwrap()
+            .serve_at(MPRIS_OBJECT_PATH, FakeMediaPlayer2)
+            .unwrap()
+            .serve_at(
+                MPRIS_OBJECT_PATH,
+                FakePlayer {
+                    playing: true,
+                    track: 1,
+                    volume: 0.5,
+                    shuffle: false,
+                    loop_status: "None".to_string(),
+                    position: (0, Instant::now()),
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .expect("Failed to start the fake player");
+
+        let connection = zbus::Connection::session()
+            .await
+            .expect("Failed to connect to the session bus");
+        let service = MprisService::with_connection(connection);
+
+        service.connect_active_player_notify(|service| {
+            let Some(player) = service.active_player() else {
+                println!("No active player");
+                return;
+            };
+            print_player(&player);
+            player.connect_closure(
+                "changed",
+                false,
+                glib::closure_local!(move |player: MediaPlayer| print_player(&player)),
+            );
+        });
+
+        glib::timeout_add_seconds_local(
+            5,
+            clone!(
+                #[weak]
+                service,
+                #[upgrade_or]
+                ControlFlow::Break,
+                move || {
+                    if let Some(player) = service.active_player() {
+                        smol::block_on(player.play_pause()).unwrap();
+                    }
+                    ControlFlow::Continue
+                }
+            ),
+        );
+
+        // Keep the player and the service alive for the lifetime of the main loop.
+        std::mem::forget(player_connection);
+        std::mem::forget(service);
+    });
+
+    main_loop.run();
+}
@@ -0,0 +1,990 @@
+//! Serves a mock NetworkManager on the session bus and drives it through the network service.
+//!
+//! The mock has a Wi-Fi and an ethernet device, a few access points, a saved Wi-Fi network whose password has to be
+//! provided by the secret agent, and a saved VPN. Every few seconds the example performs the next step of a script:
+//! connecting to networks, toggling the VPN, disconnecting and finally disabling Wi-Fi. Run it on a private session
+//! bus so the mock can own the NetworkManager name:
+//! ```sh
+//! dbus-run-session -- cargo run --example mock_network_manager
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use ballad_services::network::{
+    AccessPoint, NETWORK_MANAGER_BUS_NAME, NETWORK_MANAGER_OBJECT_PATH, NetworkService,
+    SECRET_AGENT_OBJECT_PATH, VpnConnection,
+};
+use gtk::glib::{self, ControlFlow, clone};
+use gtk::prelude::*;
+use zbus::{
+    fdo, interface,
+    message::Header,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+const WIFI_DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+const ETHERNET_DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/2";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const AGENT_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager/AgentManager";
+const PASSWORD: &str = "hunter2";
+
+fn access_point_path(index: usize) -> String {
+    format!("/org/freedesktop/NetworkManager/AccessPoint/{index}")
+}
+fn settings_connection_path(id: u32) -> String {
+    format!("{SETTINGS_PATH}/{id}")
+}
+fn active_connection_path(id: u32) -> String {
+    format!("/org/freedesktop/NetworkManager/ActiveConnection/{id}")
+}
+fn object_path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).unwrap()
+}
+fn root_path() -> OwnedObjectPath {
+    object_path("/")
+}
+
+struct MockAccessPointInfo {
+    ssid: &'static str,
+    strength: u8,
+    flags: u32,
+    wpa_flags: u32,
+    rsn_flags: u32,
+    frequency: u32,
+}
+
+enum SavedKind {
+    Wifi { ssid: String, psk: Option<String> },
+    Vpn,
+}
+
+struct SavedConnection {
+    id: u32,
+    name: String,
+    kind: SavedKind,
+}
+impl SavedConnection {
+    fn settings(&self) -> ConnectionSettings {
+        let owned = |value: Value| value.try_to_owned().unwrap();
+        let mut settings = ConnectionSettings::new();
+        let type_ = match self.kind {
+            SavedKind::Wifi { .. } => "802-11-wireless",
+            SavedKind::Vpn => "vpn",
+        };
+        settings.insert(
+            "connection".to_string(),
+            HashMap::from([
+                ("id".to_string(), owned(self.name.as_str().into())),
+                ("type".to_string(), owned(type_.into())),
+            ]),
+        );
+        if let SavedKind::Wifi { ssid, psk } = &self.kind {
+            settings.insert(
+                "802-11-wireless".to_string(),
+                HashMap::from([("ssid".to_string(), owned(ssid.as_bytes().into()))]),
+            );
+            if psk.is_some() {
+                settings.insert(
+                    "802-11-wireless-security".to_string(),
+                    HashMap::from([("key-mgmt".to_string(), owned("wpa-psk".into()))]),
+                );
+            }
+        }
+        settings
+    }
+}
+
+struct ActiveWifi {
+    active_id: u32,
+    access_point: usize,
+}
+
+struct MockState {
+    wireless_enabled: bool,
+    access_points: Vec<MockAccessPointInfo>,
+    saved: Vec<SavedConnection>,
+    wifi: Option<ActiveWifi>,
+    /// The active connection id of the VPN, while it is connected.
+    vpn: Option<u32>,
+    agent: Option<String>,
+    last_scan: i64,
+    next_id: u32,
+}
+impl MockState {
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        self.wifi
+            .iter()
+            .map(|wifi| wifi.active_id)
+            .chain(self.vpn)
+            .map(|id| object_path(&active_connection_path(id)))
+            .collect()
+    }
+}
+
+type State = Arc<Mutex<MockState>>;
+
+/// Notifies the service that the state of the mock has changed.
+async fn emit_changes(connection: &zbus::Connection) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let root = server
+        .interface::<_, MockNetworkManager>(NETWORK_MANAGER_OBJECT_PATH)
+        .await?;
+    let root_emitter = root.signal_emitter();
+    let root = root.get().await;
+    root.active_connections_changed(root_emitter).await?;
+    root.primary_connection_changed(root_emitter).await?;
+    root.state_changed(root_emitter).await?;
+
+    let wireless = server
+        .interface::<_, MockWireless>(WIFI_DEVICE_PATH)
+        .await?;
+    wireless
+        .get()
+        .await
+        .active_access_point_changed(wireless.signal_emitter())
+        .await?;
+    Ok(())
+}
+
+/// Emits the changes once the current method call has been answered.
+fn emit_changes_later(connection: &zbus::Connection) {
+    let connection = connection.clone();
+    connection
+        .executor()
+        .spawn(
+            {
+                let connection = connection.clone();
+                async move {
+                    emit_changes(&connection).await.ok();
+                }
+            },
+            "mock-nm-changes",
+        )
+        .detach();
+}
+
+async fn serve_active_connection(
+    connection: &zbus::Connection,
+    id: u32,
+    settings_id: u32,
+    name: String,
+    vpn: bool,
+) -> zbus::Result<()> {
+    connection
+        .object_server()
+        .at(
+            active_connection_path(id),
+            MockActiveConnection {
+                settings_id,
+                name,
+                vpn,
+            },
+        )
+        .await?;
+    Ok(())
+}
+
+/// Asks the registered secret agent for the password of a saved network, like NetworkManager does.
+async fn request_secret(
+    connection: &zbus::Connection,
+    agent: &str,
+    saved: ConnectionSettings,
+    settings_id: u32,
+) -> zbus::Result<Option<String>> {
+    let reply = connection
+        .call_method(
+            Some(agent),
+            SECRET_AGENT_OBJECT_PATH,
+            Some("org.freedesktop.NetworkManager.SecretAgent"),
+            "GetSecrets",
+            &(
+                saved,
+                object_path(&settings_connection_path(settings_id)),
+                "802-11-wireless-security",
+                Vec::<String>::new(),
+                1u32,
+            ),
+        )
+        .await?;
+    let secrets: ConnectionSettings = reply.body().deserialize()?;
+    Ok(secrets
+        .get("802-11-wireless-security")
+        .and_then(|setting| setting.get("psk"))
+        .and_then(|psk| String::try_from(psk.try_clone().ok()?).ok()))
+}
+
+/// Activates the saved Wi-Fi connection `settings_id` on `access_point`, asking for its password if needed.
+async fn activate_wifi(
+    connection: zbus::Connection,
+    state: State,
+    settings_id: u32,
+    access_point: usize,
+) -> zbus::Result<()> {
+    let (name, needs_secret, agent, settings) = {
+        let state = state.lock().unwrap();
+        let saved = state
+            .saved
+            .iter()
+            .find(|saved| saved.id == settings_id)
+            .unwrap();
+        let needs_secret = matches!(&saved.kind, SavedKind::Wifi { psk: None, .. })
+            && state.access_points[access_point].flags != 0;
+        (
+            saved.name.clone(),
+            needs_secret,
+            state.agent.clone(),
+            saved.settings(),
+        )
+    };
+
+    if needs_secret {
+        let Some(agent) = agent else {
+            println!("[mock nm] No secret agent registered, can't activate {name}");
+            return Ok(());
+        };
+        match request_secret(&connection, &agent, settings, settings_id).await {
+            Ok(Some(psk)) if psk == PASSWORD => println!("[mock nm] Agent provided the password"),
+            Ok(_) => {
+                println!("[mock nm] Agent provided the wrong password for {name}");
+                return Ok(());
+            }
+            Err(err) => {
+                println!("[mock nm] Agent did not provide a password for {name}: {err}");
+                return Ok(());
+            }
+        }
+    }
+
+    let active_id = state.lock().unwrap().next_id();
+    serve_active_connection(&connection, active_id, settings_id, name, false).await?;
+    state.lock().unwrap().wifi = Some(ActiveWifi {
+        active_id,
+        access_point,
+    });
+    emit_changes(&connection).await
+}
+
+struct MockNetworkManager {
+    state: State,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager")]
+impl MockNetworkManager {
+    async fn activate_connection(
+        &self,
+        connection_path: ObjectPath<'_>,
+        _device: ObjectPath<'_>,
+        specific_object: ObjectPath<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<OwnedObjectPath> {
+        println!("[mock nm] ActivateConnection {connection_path} {specific_object}");
+        let (settings_id, is_vpn) = {
+            let state = self.state.lock().unwrap();
+            let saved = state
+                .saved
+                .iter()
+                .find(|saved| settings_connection_path(saved.id) == connection_path.as_str())
+                .ok_or_else(|| fdo::Error::UnknownObject(connection_path.to_string()))?;
+            (saved.id, matches!(saved.kind, SavedKind::Vpn))
+        };
+
+        if is_vpn {
+            let active_id = {
+                let mut state = self.state.lock().unwrap();
+                let active_id = state.next_id();
+                state.vpn = Some(active_id);
+                active_id
+            };
+            serve_active_connection(connection, active_id, settings_id, "Work VPN".into(), true)
+                .await?;
+            emit_changes_later(connection);
+            return Ok(object_path(&active_connection_path(active_id)));
+        }
+
+        let access_point = (1..=self.state.lock().unwrap().access_points.len())
+            .find(|index| access_point_path(*index) == specific_object.as_str())
+            .map(|index| index - 1)
+            .ok_or_else(|| fdo::Error::InvalidArgs("Unknown access point".to_string()))?;
+
+        // Like NetworkManager, activation finishes in the background.
+        connection
+            .executor()
+            .spawn(
+                activate_wifi(
+                    connection.clone(),
+                    self.state.clone(),
+                    settings_id,
+                    access_point,
+                ),
+                "mock-nm-activate",
+            )
+            .detach();
+        Ok(root_path())
+    }
+
+    async fn add_and_activate_connection(
+        &self,
+        settings: ConnectionSettings,
+        device: ObjectPath<'_>,
+        specific_object: ObjectPath<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+        let get_string = |setting: &str, key: &str| {
+            settings
+                .get(setting)
+                .and_then(|setting| setting.get(key))
+                .and_then(|value| String::try_from(value.try_clone().ok()?).ok())
+        };
+        let name = get_string("connection", "id").unwrap_or_default();
+        let psk = get_string("802-11-wireless-security", "psk");
+        println!(
+            "[mock nm] AddAndActivateConnection {name} with {}",
+            match psk.as_deref() {
+                Some(PASSWORD) => "the correct password",
+                Some(_) => "a wrong password",
+                None => "no password",
+            }
+        );
+
+        let settings_id = {
+            let mut state = self.state.lock().unwrap();
+            let settings_id = state.next_id();
+            state.saved.push(SavedConnection {
+                id: settings_id,
+                name: name.clone(),
+                kind: SavedKind::Wifi {
+                    ssid: name,
+                    psk: psk.filter(|psk| psk == PASSWORD),
+                },
+            });
+            settings_id
+        };
+        let path = settings_connection_path(settings_id);
+        connection
+            .object_server()
+            .at(
+                path.as_str(),
+                MockSettingsConnection {
+                    state: self.state.clone(),
+                    id: settings_id,
+                },
+            )
+            .await?;
+        let settings_emitter = SignalEmitter::new(connection, SETTINGS_PATH)?;
+        MockSettings::new_connection(
+            &settings_emitter,
+            ObjectPath::try_from(path.as_str()).map_err(zbus::Error::from)?,
+        )
+        .await?;
+
+        let active = self
+            .activate_connection(
+                ObjectPath::try_from(path.as_str()).map_err(zbus::Error::from)?,
+                device,
+                specific_object,
+                connection,
+            )
+            .await?;
+        self.active_connections_changed(&emitter).await?;
+        Ok((object_path(&path), active))
+    }
+
+    async fn deactivate_connection(
+        &self,
+        active_connection: ObjectPath<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        println!("[mock nm] DeactivateConnection {active_connection}");
+        {
+            let mut state = self.state.lock().unwrap();
+            if state
+                .vpn
+                .is_some_and(|id| active_connection_path(id) == active_connection.as_str())
+            {
+                state.vpn = None;
+            }
+            if state.wifi.as_ref().is_some_and(|wifi| {
+                active_connection_path(wifi.active_id) == active_connection.as_str()
+            }) {
+                state.wifi = None;
+            }
+        }
+        connection
+            .object_server()
+            .remove::<MockActiveConnection, _>(active_connection)
+            .await?;
+        emit_changes_later(connection);
+        Ok(())
+    }
+
+    fn get_devices(&self) -> Vec<OwnedObjectPath> {
+        vec![
+            object_path(WIFI_DEVICE_PATH),
+            object_path(ETHERNET_DEVICE_PATH),
+        ]
+    }
+
+    #[zbus(property)]
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        self.state.lock().unwrap().active_connections()
+    }
+    #[zbus(property)]
+    fn primary_connection(&self) -> OwnedObjectPath {
+        self.state
+            .lock()
+            .unwrap()
+            .wifi
+            .as_ref()
+            .map(|wifi| object_path(&active_connection_path(wifi.active_id)))
+            .unwrap_or_else(root_path)
+    }
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        if self.state.lock().unwrap().wifi.is_some() {
+            70
+        } else {
+            20
+        }
+    }
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> bool {
+        self.state.lock().unwrap().wireless_enabled
+    }
+    #[zbus(property)]
+    async fn set_wireless_enabled(
+        &mut self,
+        enabled: bool,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) {
+        println!("[mock nm] WirelessEnabled = {enabled}");
+        let active = {
+            let mut state = self.state.lock().unwrap();
+            state.wireless_enabled = enabled;
+            if enabled { None } else { state.wifi.take() }
+        };
+        if let Some(active) = active {
+            connection
+                .object_server()
+                .remove::<MockActiveConnection, _>(active_connection_path(active.active_id))
+                .await
+                .ok();
+        }
+    }
+    #[zbus(property)]
+    fn wireless_hardware_enabled(&self) -> bool {
+        true
+    }
+}
+
+struct MockDevice {
+    state: State,
+    wifi: bool,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl MockDevice {
+    async fn disconnect(
+        &self,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        println!("[mock nm] Disconnect");
+        let active = self.state.lock().unwrap().wifi.take();
+        if let Some(active) = active {
+            connection
+                .object_server()
+                .remove::<MockActiveConnection, _>(active_connection_path(active.active_id))
+                .await?;
+        }
+        emit_changes_later(connection);
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn active_connection(&self) -> OwnedObjectPath {
+        match (self.wifi, self.state.lock().unwrap().wifi.as_ref()) {
+            (true, Some(wifi)) => object_path(&active_connection_path(wifi.active_id)),
+            _ => root_path(),
+        }
+    }
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        if self.wifi { 2 } else { 1 }
+    }
+    #[zbus(property)]
+    fn interface(&self) -> &str {
+        if self.wifi { "wlan0" } else { "enp1s0" }
+    }
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        if !self.wifi || self.state.lock().unwrap().wifi.is_some() {
+            100
+        } else {
+            30
+        }
+    }
+}
+
+struct MockWireless {
+    state: State,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl MockWireless {
+    fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+        let state = self.state.lock().unwrap();
+        if !state.wireless_enabled {
+            return Vec::new();
+        }
+        (1..=state.access_points.len())
+            .map(|index| object_path(&access_point_path(index)))
+            .collect()
+    }
+
+    async fn request_scan(
+        &self,
+        _options: HashMap<String, OwnedValue>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        println!("[mock nm] RequestScan");
+        {
+            let mut state = self.state.lock().unwrap();
+            state.last_scan += 1;
+            // Signals fluctuate a little between scans.
+            for access_point in state.access_points.iter_mut() {
+                access_point.strength = access_point.strength.saturating_sub(3).max(10);
+            }
+        }
+        self.last_scan_changed(&emitter).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> OwnedObjectPath {
+        self.state
+            .lock()
+            .unwrap()
+            .wifi
+            .as_ref()
+            .map(|wifi| object_path(&access_point_path(wifi.access_point + 1)))
+            .unwrap_or_else(root_path)
+    }
+    #[zbus(property)]
+    fn last_scan(&self) -> i64 {
+        self.state.lock().unwrap().last_scan
+    }
+}
+
+struct MockAccessPoint {
+    state: State,
+    index: usize,
+}
+impl MockAccessPoint {
+    fn with_info<T>(&self, f: impl FnOnce(&MockAccessPointInfo) -> T) -> T {
+        f(&self.state.lock().unwrap().access_points[self.index])
+    }
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl MockAccessPoint {
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        self.with_info(|info| info.flags)
+    }
+    #[zbus(property)]
+    fn frequency(&self) -> u32 {
+        self.with_info(|info| info.frequency)
+    }
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        self.with_info(|info| info.rsn_flags)
+    }
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        self.with_info(|info| info.ssid.as_bytes().to_vec())
+    }
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        self.with_info(|info| info.strength)
+    }
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        self.with_info(|info| info.wpa_flags)
+    }
+}
+
+struct MockActiveConnection {
+    settings_id: u32,
+    name: String,
+    vpn: bool,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+impl MockActiveConnection {
+    #[zbus(property)]
+    fn connection(&self) -> OwnedObjectPath {
+        object_path(&settings_connection_path(self.settings_id))
+    }
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        &self.name
+    }
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> &str {
+        if self.vpn { "vpn" } else { "802-11-wireless" }
+    }
+    #[zbus(property)]
+    fn vpn(&self) -> bool {
+        self.vpn
+    }
+}
+
+struct MockSettings {
+    state: State,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings")]
+impl MockSettings {
+    fn list_connections(&self) -> Vec<OwnedObjectPath> {
+        self.state
+            .lock()
+            .unwrap()
+            .saved
+            .iter()
+            .map(|saved| object_path(&settings_connection_path(saved.id)))
+            .collect()
+    }
+
+    #[zbus(signal)]
+    async fn new_connection(
+        emitter: &SignalEmitter<'_>,
+        connection: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+}
+
+struct MockSettingsConnection {
+    state: State,
+    id: u32,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+impl MockSettingsConnection {
+    fn get_settings(&self) -> fdo::Result<ConnectionSettings> {
+        self.state
+            .lock()
+            .unwrap()
+            .saved
+            .iter()
+            .find(|saved| saved.id == self.id)
+            .map(SavedConnection::settings)
+            .ok_or_else(|| fdo::Error::UnknownObject(settings_connection_path(self.id)))
+    }
+}
+
+struct MockAgentManager {
+    state: State,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.AgentManager")]
+impl MockAgentManager {
+    fn register(&self, identifier: &str, #[zbus(header)] header: Header<'_>) {
+        let sender = header.sender().map(|sender| sender.to_string());
+        println!("[mock nm] Secret agent {identifier} registered from {sender:?}");
+        self.state.lock().unwrap().agent = sender;
+    }
+
+    fn unregister(&self) {
+        self.state.lock().unwrap().agent = None;
+    }
+}
+
+fn initial_state() -> MockState {
+    MockState {
+        wireless_enabled: true,
+        access_points: vec![
+            MockAccessPointInfo {
+                ssid: "Home",
+                strength: 82,
+                flags: 0x1,
+                wpa_flags: 0,
+                rsn_flags: 0x100,
+                frequency: 5180,
+            },
+            MockAccessPointInfo {
+                ssid: "Coffee Shop",
+                strength: 64,
+                flags: 0,
+                wpa_flags: 0,
+                rsn_flags: 0,
+                frequency: 2437,
+            },
+            MockAccessPointInfo {
+                ssid: "Neighbour",
+                strength: 31,
+                flags: 0x1,
+                wpa_flags: 0,
+                rsn_flags: 0x400,
+                frequency: 2412,
+            },
+            MockAccessPointInfo {
+                ssid: "Office",
+                strength: 45,
+                flags: 0x1,
+                wpa_flags: 0,
+                rsn_flags: 0x200,
+                frequency: 5500,
+            },
+        ],
+        saved: vec![
+            SavedConnection {
+                id: 1,
+                name: "Home".to_string(),
+                kind: SavedKind::Wifi {
+                    ssid: "Home".to_string(),
+                    psk: None,
+                },
+            },
+            SavedConnection {
+                id: 2,
+                name: "Work VPN".to_string(),
+                kind: SavedKind::Vpn,
+            },
+        ],
+        wifi: None,
+        vpn: None,
+        agent: None,
+        last_scan: 0,
+        next_id: 10,
+    }
+}
+
+async fn serve_mock() -> zbus::Result<zbus::Connection> {
+    let state: State = Arc::new(Mutex::new(initial_state()));
+
+    let mut builder = zbus::connection::Builder::session()?
+        .serve_at(
+            NETWORK_MANAGER_OBJECT_PATH,
+            MockNetworkManager {
+                state: state.clone(),
+            },
+        )?
+        .serve_at(
+            WIFI_DEVICE_PATH,
+            MockDevice {
+                state: state.clone(),
+                wifi: true,
+            },
+        )?
+        .serve_at(
+            WIFI_DEVICE_PATH,
+            MockWireless {
+                state: state.clone(),
+            },
+        )?
+        .serve_at(
+            ETHERNET_DEVICE_PATH,
+            MockDevice {
+                state: state.clone(),
+                wifi: false,
+            },
+        )?
+        .serve_at(
+            SETTINGS_PATH,
+            MockSettings {
+                state: state.clone(),
+            },
+        )?
+        .serve_at(
+            AGENT_MANAGER_PATH,
+            MockAgentManager {
+                state: state.clone(),
+            },
+        )?;
+    for index in 0..state.lock().unwrap().access_points.len() {
+        builder = builder.serve_at(
+            access_point_path(index + 1),
+            MockAccessPoint {
+                state: state.clone(),
+                index,
+            },
+        )?;
+    }
+    for id in [1, 2] {
+        builder = builder.serve_at(
+            settings_connection_path(id),
+            MockSettingsConnection {
+                state: state.clone(),
+                id,
+            },
+        )?;
+    }
+
+    builder.name(NETWORK_MANAGER_BUS_NAME)?.build().await
+}
+
+fn print_state(service: &NetworkService) {
+    println!(
+        "Connected: {} via {:?} ({:?}), Wi-Fi {}",
+        service.connected(),
+        service.primary_connection(),
+        service.primary_connection_type(),
+        if service.wifi_enabled() { "on" } else { "off" },
+    );
+    for access_point in service.access_points().iter::<AccessPoint>().flatten() {
+        println!(
+            "  {} {:>3}% {} {}MHz{}",
+            if access_point.active() { "*" } else { " " },
+            access_point.strength(),
+            access_point.ssid(),
+            access_point.frequency(),
+            match access_point.known_connection() {
+                Some(_) => format!(" [{}, saved]", access_point.security().as_str()),
+                None => format!(" [{}]", access_point.security().as_str()),
+            }
+        );
+    }
+    for vpn in service.vpn_connections().iter::<VpnConnection>().flatten() {
+        println!(
+            "  VPN {}: {}",
+            vpn.name(),
+            if vpn.active() { "on" } else { "off" }
+        );
+    }
+}
+
+fn find_access_point(service: &NetworkService, ssid: &str) -> Option<AccessPoint> {
+    service
+        .access_points()
+        .iter::<AccessPoint>()
+        .flatten()
+        .find(|access_point| access_point.ssid() == ssid)
+}
+
+/// Performs the next step of the script, returning false once it has finished.
+fn run_step(service: &NetworkService, step: u32) -> bool {
+    let result = match step {
+        0 => {
+            println!("> Connecting to Home, which asks the secret agent for its password");
+            let home = find_access_point(service, "Home").unwrap();
+            smol::block_on(service.connect_to_access_point(&home, None))
+        }
+        1 => {
+            println!("> Connecting to the open Coffee Shop network");
+            let coffee_shop = find_access_point(service, "Coffee Shop").unwrap();
+            smol::block_on(service.connect_to_access_point(&coffee_shop, None))
+        }
+        2 => {
+            println!("> Connecting to Neighbour with a password");
+            let neighbour = find_access_point(service, "Neighbour").unwrap();
+            smol::block_on(service.connect_to_access_point(&neighbour, Some(PASSWORD.to_string())))
+        }
+        3 => {
+            println!("> Connecting to the enterprise Office network, which isn't supported");
+            let office = find_access_point(service, "Office").unwrap();
+            smol::block_on(service.connect_to_access_point(&office, None))
+        }
+        4 | 5 => {
+            let vpn = service
+                .vpn_connections()
+                .iter::<VpnConnection>()
+                .flatten()
+                .next()
+                .unwrap();
+            println!(
+                "> Turning {} {}",
+                vpn.name(),
+                if step == 4 { "on" } else { "off" }
+            );
+            smol::block_on(service.set_vpn_active(&vpn, step == 4))
+        }
+        6 => {
+            println!("> Scanning");
+            smol::block_on(service.request_scan())
+        }
+        7 => {
+            println!("> Disconnecting");
+            smol::block_on(service.disconnect_wifi())
+        }
+        8 => {
+            println!("> Disabling Wi-Fi");
+            smol::block_on(service.set_wifi_enabled(false))
+        }
+        _ => return false,
+    };
+    if let Err(err) = result {
+        println!("Failed: {err}");
+    }
+    true
+}
+
+fn main() {
+    let main_loop = glib::MainLoop::new(None, false);
+
+    glib::spawn_future_local(clone!(
+        #[strong]
+        main_loop,
+        async move {
+            let mock_connection = serve_mock()
+                .await
+                .expect("Failed to serve the mock NetworkManager. Is it running on this bus?");
+
+            let connection = zbus::Connection::session()
+                .await
+                .expect("Failed to connect to the session bus");
+            let service = NetworkService::with_connection(connection);
+
+            service.connect_closure(
+                "changed",
+                false,
+                glib::closure_local!(move |service: NetworkService| print_state(&service)),
+            );
+            // Stands in for the password prompt in the shell.
+            service.connect_closure(
+                "secret-requested",
+                false,
+                glib::closure_local!(move |service: NetworkService, id: u32, name: String| {
+                    println!("Password requested for {name}, answering with {PASSWORD}");
+                    service.provide_secret(id, Some(PASSWORD.to_string()));
+                }),
+            );
+
+            let step = std::cell::Cell::new(0);
+            glib::timeout_add_seconds_local(
+                3,
+                clone!(
+                    #[weak]
+                    service,
+                    #[strong]
+                    main_loop,
+                    #[upgrade_or]
+                    ControlFlow::Break,
+                    move || {
+                        if !run_step(&service, step.get()) {
+                            main_loop.quit();
+                            return ControlFlow::Break;
+                        }
+                        step.set(step.get() + 1);
+                        ControlFlow::Continue
+                    }
+                ),
+            );
+
+            // Keep the mock and the service alive for the lifetime of the main loop.
+            std::mem::forget(mock_connection);
+            std::mem::forget(service);
+        }
+    ));
+
+    main_loop.run();
+}
@@ -0,0 +1,62 @@
+//! Runs the notification service on its own and prints every notification it receives.
+//!
+//! Use a private session bus to test without replacing your running notification daemon:
+//! ```sh
+//! dbus-run-session -- sh -c 'cargo run --example notification_daemon & sleep 2; notify-send -u critical -A ok=OK "Hello" "<b>World</b>"; wait'
+//! ```
+
+use ballad_services::notifications::{Notification, NotificationsService};
+use gtk::glib::{self, closure_local};
+use gtk::prelude::*;
+
+fn main() {
+    let main_loop = glib::MainLoop::new(None, false);
+
+    glib::spawn_future_local(async {
+        let connection = zbus::Connection::session()
+            .await
+            .expect("Failed to connect to the session bus");
+        let service = NotificationsService::with_connection(connection).await;
+        if !service.available() {
+            return;
+        }
+
+        service.connect_closure(
+            "notified",
+            false,
+            closure_local!(
+                move |service: NotificationsService, notification: Notification, replaced: bool| {
+                    println!(
+                        "{} notification {} from {:?} ({:?}): {} - {}",
+                        if replaced { "Replaced" } else { "New" },
+                        notification.id(),
+                        notification.app_name(),
+                        notification.urgency(),
+                        notification.summary(),
+                        notification.body(),
+                    );
+                    for action in notification.actions() {
+                        println!("  action {}: {}", action.key, action.label);
+                    }
+
+                    // Invoke the first action to exercise the ActionInvoked signal.
+                    if let Some(action) = notification.actions().first() {
+                        smol::block_on(service.invoke_action(notification.id(), &action.key));
+                    }
+                }
+            ),
+        );
+        service.connect_closure(
+            "closed",
+            false,
+            closure_local!(move |_: NotificationsService, id: u32, reason: u32| {
+                println!("Closed notification {id} with reason {reason}");
+            }),
+        );
+
+        // Keep the service alive for the lifetime of the main loop.
+        std::mem::forget(service);
+    });
+
+    main_loop.run();
+}
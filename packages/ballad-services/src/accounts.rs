@@ -302,6 +302,12 @@ mod user_imp {
         user_name: RefCell<String>,
         #[property(get)]
         uid: Cell<u64>,
+        /// The Wayland session the user last logged into, as a `.desktop` file name without the extension.
+        #[property(get)]
+        session: RefCell<Option<String>>,
+        /// The X11 session the user last logged into.
+        #[property(get)]
+        xsession: RefCell<Option<String>>,
 
         pub(super) proxy: RwLock<Option<UserProxy<'static>>>,
     }
@@ -310,7 +316,17 @@ mod user_imp {
             let proxy = self.proxy.read().await;
             let proxy = proxy.as_ref().unwrap();
 
-            let (account_type, icon_file, email, real_name, login_frequency, user_name, uid) = join!(
+            let (
+                account_type,
+                icon_file,
+                email,
+                real_name,
+                login_frequency,
+                user_name,
+                uid,
+                session,
+                xsession,
+            ) = join!(
                 proxy.account_type(),
                 proxy.icon_file(),
                 proxy.email(),
@@ -318,6 +334,8 @@ mod user_imp {
                 proxy.login_frequency(),
                 proxy.user_name(),
                 proxy.uid(),
+                proxy.session(),
+                proxy.xsession(),
             );
 
             self.account_type
@@ -348,6 +366,12 @@ mod user_imp {
             self.obj().notify_user_name();
             self.uid.set(uid.unwrap_or_default());
             self.obj().notify_uid();
+            self.session
+                .replace(none_if_empty(session.unwrap_or_default()));
+            self.obj().notify_session();
+            self.xsession
+                .replace(none_if_empty(xsession.unwrap_or_default()));
+            self.obj().notify_xsession();
 
             self.obj().emit_by_name::<()>("changed", &[]);
         }
@@ -397,6 +421,24 @@ mod user_imp {
             self.real_name.replace(Some(name.to_string()));
             self.obj().notify_real_name();
 
+            Ok(())
+        }
+        pub async fn set_session(&self, session: &str) -> zbus::Result<()> {
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+            proxy.set_session(session).await?;
+            self.session.replace(Some(session.to_string()));
+            self.obj().notify_session();
+
+            Ok(())
+        }
+        pub async fn set_xsession(&self, xsession: &str) -> zbus::Result<()> {
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+            proxy.set_xsession(xsession).await?;
+            self.xsession.replace(Some(xsession.to_string()));
+            self.obj().notify_xsession();
+
             Ok(())
         }
     }
@@ -617,6 +659,12 @@ impl User {
     pub async fn set_real_name(&self, name: &str) -> zbus::Result<()> {
         self.imp().set_real_name(name).await
     }
+    pub async fn set_session(&self, session: &str) -> zbus::Result<()> {
+        self.imp().set_session(session).await
+    }
+    pub async fn set_xsession(&self, xsession: &str) -> zbus::Result<()> {
+        self.imp().set_xsession(xsession).await
+    }
 }
 
 thread_local! {
@@ -0,0 +1,377 @@
+use std::cell::LazyCell;
+use std::collections::HashMap;
+
+use ballad_config::{BatteryConfig, battery::CriticalBatteryAction};
+use gtk::glib::{self, Object};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use zbus::{proxy, zvariant::Value};
+
+use crate::{
+    DBUS_SESSION_CONNECTION,
+    logind::PowerAction,
+    notifications::{NOTIFICATIONS_BUS_NAME, NOTIFICATIONS_OBJECT_PATH, Urgency},
+    power_profiles::POWER_PROFILES_SERVICE,
+};
+
+const APP_NAME: &str = "Ballad";
+const POWER_SAVER_PROFILE: &str = "power-saver";
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    /// Notify method
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Profile switches are made by one task in order, so going back to the previous profile can't overtake switching away
+/// from it.
+enum ProfileChange {
+    PowerSaver,
+    Restore,
+}
+
+/// Makes the profile switches sent to it, remembering the profile to go back to.
+async fn switch_profiles(changes: smol::channel::Receiver<ProfileChange>) {
+    let mut previous_profile = None;
+    while let Ok(change) = changes.recv().await {
+        let service = POWER_PROFILES_SERVICE.with(|service| (**service).clone());
+        match change {
+            ProfileChange::PowerSaver => {
+                let previous = service.active_profile().await;
+                if previous == POWER_SAVER_PROFILE {
+                    continue;
+                }
+                match service.switch_profile(POWER_SAVER_PROFILE).await {
+                    Ok(()) => previous_profile = Some(previous),
+                    Err(err) => println!("Failed to switch to the power-saver profile: {err}"),
+                }
+            }
+            ProfileChange::Restore => {
+                let Some(profile) = previous_profile.take() else {
+                    continue;
+                };
+                if let Err(err) = service.switch_profile(&profile).await {
+                    println!("Failed to restore the {profile} power profile: {err}");
+                }
+            }
+        }
+    }
+}
+
+fn critical_action_name(action: CriticalBatteryAction) -> &'static str {
+    match action {
+        CriticalBatteryAction::Nothing => "do nothing",
+        CriticalBatteryAction::Suspend => "suspend",
+        CriticalBatteryAction::Hibernate => "hibernate",
+        CriticalBatteryAction::HybridSleep => "hybrid sleep",
+        CriticalBatteryAction::PowerOff => "power off",
+    }
+}
+
+fn power_action(action: CriticalBatteryAction) -> Option<PowerAction> {
+    match action {
+        CriticalBatteryAction::Nothing => None,
+        CriticalBatteryAction::Suspend => Some(PowerAction::Suspend),
+        CriticalBatteryAction::Hibernate => Some(PowerAction::Hibernate),
+        CriticalBatteryAction::HybridSleep => Some(PowerAction::HybridSleep),
+        CriticalBatteryAction::PowerOff => Some(PowerAction::PowerOff),
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+    use std::sync::OnceLock;
+
+    use ballad_config::{BatteryConfig, ShellConfig, battery::CriticalBatteryAction};
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, SourceId, clone, closure_local};
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+
+    use crate::config::{CONFIG_SERVICE, ConfigService};
+    use crate::logind::LOGIND_SERVICE;
+    use crate::notifications::Urgency;
+    use crate::upower::{UPOWER_SERVICE, UPowerService};
+
+    use super::{ProfileChange, critical_action_name, power_action, switch_profiles};
+
+    #[derive(Properties, Default)]
+    #[properties(wrapper_type = super::BatteryPolicyService)]
+    pub struct BatteryPolicyService {
+        /// The most severe warning level reached since the computer was last plugged in, or 0.
+        #[property(get)]
+        warning_level: Cell<u32>,
+        /// Whether the policy switched to the power-saver profile.
+        #[property(get)]
+        power_saver_engaged: Cell<bool>,
+        /// Whether the critical action will be performed unless the computer is plugged in.
+        #[property(get)]
+        critical_pending: Cell<bool>,
+
+        pub(super) config: RefCell<BatteryConfig>,
+        /// Whether power profiles are enabled in the config. The policy leaves the profile alone otherwise.
+        power_profiles_enabled: Cell<bool>,
+        profile_changes: OnceCell<smol::channel::Sender<ProfileChange>>,
+        critical_timeout: RefCell<Option<SourceId>>,
+    }
+
+    impl BatteryPolicyService {
+        pub(super) fn evaluate(&self) {
+            let (available, on_battery, percentage) = UPOWER_SERVICE.with(|service| {
+                (
+                    service.available(),
+                    service.on_battery(),
+                    service.percentage(),
+                )
+            });
+            if !available {
+                return;
+            }
+            if !on_battery {
+                self.reset();
+                return;
+            }
+
+            let config = self.config.borrow().clone();
+
+            if let Some(level) = config.warning_level_for(percentage) {
+                let level = level as u32;
+                let previous = self.warning_level.get();
+                if previous == 0 || level < previous {
+                    self.warning_level.set(level);
+                    self.obj().notify_warning_level();
+                    self.obj()
+                        .emit_by_name::<()>("warning", &[&level, &percentage]);
+                    super::send_notification(
+                        "Battery low",
+                        &format!("{percentage:.0}% of the battery remains."),
+                        "battery-low-symbolic",
+                        Urgency::Normal,
+                    );
+                }
+            }
+
+            if config
+                .power_saver_level
+                .is_some_and(|level| percentage <= level as f64)
+                && !self.power_saver_engaged.get()
+                && self.power_profiles_enabled.get()
+            {
+                self.engage_power_saver();
+            }
+
+            let action = config.critical_action;
+            if percentage <= config.critical_level as f64
+                && power_action(action).is_some()
+                && self.critical_timeout.borrow().is_none()
+            {
+                self.schedule_critical_action(action, config.critical_action_delay_seconds);
+            }
+        }
+
+        /// Forgets the warnings and undoes everything the policy did once the computer is plugged in.
+        fn reset(&self) {
+            if self.warning_level.replace(0) != 0 {
+                self.obj().notify_warning_level();
+            }
+            if let Some(timeout) = self.critical_timeout.take() {
+                timeout.remove();
+                self.critical_pending.set(false);
+                self.obj().notify_critical_pending();
+            }
+            if self.power_saver_engaged.replace(false) {
+                self.obj().notify_power_saver_engaged();
+                self.change_profile(ProfileChange::Restore);
+            }
+        }
+
+        fn engage_power_saver(&self) {
+            self.power_saver_engaged.set(true);
+            self.obj().notify_power_saver_engaged();
+            self.change_profile(ProfileChange::PowerSaver);
+        }
+
+        fn change_profile(&self, change: ProfileChange) {
+            if let Some(changes) = self.profile_changes.get() {
+                _ = changes.try_send(change);
+            }
+        }
+
+        fn schedule_critical_action(&self, action: CriticalBatteryAction, delay: u32) {
+            self.critical_pending.set(true);
+            self.obj().notify_critical_pending();
+            self.obj()
+                .emit_by_name::<()>("critical", &[&action, &delay]);
+            super::send_notification(
+                "Battery critically low",
+                &format!(
+                    "The computer will {} in {delay} seconds unless it is plugged in.",
+                    critical_action_name(action)
+                ),
+                "battery-caution-symbolic",
+                Urgency::Critical,
+            );
+
+            let timeout = glib::timeout_add_seconds_local_once(
+                delay,
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move || {
+                        this.critical_timeout.take();
+                        this.critical_pending.set(false);
+                        this.obj().notify_critical_pending();
+                        if !UPOWER_SERVICE.with(|service| service.on_battery()) {
+                            return;
+                        }
+                        let Some(action) = power_action(action) else {
+                            return;
+                        };
+                        glib::spawn_future_local(async move {
+                            let logind = LOGIND_SERVICE.with(|service| (**service).clone());
+                            if let Err(err) = logind.perform(action).await {
+                                println!("Failed to perform the critical battery action: {err}");
+                            }
+                        });
+                    }
+                ),
+            );
+            self.critical_timeout.replace(Some(timeout));
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BatteryPolicyService {
+        const NAME: &'static str = "BalladServicesBatteryPolicyService";
+        type Type = super::BatteryPolicyService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for BatteryPolicyService {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let (sender, receiver) = smol::channel::unbounded();
+            _ = self.profile_changes.set(sender);
+            glib::spawn_future_local(switch_profiles(receiver));
+
+            CONFIG_SERVICE.with(|service| {
+                let config = service.shell_config();
+                self.config.replace(config.battery);
+                self.power_profiles_enabled
+                    .set(config.power_profiles.show_selector);
+
+                service.connect_closure(
+                    "shell-config-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: ConfigService, config: &ShellConfig| {
+                            this.config.replace(config.battery.clone());
+                            this.power_profiles_enabled
+                                .set(config.power_profiles.show_selector);
+                            this.evaluate();
+                        }
+                    ),
+                );
+            });
+
+            UPOWER_SERVICE.with(|service| {
+                service.connect_closure(
+                    "battery-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: UPowerService| this.evaluate()
+                    ),
+                );
+                service.connect_on_battery_notify(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| this.evaluate()
+                ));
+            });
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("warning")
+                        .param_types([u32::static_type(), f64::static_type()])
+                        .build(),
+                    Signal::builder("critical")
+                        .param_types([CriticalBatteryAction::static_type(), u32::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+}
+
+/// Shows a notification through whichever notification daemon is running, usually our own.
+fn send_notification(summary: &str, body: &str, icon: &str, urgency: Urgency) {
+    let (summary, body, icon) = (summary.to_string(), body.to_string(), icon.to_string());
+    glib::spawn_future_local(async move {
+        let result = async {
+            let proxy = NotificationsProxy::builder(&DBUS_SESSION_CONNECTION)
+                .destination(NOTIFICATIONS_BUS_NAME)?
+                .path(NOTIFICATIONS_OBJECT_PATH)?
+                .build()
+                .await?;
+            let hints = HashMap::from([("urgency", Value::U8(urgency as u8))]);
+            proxy
+                .notify(APP_NAME, 0, &icon, &summary, &body, &[], hints, -1)
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            println!("Failed to send a battery notification: {err}");
+        }
+    });
+}
+
+glib::wrapper! {
+    /// Warns about a low battery, switches to power-saver and performs the critical action according to
+    /// [`BatteryConfig`].
+    pub struct BatteryPolicyService(ObjectSubclass<imp::BatteryPolicyService>);
+}
+impl BatteryPolicyService {
+    pub fn new() -> Self {
+        let this: Self = Object::builder().build();
+        glib::idle_add_local_once(glib::clone!(
+            #[weak]
+            this,
+            move || this.imp().evaluate()
+        ));
+        this
+    }
+
+    pub fn config(&self) -> BatteryConfig {
+        self.imp().config.borrow().clone()
+    }
+}
+impl Default for BatteryPolicyService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static BATTERY_POLICY_SERVICE: LazyCell<BatteryPolicyService> = LazyCell::new(BatteryPolicyService::new);
+}
@@ -0,0 +1,945 @@
+use std::cell::LazyCell;
+
+use gtk::{
+    glib::{self, Object},
+    prelude::*,
+    subclass::prelude::ObjectSubclassIsExt,
+};
+use zbus::zvariant::{ObjectPath, Value};
+
+use crate::DBUS_SYSTEM_CONNECTION;
+
+pub const BLUEZ_BUS_NAME: &str = "org.bluez";
+pub const BLUEZ_OBJECT_PATH: &str = "/org/bluez";
+pub const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+pub const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+pub const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+pub const AGENT_OBJECT_PATH: &str = "/com/gavinniederman/ballad/BluetoothAgent";
+/// We can show passkeys and ask for them, so BlueZ may use any pairing method.
+const AGENT_CAPABILITY: &str = "KeyboardDisplay";
+
+mod bus {
+    //! # D-Bus interface proxies for `org.bluez`
+
+    use zbus::{proxy, zvariant::ObjectPath};
+
+    #[proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+    pub trait Adapter {
+        /// RemoveDevice method
+        fn remove_device(&self, device: &ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// StartDiscovery method
+        fn start_discovery(&self) -> zbus::Result<()>;
+
+        /// StopDiscovery method
+        fn stop_discovery(&self) -> zbus::Result<()>;
+
+        /// Discoverable property
+        #[zbus(property)]
+        fn discoverable(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn set_discoverable(&self, value: bool) -> zbus::Result<()>;
+
+        /// Powered property
+        #[zbus(property)]
+        fn powered(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn set_powered(&self, value: bool) -> zbus::Result<()>;
+    }
+
+    #[proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+    pub trait Device {
+        /// CancelPairing method
+        fn cancel_pairing(&self) -> zbus::Result<()>;
+
+        /// Connect method
+        fn connect(&self) -> zbus::Result<()>;
+
+        /// Disconnect method
+        fn disconnect(&self) -> zbus::Result<()>;
+
+        /// Pair method
+        fn pair(&self) -> zbus::Result<()>;
+
+        /// Trusted property
+        #[zbus(property)]
+        fn trusted(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn set_trusted(&self, value: bool) -> zbus::Result<()>;
+    }
+
+    #[proxy(
+        interface = "org.bluez.AgentManager1",
+        default_service = "org.bluez",
+        default_path = "/org/bluez"
+    )]
+    pub trait AgentManager {
+        /// RegisterAgent method
+        fn register_agent(&self, agent: &ObjectPath<'_>, capability: &str) -> zbus::Result<()>;
+
+        /// RequestDefaultAgent method
+        fn request_default_agent(&self, agent: &ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// UnregisterAgent method
+        fn unregister_agent(&self, agent: &ObjectPath<'_>) -> zbus::Result<()>;
+    }
+}
+
+/// What a pairing agent request wants from the user.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesBluetoothRequestKind")]
+pub enum BluetoothRequestKind {
+    /// Enter a PIN code. Answered with the code.
+    PinCode,
+    /// Enter the passkey shown on the device. Answered with the passkey.
+    Passkey,
+    /// Check that the device shows the same passkey. Answered by accepting or rejecting.
+    #[default]
+    Confirmation,
+    /// Allow an unpaired device to pair. Answered by accepting or rejecting.
+    Authorization,
+    /// Allow a device to use a service. Answered by accepting or rejecting.
+    ServiceAuthorization,
+    /// Type the PIN code on the device. Needs no answer.
+    DisplayPinCode,
+    /// Type the passkey on the device. Needs no answer.
+    DisplayPasskey,
+}
+impl BluetoothRequestKind {
+    /// Whether the request is only informational.
+    pub fn is_display(&self) -> bool {
+        matches!(self, Self::DisplayPinCode | Self::DisplayPasskey)
+    }
+
+    /// Whether answering the request needs text from the user.
+    pub fn needs_input(&self) -> bool {
+        matches!(self, Self::PinCode | Self::Passkey)
+    }
+}
+
+mod agent {
+    //! An `org.bluez.Agent1` that forwards pairing requests to the shell.
+
+    use smol::channel::{Receiver, Sender};
+    use zbus::{interface, zvariant::OwnedObjectPath};
+
+    use super::BluetoothRequestKind;
+
+    #[derive(Debug, zbus::DBusError)]
+    #[zbus(prefix = "org.bluez.Error")]
+    pub enum AgentError {
+        #[zbus(error)]
+        ZBus(zbus::Error),
+        Rejected(String),
+        Canceled(String),
+    }
+
+    pub struct AgentRequest {
+        pub kind: BluetoothRequestKind,
+        pub device_path: String,
+        /// The passkey, PIN code or service UUID the request is about, if any.
+        pub value: String,
+        /// Receives the user's input when they accept. Closed when they reject.
+        pub respond: Option<Sender<String>>,
+    }
+
+    pub enum AgentEvent {
+        Request(AgentRequest),
+        Cancel,
+    }
+
+    pub struct Agent {
+        pub events: Sender<AgentEvent>,
+    }
+    impl Agent {
+        async fn display(
+            &self,
+            kind: BluetoothRequestKind,
+            device: OwnedObjectPath,
+            value: String,
+        ) {
+            self.events
+                .send(AgentEvent::Request(AgentRequest {
+                    kind,
+                    device_path: device.to_string(),
+                    value,
+                    respond: None,
+                }))
+                .await
+                .ok();
+        }
+
+        async fn request(
+            &self,
+            kind: BluetoothRequestKind,
+            device: OwnedObjectPath,
+            value: String,
+        ) -> Result<String, AgentError> {
+            let (respond, response): (_, Receiver<String>) = smol::channel::bounded(1);
+            self.events
+                .send(AgentEvent::Request(AgentRequest {
+                    kind,
+                    device_path: device.to_string(),
+                    value,
+                    respond: Some(respond),
+                }))
+                .await
+                .map_err(|_| AgentError::Rejected("The shell is not running".to_string()))?;
+            response
+                .recv()
+                .await
+                .map_err(|_| AgentError::Rejected("The user rejected the request".to_string()))
+        }
+    }
+
+    #[interface(name = "org.bluez.Agent1")]
+    impl Agent {
+        fn release(&self) {}
+
+        async fn request_pin_code(&self, device: OwnedObjectPath) -> Result<String, AgentError> {
+            self.request(BluetoothRequestKind::PinCode, device, String::new())
+                .await
+        }
+
+        async fn display_pin_code(&self, device: OwnedObjectPath, pincode: String) {
+            self.display(BluetoothRequestKind::DisplayPinCode, device, pincode)
+                .await
+        }
+
+        async fn request_passkey(&self, device: OwnedObjectPath) -> Result<u32, AgentError> {
+            let passkey = self
+                .request(BluetoothRequestKind::Passkey, device, String::new())
+                .await?;
+            passkey
+                .trim()
+                .parse()
+                .map_err(|_| AgentError::Rejected(format!("{passkey} is not a valid passkey")))
+        }
+
+        async fn display_passkey(&self, device: OwnedObjectPath, passkey: u32, _entered: u16) {
+            self.display(
+                BluetoothRequestKind::DisplayPasskey,
+                device,
+                format!("{passkey:06}"),
+            )
+            .await
+        }
+
+        async fn request_confirmation(
+            &self,
+            device: OwnedObjectPath,
+            passkey: u32,
+        ) -> Result<(), AgentError> {
+            self.request(
+                BluetoothRequestKind::Confirmation,
+                device,
+                format!("{passkey:06}"),
+            )
+            .await
+            .map(|_| ())
+        }
+
+        async fn request_authorization(&self, device: OwnedObjectPath) -> Result<(), AgentError> {
+            self.request(BluetoothRequestKind::Authorization, device, String::new())
+                .await
+                .map(|_| ())
+        }
+
+        async fn authorize_service(
+            &self,
+            device: OwnedObjectPath,
+            uuid: String,
+        ) -> Result<(), AgentError> {
+            self.request(BluetoothRequestKind::ServiceAuthorization, device, uuid)
+                .await
+                .map(|_| ())
+        }
+
+        async fn cancel(&self) {
+            self.events.send(AgentEvent::Cancel).await.ok();
+        }
+    }
+}
+
+fn value_bool(value: &Value<'_>) -> bool {
+    value.downcast_ref::<bool>().unwrap_or_default()
+}
+fn value_string(value: &Value<'_>) -> String {
+    value
+        .downcast_ref::<&str>()
+        .map(str::to_string)
+        .unwrap_or_default()
+}
+
+mod adapter_imp {
+    use std::cell::{Cell, RefCell};
+
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::BluetoothAdapter)]
+    pub struct BluetoothAdapter {
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get)]
+        pub(super) address: RefCell<String>,
+        #[property(get)]
+        pub(super) name: RefCell<String>,
+        #[property(get)]
+        pub(super) powered: Cell<bool>,
+        #[property(get)]
+        pub(super) discoverable: Cell<bool>,
+        #[property(get)]
+        pub(super) discovering: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BluetoothAdapter {
+        const NAME: &'static str = "BalladServicesBluetoothAdapter";
+        type Type = super::BluetoothAdapter;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for BluetoothAdapter {}
+}
+
+mod device_imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::BluetoothDevice)]
+    pub struct BluetoothDevice {
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get)]
+        pub(super) adapter: RefCell<String>,
+        #[property(get)]
+        pub(super) address: RefCell<String>,
+        /// The user facing name, falling back to the address for nameless devices.
+        #[property(get)]
+        pub(super) name: RefCell<String>,
+        /// A freedesktop icon name such as `audio-headset`.
+        #[property(get)]
+        pub(super) icon: RefCell<Option<String>>,
+        #[property(get)]
+        pub(super) paired: Cell<bool>,
+        #[property(get)]
+        pub(super) trusted: Cell<bool>,
+        #[property(get)]
+        pub(super) connected: Cell<bool>,
+        /// Battery percentage, or -1 if the device doesn't report one.
+        #[property(get)]
+        pub(super) battery_percentage: Cell<i32>,
+
+        pub(super) connection: RefCell<Option<zbus::Connection>>,
+    }
+    impl Default for BluetoothDevice {
+        fn default() -> Self {
+            Self {
+                path: Default::default(),
+                adapter: Default::default(),
+                address: Default::default(),
+                name: Default::default(),
+                icon: Default::default(),
+                paired: Default::default(),
+                trusted: Default::default(),
+                connected: Default::default(),
+                battery_percentage: Cell::new(-1),
+                connection: Default::default(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BluetoothDevice {
+        const NAME: &'static str = "BalladServicesBluetoothDevice";
+        type Type = super::BluetoothDevice;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for BluetoothDevice {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    use futures::FutureExt;
+    use gtk::gio::ListStore;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties, clone};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::channel::Sender;
+    use smol::stream::StreamExt;
+    use zbus::fdo::{ObjectManagerProxy, PropertiesChanged};
+    use zbus::zvariant::{ObjectPath, Value};
+
+    use super::agent::{Agent, AgentEvent};
+    use super::bus::AgentManagerProxy;
+    use super::{
+        ADAPTER_INTERFACE, BATTERY_INTERFACE, BluetoothAdapter, BluetoothDevice,
+        BluetoothRequestKind, DEVICE_INTERFACE,
+    };
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::BluetoothService)]
+    pub struct BluetoothService {
+        #[property(get)]
+        available: Cell<bool>,
+        #[property(get)]
+        adapters: ListStore,
+        /// The adapter used for discovery and power, the first one BlueZ reports.
+        #[property(get)]
+        adapter: RefCell<Option<BluetoothAdapter>>,
+        /// Whether the default adapter is powered.
+        #[property(get)]
+        powered: Cell<bool>,
+        /// Whether the default adapter is discovering devices.
+        #[property(get)]
+        discovering: Cell<bool>,
+        #[property(get)]
+        devices: ListStore,
+
+        pub(super) connection: RefCell<Option<zbus::Connection>>,
+        /// Agent requests waiting for the user, by request id.
+        pub(super) pending_requests: RefCell<HashMap<u32, Sender<String>>>,
+        next_request_id: Cell<u32>,
+    }
+    impl Default for BluetoothService {
+        fn default() -> Self {
+            Self {
+                available: Default::default(),
+                adapters: ListStore::with_type(BluetoothAdapter::static_type()),
+                adapter: Default::default(),
+                powered: Default::default(),
+                discovering: Default::default(),
+                devices: ListStore::with_type(BluetoothDevice::static_type()),
+                connection: Default::default(),
+                pending_requests: Default::default(),
+                next_request_id: Default::default(),
+            }
+        }
+    }
+
+    impl BluetoothService {
+        pub(super) fn connection(&self) -> zbus::Connection {
+            self.connection.borrow().clone().unwrap()
+        }
+
+        fn find_adapter(&self, path: &str) -> Option<BluetoothAdapter> {
+            self.adapters
+                .iter::<BluetoothAdapter>()
+                .flatten()
+                .find(|adapter| adapter.path() == path)
+        }
+
+        pub(super) fn find_device(&self, path: &str) -> Option<BluetoothDevice> {
+            self.devices
+                .iter::<BluetoothDevice>()
+                .flatten()
+                .find(|device| device.path() == path)
+        }
+
+        fn sync_default_adapter(&self) {
+            let adapter = self.adapters.item(0).and_downcast::<BluetoothAdapter>();
+            if *self.adapter.borrow() != adapter {
+                self.adapter.replace(adapter.clone());
+                self.obj().notify_adapter();
+            }
+            let (powered, discovering) = adapter
+                .map(|adapter| (adapter.powered(), adapter.discovering()))
+                .unwrap_or_default();
+            if self.powered.replace(powered) != powered {
+                self.obj().notify_powered();
+            }
+            if self.discovering.replace(discovering) != discovering {
+                self.obj().notify_discovering();
+            }
+        }
+
+        /// Applies properties of `interface` on the object at `path`, adding the object if it's new.
+        fn apply_properties<'a>(
+            &self,
+            path: &str,
+            interface: &str,
+            properties: impl IntoIterator<Item = (&'a str, &'a Value<'a>)>,
+        ) {
+            match interface {
+                ADAPTER_INTERFACE => {
+                    let adapter = self.find_adapter(path).unwrap_or_else(|| {
+                        let adapter = BluetoothAdapter::with_path(path);
+                        self.adapters.append(&adapter);
+                        adapter
+                    });
+                    adapter.apply_properties(properties);
+                    self.sync_default_adapter();
+                }
+                DEVICE_INTERFACE | BATTERY_INTERFACE => {
+                    let device = self.find_device(path).unwrap_or_else(|| {
+                        let device = BluetoothDevice::with_path(self.connection(), path);
+                        self.devices.append(&device);
+                        device
+                    });
+                    device.apply_properties(properties);
+                }
+                _ => {}
+            }
+        }
+
+        fn remove_interfaces(&self, path: &str, interfaces: &[&str]) {
+            if interfaces.contains(&ADAPTER_INTERFACE) {
+                if let Some(position) = self
+                    .adapters
+                    .iter::<BluetoothAdapter>()
+                    .position(|adapter| adapter.is_ok_and(|adapter| adapter.path() == path))
+                {
+                    self.adapters.remove(position as u32);
+                }
+                self.sync_default_adapter();
+            }
+            if interfaces.contains(&DEVICE_INTERFACE) {
+                if let Some(position) = self
+                    .devices
+                    .iter::<BluetoothDevice>()
+                    .position(|device| device.is_ok_and(|device| device.path() == path))
+                {
+                    self.devices.remove(position as u32);
+                }
+            } else if interfaces.contains(&BATTERY_INTERFACE)
+                && let Some(device) = self.find_device(path)
+            {
+                device.imp().battery_percentage.set(-1);
+                device.notify_battery_percentage();
+                device.emit_by_name::<()>("changed", &[]);
+            }
+        }
+
+        fn handle_agent_event(&self, event: AgentEvent) {
+            match event {
+                AgentEvent::Request(request) => {
+                    let id = self.next_request_id.get();
+                    self.next_request_id.set(id.wrapping_add(1));
+                    if let Some(respond) = request.respond {
+                        self.pending_requests.borrow_mut().insert(id, respond);
+                    }
+                    let device_name = self
+                        .find_device(&request.device_path)
+                        .map(|device| device.name())
+                        .unwrap_or(request.device_path);
+                    self.obj().emit_by_name::<()>(
+                        "agent-request",
+                        &[&id, &request.kind, &device_name, &request.value],
+                    );
+                }
+                // BlueZ only cancels the request it is currently waiting on, so every pending one goes.
+                AgentEvent::Cancel => {
+                    let cancelled = self.pending_requests.take();
+                    for id in cancelled.keys() {
+                        self.obj()
+                            .emit_by_name::<()>("agent-request-cancelled", &[id]);
+                    }
+                }
+            }
+        }
+
+        async fn register_agent(&self) -> zbus::Result<()> {
+            let connection = self.connection();
+            let (events, receiver) = smol::channel::unbounded();
+            connection
+                .object_server()
+                .at(super::AGENT_OBJECT_PATH, Agent { events })
+                .await?;
+
+            let agent_manager = AgentManagerProxy::new(&connection).await?;
+            let path = ObjectPath::try_from(super::AGENT_OBJECT_PATH)?;
+            agent_manager
+                .register_agent(&path, super::AGENT_CAPABILITY)
+                .await?;
+            agent_manager.request_default_agent(&path).await?;
+
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while let Ok(event) = receiver.recv().await {
+                        this.handle_agent_event(event);
+                    }
+                }
+            ));
+
+            Ok(())
+        }
+
+        async fn watch(&self, object_manager: ObjectManagerProxy<'static>) -> zbus::Result<()> {
+            let connection = self.connection();
+            let mut added_stream = object_manager.receive_interfaces_added().await?;
+            let mut removed_stream = object_manager.receive_interfaces_removed().await?;
+
+            let properties_changed = zbus::MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .sender(super::BLUEZ_BUS_NAME)?
+                .interface("org.freedesktop.DBus.Properties")?
+                .member("PropertiesChanged")?
+                .path_namespace(super::BLUEZ_OBJECT_PATH)?
+                .build();
+            let mut properties_stream =
+                zbus::MessageStream::for_match_rule(properties_changed, &connection, None).await?;
+
+            loop {
+                futures::select! {
+                    added = added_stream.next().fuse() => {
+                        let Some(added) = added else { break };
+                        let Ok(args) = added.args() else { continue };
+                        for (interface, properties) in args.interfaces_and_properties() {
+                            self.apply_properties(
+                                args.object_path().as_str(),
+                                interface.as_str(),
+                                properties.iter().map(|(name, value)| (*name, value)),
+                            );
+                        }
+                    },
+                    removed = removed_stream.next().fuse() => {
+                        let Some(removed) = removed else { break };
+                        let Ok(args) = removed.args() else { continue };
+                        let interfaces = args
+                            .interfaces()
+                            .iter()
+                            .map(|interface| interface.as_str())
+                            .collect::<Vec<_>>();
+                        self.remove_interfaces(args.object_path().as_str(), &interfaces);
+                    },
+                    message = properties_stream.next().fuse() => {
+                        let Some(Ok(message)) = message else { break };
+                        let Some(changed) = PropertiesChanged::from_message(message) else {
+                            continue;
+                        };
+                        let Some(path) = changed.message().header().path().map(|path| path.to_string()) else {
+                            continue;
+                        };
+                        let Ok(args) = changed.args() else { continue };
+                        // Objects we don't know about yet are picked up by InterfacesAdded.
+                        if self.find_adapter(&path).is_none() && self.find_device(&path).is_none() {
+                            continue;
+                        }
+                        self.apply_properties(
+                            &path,
+                            args.interface_name().as_str(),
+                            args.changed_properties().iter().map(|(name, value)| (*name, value)),
+                        );
+                    },
+                }
+            }
+
+            Ok(())
+        }
+
+        pub(super) async fn start(&self) {
+            let connection = self.connection();
+            let object_manager = match ObjectManagerProxy::builder(&connection)
+                .destination(super::BLUEZ_BUS_NAME)
+                .and_then(|builder| builder.path("/"))
+            {
+                Ok(builder) => builder.build().await,
+                Err(err) => Err(err),
+            };
+            let Ok(object_manager) = object_manager else {
+                println!(
+                    "Failed to create BlueZ ObjectManagerProxy. Bluetooth service will not function!"
+                );
+                return;
+            };
+            let Ok(objects) = object_manager.get_managed_objects().await else {
+                println!("BlueZ is not running. Bluetooth service will not function!");
+                return;
+            };
+
+            // Adapters first, so the default adapter is known before any devices show up.
+            let mut objects = objects.into_iter().collect::<Vec<_>>();
+            objects.sort_by_key(|(path, _)| path.to_string());
+            for (path, interfaces) in objects.iter() {
+                for (interface, properties) in interfaces {
+                    self.apply_properties(
+                        path.as_str(),
+                        interface.as_str(),
+                        properties
+                            .iter()
+                            .map(|(name, value)| (name.as_str(), &**value)),
+                    );
+                }
+            }
+            self.available.set(true);
+            self.obj().notify_available();
+
+            if let Err(err) = self.register_agent().await {
+                println!("Failed to register Bluetooth pairing agent: {err}");
+            }
+            if let Err(err) = self.watch(object_manager).await {
+                println!("Failed to watch BlueZ objects: {err}");
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BluetoothService {
+        const NAME: &'static str = "BalladServicesBluetoothService";
+        type Type = super::BluetoothService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for BluetoothService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    // Emitted with a request id, what is being asked, the device's name and the passkey, PIN code or
+                    // service the request is about.
+                    Signal::builder("agent-request")
+                        .param_types([
+                            u32::static_type(),
+                            BluetoothRequestKind::static_type(),
+                            String::static_type(),
+                            String::static_type(),
+                        ])
+                        .build(),
+                    Signal::builder("agent-request-cancelled")
+                        .param_types([u32::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct BluetoothAdapter(ObjectSubclass<adapter_imp::BluetoothAdapter>);
+}
+impl BluetoothAdapter {
+    fn with_path(path: &str) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().path.replace(path.to_string());
+        this
+    }
+
+    fn apply_properties<'a>(&self, properties: impl IntoIterator<Item = (&'a str, &'a Value<'a>)>) {
+        let imp = self.imp();
+        for (name, value) in properties {
+            match name {
+                "Address" => {
+                    imp.address.replace(value_string(value));
+                    self.notify_address();
+                }
+                "Alias" => {
+                    imp.name.replace(value_string(value));
+                    self.notify_name();
+                }
+                "Powered" => {
+                    imp.powered.set(value_bool(value));
+                    self.notify_powered();
+                }
+                "Discoverable" => {
+                    imp.discoverable.set(value_bool(value));
+                    self.notify_discoverable();
+                }
+                "Discovering" => {
+                    imp.discovering.set(value_bool(value));
+                    self.notify_discovering();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct BluetoothDevice(ObjectSubclass<device_imp::BluetoothDevice>);
+}
+impl BluetoothDevice {
+    fn with_path(connection: zbus::Connection, path: &str) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().path.replace(path.to_string());
+        this.imp().connection.replace(Some(connection));
+        this
+    }
+
+    fn apply_properties<'a>(&self, properties: impl IntoIterator<Item = (&'a str, &'a Value<'a>)>) {
+        let imp = self.imp();
+        for (name, value) in properties {
+            match name {
+                "Adapter" => {
+                    let adapter = value
+                        .downcast_ref::<ObjectPath>()
+                        .map(|path| path.to_string())
+                        .unwrap_or_default();
+                    imp.adapter.replace(adapter);
+                    self.notify_adapter();
+                }
+                "Address" => {
+                    imp.address.replace(value_string(value));
+                    self.notify_address();
+                    if imp.name.borrow().is_empty() {
+                        imp.name.replace(value_string(value));
+                        self.notify_name();
+                    }
+                }
+                "Alias" => {
+                    imp.name.replace(value_string(value));
+                    self.notify_name();
+                }
+                "Icon" => {
+                    imp.icon.replace(Some(value_string(value)));
+                    self.notify_icon();
+                }
+                "Paired" => {
+                    imp.paired.set(value_bool(value));
+                    self.notify_paired();
+                }
+                "Trusted" => {
+                    imp.trusted.set(value_bool(value));
+                    self.notify_trusted();
+                }
+                "Connected" => {
+                    imp.connected.set(value_bool(value));
+                    self.notify_connected();
+                }
+                "Percentage" => {
+                    imp.battery_percentage
+                        .set(value.downcast_ref::<u8>().map(i32::from).unwrap_or(-1));
+                    self.notify_battery_percentage();
+                }
+                _ => {}
+            }
+        }
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
+    async fn proxy(&self) -> zbus::Result<bus::DeviceProxy<'static>> {
+        let connection = self.imp().connection.borrow().clone().unwrap();
+        bus::DeviceProxy::builder(&connection)
+            .path(self.path())?
+            .build()
+            .await
+    }
+
+    pub async fn connect(&self) -> zbus::Result<()> {
+        self.proxy().await?.connect().await
+    }
+
+    pub async fn disconnect(&self) -> zbus::Result<()> {
+        self.proxy().await?.disconnect().await
+    }
+
+    /// Pairs with the device and trusts it, so it can reconnect by itself later.
+    ///
+    /// Pairing waits on the pairing agent, which is answered from the main loop. Don't block the main loop on this.
+    pub async fn pair(&self) -> zbus::Result<()> {
+        let proxy = self.proxy().await?;
+        proxy.pair().await?;
+        proxy.set_trusted(true).await
+    }
+
+    pub async fn cancel_pairing(&self) -> zbus::Result<()> {
+        self.proxy().await?.cancel_pairing().await
+    }
+}
+
+glib::wrapper! {
+    pub struct BluetoothService(ObjectSubclass<imp::BluetoothService>);
+}
+impl BluetoothService {
+    pub fn new() -> Self {
+        Self::with_connection(DBUS_SYSTEM_CONNECTION.clone())
+    }
+
+    /// Talks to BlueZ on `connection` instead of the system bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().connection.replace(Some(connection));
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                this.imp().start().await;
+            }
+        ));
+
+        this
+    }
+
+    async fn adapter_proxy(&self) -> zbus::Result<bus::AdapterProxy<'static>> {
+        let Some(adapter) = self.adapter() else {
+            return Err(zbus::Error::Failure(
+                "There is no Bluetooth adapter".to_string(),
+            ));
+        };
+        bus::AdapterProxy::builder(&self.imp().connection())
+            .path(adapter.path())?
+            .build()
+            .await
+    }
+
+    pub async fn set_powered(&self, powered: bool) -> zbus::Result<()> {
+        self.adapter_proxy().await?.set_powered(powered).await
+    }
+
+    pub async fn set_discoverable(&self, discoverable: bool) -> zbus::Result<()> {
+        self.adapter_proxy()
+            .await?
+            .set_discoverable(discoverable)
+            .await
+    }
+
+    pub async fn set_discovering(&self, discovering: bool) -> zbus::Result<()> {
+        let proxy = self.adapter_proxy().await?;
+        if discovering {
+            proxy.start_discovery().await
+        } else {
+            proxy.stop_discovery().await
+        }
+    }
+
+    /// Unpairs `device` and forgets about it.
+    pub async fn remove_device(&self, device: &BluetoothDevice) -> zbus::Result<()> {
+        let connection = self.imp().connection();
+        let path = ObjectPath::try_from(device.path())?;
+        bus::AdapterProxy::builder(&connection)
+            .path(device.adapter())?
+            .build()
+            .await?
+            .remove_device(&path)
+            .await
+    }
+
+    /// Answers an `agent-request` signal. `input` is the PIN code or passkey for requests that need one.
+    pub fn accept_request(&self, request_id: u32, input: Option<String>) {
+        if let Some(respond) = self.imp().pending_requests.borrow_mut().remove(&request_id) {
+            respond.try_send(input.unwrap_or_default()).ok();
+        }
+    }
+
+    pub fn reject_request(&self, request_id: u32) {
+        self.imp().pending_requests.borrow_mut().remove(&request_id);
+    }
+}
+impl Default for BluetoothService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static BLUETOOTH_SERVICE: LazyCell<BluetoothService> = LazyCell::new(BluetoothService::new);
+}
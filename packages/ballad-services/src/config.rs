@@ -6,7 +6,7 @@ mod imp {
     use std::cell::RefCell;
     use std::sync::OnceLock;
 
-    use ballad_config::{ServiceConfig, ShellConfig, ThemeConfig};
+    use ballad_config::{ServiceConfig, ShellConfig, ThemeConfig, WallpaperConfig};
     use gtk::gio::Cancellable;
     use gtk::glib::clone;
     use gtk::glib::subclass::Signal;
@@ -90,6 +90,12 @@ mod imp {
                             this.obj()
                                 .emit_by_name::<()>("shell-theme-config-changed", &[&config.theme]);
                         }
+                        if config.wallpaper != this.last_shell_config.borrow().wallpaper {
+                            this.obj().emit_by_name::<()>(
+                                "shell-wallpaper-config-changed",
+                                &[&config.wallpaper],
+                            );
+                        }
 
                         this.obj().notify_shell_config();
 
@@ -129,6 +135,9 @@ mod imp {
                     Signal::builder("shell-theme-config-changed")
                         .param_types([ThemeConfig::static_type()])
                         .build(),
+                    Signal::builder("shell-wallpaper-config-changed")
+                        .param_types([WallpaperConfig::static_type()])
+                        .build(),
                     Signal::builder("service-config-changed")
                         .param_types([ServiceConfig::static_type()])
                         .build(),
@@ -0,0 +1,185 @@
+use std::cell::LazyCell;
+
+use ballad_config::{NotificationsConfig, TimeOfDay, notifications::NotificationAppRule};
+use gtk::{
+    glib::{self, Object},
+    subclass::prelude::ObjectSubclassIsExt,
+};
+
+use crate::{
+    config::CONFIG_SERVICE,
+    notifications::{Notification, Urgency},
+};
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use ballad_config::{NotificationsConfig, ShellConfig};
+    use gtk::glib::{self, ControlFlow, clone, closure_local};
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+
+    use crate::config::{CONFIG_SERVICE, ConfigService};
+
+    #[derive(Properties, Default)]
+    #[properties(wrapper_type = super::DoNotDisturbService)]
+    pub struct DoNotDisturbService {
+        /// Whether do not disturb was manually enabled.
+        #[property(get)]
+        enabled: Cell<bool>,
+        /// Whether the current time is within the configured quiet hours.
+        #[property(get)]
+        quiet_hours_active: Cell<bool>,
+        /// Whether popups are currently being suppressed, either manually or by quiet hours.
+        #[property(get)]
+        active: Cell<bool>,
+
+        pub(super) config: RefCell<NotificationsConfig>,
+    }
+
+    impl DoNotDisturbService {
+        pub(super) fn update(&self, config: NotificationsConfig) {
+            let quiet_hours_active = config
+                .quiet_hours
+                .is_some_and(|quiet_hours| quiet_hours.contains(super::now()));
+
+            if self.enabled.replace(config.do_not_disturb) != config.do_not_disturb {
+                self.obj().notify_enabled();
+            }
+            if self.quiet_hours_active.replace(quiet_hours_active) != quiet_hours_active {
+                self.obj().notify_quiet_hours_active();
+            }
+            let active = config.do_not_disturb || quiet_hours_active;
+            if self.active.replace(active) != active {
+                self.obj().notify_active();
+            }
+
+            self.config.replace(config);
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DoNotDisturbService {
+        const NAME: &'static str = "BalladServicesDoNotDisturbService";
+        type Type = super::DoNotDisturbService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for DoNotDisturbService {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            CONFIG_SERVICE.with(|service| {
+                self.update(service.shell_config().notifications);
+
+                service.connect_closure(
+                    "shell-config-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: ConfigService, config: &ShellConfig| {
+                            this.update(config.notifications.clone());
+                        }
+                    ),
+                );
+            });
+
+            // Quiet hours are checked every 30 seconds so they start within a minute of the configured time.
+            glib::timeout_add_seconds_local(
+                30,
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    #[upgrade_or]
+                    ControlFlow::Break,
+                    move || {
+                        let config = this.config.borrow().clone();
+                        this.update(config);
+                        ControlFlow::Continue
+                    }
+                ),
+            );
+        }
+    }
+}
+
+/// The current local time of day.
+fn now() -> TimeOfDay {
+    glib::DateTime::now_local()
+        .map(|now| TimeOfDay::new(now.hour() as u8, now.minute() as u8))
+        .unwrap_or_default()
+}
+
+glib::wrapper! {
+    pub struct DoNotDisturbService(ObjectSubclass<imp::DoNotDisturbService>);
+}
+impl DoNotDisturbService {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    /// Manually enables or disables do not disturb. This is saved to the shell config.
+    pub fn set_enabled(&self, enabled: bool) {
+        CONFIG_SERVICE.with(|service| {
+            let mut config = service.shell_config();
+            config.notifications.do_not_disturb = enabled;
+            service.set_shell_config(config);
+        });
+    }
+
+    pub fn config(&self) -> NotificationsConfig {
+        self.imp().config.borrow().clone()
+    }
+
+    fn matches_rule<T>(
+        &self,
+        notification: &Notification,
+        f: impl Fn(&NotificationAppRule) -> Option<T>,
+    ) -> Option<T> {
+        let config = self.imp().config.borrow();
+        let app_name = notification.app_name();
+        let desktop_entry = notification.desktop_entry();
+
+        [Some(app_name.as_str()), desktop_entry.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|app| config.rule_for(app))
+            .find_map(f)
+    }
+
+    /// Whether a popup should be shown for `notification`.
+    /// Notifications that are not shown as popups are still added to the notification center.
+    pub fn should_popup(&self, notification: &Notification) -> bool {
+        if self
+            .matches_rule(notification, |rule| rule.mute.then_some(()))
+            .is_some()
+        {
+            return false;
+        }
+        if !self.active() {
+            return true;
+        }
+
+        let always_show_critical = self
+            .matches_rule(notification, |rule| rule.always_show_critical)
+            .unwrap_or(self.imp().config.borrow().always_show_critical);
+        always_show_critical && notification.urgency() == Urgency::Critical
+    }
+
+    /// Whether `notification` may be written to the notification history.
+    pub fn should_persist(&self, notification: &Notification) -> bool {
+        !notification.transient()
+            && self
+                .matches_rule(notification, |rule| rule.never_persist.then_some(()))
+                .is_none()
+    }
+}
+impl Default for DoNotDisturbService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static DO_NOT_DISTURB_SERVICE: LazyCell<DoNotDisturbService> = LazyCell::new(DoNotDisturbService::new);
+}
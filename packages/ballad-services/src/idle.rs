@@ -0,0 +1,415 @@
+use std::cell::LazyCell;
+
+use ballad_config::IdleConfig;
+use gtk::glib::{self, Object};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+/// A stage of idleness, each with its own timeout in [`ballad_config::idle::IdleTimeouts`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesIdleStage")]
+pub enum IdleStage {
+    #[default]
+    Dim,
+    Lock,
+    DpmsOff,
+    Suspend,
+}
+
+mod wayland {
+    use wayland_client::{
+        Connection, Dispatch, QueueHandle,
+        globals::{GlobalListContents, registry_queue_init},
+        protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+    };
+    use wayland_protocols::ext::idle_notify::v1::client::{
+        ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+        ext_idle_notifier_v1::ExtIdleNotifierV1,
+    };
+
+    use super::IdleStage;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum IdleEvent {
+        Idled(IdleStage),
+        Resumed(IdleStage),
+    }
+
+    /// Lives on the thread dispatching Wayland events.
+    pub struct State {
+        sender: smol::channel::Sender<IdleEvent>,
+    }
+
+    impl Dispatch<WlRegistry, GlobalListContents> for State {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: <WlRegistry as wayland_client::Proxy>::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<WlSeat, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &WlSeat,
+            _: <WlSeat as wayland_client::Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ExtIdleNotifierV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &ExtIdleNotifierV1,
+            _: <ExtIdleNotifierV1 as wayland_client::Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ExtIdleNotificationV1, IdleStage> for State {
+        fn event(
+            state: &mut Self,
+            _: &ExtIdleNotificationV1,
+            event: ext_idle_notification_v1::Event,
+            stage: &IdleStage,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            let event = match event {
+                ext_idle_notification_v1::Event::Idled => IdleEvent::Idled(*stage),
+                ext_idle_notification_v1::Event::Resumed => IdleEvent::Resumed(*stage),
+                _ => return,
+            };
+            _ = state.sender.send_blocking(event);
+        }
+    }
+
+    /// Our own Wayland connection for `ext-idle-notify-v1`. Its events are dispatched on a separate thread and sent
+    /// back through a channel.
+    pub struct IdleNotifier {
+        connection: Connection,
+        queue: QueueHandle<State>,
+        notifier: ExtIdleNotifierV1,
+        seat: WlSeat,
+        notifications: Vec<ExtIdleNotificationV1>,
+    }
+    impl IdleNotifier {
+        pub fn connect(
+            sender: smol::channel::Sender<IdleEvent>,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let connection = Connection::connect_to_env()?;
+            let (globals, mut event_queue) = registry_queue_init::<State>(&connection)?;
+            let queue = event_queue.handle();
+            let notifier = globals.bind::<ExtIdleNotifierV1, _, _>(&queue, 1..=1, ())?;
+            let seat = globals.bind::<WlSeat, _, _>(&queue, 1..=1, ())?;
+
+            let mut state = State { sender };
+            std::thread::spawn(move || {
+                while event_queue.blocking_dispatch(&mut state).is_ok() {}
+                println!("Lost the Wayland connection. Idle service will not function!");
+            });
+
+            Ok(Self {
+                connection,
+                queue,
+                notifier,
+                seat,
+                notifications: Vec::new(),
+            })
+        }
+
+        /// Replaces the notifications with one per stage, which also restarts their timers. The compositor won't
+        /// report idleness while a Wayland idle inhibitor is active.
+        pub fn set_timeouts(&mut self, timeouts: &[(IdleStage, u32)]) {
+            for notification in self.notifications.drain(..) {
+                notification.destroy();
+            }
+            for &(stage, seconds) in timeouts {
+                self.notifications.push(self.notifier.get_idle_notification(
+                    seconds.saturating_mul(1000),
+                    &self.seat,
+                    &self.queue,
+                    stage,
+                ));
+            }
+            _ = self.connection.flush();
+        }
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use ballad_config::{IdleConfig, ShellConfig};
+    use gtk::glib::{self, clone, closure_local};
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+    use zbus::zvariant::OwnedFd;
+
+    use super::IdleStage;
+    use super::wayland::{IdleEvent, IdleNotifier};
+    use crate::brightness::BRIGHTNESS_SERVICE;
+    use crate::config::{CONFIG_SERVICE, ConfigService};
+    use crate::logind::{LOGIND_SERVICE, PowerAction};
+    use crate::niri::NIRI_SERVICE;
+    use crate::upower::UPOWER_SERVICE;
+
+    #[derive(Properties, Default)]
+    #[properties(wrapper_type = super::IdleService)]
+    pub struct IdleService {
+        /// Whether the compositor supports `ext-idle-notify-v1`.
+        #[property(get)]
+        available: Cell<bool>,
+        /// Whether the user asked to stay awake. Holds a logind idle inhibitor while enabled.
+        #[property(get)]
+        caffeine: Cell<bool>,
+        #[property(get)]
+        dimmed: Cell<bool>,
+        #[property(get)]
+        outputs_off: Cell<bool>,
+
+        pub(super) config: RefCell<IdleConfig>,
+        notifier: RefCell<Option<IdleNotifier>>,
+        caffeine_inhibitor: RefCell<Option<OwnedFd>>,
+        /// The brightness to go back to when the user returns.
+        brightness_before_dim: Cell<f64>,
+    }
+
+    impl IdleService {
+        pub(super) fn update_timeouts(&self) {
+            let mut notifier = self.notifier.borrow_mut();
+            let Some(notifier) = notifier.as_mut() else {
+                return;
+            };
+            let config = self.config.borrow();
+            if !config.enabled {
+                notifier.set_timeouts(&[]);
+                return;
+            }
+
+            let on_battery =
+                UPOWER_SERVICE.with(|service| service.available() && service.on_battery());
+            let timeouts = config.timeouts(on_battery);
+            let stages = [
+                (IdleStage::Dim, timeouts.dim),
+                (IdleStage::Lock, timeouts.lock),
+                (IdleStage::DpmsOff, timeouts.dpms_off),
+                (IdleStage::Suspend, timeouts.suspend),
+            ]
+            .into_iter()
+            .filter_map(|(stage, timeout)| Some((stage, timeout?)))
+            .collect::<Vec<_>>();
+            notifier.set_timeouts(&stages);
+        }
+
+        /// Whether caffeine or another program's logind idle inhibitor keeps us awake.
+        async fn inhibited(&self) -> bool {
+            if self.caffeine.get() {
+                return true;
+            }
+            let logind = LOGIND_SERVICE.with(|service| (**service).clone());
+            logind.inhibitors().await.is_ok_and(|inhibitors| {
+                inhibitors
+                    .iter()
+                    .any(|inhibitor| inhibitor.inhibits("idle") && inhibitor.blocks())
+            })
+        }
+
+        async fn idled(&self, stage: IdleStage) {
+            if self.inhibited().await {
+                return;
+            }
+            let logind = LOGIND_SERVICE.with(|service| (**service).clone());
+            let result = match stage {
+                IdleStage::Dim => {
+                    self.dim();
+                    Ok(())
+                }
+                IdleStage::Lock => logind.perform(PowerAction::Lock).await,
+                IdleStage::DpmsOff => {
+                    self.outputs_off.set(true);
+                    self.obj().notify_outputs_off();
+                    let niri = NIRI_SERVICE.with(|service| (**service).clone());
+                    niri.power_off_monitors().await;
+                    Ok(())
+                }
+                IdleStage::Suspend => {
+                    if self.config.borrow().lock_before_suspend {
+                        _ = logind.perform(PowerAction::Lock).await;
+                    }
+                    logind.perform(PowerAction::Suspend).await
+                }
+            };
+            if let Err(err) = result {
+                println!("Failed to perform the {stage:?} idle stage: {err}");
+            }
+        }
+
+        async fn resumed(&self, stage: IdleStage) {
+            match stage {
+                IdleStage::Dim => self.undim(),
+                IdleStage::DpmsOff if self.outputs_off.replace(false) => {
+                    self.obj().notify_outputs_off();
+                    let niri = NIRI_SERVICE.with(|service| (**service).clone());
+                    niri.power_on_monitors().await;
+                }
+                _ => {}
+            }
+        }
+
+        fn dim(&self) {
+            if self.dimmed.get() || !BRIGHTNESS_SERVICE.with(|service| service.available_blocking())
+            {
+                return;
+            }
+            let brightness = BRIGHTNESS_SERVICE.with(|service| service.brightness_blocking());
+            self.brightness_before_dim.set(brightness);
+            let dimmed = brightness * self.config.borrow().dim_brightness.clamp(0.0, 1.0);
+            BRIGHTNESS_SERVICE.with(|service| service.set_brightness(dimmed));
+            self.dimmed.set(true);
+            self.obj().notify_dimmed();
+        }
+
+        fn undim(&self) {
+            if !self.dimmed.replace(false) {
+                return;
+            }
+            let brightness = self.brightness_before_dim.get();
+            BRIGHTNESS_SERVICE.with(|service| service.set_brightness(brightness));
+            self.obj().notify_dimmed();
+        }
+
+        pub(super) fn set_caffeine(&self, enabled: bool) {
+            if self.caffeine.replace(enabled) == enabled {
+                return;
+            }
+            self.obj().notify_caffeine();
+            if !enabled {
+                self.caffeine_inhibitor.take();
+                return;
+            }
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    let logind = LOGIND_SERVICE.with(|service| (**service).clone());
+                    match logind.inhibit("idle", "Caffeine mode is on", "block").await {
+                        // It may have been turned off while waiting for logind.
+                        Ok(fd) if this.caffeine.get() => {
+                            this.caffeine_inhibitor.replace(Some(fd));
+                        }
+                        Ok(_) => {}
+                        Err(err) => println!("Failed to take an idle inhibitor: {err}"),
+                    }
+                }
+            ));
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for IdleService {
+        const NAME: &'static str = "BalladServicesIdleService";
+        type Type = super::IdleService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for IdleService {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let (sender, receiver) = smol::channel::unbounded();
+            match IdleNotifier::connect(sender) {
+                Ok(notifier) => {
+                    self.notifier.replace(Some(notifier));
+                    self.available.set(true);
+                }
+                Err(err) => {
+                    println!(
+                        "Failed to set up ext-idle-notify-v1 ({err}). Idle service will not function!"
+                    );
+                    return;
+                }
+            }
+
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while let Ok(event) = receiver.recv().await {
+                        match event {
+                            IdleEvent::Idled(stage) => this.idled(stage).await,
+                            IdleEvent::Resumed(stage) => this.resumed(stage).await,
+                        }
+                    }
+                }
+            ));
+
+            CONFIG_SERVICE.with(|service| {
+                self.config.replace(service.shell_config().idle);
+
+                service.connect_closure(
+                    "shell-config-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: ConfigService, config: &ShellConfig| {
+                            if *this.config.borrow() == config.idle {
+                                return;
+                            }
+                            this.config.replace(config.idle.clone());
+                            this.update_timeouts();
+                        }
+                    ),
+                );
+            });
+
+            UPOWER_SERVICE.with(|service| {
+                service.connect_on_battery_notify(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| this.update_timeouts()
+                ));
+            });
+
+            self.update_timeouts();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Dims the screen, locks, turns the outputs off and suspends after the configured [`IdleConfig`] timeouts.
+    pub struct IdleService(ObjectSubclass<imp::IdleService>);
+}
+impl IdleService {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn config(&self) -> IdleConfig {
+        self.imp().config.borrow().clone()
+    }
+
+    /// Keeps the computer awake while enabled, whatever the timeouts are.
+    pub fn set_caffeine(&self, enabled: bool) {
+        self.imp().set_caffeine(enabled);
+    }
+}
+impl Default for IdleService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static IDLE_SERVICE: LazyCell<IdleService> = LazyCell::new(IdleService::new);
+}
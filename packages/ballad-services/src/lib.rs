@@ -2,12 +2,25 @@ use std::sync::LazyLock;
 
 pub mod accounts;
 pub mod audio;
+pub mod battery_policy;
+pub mod bluetooth;
 pub mod brightness;
 pub mod config;
+pub mod do_not_disturb;
+pub mod idle;
+pub mod logind;
+pub mod mpris;
+pub mod network;
 pub mod niri;
+pub mod notifications;
 pub mod reactive;
+pub mod theme_outputs;
+pub mod theme_switch;
+pub mod tray;
 pub mod upower;
 pub mod power_profiles;
 
 pub(crate) static DBUS_SYSTEM_CONNECTION: LazyLock<zbus::Connection> =
     LazyLock::new(|| smol::block_on(zbus::Connection::system()).unwrap());
+pub(crate) static DBUS_SESSION_CONNECTION: LazyLock<zbus::Connection> =
+    LazyLock::new(|| smol::block_on(zbus::Connection::session()).unwrap());
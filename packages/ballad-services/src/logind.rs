@@ -0,0 +1,577 @@
+use std::cell::LazyCell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use gtk::glib::{self, Object};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use crate::DBUS_SYSTEM_CONNECTION;
+
+pub const LOGIND_BUS_NAME: &str = "org.freedesktop.login1";
+pub const LOGIND_OBJECT_PATH: &str = "/org/freedesktop/login1";
+
+mod bus {
+    use zbus::proxy;
+
+    #[proxy(
+        interface = "org.freedesktop.login1.Manager",
+        default_service = "org.freedesktop.login1",
+        default_path = "/org/freedesktop/login1"
+    )]
+    pub trait Manager {
+        /// Suspend method
+        fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+        /// Hibernate method
+        fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+
+        /// HybridSleep method
+        fn hybrid_sleep(&self, interactive: bool) -> zbus::Result<()>;
+
+        /// PowerOff method
+        fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+        /// Reboot method
+        fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+
+        /// CanSuspend method
+        fn can_suspend(&self) -> zbus::Result<String>;
+
+        /// CanHibernate method
+        fn can_hibernate(&self) -> zbus::Result<String>;
+
+        /// CanHybridSleep method
+        fn can_hybrid_sleep(&self) -> zbus::Result<String>;
+
+        /// CanPowerOff method
+        fn can_power_off(&self) -> zbus::Result<String>;
+
+        /// CanReboot method
+        fn can_reboot(&self) -> zbus::Result<String>;
+
+        /// Inhibit method
+        fn inhibit(
+            &self,
+            what: &str,
+            who: &str,
+            why: &str,
+            mode: &str,
+        ) -> zbus::Result<zbus::zvariant::OwnedFd>;
+
+        /// ListInhibitors method
+        #[allow(clippy::type_complexity)]
+        fn list_inhibitors(&self) -> zbus::Result<Vec<(String, String, String, String, u32, u32)>>;
+
+        /// ScheduleShutdown method
+        fn schedule_shutdown(&self, type_: &str, usec: u64) -> zbus::Result<()>;
+
+        /// GetSession method
+        fn get_session(&self, session_id: &str) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+        /// CancelScheduledShutdown method
+        fn cancel_scheduled_shutdown(&self) -> zbus::Result<bool>;
+
+        /// PrepareForSleep signal
+        #[zbus(signal)]
+        fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+
+        /// BlockInhibited property
+        #[zbus(property)]
+        fn block_inhibited(&self) -> zbus::Result<String>;
+
+        /// ScheduledShutdown property
+        #[zbus(property)]
+        fn scheduled_shutdown(&self) -> zbus::Result<(String, u64)>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.login1.Session",
+        default_service = "org.freedesktop.login1",
+        default_path = "/org/freedesktop/login1/session/auto"
+    )]
+    pub trait Session {
+        /// Lock method
+        fn lock(&self) -> zbus::Result<()>;
+
+        /// Unlock method
+        fn unlock(&self) -> zbus::Result<()>;
+
+        /// Terminate method
+        fn terminate(&self) -> zbus::Result<()>;
+
+        /// SetLockedHint method
+        fn set_locked_hint(&self, locked: bool) -> zbus::Result<()>;
+
+        /// Lock signal
+        #[zbus(signal)]
+        fn lock(&self) -> zbus::Result<()>;
+
+        /// Unlock signal
+        #[zbus(signal)]
+        fn unlock(&self) -> zbus::Result<()>;
+
+        /// LockedHint property
+        #[zbus(property)]
+        fn locked_hint(&self) -> zbus::Result<bool>;
+    }
+}
+
+use bus::SessionProxy;
+
+/// An action of the session power menu.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesPowerAction")]
+pub enum PowerAction {
+    #[default]
+    Lock,
+    Logout,
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    Reboot,
+    PowerOff,
+}
+impl PowerAction {
+    pub const ALL: [Self; 7] = [
+        Self::Lock,
+        Self::Logout,
+        Self::Suspend,
+        Self::Hibernate,
+        Self::HybridSleep,
+        Self::Reboot,
+        Self::PowerOff,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lock => "Lock",
+            Self::Logout => "Log out",
+            Self::Suspend => "Suspend",
+            Self::Hibernate => "Hibernate",
+            Self::HybridSleep => "Hybrid sleep",
+            Self::Reboot => "Restart",
+            Self::PowerOff => "Shut down",
+        }
+    }
+
+    /// The inhibitor lock that can block or delay the action, as logind names it.
+    pub fn inhibitor_what(&self) -> Option<&'static str> {
+        match self {
+            Self::Lock | Self::Logout => None,
+            Self::Suspend | Self::Hibernate | Self::HybridSleep => Some("sleep"),
+            Self::Reboot | Self::PowerOff => Some("shutdown"),
+        }
+    }
+
+    /// The type `ScheduleShutdown` takes, for actions that can be scheduled.
+    fn shutdown_type(&self) -> Option<&'static str> {
+        match self {
+            Self::Reboot => Some("reboot"),
+            Self::PowerOff => Some("poweroff"),
+            _ => None,
+        }
+    }
+}
+
+/// A program holding an inhibitor lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inhibitor {
+    /// Colon separated list of what is inhibited, like `shutdown:sleep`.
+    pub what: String,
+    pub who: String,
+    pub why: String,
+    /// `block` or `delay`.
+    pub mode: String,
+    pub uid: u32,
+    pub pid: u32,
+}
+impl Inhibitor {
+    pub fn inhibits(&self, what: &str) -> bool {
+        self.what.split(':').any(|inhibited| inhibited == what)
+    }
+
+    pub fn blocks(&self) -> bool {
+        self.mode == "block"
+    }
+}
+
+fn can(answer: zbus::Result<String>) -> bool {
+    // "challenge" means polkit will ask for a password, which is still possible.
+    matches!(answer.as_deref(), Ok("yes" | "challenge"))
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use futures::join;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, clone};
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+    use smol::stream::StreamExt;
+
+    use super::bus::{ManagerProxy, SessionProxy};
+    use super::can;
+
+    #[derive(Properties, Default)]
+    #[properties(wrapper_type = super::LogindService)]
+    pub struct LogindService {
+        /// Whether logind could be reached.
+        #[property(get)]
+        available: Cell<bool>,
+        #[property(get)]
+        can_suspend: Cell<bool>,
+        #[property(get)]
+        can_hibernate: Cell<bool>,
+        #[property(get)]
+        can_hybrid_sleep: Cell<bool>,
+        #[property(get)]
+        can_reboot: Cell<bool>,
+        #[property(get)]
+        can_power_off: Cell<bool>,
+        /// Colon separated list of what block inhibitors currently prevent, like `shutdown:sleep`.
+        #[property(get)]
+        block_inhibited: RefCell<String>,
+        /// The kind of the scheduled shutdown, like `poweroff` or `reboot`, or empty if none is scheduled.
+        #[property(get)]
+        scheduled_shutdown_kind: RefCell<String>,
+        /// When the scheduled shutdown happens in microseconds since the Unix epoch, or 0.
+        #[property(get)]
+        scheduled_shutdown_time: Cell<u64>,
+
+        pub(super) connection: RefCell<Option<zbus::Connection>>,
+        /// The session we run in, resolved to its real path so its signals can be received.
+        pub(super) session: RefCell<Option<SessionProxy<'static>>>,
+    }
+
+    impl LogindService {
+        pub(super) fn connection(&self) -> zbus::Connection {
+            self.connection.borrow().clone().unwrap()
+        }
+
+        pub(super) async fn manager(&self) -> zbus::Result<ManagerProxy<'static>> {
+            ManagerProxy::new(&self.connection()).await
+        }
+
+        fn set_available(&self, available: bool) {
+            if self.available.replace(available) != available {
+                self.obj().notify_available();
+            }
+        }
+
+        pub(super) async fn refresh(&self) -> zbus::Result<()> {
+            let manager = match self.manager().await {
+                Ok(manager) => manager,
+                Err(err) => {
+                    self.set_available(false);
+                    return Err(err);
+                }
+            };
+            let (
+                can_suspend,
+                can_hibernate,
+                can_hybrid_sleep,
+                can_reboot,
+                can_power_off,
+                block_inhibited,
+                scheduled_shutdown,
+            ) = join!(
+                manager.can_suspend(),
+                manager.can_hibernate(),
+                manager.can_hybrid_sleep(),
+                manager.can_reboot(),
+                manager.can_power_off(),
+                manager.block_inhibited(),
+                manager.scheduled_shutdown(),
+            );
+
+            // Only the method calls reach logind every time. The properties may come from the proxy's cache.
+            let available = [
+                &can_suspend,
+                &can_hibernate,
+                &can_hybrid_sleep,
+                &can_reboot,
+                &can_power_off,
+            ]
+            .iter()
+            .any(|answer| answer.is_ok());
+            self.set_available(available);
+            self.can_suspend.set(can(can_suspend));
+            self.obj().notify_can_suspend();
+            self.can_hibernate.set(can(can_hibernate));
+            self.obj().notify_can_hibernate();
+            self.can_hybrid_sleep.set(can(can_hybrid_sleep));
+            self.obj().notify_can_hybrid_sleep();
+            self.can_reboot.set(can(can_reboot));
+            self.obj().notify_can_reboot();
+            self.can_power_off.set(can(can_power_off));
+            self.obj().notify_can_power_off();
+            self.block_inhibited
+                .replace(block_inhibited.unwrap_or_default());
+            self.obj().notify_block_inhibited();
+
+            let (kind, time) = scheduled_shutdown.unwrap_or_default();
+            self.scheduled_shutdown_kind.replace(kind);
+            self.obj().notify_scheduled_shutdown_kind();
+            self.scheduled_shutdown_time.set(time);
+            self.obj().notify_scheduled_shutdown_time();
+
+            if available {
+                Ok(())
+            } else {
+                Err(zbus::Error::Failure("logind didn't answer".to_string()))
+            }
+        }
+
+        pub(super) async fn start(&self) {
+            if let Err(err) = self.refresh().await {
+                println!("Failed to connect to logind ({err}). Logind service will not function!");
+                return;
+            }
+
+            let Ok(properties_proxy) = zbus::fdo::PropertiesProxy::new(
+                &self.connection(),
+                super::LOGIND_BUS_NAME,
+                super::LOGIND_OBJECT_PATH,
+            )
+            .await
+            else {
+                return;
+            };
+            let Ok(mut stream) = properties_proxy.receive_properties_changed().await else {
+                return;
+            };
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while stream.next().await.is_some() {
+                        _ = this.refresh().await;
+                    }
+                }
+            ));
+
+            if let Err(err) = self.watch_session().await {
+                println!(
+                    "Failed to find the logind session ({err}). Lock requests will be missed!"
+                );
+            }
+        }
+
+        /// Forwards the session's `Lock` and `Unlock` signals, sent by `loginctl lock-session` for example.
+        async fn watch_session(&self) -> zbus::Result<()> {
+            let path = self.manager().await?.get_session("auto").await?;
+            let session = SessionProxy::builder(&self.connection())
+                .path(path)?
+                .build()
+                .await?;
+            let mut lock_stream = session.receive_lock().await?;
+            let mut unlock_stream = session.receive_unlock().await?;
+            self.session.replace(Some(session));
+
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while lock_stream.next().await.is_some() {
+                        this.obj().emit_by_name::<()>("lock", &[]);
+                    }
+                }
+            ));
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while unlock_stream.next().await.is_some() {
+                        this.obj().emit_by_name::<()>("unlock", &[]);
+                    }
+                }
+            ));
+
+            Ok(())
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LogindService {
+        const NAME: &'static str = "BalladServicesLogindService";
+        type Type = super::LogindService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for LogindService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("lock").build(),
+                    Signal::builder("unlock").build(),
+                ]
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct LogindService(ObjectSubclass<imp::LogindService>);
+}
+impl LogindService {
+    pub fn new() -> Self {
+        Self::with_connection(DBUS_SYSTEM_CONNECTION.clone())
+    }
+
+    /// Talks to logind on `connection` instead of the system bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().connection.replace(Some(connection));
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                this.imp().start().await;
+            }
+        ));
+
+        this
+    }
+
+    /// Checks again which actions are possible. logind doesn't announce when that changes.
+    pub async fn refresh(&self) -> zbus::Result<()> {
+        self.imp().refresh().await
+    }
+
+    /// Whether logind allows `action`, possibly after authenticating.
+    pub fn can(&self, action: PowerAction) -> bool {
+        match action {
+            PowerAction::Lock | PowerAction::Logout => self.available(),
+            PowerAction::Suspend => self.can_suspend(),
+            PowerAction::Hibernate => self.can_hibernate(),
+            PowerAction::HybridSleep => self.can_hybrid_sleep(),
+            PowerAction::Reboot => self.can_reboot(),
+            PowerAction::PowerOff => self.can_power_off(),
+        }
+    }
+
+    /// Asks logind to perform `action`. Polkit may ask the user to authenticate first.
+    pub async fn perform(&self, action: PowerAction) -> zbus::Result<()> {
+        let connection = self.imp().connection();
+        let manager = self.imp().manager().await?;
+        match action {
+            PowerAction::Lock => SessionProxy::new(&connection).await?.lock().await,
+            PowerAction::Logout => SessionProxy::new(&connection).await?.terminate().await,
+            PowerAction::Suspend => manager.suspend(true).await,
+            PowerAction::Hibernate => manager.hibernate(true).await,
+            PowerAction::HybridSleep => manager.hybrid_sleep(true).await,
+            PowerAction::Reboot => manager.reboot(true).await,
+            PowerAction::PowerOff => manager.power_off(true).await,
+        }
+    }
+
+    /// Tells logind whether the session is locked, so other programs can see it.
+    pub async fn set_locked_hint(&self, locked: bool) -> zbus::Result<()> {
+        let session = self.imp().session.borrow().clone();
+        match session {
+            Some(session) => session.set_locked_hint(locked).await,
+            None => {
+                SessionProxy::new(&self.imp().connection())
+                    .await?
+                    .set_locked_hint(locked)
+                    .await
+            }
+        }
+    }
+
+    /// Takes an inhibitor lock for `what`, like `idle` or `sleep`. It is held until the returned file descriptor is
+    /// dropped.
+    pub async fn inhibit(
+        &self,
+        what: &str,
+        why: &str,
+        mode: &str,
+    ) -> zbus::Result<zbus::zvariant::OwnedFd> {
+        self.imp()
+            .manager()
+            .await?
+            .inhibit(what, "Ballad", why, mode)
+            .await
+    }
+
+    pub async fn inhibitors(&self) -> zbus::Result<Vec<Inhibitor>> {
+        Ok(self
+            .imp()
+            .manager()
+            .await?
+            .list_inhibitors()
+            .await?
+            .into_iter()
+            .map(|(what, who, why, mode, uid, pid)| Inhibitor {
+                what,
+                who,
+                why,
+                mode,
+                uid,
+                pid,
+            })
+            .collect())
+    }
+
+    /// The inhibitors that would block or delay `action`.
+    pub async fn inhibitors_for(&self, action: PowerAction) -> zbus::Result<Vec<Inhibitor>> {
+        let Some(what) = action.inhibitor_what() else {
+            return Ok(Vec::new());
+        };
+        Ok(self
+            .inhibitors()
+            .await?
+            .into_iter()
+            .filter(|inhibitor| inhibitor.inhibits(what))
+            .collect())
+    }
+
+    /// Has logind reboot or power off after `delay`. Other actions can't be scheduled.
+    pub async fn schedule(&self, action: PowerAction, delay: Duration) -> zbus::Result<()> {
+        let Some(kind) = action.shutdown_type() else {
+            return Err(zbus::Error::Failure(format!(
+                "{} can't be scheduled",
+                action.name()
+            )));
+        };
+        let time = SystemTime::now()
+            .checked_add(delay)
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        self.imp()
+            .manager()
+            .await?
+            .schedule_shutdown(kind, time.as_micros() as u64)
+            .await
+    }
+
+    pub async fn cancel_scheduled(&self) -> zbus::Result<bool> {
+        self.imp()
+            .manager()
+            .await?
+            .cancel_scheduled_shutdown()
+            .await
+    }
+
+    /// How long until the scheduled shutdown, if there is one.
+    pub fn scheduled_shutdown_in(&self) -> Option<Duration> {
+        let time = self.scheduled_shutdown_time();
+        if time == 0 {
+            return None;
+        }
+        let time = UNIX_EPOCH + Duration::from_micros(time);
+        Some(time.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+}
+impl Default for LogindService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static LOGIND_SERVICE: LazyCell<LogindService> = LazyCell::new(LogindService::new);
+}
@@ -0,0 +1,799 @@
+use std::cell::LazyCell;
+
+use gtk::{
+    glib::{self, Object},
+    subclass::prelude::ObjectSubclassIsExt,
+};
+
+use crate::DBUS_SESSION_CONNECTION;
+
+pub use player_imp::{LoopStatus, PlaybackStatus};
+
+/// Every MPRIS player owns a bus name starting with this prefix.
+pub const MPRIS_BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+pub const MPRIS_OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+mod bus {
+    //! # D-Bus interface proxies for: `org.mpris.MediaPlayer2` and `org.mpris.MediaPlayer2.Player`
+
+    use std::collections::HashMap;
+
+    use zbus::{proxy, zvariant::OwnedValue};
+
+    #[proxy(
+        interface = "org.mpris.MediaPlayer2",
+        default_path = "/org/mpris/MediaPlayer2"
+    )]
+    pub trait MediaPlayer2 {
+        /// Quit method
+        fn quit(&self) -> zbus::Result<()>;
+
+        /// Raise method
+        fn raise(&self) -> zbus::Result<()>;
+
+        /// CanQuit property
+        #[zbus(property)]
+        fn can_quit(&self) -> zbus::Result<bool>;
+
+        /// CanRaise property
+        #[zbus(property)]
+        fn can_raise(&self) -> zbus::Result<bool>;
+
+        /// DesktopEntry property
+        #[zbus(property)]
+        fn desktop_entry(&self) -> zbus::Result<String>;
+
+        /// Identity property
+        #[zbus(property)]
+        fn identity(&self) -> zbus::Result<String>;
+    }
+
+    #[proxy(
+        interface = "org.mpris.MediaPlayer2.Player",
+        default_path = "/org/mpris/MediaPlayer2"
+    )]
+    pub trait Player {
+        /// Next method
+        fn next(&self) -> zbus::Result<()>;
+
+        /// OpenUri method
+        fn open_uri(&self, uri: &str) -> zbus::Result<()>;
+
+        /// Pause method
+        fn pause(&self) -> zbus::Result<()>;
+
+        /// Play method
+        fn play(&self) -> zbus::Result<()>;
+
+        /// PlayPause method
+        fn play_pause(&self) -> zbus::Result<()>;
+
+        /// Previous method
+        fn previous(&self) -> zbus::Result<()>;
+
+        /// Seek method
+        fn seek(&self, offset: i64) -> zbus::Result<()>;
+
+        /// SetPosition method
+        fn set_position(
+            &self,
+            track_id: &zbus::zvariant::ObjectPath<'_>,
+            position: i64,
+        ) -> zbus::Result<()>;
+
+        /// Stop method
+        fn stop(&self) -> zbus::Result<()>;
+
+        /// Seeked signal
+        #[zbus(signal)]
+        fn seeked(&self, position: i64) -> zbus::Result<()>;
+
+        /// CanControl property
+        #[zbus(property)]
+        fn can_control(&self) -> zbus::Result<bool>;
+
+        /// CanGoNext property
+        #[zbus(property)]
+        fn can_go_next(&self) -> zbus::Result<bool>;
+
+        /// CanGoPrevious property
+        #[zbus(property)]
+        fn can_go_previous(&self) -> zbus::Result<bool>;
+
+        /// CanPause property
+        #[zbus(property)]
+        fn can_pause(&self) -> zbus::Result<bool>;
+
+        /// CanPlay property
+        #[zbus(property)]
+        fn can_play(&self) -> zbus::Result<bool>;
+
+        /// CanSeek property
+        #[zbus(property)]
+        fn can_seek(&self) -> zbus::Result<bool>;
+
+        /// LoopStatus property
+        #[zbus(property)]
+        fn loop_status(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn set_loop_status(&self, value: &str) -> zbus::Result<()>;
+
+        /// Metadata property
+        #[zbus(property)]
+        fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+
+        /// Position property
+        #[zbus(property(emits_changed_signal = "false"))]
+        fn position(&self) -> zbus::Result<i64>;
+
+        /// Shuffle property
+        #[zbus(property)]
+        fn shuffle(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn set_shuffle(&self, value: bool) -> zbus::Result<()>;
+
+        /// Volume property
+        #[zbus(property)]
+        fn volume(&self) -> zbus::Result<f64>;
+        #[zbus(property)]
+        fn set_volume(&self, value: f64) -> zbus::Result<()>;
+
+        /// PlaybackStatus property
+        #[zbus(property)]
+        fn playback_status(&self) -> zbus::Result<String>;
+    }
+}
+
+/// The parts of `Metadata` that are shown in the shell.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Metadata {
+    track_id: Option<String>,
+    length: i64,
+    title: String,
+    artist: String,
+    album: String,
+    art_url: Option<String>,
+}
+impl Metadata {
+    fn from_map(mut map: std::collections::HashMap<String, zbus::zvariant::OwnedValue>) -> Self {
+        let mut take_string = |key: &str| {
+            map.remove(key)
+                .and_then(|value| String::try_from(value).ok())
+        };
+
+        let title = take_string("xesam:title").unwrap_or_default();
+        let album = take_string("xesam:album").unwrap_or_default();
+        let art_url = take_string("mpris:artUrl").filter(|url| !url.is_empty());
+
+        let track_id = map
+            .remove("mpris:trackid")
+            .and_then(|value| zbus::zvariant::OwnedObjectPath::try_from(value).ok())
+            .map(|path| path.to_string());
+        // Some players send the length as an unsigned integer even though the spec says it is signed.
+        let length = map
+            .remove("mpris:length")
+            .and_then(|value| {
+                value
+                    .downcast_ref::<i64>()
+                    .ok()
+                    .or_else(|| value.downcast_ref::<u64>().ok().map(|length| length as i64))
+            })
+            .unwrap_or_default();
+        let artist = map
+            .remove("xesam:artist")
+            .and_then(|value| Vec::<String>::try_from(value).ok())
+            .map(|artists| artists.join(", "))
+            .unwrap_or_default();
+
+        Self {
+            track_id,
+            length,
+            title,
+            artist,
+            album,
+            art_url,
+        }
+    }
+}
+
+mod player_imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use futures::{FutureExt, join};
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, ControlFlow, Properties, clone};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::lock::RwLock;
+    use smol::stream::StreamExt;
+
+    use super::Metadata;
+    use super::bus::{MediaPlayer2Proxy, PlayerProxy};
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+    #[repr(u8)]
+    #[enum_type(name = "BalladServicesPlaybackStatus")]
+    pub enum PlaybackStatus {
+        Playing,
+        Paused,
+        #[default]
+        Stopped,
+    }
+    impl From<&str> for PlaybackStatus {
+        fn from(value: &str) -> Self {
+            match value {
+                "Playing" => Self::Playing,
+                "Paused" => Self::Paused,
+                _ => Self::Stopped,
+            }
+        }
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+    #[repr(u8)]
+    #[enum_type(name = "BalladServicesLoopStatus")]
+    pub enum LoopStatus {
+        #[default]
+        None,
+        Track,
+        Playlist,
+    }
+    impl LoopStatus {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Self::None => "None",
+                Self::Track => "Track",
+                Self::Playlist => "Playlist",
+            }
+        }
+    }
+    impl From<&str> for LoopStatus {
+        fn from(value: &str) -> Self {
+            match value {
+                "Track" => Self::Track,
+                "Playlist" => Self::Playlist,
+                _ => Self::None,
+            }
+        }
+    }
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::MediaPlayer)]
+    pub struct MediaPlayer {
+        /// The bus name this player owns, e.g. `org.mpris.MediaPlayer2.spotify`.
+        #[property(get)]
+        pub(super) bus_name: RefCell<String>,
+        #[property(get)]
+        identity: RefCell<String>,
+        #[property(get)]
+        desktop_entry: RefCell<Option<String>>,
+
+        #[property(get, builder(PlaybackStatus::Stopped))]
+        playback_status: Cell<PlaybackStatus>,
+        #[property(get, builder(LoopStatus::None))]
+        loop_status: Cell<LoopStatus>,
+        #[property(get)]
+        shuffle: Cell<bool>,
+        #[property(get)]
+        volume: Cell<f64>,
+        /// Position in the current track in microseconds.
+        #[property(get)]
+        position: Cell<i64>,
+
+        #[property(get)]
+        track_id: RefCell<Option<String>>,
+        /// Length of the current track in microseconds.
+        #[property(get)]
+        length: Cell<i64>,
+        #[property(get)]
+        title: RefCell<String>,
+        #[property(get)]
+        artist: RefCell<String>,
+        #[property(get)]
+        album: RefCell<String>,
+        #[property(get)]
+        art_url: RefCell<Option<String>>,
+
+        #[property(get)]
+        can_control: Cell<bool>,
+        #[property(get)]
+        can_play: Cell<bool>,
+        #[property(get)]
+        can_pause: Cell<bool>,
+        #[property(get)]
+        can_go_next: Cell<bool>,
+        #[property(get)]
+        can_go_previous: Cell<bool>,
+        #[property(get)]
+        can_seek: Cell<bool>,
+
+        /// Monotonic time of when this player last started playing, used to pick the active player.
+        #[property(get)]
+        last_active: Cell<i64>,
+
+        pub(super) media_player_proxy: RwLock<Option<MediaPlayer2Proxy<'static>>>,
+        pub(super) proxy: RwLock<Option<PlayerProxy<'static>>>,
+    }
+    impl MediaPlayer {
+        pub async fn update(&self) {
+            {
+                let media_player_proxy = self.media_player_proxy.read().await;
+                let media_player_proxy = media_player_proxy.as_ref().unwrap();
+                let (identity, desktop_entry) = join!(
+                    media_player_proxy.identity(),
+                    media_player_proxy.desktop_entry()
+                );
+
+                self.identity.replace(identity.unwrap_or_default());
+                self.obj().notify_identity();
+                self.desktop_entry
+                    .replace(desktop_entry.ok().filter(|entry| !entry.is_empty()));
+                self.obj().notify_desktop_entry();
+            }
+
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+
+            let (playback_status, loop_status, shuffle, volume, position, metadata) = join!(
+                proxy.playback_status(),
+                proxy.loop_status(),
+                proxy.shuffle(),
+                proxy.volume(),
+                proxy.position(),
+                proxy.metadata(),
+            );
+            let (can_control, can_play, can_pause, can_go_next, can_go_previous, can_seek) = join!(
+                proxy.can_control(),
+                proxy.can_play(),
+                proxy.can_pause(),
+                proxy.can_go_next(),
+                proxy.can_go_previous(),
+                proxy.can_seek(),
+            );
+
+            let playback_status =
+                PlaybackStatus::from(playback_status.unwrap_or_default().as_str());
+            if self.playback_status.replace(playback_status) != playback_status
+                && playback_status == PlaybackStatus::Playing
+            {
+                self.last_active.set(glib::monotonic_time());
+                self.obj().notify_last_active();
+            }
+            self.obj().notify_playback_status();
+            self.loop_status
+                .set(LoopStatus::from(loop_status.unwrap_or_default().as_str()));
+            self.obj().notify_loop_status();
+            self.shuffle.set(shuffle.unwrap_or_default());
+            self.obj().notify_shuffle();
+            self.volume.set(volume.unwrap_or_default());
+            self.obj().notify_volume();
+            self.position.set(position.unwrap_or_default());
+            self.obj().notify_position();
+
+            let metadata = Metadata::from_map(metadata.unwrap_or_default());
+            self.track_id.replace(metadata.track_id);
+            self.obj().notify_track_id();
+            self.length.set(metadata.length);
+            self.obj().notify_length();
+            self.title.replace(metadata.title);
+            self.obj().notify_title();
+            self.artist.replace(metadata.artist);
+            self.obj().notify_artist();
+            self.album.replace(metadata.album);
+            self.obj().notify_album();
+            self.art_url.replace(metadata.art_url);
+            self.obj().notify_art_url();
+
+            self.can_control.set(can_control.unwrap_or_default());
+            self.obj().notify_can_control();
+            self.can_play.set(can_play.unwrap_or_default());
+            self.obj().notify_can_play();
+            self.can_pause.set(can_pause.unwrap_or_default());
+            self.obj().notify_can_pause();
+            self.can_go_next.set(can_go_next.unwrap_or_default());
+            self.obj().notify_can_go_next();
+            self.can_go_previous
+                .set(can_go_previous.unwrap_or_default());
+            self.obj().notify_can_go_previous();
+            self.can_seek.set(can_seek.unwrap_or_default());
+            self.obj().notify_can_seek();
+
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        pub(super) fn set_position_local(&self, position: i64) {
+            self.position.set(position);
+            self.obj().notify_position();
+        }
+
+        pub async fn refresh_position(&self) {
+            let proxy = self.proxy.read().await;
+            let Some(proxy) = proxy.as_ref() else {
+                return;
+            };
+            if let Ok(position) = proxy.position().await {
+                self.set_position_local(position);
+            }
+        }
+
+        pub(super) fn subscribe_to_updates(&self) {
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    let proxy = this.proxy.read().await.clone().unwrap();
+                    let bus_name = this.bus_name.borrow().clone();
+                    let Ok(properties_proxy) =
+                        zbus::fdo::PropertiesProxy::builder(proxy.inner().connection())
+                            .destination(bus_name)
+                            .unwrap()
+                            .path(super::MPRIS_OBJECT_PATH)
+                            .unwrap()
+                            .build()
+                            .await
+                    else {
+                        return;
+                    };
+
+                    let mut changed_stream = properties_proxy
+                        .receive_properties_changed()
+                        .await
+                        .expect("Failed to receive properties_changed signal");
+                    let mut seeked_stream = proxy
+                        .receive_seeked()
+                        .await
+                        .expect("Failed to receive seeked signal");
+
+                    loop {
+                        futures::select! {
+                            changed = changed_stream.next().fuse() => {
+                                if changed.is_none() {
+                                    break;
+                                }
+                                this.update().await;
+                            },
+                            seeked = seeked_stream.next().fuse() => {
+                                let Some(seeked) = seeked else {
+                                    break;
+                                };
+                                if let Ok(args) = seeked.args() {
+                                    this.set_position_local(args.position);
+                                }
+                            },
+                        }
+                    }
+                }
+            ));
+
+            // Position is not announced through PropertiesChanged, so it is polled while playing.
+            glib::timeout_add_seconds_local(
+                1,
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    #[upgrade_or]
+                    ControlFlow::Break,
+                    move || {
+                        if this.playback_status.get() == PlaybackStatus::Playing {
+                            glib::spawn_future_local(clone!(
+                                #[weak]
+                                this,
+                                async move {
+                                    this.refresh_position().await;
+                                }
+                            ));
+                        }
+                        ControlFlow::Continue
+                    }
+                ),
+            );
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MediaPlayer {
+        const NAME: &'static str = "BalladServicesMediaPlayer";
+        type Type = super::MediaPlayer;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MediaPlayer {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+}
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use gtk::gio::ListStore;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties, clone};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::stream::StreamExt;
+
+    use super::{MPRIS_BUS_NAME_PREFIX, MediaPlayer, PlaybackStatus};
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::MprisService)]
+    pub struct MprisService {
+        #[property(get)]
+        players: ListStore,
+        /// The player that most recently started playing. Falls back to any remaining player.
+        #[property(get)]
+        active_player: RefCell<Option<MediaPlayer>>,
+    }
+    impl Default for MprisService {
+        fn default() -> Self {
+            Self {
+                players: ListStore::with_type(MediaPlayer::static_type()),
+                active_player: Default::default(),
+            }
+        }
+    }
+
+    impl MprisService {
+        fn player_position(&self, bus_name: &str) -> Option<u32> {
+            self.players
+                .iter::<MediaPlayer>()
+                .filter_map(|player| player.ok())
+                .position(|player| player.bus_name() == bus_name)
+                .map(|position| position as u32)
+        }
+
+        fn select_active_player(&self) {
+            let players = self
+                .players
+                .iter::<MediaPlayer>()
+                .filter_map(|player| player.ok())
+                .collect::<Vec<_>>();
+
+            let active = players
+                .iter()
+                .filter(|player| player.playback_status() == PlaybackStatus::Playing)
+                .max_by_key(|player| player.last_active())
+                .or_else(|| players.iter().max_by_key(|player| player.last_active()))
+                .cloned();
+
+            if *self.active_player.borrow() != active {
+                self.active_player.replace(active);
+                self.obj().notify_active_player();
+            }
+        }
+
+        pub(super) async fn add_player(&self, connection: &zbus::Connection, bus_name: &str) {
+            if self.player_position(bus_name).is_some() {
+                return;
+            }
+            let player = match MediaPlayer::with_bus_name(connection, bus_name).await {
+                Ok(player) => player,
+                Err(err) => {
+                    println!("Failed to connect to MPRIS player {bus_name}: {err}");
+                    return;
+                }
+            };
+
+            player.connect_playback_status_notify(clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_| this.select_active_player()
+            ));
+            self.players.append(&player);
+            self.select_active_player();
+            self.obj().emit_by_name::<()>("players-changed", &[]);
+        }
+
+        pub(super) fn remove_player(&self, bus_name: &str) {
+            let Some(position) = self.player_position(bus_name) else {
+                return;
+            };
+            self.players.remove(position);
+            self.select_active_player();
+            self.obj().emit_by_name::<()>("players-changed", &[]);
+        }
+
+        pub(super) async fn watch(&self, connection: zbus::Connection) -> zbus::Result<()> {
+            let dbus_proxy = zbus::fdo::DBusProxy::new(&connection).await?;
+            let mut owner_changed_stream = dbus_proxy.receive_name_owner_changed().await?;
+
+            for name in dbus_proxy.list_names().await? {
+                if name.starts_with(MPRIS_BUS_NAME_PREFIX) {
+                    self.add_player(&connection, &name).await;
+                }
+            }
+
+            while let Some(signal) = owner_changed_stream.next().await {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if !args.name().starts_with(MPRIS_BUS_NAME_PREFIX) {
+                    continue;
+                }
+
+                if args.old_owner().is_some() {
+                    self.remove_player(args.name());
+                }
+                if args.new_owner().is_some() {
+                    self.add_player(&connection, args.name()).await;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MprisService {
+        const NAME: &'static str = "BalladServicesMprisService";
+        type Type = super::MprisService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MprisService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("players-changed").build()])
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct MprisService(ObjectSubclass<imp::MprisService>);
+}
+impl MprisService {
+    pub fn new() -> Self {
+        Self::with_connection(DBUS_SESSION_CONNECTION.clone())
+    }
+
+    /// Watches for players on `connection` instead of the default session bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                if let Err(err) = this.imp().watch(connection).await {
+                    println!(
+                        "Failed to watch for MPRIS players: {err}. Media service will not function!"
+                    );
+                }
+            }
+        ));
+
+        this
+    }
+}
+impl Default for MprisService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+glib::wrapper! {
+    pub struct MediaPlayer(ObjectSubclass<player_imp::MediaPlayer>);
+}
+impl MediaPlayer {
+    pub async fn with_bus_name(
+        connection: &zbus::Connection,
+        bus_name: &str,
+    ) -> zbus::Result<Self> {
+        let media_player_proxy = bus::MediaPlayer2Proxy::builder(connection)
+            .destination(bus_name.to_string())?
+            .build()
+            .await?;
+        let proxy = bus::PlayerProxy::builder(connection)
+            .destination(bus_name.to_string())?
+            .build()
+            .await?;
+
+        let player: Self = Object::builder().build();
+        player.imp().bus_name.replace(bus_name.to_string());
+        player
+            .imp()
+            .media_player_proxy
+            .write()
+            .await
+            .replace(media_player_proxy);
+        player.imp().proxy.write().await.replace(proxy);
+
+        player.imp().update().await;
+        player.imp().subscribe_to_updates();
+
+        Ok(player)
+    }
+
+    pub async fn play_pause(&self) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .play_pause()
+            .await
+    }
+    pub async fn next(&self) -> zbus::Result<()> {
+        self.imp().proxy.read().await.as_ref().unwrap().next().await
+    }
+    pub async fn previous(&self) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .previous()
+            .await
+    }
+    pub async fn stop(&self) -> zbus::Result<()> {
+        self.imp().proxy.read().await.as_ref().unwrap().stop().await
+    }
+
+    /// Seeks to `position` microseconds into the current track.
+    pub async fn set_position(&self, position: i64) -> zbus::Result<()> {
+        let Some(track_id) = self.track_id() else {
+            return Ok(());
+        };
+        let track_id = zbus::zvariant::ObjectPath::try_from(track_id.as_str())?;
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .set_position(&track_id, position)
+            .await?;
+        self.imp().set_position_local(position);
+
+        Ok(())
+    }
+    pub async fn set_volume(&self, volume: f64) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .set_volume(volume)
+            .await
+    }
+    pub async fn set_shuffle(&self, shuffle: bool) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .set_shuffle(shuffle)
+            .await
+    }
+    pub async fn set_loop_status(&self, loop_status: LoopStatus) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .set_loop_status(loop_status.as_str())
+            .await
+    }
+    /// Brings the player's window to the front.
+    pub async fn raise(&self) -> zbus::Result<()> {
+        self.imp()
+            .media_player_proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .raise()
+            .await
+    }
+}
+
+thread_local! {
+    pub static MPRIS_SERVICE: LazyCell<MprisService> = LazyCell::new(MprisService::new);
+}
@@ -0,0 +1,1252 @@
+use std::{cell::LazyCell, collections::HashMap};
+
+use gtk::{
+    glib::{self, Object},
+    subclass::prelude::ObjectSubclassIsExt,
+};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
+
+use crate::DBUS_SYSTEM_CONNECTION;
+
+pub use device_imp::DeviceType;
+
+pub const NETWORK_MANAGER_BUS_NAME: &str = "org.freedesktop.NetworkManager";
+pub const NETWORK_MANAGER_OBJECT_PATH: &str = "/org/freedesktop/NetworkManager";
+pub const SECRET_AGENT_OBJECT_PATH: &str = "/org/freedesktop/NetworkManager/SecretAgent";
+/// The identifier our secret agent registers with.
+pub const SECRET_AGENT_IDENTIFIER: &str = "com.gavinniederman.ballad";
+
+/// `NM_STATE_CONNECTED_GLOBAL`
+const STATE_CONNECTED_GLOBAL: u32 = 70;
+/// `NM_DEVICE_STATE_ACTIVATED`
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+/// `NM_802_11_AP_FLAGS_PRIVACY`
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// `NM_802_11_AP_SEC_KEY_MGMT_PSK`
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+/// `NM_802_11_AP_SEC_KEY_MGMT_802_1X`
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+/// `NM_802_11_AP_SEC_KEY_MGMT_SAE`
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+
+/// Connection settings as exchanged with NetworkManager, `a{sa{sv}}`.
+type ConnectionSettings = HashMap<String, HashMap<String, zbus::zvariant::OwnedValue>>;
+
+mod bus {
+    //! # D-Bus interface proxies for the parts of `org.freedesktop.NetworkManager` used by the shell
+    #![allow(clippy::type_complexity)]
+
+    use std::collections::HashMap;
+
+    use zbus::{
+        proxy,
+        zvariant::{ObjectPath, OwnedObjectPath, Value},
+    };
+
+    use super::ConnectionSettings;
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager",
+        default_service = "org.freedesktop.NetworkManager",
+        default_path = "/org/freedesktop/NetworkManager"
+    )]
+    pub trait NetworkManager {
+        /// ActivateConnection method
+        fn activate_connection(
+            &self,
+            connection: &ObjectPath<'_>,
+            device: &ObjectPath<'_>,
+            specific_object: &ObjectPath<'_>,
+        ) -> zbus::Result<OwnedObjectPath>;
+
+        /// AddAndActivateConnection method
+        fn add_and_activate_connection(
+            &self,
+            connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+            device: &ObjectPath<'_>,
+            specific_object: &ObjectPath<'_>,
+        ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+        /// DeactivateConnection method
+        fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// GetDevices method
+        fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+        /// ActiveConnections property
+        #[zbus(property)]
+        fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+        /// PrimaryConnection property
+        #[zbus(property)]
+        fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+        /// State property
+        #[zbus(property)]
+        fn state(&self) -> zbus::Result<u32>;
+
+        /// WirelessEnabled property
+        #[zbus(property)]
+        fn wireless_enabled(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+
+        /// WirelessHardwareEnabled property
+        #[zbus(property)]
+        fn wireless_hardware_enabled(&self) -> zbus::Result<bool>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.Device",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    pub trait Device {
+        /// Disconnect method
+        fn disconnect(&self) -> zbus::Result<()>;
+
+        /// ActiveConnection property
+        #[zbus(property)]
+        fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+        /// DeviceType property
+        #[zbus(property)]
+        fn device_type(&self) -> zbus::Result<u32>;
+
+        /// Interface property
+        #[zbus(property)]
+        fn interface(&self) -> zbus::Result<String>;
+
+        /// State property
+        #[zbus(property)]
+        fn state(&self) -> zbus::Result<u32>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.Device.Wireless",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    pub trait Wireless {
+        /// GetAllAccessPoints method
+        fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+        /// RequestScan method
+        fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+        /// AccessPointAdded signal
+        #[zbus(signal)]
+        fn access_point_added(&self, access_point: ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// AccessPointRemoved signal
+        #[zbus(signal)]
+        fn access_point_removed(&self, access_point: ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// ActiveAccessPoint property
+        #[zbus(property)]
+        fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+
+        /// LastScan property
+        #[zbus(property)]
+        fn last_scan(&self) -> zbus::Result<i64>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.AccessPoint",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    pub trait AccessPoint {
+        /// Flags property
+        #[zbus(property)]
+        fn flags(&self) -> zbus::Result<u32>;
+
+        /// Frequency property
+        #[zbus(property)]
+        fn frequency(&self) -> zbus::Result<u32>;
+
+        /// RsnFlags property
+        #[zbus(property)]
+        fn rsn_flags(&self) -> zbus::Result<u32>;
+
+        /// Ssid property
+        #[zbus(property)]
+        fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+        /// Strength property
+        #[zbus(property)]
+        fn strength(&self) -> zbus::Result<u8>;
+
+        /// WpaFlags property
+        #[zbus(property)]
+        fn wpa_flags(&self) -> zbus::Result<u32>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.Connection.Active",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    pub trait ActiveConnection {
+        /// Connection property
+        #[zbus(property)]
+        fn connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+        /// Id property
+        #[zbus(property)]
+        fn id(&self) -> zbus::Result<String>;
+
+        /// Type property
+        #[zbus(property, name = "Type")]
+        fn type_(&self) -> zbus::Result<String>;
+
+        /// Vpn property
+        #[zbus(property)]
+        fn vpn(&self) -> zbus::Result<bool>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.Settings",
+        default_service = "org.freedesktop.NetworkManager",
+        default_path = "/org/freedesktop/NetworkManager/Settings"
+    )]
+    pub trait Settings {
+        /// ListConnections method
+        fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+        /// ConnectionRemoved signal
+        #[zbus(signal)]
+        fn connection_removed(&self, connection: ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// NewConnection signal
+        #[zbus(signal)]
+        fn new_connection(&self, connection: ObjectPath<'_>) -> zbus::Result<()>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.Settings.Connection",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    pub trait SettingsConnection {
+        /// GetSettings method
+        fn get_settings(&self) -> zbus::Result<ConnectionSettings>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.NetworkManager.AgentManager",
+        default_service = "org.freedesktop.NetworkManager",
+        default_path = "/org/freedesktop/NetworkManager/AgentManager"
+    )]
+    pub trait AgentManager {
+        /// Register method
+        fn register(&self, identifier: &str) -> zbus::Result<()>;
+
+        /// Unregister method
+        fn unregister(&self) -> zbus::Result<()>;
+    }
+}
+
+mod agent {
+    //! A `org.freedesktop.NetworkManager.SecretAgent` that forwards password requests to the shell.
+
+    use smol::channel::{Receiver, Sender};
+    use zbus::{interface, zvariant::OwnedObjectPath};
+
+    use super::ConnectionSettings;
+
+    #[derive(Debug, zbus::DBusError)]
+    #[zbus(prefix = "org.freedesktop.NetworkManager.SecretAgent")]
+    pub enum SecretAgentError {
+        #[zbus(error)]
+        ZBus(zbus::Error),
+        NoSecrets(String),
+        UserCanceled(String),
+    }
+
+    pub struct SecretRequest {
+        pub connection_path: String,
+        /// The user facing name of the connection.
+        pub connection_id: String,
+        pub setting_name: String,
+        /// Receives the secret, or nothing if the user cancelled.
+        pub respond: Sender<String>,
+    }
+
+    pub enum AgentEvent {
+        Request(SecretRequest),
+        Cancel {
+            connection_path: String,
+            setting_name: String,
+        },
+    }
+
+    pub struct SecretAgent {
+        pub events: Sender<AgentEvent>,
+    }
+
+    /// The key a secret is stored under for each kind of setting we can ask for.
+    fn secret_key(settings: &ConnectionSettings, setting_name: &str) -> Option<&'static str> {
+        match setting_name {
+            "802-11-wireless-security" => {
+                let key_mgmt = settings
+                    .get(setting_name)
+                    .and_then(|setting| setting.get("key-mgmt"))
+                    .and_then(|value| String::try_from(value.try_clone().ok()?).ok());
+                match key_mgmt.as_deref() {
+                    Some("none") => Some("wep-key0"),
+                    Some("wpa-psk" | "sae") | None => Some("psk"),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    #[interface(name = "org.freedesktop.NetworkManager.SecretAgent")]
+    impl SecretAgent {
+        async fn get_secrets(
+            &self,
+            settings: ConnectionSettings,
+            connection_path: OwnedObjectPath,
+            setting_name: String,
+            _hints: Vec<String>,
+            _flags: u32,
+        ) -> Result<ConnectionSettings, SecretAgentError> {
+            let Some(key) = secret_key(&settings, &setting_name) else {
+                return Err(SecretAgentError::NoSecrets(format!(
+                    "Secrets for {setting_name} are not supported"
+                )));
+            };
+            let connection_id = settings
+                .get("connection")
+                .and_then(|setting| setting.get("id"))
+                .and_then(|value| String::try_from(value.try_clone().ok()?).ok())
+                .unwrap_or_default();
+
+            let (respond, response): (_, Receiver<String>) = smol::channel::bounded(1);
+            self.events
+                .send(AgentEvent::Request(SecretRequest {
+                    connection_path: connection_path.to_string(),
+                    connection_id,
+                    setting_name: setting_name.clone(),
+                    respond,
+                }))
+                .await
+                .map_err(|_| SecretAgentError::NoSecrets("The shell is not running".to_string()))?;
+
+            let Ok(secret) = response.recv().await else {
+                return Err(SecretAgentError::UserCanceled(
+                    "The user cancelled the request".to_string(),
+                ));
+            };
+
+            let mut setting = std::collections::HashMap::new();
+            setting.insert(
+                key.to_string(),
+                zbus::zvariant::Value::from(secret)
+                    .try_into()
+                    .map_err(zbus::Error::from)?,
+            );
+            let mut secrets = ConnectionSettings::new();
+            secrets.insert(setting_name, setting);
+            Ok(secrets)
+        }
+
+        async fn cancel_get_secrets(&self, connection_path: OwnedObjectPath, setting_name: String) {
+            self.events
+                .send(AgentEvent::Cancel {
+                    connection_path: connection_path.to_string(),
+                    setting_name,
+                })
+                .await
+                .ok();
+        }
+
+        /// Secrets are left to NetworkManager to store.
+        fn save_secrets(&self, _settings: ConnectionSettings, _connection_path: OwnedObjectPath) {}
+
+        fn delete_secrets(&self, _settings: ConnectionSettings, _connection_path: OwnedObjectPath) {
+        }
+    }
+}
+
+/// What kind of authentication an access point requires.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesWifiSecurity")]
+pub enum WifiSecurity {
+    #[default]
+    Open,
+    Wep,
+    Wpa,
+    Wpa2,
+    Wpa3,
+    Enterprise,
+}
+impl WifiSecurity {
+    fn from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Self {
+        let key_mgmt = wpa_flags | rsn_flags;
+        if key_mgmt & AP_SEC_KEY_MGMT_802_1X != 0 {
+            Self::Enterprise
+        } else if rsn_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+            Self::Wpa3
+        } else if rsn_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+            Self::Wpa2
+        } else if wpa_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+            Self::Wpa
+        } else if flags & AP_FLAGS_PRIVACY != 0 {
+            Self::Wep
+        } else {
+            Self::Open
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "Open",
+            Self::Wep => "WEP",
+            Self::Wpa => "WPA",
+            Self::Wpa2 => "WPA2",
+            Self::Wpa3 => "WPA3",
+            Self::Enterprise => "Enterprise",
+        }
+    }
+
+    /// The `802-11-wireless-security` settings for connecting with `password`.
+    /// Without a password NetworkManager will ask our secret agent for one.
+    fn security_settings(
+        &self,
+        password: Option<String>,
+    ) -> Option<HashMap<&'static str, Value<'static>>> {
+        let (key_mgmt, key) = match self {
+            Self::Open | Self::Enterprise => return None,
+            Self::Wep => ("none", "wep-key0"),
+            Self::Wpa | Self::Wpa2 => ("wpa-psk", "psk"),
+            Self::Wpa3 => ("sae", "psk"),
+        };
+        let mut settings = HashMap::from([("key-mgmt", Value::from(key_mgmt))]);
+        if let Some(password) = password.filter(|password| !password.is_empty()) {
+            settings.insert(key, Value::from(password));
+        }
+        Some(settings)
+    }
+}
+
+mod access_point_imp {
+    use std::cell::{Cell, RefCell};
+
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    use super::WifiSecurity;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::AccessPoint)]
+    pub struct AccessPoint {
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get)]
+        pub(super) ssid: RefCell<String>,
+        /// Signal strength in percent.
+        #[property(get)]
+        pub(super) strength: Cell<u8>,
+        /// Frequency in MHz.
+        #[property(get)]
+        pub(super) frequency: Cell<u32>,
+        #[property(get, builder(WifiSecurity::Open))]
+        pub(super) security: Cell<WifiSecurity>,
+        /// Whether the device is currently connected to this access point.
+        #[property(get)]
+        pub(super) active: Cell<bool>,
+        /// The saved connection for this network, if there is one.
+        #[property(get)]
+        pub(super) known_connection: RefCell<Option<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AccessPoint {
+        const NAME: &'static str = "BalladServicesAccessPoint";
+        type Type = super::AccessPoint;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for AccessPoint {}
+}
+
+mod vpn_imp {
+    use std::cell::RefCell;
+
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::VpnConnection)]
+    pub struct VpnConnection {
+        /// Path of the saved connection.
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get)]
+        pub(super) name: RefCell<String>,
+        /// Path of the active connection while this VPN is connected.
+        #[property(get)]
+        pub(super) active_connection: RefCell<Option<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VpnConnection {
+        const NAME: &'static str = "BalladServicesVpnConnection";
+        type Type = super::VpnConnection;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for VpnConnection {}
+}
+
+mod device_imp {
+    use std::cell::{Cell, RefCell};
+
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+    #[repr(u8)]
+    #[enum_type(name = "BalladServicesNetworkDeviceType")]
+    pub enum DeviceType {
+        Ethernet,
+        Wifi,
+        Bluetooth,
+        #[default]
+        Other,
+    }
+    impl From<u32> for DeviceType {
+        fn from(value: u32) -> Self {
+            match value {
+                1 => Self::Ethernet,
+                2 => Self::Wifi,
+                5 => Self::Bluetooth,
+                _ => Self::Other,
+            }
+        }
+    }
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::NetworkDevice)]
+    pub struct NetworkDevice {
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get)]
+        pub(super) interface: RefCell<String>,
+        #[property(get, builder(DeviceType::Other))]
+        pub(super) device_type: Cell<DeviceType>,
+        #[property(get)]
+        pub(super) connected: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NetworkDevice {
+        const NAME: &'static str = "BalladServicesNetworkDevice";
+        type Type = super::NetworkDevice;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for NetworkDevice {}
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    use futures::{FutureExt, join};
+    use gtk::gio::ListStore;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties, clone};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::channel::Sender;
+    use smol::lock::RwLock;
+    use smol::stream::StreamExt;
+    use zbus::zvariant::OwnedObjectPath;
+
+    use super::agent::{AgentEvent, SecretAgent};
+    use super::bus::{
+        AccessPointProxy, ActiveConnectionProxy, AgentManagerProxy, DeviceProxy,
+        NetworkManagerProxy, SettingsConnectionProxy, SettingsProxy, WirelessProxy,
+    };
+    use super::{
+        AccessPoint, DEVICE_STATE_ACTIVATED, DeviceType, NetworkDevice, STATE_CONNECTED_GLOBAL,
+        VpnConnection, WifiSecurity,
+    };
+
+    /// A secret request from NetworkManager waiting for the user to answer it.
+    pub(super) struct PendingSecret {
+        pub connection_path: String,
+        pub setting_name: String,
+        pub respond: Sender<String>,
+    }
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::NetworkService)]
+    pub struct NetworkService {
+        #[property(get)]
+        available: Cell<bool>,
+        /// Whether any connection has full internet access.
+        #[property(get)]
+        connected: Cell<bool>,
+        /// The name of the connection used for the default route.
+        #[property(get)]
+        primary_connection: RefCell<Option<String>>,
+        #[property(get)]
+        primary_connection_type: RefCell<Option<String>>,
+
+        #[property(get)]
+        wifi_available: Cell<bool>,
+        #[property(get)]
+        wifi_enabled: Cell<bool>,
+        /// Visible access points, one per network, strongest first.
+        #[property(get)]
+        access_points: ListStore,
+        #[property(get)]
+        active_access_point: RefCell<Option<AccessPoint>>,
+
+        #[property(get)]
+        vpn_connections: ListStore,
+        #[property(get)]
+        devices: ListStore,
+
+        pub(super) connection: RefCell<Option<zbus::Connection>>,
+        pub(super) proxy: RwLock<Option<NetworkManagerProxy<'static>>>,
+        pub(super) wifi_device: RwLock<Option<OwnedObjectPath>>,
+        pub(super) pending_secrets: RefCell<HashMap<u32, PendingSecret>>,
+        next_secret_id: Cell<u32>,
+    }
+    impl Default for NetworkService {
+        fn default() -> Self {
+            Self {
+                available: Default::default(),
+                connected: Default::default(),
+                primary_connection: Default::default(),
+                primary_connection_type: Default::default(),
+                wifi_available: Default::default(),
+                wifi_enabled: Default::default(),
+                access_points: ListStore::with_type(AccessPoint::static_type()),
+                active_access_point: Default::default(),
+                vpn_connections: ListStore::with_type(VpnConnection::static_type()),
+                devices: ListStore::with_type(NetworkDevice::static_type()),
+                connection: Default::default(),
+                proxy: Default::default(),
+                wifi_device: Default::default(),
+                pending_secrets: Default::default(),
+                next_secret_id: Default::default(),
+            }
+        }
+    }
+
+    impl NetworkService {
+        pub(super) fn connection(&self) -> zbus::Connection {
+            self.connection.borrow().clone().unwrap()
+        }
+
+        async fn update_devices(&self) -> Option<OwnedObjectPath> {
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+            let connection = self.connection();
+
+            let mut wifi_device = None;
+            self.devices.remove_all();
+            for path in proxy.get_devices().await.unwrap_or_default() {
+                let Ok(device) = DeviceProxy::builder(&connection)
+                    .path(path.clone())
+                    .unwrap()
+                    .build()
+                    .await
+                else {
+                    continue;
+                };
+                let (device_type, interface, state) =
+                    join!(device.device_type(), device.interface(), device.state());
+                let device_type = DeviceType::from(device_type.unwrap_or_default());
+                if device_type == DeviceType::Other {
+                    continue;
+                }
+                if device_type == DeviceType::Wifi && wifi_device.is_none() {
+                    wifi_device = Some(path.clone());
+                }
+
+                let network_device: NetworkDevice = glib::Object::builder().build();
+                let imp = network_device.imp();
+                imp.path.replace(path.to_string());
+                imp.interface.replace(interface.unwrap_or_default());
+                imp.device_type.set(device_type);
+                imp.connected
+                    .set(state.unwrap_or_default() == DEVICE_STATE_ACTIVATED);
+                self.devices.append(&network_device);
+            }
+
+            wifi_device
+        }
+
+        /// Returns the saved Wi-Fi connections by SSID, and fills in the saved VPN connections.
+        async fn update_saved_connections(
+            &self,
+            active_connections: &HashMap<String, String>,
+        ) -> HashMap<String, String> {
+            let connection = self.connection();
+            let Ok(settings) = SettingsProxy::new(&connection).await else {
+                return HashMap::new();
+            };
+
+            let mut known_networks = HashMap::new();
+            self.vpn_connections.remove_all();
+            for path in settings.list_connections().await.unwrap_or_default() {
+                let Ok(saved) = SettingsConnectionProxy::builder(&connection)
+                    .path(path.clone())
+                    .unwrap()
+                    .build()
+                    .await
+                else {
+                    continue;
+                };
+                let Ok(settings) = saved.get_settings().await else {
+                    continue;
+                };
+                let get_string = |setting: &str, key: &str| {
+                    settings
+                        .get(setting)
+                        .and_then(|setting| setting.get(key))
+                        .and_then(|value| String::try_from(value.try_clone().ok()?).ok())
+                };
+
+                match get_string("connection", "type").as_deref() {
+                    Some("802-11-wireless") => {
+                        let ssid = settings
+                            .get("802-11-wireless")
+                            .and_then(|setting| setting.get("ssid"))
+                            .and_then(|value| Vec::<u8>::try_from(value.try_clone().ok()?).ok());
+                        if let Some(ssid) = ssid {
+                            known_networks.insert(
+                                String::from_utf8_lossy(&ssid).to_string(),
+                                path.to_string(),
+                            );
+                        }
+                    }
+                    Some("vpn" | "wireguard") => {
+                        let vpn: VpnConnection = glib::Object::builder().build();
+                        let imp = vpn.imp();
+                        imp.path.replace(path.to_string());
+                        imp.name
+                            .replace(get_string("connection", "id").unwrap_or_default());
+                        imp.active_connection
+                            .replace(active_connections.get(path.as_str()).cloned());
+                        self.vpn_connections.append(&vpn);
+                    }
+                    _ => {}
+                }
+            }
+
+            known_networks
+        }
+
+        async fn update_access_points(
+            &self,
+            wifi_device: &OwnedObjectPath,
+            known_networks: &HashMap<String, String>,
+        ) {
+            let connection = self.connection();
+            let Ok(wireless) = WirelessProxy::builder(&connection)
+                .path(wifi_device.clone())
+                .unwrap()
+                .build()
+                .await
+            else {
+                return;
+            };
+
+            let active_path = wireless.active_access_point().await.ok();
+            let mut networks: HashMap<String, AccessPoint> = HashMap::new();
+            for path in wireless.get_all_access_points().await.unwrap_or_default() {
+                let Ok(access_point) = AccessPointProxy::builder(&connection)
+                    .path(path.clone())
+                    .unwrap()
+                    .build()
+                    .await
+                else {
+                    continue;
+                };
+                let (ssid, strength, frequency, flags, wpa_flags, rsn_flags) = join!(
+                    access_point.ssid(),
+                    access_point.strength(),
+                    access_point.frequency(),
+                    access_point.flags(),
+                    access_point.wpa_flags(),
+                    access_point.rsn_flags(),
+                );
+                // Hidden networks can't be shown without knowing their name.
+                let ssid = String::from_utf8_lossy(&ssid.unwrap_or_default()).to_string();
+                if ssid.is_empty() {
+                    continue;
+                }
+                let active = active_path.as_ref() == Some(&path);
+                let strength = strength.unwrap_or_default();
+
+                // Only the strongest access point of each network is kept, unless we're connected to another one.
+                if networks.get(&ssid).is_some_and(|existing| {
+                    existing.active() || (!active && existing.strength() >= strength)
+                }) {
+                    continue;
+                }
+
+                let ap: AccessPoint = glib::Object::builder().build();
+                let imp = ap.imp();
+                imp.path.replace(path.to_string());
+                imp.ssid.replace(ssid.clone());
+                imp.strength.set(strength);
+                imp.frequency.set(frequency.unwrap_or_default());
+                imp.security.set(WifiSecurity::from_flags(
+                    flags.unwrap_or_default(),
+                    wpa_flags.unwrap_or_default(),
+                    rsn_flags.unwrap_or_default(),
+                ));
+                imp.active.set(active);
+                imp.known_connection
+                    .replace(known_networks.get(&ssid).cloned());
+                networks.insert(ssid, ap);
+            }
+
+            let mut networks = networks.into_values().collect::<Vec<_>>();
+            networks.sort_by(|a, b| {
+                b.active()
+                    .cmp(&a.active())
+                    .then(b.strength().cmp(&a.strength()))
+            });
+
+            // Device properties change often, so leave the list alone unless something visible changed.
+            let summary =
+                |ap: &AccessPoint| (ap.path(), ap.strength(), ap.active(), ap.known_connection());
+            let unchanged = networks.len() == self.access_points.n_items() as usize
+                && self
+                    .access_points
+                    .iter::<AccessPoint>()
+                    .flatten()
+                    .zip(networks.iter())
+                    .all(|(old, new)| summary(&old) == summary(new));
+            if unchanged {
+                return;
+            }
+
+            let active = networks.iter().find(|ap| ap.active()).cloned();
+            self.access_points.remove_all();
+            for ap in networks {
+                self.access_points.append(&ap);
+            }
+            self.active_access_point.replace(active);
+            self.obj().notify_active_access_point();
+        }
+
+        pub async fn update(&self) {
+            let connection = self.connection();
+            let (wifi_enabled, state, primary_connection, active_connection_paths) = {
+                let proxy = self.proxy.read().await;
+                let proxy = proxy.as_ref().unwrap();
+                join!(
+                    proxy.wireless_enabled(),
+                    proxy.state(),
+                    proxy.primary_connection(),
+                    proxy.active_connections(),
+                )
+            };
+
+            self.wifi_enabled.set(wifi_enabled.unwrap_or_default());
+            self.obj().notify_wifi_enabled();
+            self.connected
+                .set(state.unwrap_or_default() == STATE_CONNECTED_GLOBAL);
+            self.obj().notify_connected();
+
+            let primary_connection = match primary_connection {
+                Ok(path) if path.as_str() != "/" => ActiveConnectionProxy::builder(&connection)
+                    .path(path)
+                    .unwrap()
+                    .build()
+                    .await
+                    .ok(),
+                _ => None,
+            };
+            let (primary_id, primary_type) = match primary_connection.as_ref() {
+                Some(primary) => {
+                    let (id, type_) = join!(primary.id(), primary.type_());
+                    (id.ok(), type_.ok())
+                }
+                None => (None, None),
+            };
+            self.primary_connection.replace(primary_id);
+            self.obj().notify_primary_connection();
+            self.primary_connection_type.replace(primary_type);
+            self.obj().notify_primary_connection_type();
+
+            // Maps saved connection paths to the active connection using them.
+            let mut active_connections = HashMap::new();
+            for path in active_connection_paths.unwrap_or_default() {
+                let Ok(active) = ActiveConnectionProxy::builder(&connection)
+                    .path(path.clone())
+                    .unwrap()
+                    .build()
+                    .await
+                else {
+                    continue;
+                };
+                if let Ok(saved) = active.connection().await {
+                    active_connections.insert(saved.to_string(), path.to_string());
+                }
+            }
+
+            let wifi_device = self.update_devices().await;
+            let known_networks = self.update_saved_connections(&active_connections).await;
+            if let Some(wifi_device) = wifi_device.as_ref() {
+                self.update_access_points(wifi_device, &known_networks)
+                    .await;
+            } else {
+                self.access_points.remove_all();
+            }
+            self.wifi_available.set(wifi_device.is_some());
+            self.obj().notify_wifi_available();
+            *self.wifi_device.write().await = wifi_device;
+
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        fn handle_agent_event(&self, event: AgentEvent) {
+            match event {
+                AgentEvent::Request(request) => {
+                    let id = self.next_secret_id.get();
+                    self.next_secret_id.set(id.wrapping_add(1));
+                    self.pending_secrets.borrow_mut().insert(
+                        id,
+                        PendingSecret {
+                            connection_path: request.connection_path,
+                            setting_name: request.setting_name,
+                            respond: request.respond,
+                        },
+                    );
+                    self.obj()
+                        .emit_by_name::<()>("secret-requested", &[&id, &request.connection_id]);
+                }
+                AgentEvent::Cancel {
+                    connection_path,
+                    setting_name,
+                } => {
+                    let cancelled = self
+                        .pending_secrets
+                        .borrow()
+                        .iter()
+                        .filter(|(_, pending)| {
+                            pending.connection_path == connection_path
+                                && pending.setting_name == setting_name
+                        })
+                        .map(|(id, _)| *id)
+                        .collect::<Vec<_>>();
+                    for id in cancelled {
+                        self.pending_secrets.borrow_mut().remove(&id);
+                        self.obj()
+                            .emit_by_name::<()>("secret-request-cancelled", &[&id]);
+                    }
+                }
+            }
+        }
+
+        async fn register_agent(&self) -> zbus::Result<()> {
+            let connection = self.connection();
+            let (events, receiver) = smol::channel::unbounded();
+            connection
+                .object_server()
+                .at(super::SECRET_AGENT_OBJECT_PATH, SecretAgent { events })
+                .await?;
+            AgentManagerProxy::new(&connection)
+                .await?
+                .register(super::SECRET_AGENT_IDENTIFIER)
+                .await?;
+
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while let Ok(event) = receiver.recv().await {
+                        this.handle_agent_event(event);
+                    }
+                }
+            ));
+
+            Ok(())
+        }
+
+        async fn subscribe_to_updates(&self) -> zbus::Result<()> {
+            let connection = self.connection();
+            let properties_proxy = zbus::fdo::PropertiesProxy::builder(&connection)
+                .destination(super::NETWORK_MANAGER_BUS_NAME)?
+                .path(super::NETWORK_MANAGER_OBJECT_PATH)?
+                .build()
+                .await?;
+            let mut root_stream = properties_proxy.receive_properties_changed().await?;
+
+            let settings = SettingsProxy::new(&connection).await?;
+            let mut new_connection_stream = settings.receive_new_connection().await?;
+            let mut removed_connection_stream = settings.receive_connection_removed().await?;
+
+            // Scans, access point changes and device state changes all come from devices.
+            let device_properties = zbus::MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .sender(super::NETWORK_MANAGER_BUS_NAME)?
+                .interface("org.freedesktop.DBus.Properties")?
+                .member("PropertiesChanged")?
+                .path_namespace("/org/freedesktop/NetworkManager/Devices")?
+                .build();
+            let mut device_stream =
+                zbus::MessageStream::for_match_rule(device_properties, &connection, None).await?;
+
+            loop {
+                let more = futures::select! {
+                    changed = root_stream.next().fuse() => changed.is_some(),
+                    added = new_connection_stream.next().fuse() => added.is_some(),
+                    removed = removed_connection_stream.next().fuse() => removed.is_some(),
+                    device = device_stream.next().fuse() => device.is_some(),
+                };
+                if !more {
+                    break;
+                }
+                self.update().await;
+            }
+
+            Ok(())
+        }
+
+        pub(super) async fn start(&self) {
+            let connection = self.connection();
+            let Ok(proxy) = NetworkManagerProxy::new(&connection).await else {
+                println!(
+                    "Failed to create NetworkManagerProxy. Network service will not function!"
+                );
+                return;
+            };
+            // Creating the proxy succeeds even if NetworkManager isn't running.
+            if proxy.state().await.is_err() {
+                println!("NetworkManager is not running. Network service will not function!");
+                return;
+            }
+            self.proxy.write().await.replace(proxy);
+            self.available.set(true);
+            self.obj().notify_available();
+
+            self.update().await;
+
+            if let Err(err) = self.register_agent().await {
+                println!("Failed to register NetworkManager secret agent: {err}");
+            }
+            if let Err(err) = self.subscribe_to_updates().await {
+                println!("Failed to subscribe to NetworkManager updates: {err}");
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NetworkService {
+        const NAME: &'static str = "BalladServicesNetworkService";
+        type Type = super::NetworkService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for NetworkService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed").build(),
+                    // Emitted with a request id and the connection name when NetworkManager needs a password.
+                    Signal::builder("secret-requested")
+                        .param_types([u32::static_type(), String::static_type()])
+                        .build(),
+                    Signal::builder("secret-request-cancelled")
+                        .param_types([u32::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct AccessPoint(ObjectSubclass<access_point_imp::AccessPoint>);
+}
+impl AccessPoint {
+    pub fn secured(&self) -> bool {
+        self.security() != WifiSecurity::Open
+    }
+}
+
+glib::wrapper! {
+    pub struct VpnConnection(ObjectSubclass<vpn_imp::VpnConnection>);
+}
+impl VpnConnection {
+    pub fn active(&self) -> bool {
+        self.active_connection().is_some()
+    }
+}
+
+glib::wrapper! {
+    pub struct NetworkDevice(ObjectSubclass<device_imp::NetworkDevice>);
+}
+
+glib::wrapper! {
+    pub struct NetworkService(ObjectSubclass<imp::NetworkService>);
+}
+impl NetworkService {
+    pub fn new() -> Self {
+        Self::with_connection(DBUS_SYSTEM_CONNECTION.clone())
+    }
+
+    /// Talks to NetworkManager on `connection` instead of the system bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().connection.replace(Some(connection));
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                this.imp().start().await;
+            }
+        ));
+
+        this
+    }
+
+    async fn with_proxy<T>(
+        &self,
+        f: impl AsyncFnOnce(&bus::NetworkManagerProxy<'static>) -> zbus::Result<T>,
+    ) -> zbus::Result<T> {
+        let proxy = self.imp().proxy.read().await;
+        let Some(proxy) = proxy.as_ref() else {
+            return Err(zbus::Error::Failure(
+                "NetworkManager is not available".to_string(),
+            ));
+        };
+        f(proxy).await
+    }
+
+    async fn wifi_device(&self) -> zbus::Result<OwnedObjectPath> {
+        self.imp()
+            .wifi_device
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| zbus::Error::Failure("There is no Wi-Fi device".to_string()))
+    }
+
+    pub async fn set_wifi_enabled(&self, enabled: bool) -> zbus::Result<()> {
+        self.with_proxy(async |proxy| proxy.set_wireless_enabled(enabled).await)
+            .await
+    }
+
+    pub async fn request_scan(&self) -> zbus::Result<()> {
+        let wifi_device = self.wifi_device().await?;
+        bus::WirelessProxy::builder(&self.imp().connection())
+            .path(wifi_device)?
+            .build()
+            .await?
+            .request_scan(HashMap::new())
+            .await
+    }
+
+    /// Connects to `access_point`, using its saved connection if there is one.
+    /// `password` is only used when creating a new connection to a secured network.
+    pub async fn connect_to_access_point(
+        &self,
+        access_point: &AccessPoint,
+        password: Option<String>,
+    ) -> zbus::Result<()> {
+        let wifi_device = self.wifi_device().await?;
+        let access_point_path = ObjectPath::try_from(access_point.path())?;
+
+        if let Some(known_connection) = access_point.known_connection() {
+            let known_connection = ObjectPath::try_from(known_connection)?;
+            return self
+                .with_proxy(async |proxy| {
+                    proxy
+                        .activate_connection(&known_connection, &wifi_device, &access_point_path)
+                        .await
+                })
+                .await
+                .map(|_| ());
+        }
+
+        let ssid = access_point.ssid();
+        let mut settings = HashMap::new();
+        settings.insert(
+            "connection",
+            HashMap::from([
+                ("id", Value::from(ssid.clone())),
+                ("type", Value::from("802-11-wireless")),
+            ]),
+        );
+        settings.insert(
+            "802-11-wireless",
+            HashMap::from([("ssid", Value::from(ssid.into_bytes()))]),
+        );
+        if access_point.security() == WifiSecurity::Enterprise {
+            return Err(zbus::Error::Failure(
+                "Enterprise networks must be set up in NetworkManager first".to_string(),
+            ));
+        }
+        if let Some(security_settings) = access_point.security().security_settings(password) {
+            settings.insert("802-11-wireless-security", security_settings);
+        }
+
+        self.with_proxy(async |proxy| {
+            proxy
+                .add_and_activate_connection(settings, &wifi_device, &access_point_path)
+                .await
+        })
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn disconnect_wifi(&self) -> zbus::Result<()> {
+        let wifi_device = self.wifi_device().await?;
+        bus::DeviceProxy::builder(&self.imp().connection())
+            .path(wifi_device)?
+            .build()
+            .await?
+            .disconnect()
+            .await
+    }
+
+    pub async fn set_vpn_active(&self, vpn: &VpnConnection, active: bool) -> zbus::Result<()> {
+        match (active, vpn.active_connection()) {
+            (true, None) => {
+                let path = ObjectPath::try_from(vpn.path())?;
+                let none = ObjectPath::try_from("/")?;
+                self.with_proxy(async |proxy| proxy.activate_connection(&path, &none, &none).await)
+                    .await
+                    .map(|_| ())
+            }
+            (false, Some(active_connection)) => {
+                let active_connection = ObjectPath::try_from(active_connection)?;
+                self.with_proxy(async |proxy| proxy.deactivate_connection(&active_connection).await)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Answers a `secret-requested` signal. Passing `None` cancels the request.
+    pub fn provide_secret(&self, request_id: u32, secret: Option<String>) {
+        let Some(pending) = self.imp().pending_secrets.borrow_mut().remove(&request_id) else {
+            return;
+        };
+        if let Some(secret) = secret {
+            pending.respond.try_send(secret).ok();
+        }
+    }
+}
+impl Default for NetworkService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static NETWORK_SERVICE: LazyCell<NetworkService> = LazyCell::new(NetworkService::new);
+}
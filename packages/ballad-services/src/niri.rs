@@ -471,6 +471,19 @@ mod imp {
             ))
             .await;
         }
+
+        pub async fn power_off_monitors(&self) {
+            self.send_message(niri_ipc::Request::Action(
+                niri_ipc::Action::PowerOffMonitors {},
+            ))
+            .await;
+        }
+        pub async fn power_on_monitors(&self) {
+            self.send_message(niri_ipc::Request::Action(
+                niri_ipc::Action::PowerOnMonitors {},
+            ))
+            .await;
+        }
     }
 
     impl Default for NiriService {
@@ -577,6 +590,12 @@ impl NiriService {
     pub async fn do_screen_transition(&self, delay: Option<Duration>) {
         self.imp().do_screen_transition(delay).await;
     }
+    pub async fn power_off_monitors(&self) {
+        self.imp().power_off_monitors().await;
+    }
+    pub async fn power_on_monitors(&self) {
+        self.imp().power_on_monitors().await;
+    }
 }
 impl Default for NiriService {
     fn default() -> Self {
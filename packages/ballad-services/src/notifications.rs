@@ -0,0 +1,914 @@
+use std::{cell::LazyCell, collections::HashMap, path::PathBuf};
+
+use gtk::{
+    glib::{self, Object, clone},
+    prelude::*,
+    subclass::prelude::ObjectSubclassIsExt,
+};
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::OwnedValue;
+
+use crate::DBUS_SESSION_CONNECTION;
+
+pub const NOTIFICATIONS_BUS_NAME: &str = "org.freedesktop.Notifications";
+pub const NOTIFICATIONS_OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+
+/// How long a notification is shown for when the sending application asks for the server default.
+pub const DEFAULT_EXPIRE_TIMEOUT_MILLIS: u32 = 5000;
+/// How long the history waits for more changes before it is written, so a burst of notifications is written once.
+const HISTORY_SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum, Serialize, Deserialize)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesNotificationUrgency")]
+pub enum Urgency {
+    Low = 0,
+    #[default]
+    Normal = 1,
+    Critical = 2,
+}
+impl Urgency {
+    pub fn as_class_name(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+}
+impl From<u8> for Urgency {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Low,
+            2 => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// The reason sent with the `NotificationClosed` signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    CloseNotification = 3,
+    Undefined = 4,
+}
+
+/// An image attached to a notification through the `image-data`, `image-path` or `icon_data` hints.
+#[derive(Debug, Clone, PartialEq, Eq, glib::Boxed, Serialize, Deserialize)]
+#[boxed_type(name = "BalladServicesNotificationImage", nullable)]
+pub enum NotificationImage {
+    /// Raw pixel data.
+    Data {
+        width: i32,
+        height: i32,
+        rowstride: i32,
+        has_alpha: bool,
+        bits_per_sample: i32,
+        channels: i32,
+        data: Vec<u8>,
+    },
+    /// A path to an image file on disk.
+    Path(String),
+    /// A freedesktop icon name.
+    IconName(String),
+}
+impl NotificationImage {
+    /// Parses an `image-path` hint or `app_icon` argument which can either be a URI, a path or an icon name.
+    fn from_path_or_name(value: &str) -> Option<Self> {
+        if value.is_empty() {
+            None
+        } else if let Some(path) = value.strip_prefix("file://") {
+            Some(Self::Path(path.to_string()))
+        } else if value.starts_with('/') {
+            Some(Self::Path(value.to_string()))
+        } else {
+            Some(Self::IconName(value.to_string()))
+        }
+    }
+
+    fn from_data_hint(value: OwnedValue) -> Option<Self> {
+        let (width, height, rowstride, has_alpha, bits_per_sample, channels, data): (
+            i32,
+            i32,
+            i32,
+            bool,
+            i32,
+            i32,
+            Vec<u8>,
+        ) = value.try_into().ok()?;
+
+        Some(Self::Data {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            bits_per_sample,
+            channels,
+            data,
+        })
+    }
+}
+
+/// A notification action, made up of the key sent back in `ActionInvoked` and a human readable label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationAction {
+    pub key: String,
+    pub label: String,
+}
+
+/// Everything a client sent in a `Notify` call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct NotificationData {
+    pub id: u32,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<NotificationAction>,
+    pub urgency: Urgency,
+    pub expire_timeout: i32,
+    pub image: Option<NotificationImage>,
+    pub desktop_entry: Option<String>,
+    pub category: Option<String>,
+    pub transient: bool,
+    pub resident: bool,
+    pub timestamp: i64,
+}
+impl NotificationData {
+    #[allow(clippy::too_many_arguments)]
+    fn from_notify(
+        id: u32,
+        app_name: String,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        mut hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> Self {
+        fn take_string(hints: &mut HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+            hints
+                .remove(key)
+                .and_then(|value| String::try_from(value).ok())
+        }
+        fn take_bool(hints: &mut HashMap<String, OwnedValue>, key: &str) -> bool {
+            hints
+                .remove(key)
+                .and_then(|value| bool::try_from(value).ok())
+                .unwrap_or_default()
+        }
+
+        let urgency = hints
+            .remove("urgency")
+            .and_then(|value| u8::try_from(value).ok())
+            .map(Urgency::from)
+            .unwrap_or_default();
+
+        // Spec order of precedence: image-data, image-path, app_icon. `image_data`, `image_path` and `icon_data` are deprecated names.
+        let image = ["image-data", "image_data", "icon_data"]
+            .into_iter()
+            .find_map(|key| {
+                hints
+                    .remove(key)
+                    .and_then(NotificationImage::from_data_hint)
+            })
+            .or_else(|| {
+                ["image-path", "image_path"].into_iter().find_map(|key| {
+                    take_string(&mut hints, key)
+                        .and_then(|path| NotificationImage::from_path_or_name(&path))
+                })
+            })
+            .or_else(|| NotificationImage::from_path_or_name(&app_icon));
+
+        // Actions are sent as a flat list of alternating keys and labels.
+        let actions = actions
+            .chunks_exact(2)
+            .map(|pair| NotificationAction {
+                key: pair[0].clone(),
+                label: pair[1].clone(),
+            })
+            .collect();
+
+        Self {
+            id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            actions,
+            urgency,
+            expire_timeout,
+            image,
+            desktop_entry: take_string(&mut hints, "desktop-entry"),
+            category: take_string(&mut hints, "category"),
+            transient: take_bool(&mut hints, "transient"),
+            resident: take_bool(&mut hints, "resident"),
+            timestamp: glib::real_time() / 1_000_000,
+        }
+    }
+}
+
+/// Path of the file notification history is persisted to between shell restarts.
+pub fn history_path() -> PathBuf {
+    xdg::BaseDirectories::with_prefix("ballad")
+        .unwrap()
+        .place_state_file("notification_history.toml")
+        .unwrap()
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct NotificationHistory {
+    notifications: Vec<NotificationData>,
+}
+
+fn read_history() -> Vec<NotificationData> {
+    std::fs::read_to_string(history_path())
+        .ok()
+        .and_then(|content| toml::from_str::<NotificationHistory>(&content).ok())
+        .map(|history| history.notifications)
+        .unwrap_or_default()
+}
+
+fn write_history(notifications: Vec<NotificationData>) {
+    let notifications = notifications
+        .into_iter()
+        .map(|notification| NotificationData {
+            // The client that sent these is not around to receive them after a restart.
+            actions: Vec::new(),
+            // Raw image data is too large to be worth keeping around.
+            image: notification
+                .image
+                .filter(|image| !matches!(image, NotificationImage::Data { .. })),
+            ..notification
+        })
+        .collect();
+
+    let history = NotificationHistory { notifications };
+    match toml::to_string(&history) {
+        Ok(content) => {
+            if let Err(err) = std::fs::write(history_path(), content) {
+                println!("Failed to write notification history: {err}");
+            }
+        }
+        Err(err) => println!("Failed to serialize notification history: {err}"),
+    }
+}
+
+mod bus {
+    //! # D-Bus server for: `org.freedesktop.Notifications`
+    //!
+    //! Implements version 1.2 of the [Desktop Notifications Specification](https://specifications.freedesktop.org/notification-spec/latest/).
+
+    use std::collections::HashMap;
+
+    use smol::channel::Sender;
+    use zbus::{interface, object_server::SignalEmitter, zvariant::OwnedValue};
+
+    use super::NotificationData;
+
+    #[derive(Debug, Clone)]
+    pub(crate) enum ServerEvent {
+        Notify(Box<NotificationData>),
+        Close(u32),
+    }
+
+    #[derive(Debug)]
+    pub(crate) struct NotificationsServer {
+        pub(crate) events: Sender<ServerEvent>,
+        pub(crate) next_id: u32,
+    }
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl NotificationsServer {
+        /// GetCapabilities method
+        fn get_capabilities(&self) -> Vec<&'static str> {
+            vec![
+                "actions",
+                "body",
+                "body-markup",
+                "icon-static",
+                "persistence",
+            ]
+        }
+
+        /// Notify method
+        #[allow(clippy::too_many_arguments)]
+        async fn notify(
+            &mut self,
+            app_name: String,
+            replaces_id: u32,
+            app_icon: String,
+            summary: String,
+            body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            expire_timeout: i32,
+        ) -> u32 {
+            let id = if replaces_id != 0 {
+                replaces_id
+            } else {
+                // Ids must never be 0, so skip it when wrapping around.
+                self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+                self.next_id
+            };
+
+            let data = NotificationData::from_notify(
+                id,
+                app_name,
+                app_icon,
+                summary,
+                body,
+                actions,
+                hints,
+                expire_timeout,
+            );
+            _ = self.events.send(ServerEvent::Notify(Box::new(data))).await;
+
+            id
+        }
+
+        /// CloseNotification method
+        async fn close_notification(&self, id: u32) {
+            _ = self.events.send(ServerEvent::Close(id)).await;
+        }
+
+        /// GetServerInformation method
+        fn get_server_information(
+            &self,
+        ) -> (&'static str, &'static str, &'static str, &'static str) {
+            (
+                "Ballad",
+                "Gavin Niederman",
+                env!("CARGO_PKG_VERSION"),
+                "1.2",
+            )
+        }
+
+        /// NotificationClosed signal
+        #[zbus(signal)]
+        pub(crate) async fn notification_closed(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            reason: u32,
+        ) -> zbus::Result<()>;
+
+        /// ActionInvoked signal
+        #[zbus(signal)]
+        pub(crate) async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            action_key: &str,
+        ) -> zbus::Result<()>;
+    }
+}
+
+mod notification_imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use gtk::glib;
+    use gtk::glib::subclass::Signal;
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+
+    use super::{NotificationAction, NotificationImage, Urgency};
+
+    #[derive(Debug, Default, Properties)]
+    #[properties(wrapper_type = super::Notification)]
+    pub struct Notification {
+        /// Id assigned to the notification by the server.
+        #[property(get)]
+        pub(super) id: Cell<u32>,
+        /// Name of the application that sent the notification, if it gave one.
+        #[property(get)]
+        pub(super) app_name: RefCell<String>,
+        /// The `app_icon` argument the notification was sent with.
+        #[property(get)]
+        pub(super) app_icon: RefCell<String>,
+        #[property(get)]
+        pub(super) summary: RefCell<String>,
+        /// Body text. This may contain the markup subset described by the spec.
+        #[property(get)]
+        pub(super) body: RefCell<String>,
+        #[property(get, builder(Urgency::Normal))]
+        pub(super) urgency: Cell<Urgency>,
+        /// Requested timeout in milliseconds. -1 uses the server default and 0 never expires.
+        #[property(get, default_value = -1)]
+        pub(super) expire_timeout: Cell<i32>,
+        #[property(get, nullable)]
+        pub(super) image: RefCell<Option<NotificationImage>>,
+        /// Desktop entry (without the `.desktop` suffix) of the sending application.
+        #[property(get)]
+        pub(super) desktop_entry: RefCell<Option<String>>,
+        #[property(get)]
+        pub(super) category: RefCell<Option<String>>,
+        /// Transient notifications are never kept after they expire.
+        #[property(get)]
+        pub(super) transient: Cell<bool>,
+        /// Resident notifications are not removed when one of their actions is invoked.
+        #[property(get)]
+        pub(super) resident: Cell<bool>,
+        /// Unix timestamp in seconds of when the notification was last updated.
+        #[property(get)]
+        pub(super) timestamp: Cell<i64>,
+        /// Whether the notification timed out and should no longer be shown as a popup.
+        #[property(get)]
+        pub(super) expired: Cell<bool>,
+
+        pub(super) actions: RefCell<Vec<NotificationAction>>,
+        pub(super) expire_source: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Notification {
+        const NAME: &'static str = "BalladServicesNotification";
+        type Type = super::Notification;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Notification {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+
+        fn dispose(&self) {
+            if let Some(source) = self.expire_source.take() {
+                source.remove();
+            }
+        }
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use gtk::gio::{self, ListStore};
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, clone};
+    use gtk::{glib::Properties, prelude::*, subclass::prelude::*};
+    use zbus::object_server::InterfaceRef;
+
+    use crate::do_not_disturb::DO_NOT_DISTURB_SERVICE;
+
+    use super::bus::{NotificationsServer, ServerEvent};
+    use super::{
+        CloseReason, DEFAULT_EXPIRE_TIMEOUT_MILLIS, HISTORY_SAVE_DELAY, NOTIFICATIONS_BUS_NAME,
+        NOTIFICATIONS_OBJECT_PATH, Notification, NotificationData, Urgency,
+    };
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::NotificationsService)]
+    pub struct NotificationsService {
+        /// Whether this service owns `org.freedesktop.Notifications`.
+        #[property(get)]
+        available: Cell<bool>,
+        /// Every notification that has not been dismissed, oldest first.
+        #[property(get)]
+        notifications: ListStore,
+
+        connection: RefCell<Option<zbus::Connection>>,
+        /// Whether the history changed since it was last written.
+        history_changed: Cell<bool>,
+        /// Whether a task is waiting to write the history, or writing it.
+        history_writer: Cell<bool>,
+    }
+    impl Default for NotificationsService {
+        fn default() -> Self {
+            Self {
+                available: Cell::new(false),
+                notifications: ListStore::with_type(Notification::static_type()),
+
+                connection: Default::default(),
+                history_changed: Cell::new(false),
+                history_writer: Cell::new(false),
+            }
+        }
+    }
+
+    impl NotificationsService {
+        pub fn notification_by_id(&self, id: u32) -> Option<Notification> {
+            self.notifications
+                .iter::<Notification>()
+                .filter_map(|notification| notification.ok())
+                .find(|notification| notification.id() == id)
+        }
+
+        fn load_history(&self) -> u32 {
+            let history = super::read_history();
+            let last_id = history.iter().map(|data| data.id).max().unwrap_or_default();
+
+            for data in history {
+                let notification = Notification::new();
+                notification.update(data);
+                // Restored notifications are only shown in the notification center.
+                notification.imp().expired.set(true);
+                notification.notify_expired();
+                self.notifications.append(&notification);
+            }
+
+            last_id
+        }
+
+        /// Writes the history shortly, off the main thread. Changes made while it is written are written after.
+        fn save_history(&self) {
+            self.history_changed.set(true);
+            if self.history_writer.replace(true) {
+                return;
+            }
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while this.history_changed.get() {
+                        glib::timeout_future(HISTORY_SAVE_DELAY).await;
+                        this.history_changed.set(false);
+                        let history = this.persisted_history();
+                        _ = gio::spawn_blocking(move || super::write_history(history)).await;
+                    }
+                    this.history_writer.set(false);
+                }
+            ));
+        }
+
+        /// The notifications that are kept between restarts, up to the history limit.
+        fn persisted_history(&self) -> Vec<NotificationData> {
+            let (persisted, limit) = DO_NOT_DISTURB_SERVICE.with(|dnd| {
+                let persisted = self
+                    .notifications
+                    .iter::<Notification>()
+                    .filter_map(|notification| notification.ok())
+                    .filter(|notification| dnd.should_persist(notification))
+                    .map(|notification| notification.data())
+                    .collect::<Vec<_>>();
+                (persisted, dnd.config().history_limit as usize)
+            });
+
+            let skip = persisted.len().saturating_sub(limit);
+            persisted.into_iter().skip(skip).collect()
+        }
+
+        /// Drops the oldest notifications that are no longer shown as popups once the history limit is exceeded.
+        fn trim(&self) {
+            let limit = DO_NOT_DISTURB_SERVICE.with(|dnd| dnd.config().history_limit);
+            while self.notifications.n_items() > limit {
+                let Some(position) = self
+                    .notifications
+                    .iter::<Notification>()
+                    .filter_map(|notification| notification.ok())
+                    .position(|notification| notification.expired())
+                else {
+                    break;
+                };
+                self.notifications.remove(position as u32);
+            }
+        }
+
+        pub(super) async fn serve(&self, connection: zbus::Connection) -> zbus::Result<()> {
+            // Ids continue from the history so restored notifications are never replaced by new ones.
+            let last_id = self.load_history();
+
+            let (sender, receiver) = smol::channel::unbounded();
+            connection
+                .object_server()
+                .at(
+                    NOTIFICATIONS_OBJECT_PATH,
+                    NotificationsServer {
+                        events: sender,
+                        next_id: last_id,
+                    },
+                )
+                .await?;
+            connection.request_name(NOTIFICATIONS_BUS_NAME).await?;
+
+            self.connection.replace(Some(connection));
+            self.available.set(true);
+            self.obj().notify_available();
+
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    while let Ok(event) = receiver.recv().await {
+                        match event {
+                            ServerEvent::Notify(data) => this.handle_notify(*data),
+                            ServerEvent::Close(id) => {
+                                this.close(id, CloseReason::CloseNotification).await
+                            }
+                        }
+                    }
+                }
+            ));
+
+            Ok(())
+        }
+
+        fn handle_notify(&self, data: NotificationData) {
+            let (notification, replaced) = match self.notification_by_id(data.id) {
+                Some(notification) => (notification, true),
+                None => (Notification::new(), false),
+            };
+            notification.update(data);
+
+            if let Some(source) = notification.imp().expire_source.take() {
+                source.remove();
+            }
+            let timeout = match notification.expire_timeout() {
+                // Critical notifications should only be closed by the user.
+                _ if notification.urgency() == Urgency::Critical => None,
+                0 => None,
+                timeout if timeout < 0 => Some(DEFAULT_EXPIRE_TIMEOUT_MILLIS),
+                timeout => Some(timeout as u32),
+            };
+            if let Some(timeout) = timeout {
+                let source = glib::timeout_add_local_once(
+                    Duration::from_millis(timeout as u64),
+                    clone!(
+                        #[weak(rename_to = this)]
+                        self,
+                        #[weak]
+                        notification,
+                        move || {
+                            notification.imp().expire_source.take();
+                            glib::spawn_future_local(async move {
+                                this.expire(&notification).await;
+                            });
+                        }
+                    ),
+                );
+                notification.imp().expire_source.replace(Some(source));
+            }
+
+            if !replaced {
+                self.notifications.append(&notification);
+                self.trim();
+            }
+            self.obj()
+                .emit_by_name::<()>("notified", &[&notification, &replaced]);
+
+            self.save_history();
+        }
+
+        async fn expire(&self, notification: &Notification) {
+            notification.imp().expired.set(true);
+            notification.notify_expired();
+            notification.emit_by_name::<()>("changed", &[]);
+            self.obj().emit_by_name::<()>("expired", &[notification]);
+
+            // The client is told the notification is gone, even if it stays in the notification center.
+            self.emit_closed(notification.id(), CloseReason::Expired)
+                .await;
+            // Transient notifications aren't kept once they expire.
+            if notification.transient() {
+                self.close(notification.id(), CloseReason::Expired).await;
+            }
+        }
+
+        async fn server(&self) -> Option<InterfaceRef<NotificationsServer>> {
+            let connection = self.connection.borrow().clone()?;
+            connection
+                .object_server()
+                .interface::<_, NotificationsServer>(NOTIFICATIONS_OBJECT_PATH)
+                .await
+                .ok()
+        }
+
+        async fn emit_closed(&self, id: u32, reason: CloseReason) {
+            let Some(server) = self.server().await else {
+                return;
+            };
+            _ = NotificationsServer::notification_closed(
+                server.signal_emitter(),
+                id,
+                reason as u32,
+            )
+            .await;
+        }
+
+        pub async fn close(&self, id: u32, reason: CloseReason) {
+            let Some(notification) = self.notification_by_id(id) else {
+                return;
+            };
+            if let Some(source) = notification.imp().expire_source.take() {
+                source.remove();
+            }
+            if let Some(position) = self.notifications.find(&notification) {
+                self.notifications.remove(position);
+            }
+            self.save_history();
+
+            // Expired notifications already told the client they were closed.
+            if !notification.expired() {
+                self.emit_closed(id, reason).await;
+            }
+            self.obj()
+                .emit_by_name::<()>("closed", &[&id, &(reason as u32)]);
+        }
+
+        pub async fn clear(&self) {
+            let ids = self
+                .notifications
+                .iter::<Notification>()
+                .filter_map(|notification| notification.ok())
+                .map(|notification| notification.id())
+                .collect::<Vec<_>>();
+            for id in ids {
+                self.close(id, CloseReason::Dismissed).await;
+            }
+        }
+
+        pub async fn invoke_action(&self, id: u32, action_key: &str) {
+            let Some(notification) = self.notification_by_id(id) else {
+                return;
+            };
+
+            // The client was told an expired notification is closed, so it isn't told about actions on it anymore.
+            if !notification.expired()
+                && let Some(server) = self.server().await
+            {
+                _ = NotificationsServer::action_invoked(server.signal_emitter(), id, action_key)
+                    .await;
+            }
+
+            if !notification.resident() {
+                self.close(id, CloseReason::Dismissed).await;
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NotificationsService {
+        const NAME: &'static str = "BalladServicesNotificationsService";
+        type Type = super::NotificationsService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for NotificationsService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    // Emitted with the notification and whether it replaced an existing one.
+                    Signal::builder("notified")
+                        .param_types([Notification::static_type(), bool::static_type()])
+                        .build(),
+                    Signal::builder("expired")
+                        .param_types([Notification::static_type()])
+                        .build(),
+                    // Emitted with the id and close reason of a notification that was removed.
+                    Signal::builder("closed")
+                        .param_types([u32::static_type(), u32::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct NotificationsService(ObjectSubclass<imp::NotificationsService>);
+}
+impl NotificationsService {
+    /// Creates a notification daemon on the session bus.
+    pub fn new() -> Self {
+        let this: Self = Object::builder().build();
+
+        glib::spawn_future_local(clone!(
+            #[weak]
+            this,
+            async move {
+                this.serve(DBUS_SESSION_CONNECTION.clone()).await;
+            }
+        ));
+
+        this
+    }
+
+    /// Creates a notification daemon that serves on `connection` instead of the session bus.
+    /// This is useful for running the daemon against a private bus.
+    pub async fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+        this.serve(connection).await;
+        this
+    }
+
+    async fn serve(&self, connection: zbus::Connection) {
+        if let Err(err) = self.imp().serve(connection).await {
+            println!(
+                "Failed to acquire {NOTIFICATIONS_BUS_NAME} ({err}). Is another notification daemon running? Notifications service will not function!"
+            );
+        }
+    }
+
+    pub fn notification_by_id(&self, id: u32) -> Option<Notification> {
+        self.imp().notification_by_id(id)
+    }
+
+    /// Closes a notification because the user dismissed it.
+    pub async fn dismiss(&self, id: u32) {
+        self.imp().close(id, CloseReason::Dismissed).await;
+    }
+    /// Dismisses every notification.
+    pub async fn clear(&self) {
+        self.imp().clear().await;
+    }
+    /// Sends `ActionInvoked` to the client and closes the notification unless it is resident.
+    pub async fn invoke_action(&self, id: u32, action_key: &str) {
+        self.imp().invoke_action(id, action_key).await;
+    }
+}
+impl Default for NotificationsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+glib::wrapper! {
+    pub struct Notification(ObjectSubclass<notification_imp::Notification>);
+}
+impl Notification {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn actions(&self) -> Vec<NotificationAction> {
+        self.imp().actions.borrow().clone()
+    }
+    /// The action invoked when the notification itself is clicked, if the client provided one.
+    pub fn default_action(&self) -> Option<NotificationAction> {
+        self.imp()
+            .actions
+            .borrow()
+            .iter()
+            .find(|action| action.key == "default")
+            .cloned()
+    }
+
+    pub(crate) fn data(&self) -> NotificationData {
+        NotificationData {
+            id: self.id(),
+            app_name: self.app_name(),
+            app_icon: self.app_icon(),
+            summary: self.summary(),
+            body: self.body(),
+            actions: self.actions(),
+            urgency: self.urgency(),
+            expire_timeout: self.expire_timeout(),
+            image: self.image(),
+            desktop_entry: self.desktop_entry(),
+            category: self.category(),
+            transient: self.transient(),
+            resident: self.resident(),
+            timestamp: self.timestamp(),
+        }
+    }
+
+    pub(crate) fn update(&self, data: NotificationData) {
+        let imp = self.imp();
+
+        imp.id.set(data.id);
+        self.notify_id();
+        imp.app_name.replace(data.app_name);
+        self.notify_app_name();
+        imp.app_icon.replace(data.app_icon);
+        self.notify_app_icon();
+        imp.summary.replace(data.summary);
+        self.notify_summary();
+        imp.body.replace(data.body);
+        self.notify_body();
+        imp.urgency.set(data.urgency);
+        self.notify_urgency();
+        imp.expire_timeout.set(data.expire_timeout);
+        self.notify_expire_timeout();
+        imp.image.replace(data.image);
+        self.notify_image();
+        imp.desktop_entry.replace(data.desktop_entry);
+        self.notify_desktop_entry();
+        imp.category.replace(data.category);
+        self.notify_category();
+        imp.transient.set(data.transient);
+        self.notify_transient();
+        imp.resident.set(data.resident);
+        self.notify_resident();
+        imp.timestamp.set(data.timestamp);
+        self.notify_timestamp();
+        imp.expired.set(false);
+        self.notify_expired();
+        imp.actions.replace(data.actions);
+
+        self.emit_by_name::<()>("changed", &[]);
+    }
+}
+impl Default for Notification {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static NOTIFICATIONS_SERVICE: LazyCell<NotificationsService> = LazyCell::new(NotificationsService::new);
+}
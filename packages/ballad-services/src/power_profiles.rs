@@ -1,11 +1,24 @@
 use std::cell::LazyCell;
+use std::collections::HashMap;
 
+use ballad_config::{PowerProfile, ProfileHoldRule, ShellConfig};
 use ballad_macro::Reactive;
 use futures::join;
+use gtk::{gio, glib, prelude::*};
 use smol::stream::StreamExt;
-use zbus::proxy;
+use zbus::{proxy, zvariant::OwnedValue};
 
-use crate::{DBUS_SYSTEM_CONNECTION, reactive_wrapper};
+use crate::{
+    DBUS_SYSTEM_CONNECTION,
+    config::{CONFIG_SERVICE, ConfigService},
+    niri::{NIRI_SERVICE, NiriService, Window},
+    reactive_wrapper,
+};
+
+/// The bus name power-profiles-daemon owns while it runs.
+const BUS_NAME: &str = "org.freedesktop.UPower.PowerProfiles";
+/// The application ID holds taken for [`ProfileHoldRule`]s are reported under.
+const HOLD_APPLICATION_ID: &str = "com.gavinniederman.ballad-shell";
 
 #[proxy(
     interface = "org.freedesktop.UPower.PowerProfiles",
@@ -58,13 +71,41 @@ trait PowerProfiles {
     fn version(&self) -> zbus::Result<String>;
 }
 
+/// A profile an application asked power-profiles-daemon to keep active.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileHold {
+    pub profile: String,
+    pub reason: String,
+    pub application_id: String,
+}
+impl ProfileHold {
+    fn from_dict(dict: &HashMap<String, OwnedValue>) -> Self {
+        let get = |key: &str| {
+            dict.get(key)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        Self {
+            profile: get("Profile"),
+            reason: get("Reason"),
+            application_id: get("ApplicationId"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Reactive)]
 #[wrapper_type(PowerProfilesService)]
 pub struct PowerProfilesServiceInner {
+    /// Whether power-profiles-daemon is running, as far as the last update could reach it.
+    #[property(get)]
+    pub available: bool,
     #[property(get)]
     pub active_profile: String,
     #[property(get)]
     pub profiles: Vec<String>,
+    #[property(get)]
+    pub holds: Vec<ProfileHold>,
 
     #[property(get)]
     pub performance_degraded: bool,
@@ -76,26 +117,36 @@ pub struct PowerProfilesServiceInner {
 
 impl PowerProfilesServiceInner {
     pub async fn update(&mut self) {
-        if let Some(proxy) = self.proxy.as_ref() {
-            let (active_profile, profiles, performance_degraded, performance_inhibited) = join!(
-                proxy.active_profile(),
-                proxy.profiles(),
-                proxy.performance_degraded(),
-                proxy.performance_inhibited()
-            );
+        let Some(proxy) = self.proxy.as_ref() else {
+            self.available = false;
+            return;
+        };
+        let (active_profile, profiles, holds, performance_degraded, performance_inhibited) = join!(
+            proxy.active_profile(),
+            proxy.profiles(),
+            proxy.active_profile_holds(),
+            proxy.performance_degraded(),
+            proxy.performance_inhibited()
+        );
 
-            self.active_profile = active_profile.unwrap_or_default();
-            self.profiles = profiles
-                .unwrap_or_default()
-                .iter()
-                .map(|profile| {
-                    let string: String = profile["Profile"].clone().try_into().unwrap();
-                    string
-                })
-                .collect();
-            self.performance_degraded = performance_degraded.unwrap_or_default() == "true";
-            self.performance_inhibited = performance_inhibited.unwrap_or_default() == "true";
-        }
+        // The proxy doesn't cache properties, so this only succeeds if the daemon answered.
+        self.available = active_profile.is_ok();
+        self.active_profile = active_profile.unwrap_or_default();
+        self.profiles = profiles
+            .unwrap_or_default()
+            .iter()
+            .map(|profile| {
+                let string: String = profile["Profile"].clone().try_into().unwrap();
+                string
+            })
+            .collect();
+        self.holds = holds
+            .unwrap_or_default()
+            .iter()
+            .map(ProfileHold::from_dict)
+            .collect();
+        self.performance_degraded = performance_degraded.unwrap_or_default() == "true";
+        self.performance_inhibited = performance_inhibited.unwrap_or_default() == "true";
     }
 }
 
@@ -107,7 +158,11 @@ impl PowerProfilesService {
             inner: Default::default(),
         };
 
-        let Ok(proxy) = PowerProfilesProxy::new(&DBUS_SYSTEM_CONNECTION).await else {
+        let proxy = PowerProfilesProxy::builder(&DBUS_SYSTEM_CONNECTION)
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await;
+        let Ok(proxy) = proxy else {
             println!(
                 "Failed to connect to power-profiles-daemon. Power profiles service will not be available."
             );
@@ -118,30 +173,186 @@ impl PowerProfilesService {
             inner.proxy = Some(proxy);
 
             smol::block_on(inner.update())
-        });     
+        });
+        if !this.available().await {
+            println!(
+                "power-profiles-daemon isn't running. Power profiles will be hidden until it starts."
+            );
+        }
 
         let this2 = this.clone();
         gtk::glib::spawn_future_local(async move {
-            let proxy = zbus::fdo::PropertiesProxy::new(&DBUS_SYSTEM_CONNECTION, "org.freedesktop.UPower.PowerProfiles", "/org/freedesktop/UPower/PowerProfiles")
-                .await
-                .unwrap();
-            
+            let proxy = zbus::fdo::PropertiesProxy::new(
+                &DBUS_SYSTEM_CONNECTION,
+                "org.freedesktop.UPower.PowerProfiles",
+                "/org/freedesktop/UPower/PowerProfiles",
+            )
+            .await
+            .unwrap();
+
             let mut stream = proxy.receive_properties_changed().await.unwrap();
             while stream.next().await.is_some() {
-                let mut inner = this2.inner.get().await;
-                inner.update().await;
-                this2.inner.set(inner).await;
+                this2.refresh().await;
+            }
+        });
+
+        // Follows the daemon starting and stopping.
+        let this2 = this.clone();
+        gtk::glib::spawn_future_local(async move {
+            let Ok(dbus_proxy) = zbus::fdo::DBusProxy::new(&DBUS_SYSTEM_CONNECTION).await else {
+                return;
+            };
+            let Ok(mut owner_changed_stream) = dbus_proxy.receive_name_owner_changed().await else {
+                return;
+            };
+            while let Some(signal) = owner_changed_stream.next().await {
+                if signal.args().is_ok_and(|args| args.name() == BUS_NAME) {
+                    this2.refresh().await;
+                }
             }
         });
 
         this
     }
 
+    async fn refresh(&self) {
+        let mut inner = self.inner.get().await;
+        inner.update().await;
+        self.inner.set(inner).await;
+    }
+
     pub fn set_active_profile(&self, profile: String) {
         self.inner.apply(|inner| {
             inner.active_profile = profile;
         });
     }
+
+    /// Asks power-profiles-daemon to switch to `profile`.
+    pub async fn switch_profile(&self, profile: &str) -> zbus::Result<()> {
+        let mut inner = self.inner.get().await;
+        let Some(proxy) = inner.proxy.as_ref() else {
+            return Err(zbus::Error::Failure(
+                "power-profiles-daemon is not available".to_string(),
+            ));
+        };
+        proxy.set_active_profile(profile).await?;
+        inner.active_profile = profile.to_string();
+        self.inner.set(inner).await;
+        Ok(())
+    }
+
+    async fn proxy(&self) -> zbus::Result<PowerProfilesProxy<'static>> {
+        self.inner.get().await.proxy.ok_or_else(|| {
+            zbus::Error::Failure("power-profiles-daemon is not available".to_string())
+        })
+    }
+
+    /// Keeps `profile` active until the hold is released or the shell exits. Returns the cookie to release it with.
+    pub async fn hold_profile(
+        &self,
+        profile: PowerProfile,
+        reason: &str,
+        application_id: &str,
+    ) -> zbus::Result<u32> {
+        self.proxy()
+            .await?
+            .hold_profile(profile.as_str(), reason, application_id)
+            .await
+    }
+
+    pub async fn release_profile(&self, cookie: u32) -> zbus::Result<()> {
+        self.proxy().await?.release_profile(cookie).await
+    }
+
+    /// Takes or releases holds so every rule with an open window holds its profile. `rule_holds` has the cookies of
+    /// the holds taken for each matching rule, by the rule's app ID.
+    async fn apply_hold_rules(
+        &self,
+        rule_holds: &mut HashMap<String, u32>,
+        rules: &[ProfileHoldRule],
+        app_ids: &[String],
+    ) {
+        let wanted = rules
+            .iter()
+            .filter(|rule| app_ids.iter().any(|app_id| rule.matches(app_id)))
+            .collect::<Vec<_>>();
+
+        for (app_id, cookie) in rule_holds.clone() {
+            if !wanted.iter().any(|rule| rule.app_id == app_id) {
+                rule_holds.remove(&app_id);
+                if let Err(err) = self.release_profile(cookie).await {
+                    println!("Failed to release the power profile held for {app_id}: {err}");
+                }
+            }
+        }
+        for rule in wanted {
+            if rule_holds.contains_key(&rule.app_id) {
+                continue;
+            }
+            let reason = format!("A window of {} is open", rule.app_id);
+            match self
+                .hold_profile(rule.profile, &reason, HOLD_APPLICATION_ID)
+                .await
+            {
+                Ok(cookie) => {
+                    rule_holds.insert(rule.app_id.clone(), cookie);
+                }
+                Err(err) => println!("Failed to hold a power profile for {}: {err}", rule.app_id),
+            }
+        }
+    }
+
+    /// Follows the open windows and the config, holding profiles according to the configured rules.
+    pub fn watch_hold_rules(&self) {
+        // One task applies the rules, one update after another, so no cookie is lost and every hold gets released.
+        let (sender, receiver) = smol::channel::unbounded::<(Vec<ProfileHoldRule>, Vec<String>)>();
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let mut rule_holds = HashMap::new();
+            while let Ok(mut update) = receiver.recv().await {
+                // Only the latest windows and rules matter.
+                while let Ok(newer) = receiver.try_recv() {
+                    update = newer;
+                }
+                let (rules, app_ids) = update;
+                this.apply_hold_rules(&mut rule_holds, &rules, &app_ids)
+                    .await;
+            }
+        });
+
+        let update = move || {
+            let rules =
+                CONFIG_SERVICE.with(|service| service.shell_config().power_profiles.hold_rules);
+            let app_ids = NIRI_SERVICE.with(|service| {
+                service
+                    .windows()
+                    .iter::<Window>()
+                    .filter_map(|window| window.ok()?.app_id())
+                    .collect::<Vec<_>>()
+            });
+            _ = sender.try_send((rules, app_ids));
+        };
+
+        update();
+        NIRI_SERVICE.with(|service| {
+            service.connect_closure(
+                "windows-changed",
+                false,
+                glib::closure_local!(
+                    #[strong]
+                    update,
+                    move |_: NiriService, _: gio::ListStore| update()
+                ),
+            );
+        });
+        CONFIG_SERVICE.with(|service| {
+            service.connect_closure(
+                "shell-config-changed",
+                false,
+                glib::closure_local!(move |_: ConfigService, _: &ShellConfig| update()),
+            );
+        });
+    }
 }
 
 impl Default for PowerProfilesService {
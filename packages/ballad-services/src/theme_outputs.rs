@@ -0,0 +1,142 @@
+use std::cell::LazyCell;
+
+use ballad_config::ThemeConfig;
+use gtk::glib::{self, Object};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+/// Runs the reload command of an output after its file changed.
+fn run_reload_command(command: String) {
+    glib::spawn_future_local(async move {
+        match smol::process::Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .status()
+            .await
+        {
+            Ok(status) if status.success() => {}
+            Ok(status) => println!("Theme reload command `{command}` exited with {status}"),
+            Err(err) => println!("Failed to run theme reload command `{command}`: {err}"),
+        }
+    });
+}
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use ballad_config::ThemeConfig;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, closure_local};
+    use gtk::{prelude::*, subclass::prelude::*};
+
+    use crate::config::{CONFIG_SERVICE, ConfigService};
+
+    use super::run_reload_command;
+
+    #[derive(Default)]
+    pub struct ThemeOutputsService {
+        pub(super) config: RefCell<ThemeConfig>,
+    }
+
+    impl ThemeOutputsService {
+        pub(super) fn write_outputs(&self) {
+            let config = self.config.borrow().clone();
+            if config.outputs.is_empty() {
+                return;
+            }
+            let theme = config.theme();
+
+            for output in &config.outputs {
+                match output.write(&theme) {
+                    Ok(true) => {
+                        if let Some(command) = &output.reload {
+                            run_reload_command(command.clone());
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(err) => {
+                        println!(
+                            "Failed to write {} from the {} template: {err}",
+                            output.path, output.template
+                        );
+                        self.obj()
+                            .emit_by_name::<()>("output-failed", &[&output.path, &err.to_string()]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ThemeOutputsService {
+        const NAME: &'static str = "BalladServicesThemeOutputsService";
+        type Type = super::ThemeOutputsService;
+    }
+
+    impl ObjectImpl for ThemeOutputsService {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("output-failed")
+                        .param_types([String::static_type(), String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            CONFIG_SERVICE.with(|service| {
+                self.config.replace(service.shell_config().theme);
+
+                service.connect_closure(
+                    "shell-theme-config-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: ConfigService, config: &ThemeConfig| {
+                            this.config.replace(config.clone());
+                            this.write_outputs();
+                        }
+                    ),
+                );
+            });
+
+            // The theme may have changed while the shell wasn't running.
+            self.write_outputs();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Renders the selected theme into the configured [`ballad_config::theme::templates::ThemeOutput`]s whenever the
+    /// theme changes, so GTK, Qt, terminals and niri use its colors too.
+    pub struct ThemeOutputsService(ObjectSubclass<imp::ThemeOutputsService>);
+}
+impl ThemeOutputsService {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn config(&self) -> ThemeConfig {
+        self.imp().config.borrow().clone()
+    }
+
+    /// Writes every output again, like after a template was edited.
+    pub fn write_outputs(&self) {
+        self.imp().write_outputs();
+    }
+}
+impl Default for ThemeOutputsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static THEME_OUTPUTS_SERVICE: LazyCell<ThemeOutputsService> =
+        LazyCell::new(ThemeOutputsService::new);
+}
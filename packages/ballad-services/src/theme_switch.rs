@@ -0,0 +1,256 @@
+use std::cell::LazyCell;
+
+use ballad_config::{
+    ShellConfig, ThemeConfig,
+    theme::{ThemeVariant, schedule::ThemeSwitchConfig},
+};
+use gtk::glib::{self, Object};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use zbus::{proxy, zvariant::Value};
+
+use crate::config::CONFIG_SERVICE;
+
+/// How often the clock is checked against the schedule. Checking instead of waiting for the next switch keeps working
+/// after suspend and when the clock or time zone changes.
+const CHECK_INTERVAL_SECONDS: u32 = 60;
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+#[proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait PortalSettings {
+    /// ReadOne method
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<zbus::zvariant::OwnedValue>;
+
+    /// SettingChanged signal
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// The variant a `color-scheme` value asks for. 1 prefers dark and 2 prefers light, while 0 has no preference.
+fn color_scheme_variant(value: &Value<'_>) -> Option<ThemeVariant> {
+    match value {
+        Value::U32(1) => Some(ThemeVariant::Dark),
+        Value::U32(2) => Some(ThemeVariant::Light),
+        // Older portals wrap the value in another variant.
+        Value::Value(value) => color_scheme_variant(value),
+        _ => None,
+    }
+}
+
+/// Selects the theme of `variant`, unless the selected theme already is of that variant. That keeps a theme picked by
+/// hand, and doesn't fight the shell setting the color scheme to match the theme.
+fn switch_to(config: &ThemeSwitchConfig, variant: ThemeVariant) {
+    CONFIG_SERVICE.with(|service| {
+        let shell_config = service.shell_config();
+        let selected = &shell_config.theme.selected_theme;
+        if selected.is_light() == Some(variant.is_light()) {
+            return;
+        }
+        service.set_shell_config(ShellConfig {
+            theme: ThemeConfig {
+                selected_theme: config.theme(variant).clone(),
+                ..shell_config.theme
+            },
+            ..shell_config
+        });
+    });
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use ballad_config::{
+        ThemeConfig,
+        theme::{
+            ThemeVariant,
+            schedule::{ThemeSwitchConfig, ThemeSwitchMode},
+        },
+    };
+    use gtk::glib::{self, clone, closure_local};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::stream::StreamExt;
+
+    use crate::DBUS_SESSION_CONNECTION;
+    use crate::config::{CONFIG_SERVICE, ConfigService};
+
+    use super::{
+        APPEARANCE_NAMESPACE, CHECK_INTERVAL_SECONDS, COLOR_SCHEME_KEY, PortalSettingsProxy,
+        color_scheme_variant, switch_to,
+    };
+
+    #[derive(Default)]
+    pub struct ThemeSwitchService {
+        pub(super) config: RefCell<ThemeSwitchConfig>,
+        /// The variant the schedule last asked for. The theme is only switched when this changes, so a theme picked by
+        /// hand lasts until the next switch.
+        scheduled: Cell<Option<ThemeVariant>>,
+        pub(super) portal: RefCell<Option<PortalSettingsProxy<'static>>>,
+    }
+
+    impl ThemeSwitchService {
+        pub(super) fn check_schedule(&self) {
+            let Ok(now) = glib::DateTime::now_local() else {
+                return;
+            };
+            let minute = now.hour() as f64 * 60.0 + now.minute() as f64 + now.seconds() / 60.0;
+            let utc_offset = (now.utc_offset().as_seconds() / 60) as i32;
+            let config = self.config.borrow().clone();
+            let Some(variant) =
+                config.scheduled_variant(now.day_of_year() as u32, utc_offset, minute)
+            else {
+                self.scheduled.set(None);
+                return;
+            };
+            if self.scheduled.replace(Some(variant)) != Some(variant) {
+                switch_to(&config, variant);
+            }
+        }
+
+        /// Switches to the variant the portal asks for, if the theme follows it.
+        pub(super) fn follow_portal(&self, variant: Option<ThemeVariant>) {
+            let config = self.config.borrow().clone();
+            if let (ThemeSwitchMode::Portal, Some(variant)) = (config.mode, variant) {
+                switch_to(&config, variant);
+            }
+        }
+
+        /// Reads the color scheme from the portal, like when the theme starts following it.
+        pub(super) fn read_portal(&self) {
+            let Some(portal) = self.portal.borrow().clone() else {
+                return;
+            };
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    match portal
+                        .read_one(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY)
+                        .await
+                    {
+                        Ok(value) => this.follow_portal(color_scheme_variant(&value)),
+                        Err(err) => {
+                            println!("Failed to read the color scheme from the portal: {err}")
+                        }
+                    }
+                }
+            ));
+        }
+
+        fn watch_portal(&self) {
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    let portal = match PortalSettingsProxy::new(&DBUS_SESSION_CONNECTION).await {
+                        Ok(portal) => portal,
+                        Err(err) => {
+                            println!("Failed to connect to the settings portal: {err}");
+                            return;
+                        }
+                    };
+                    let Ok(mut changes) = portal.receive_setting_changed().await else {
+                        println!("Failed to watch the settings portal for color scheme changes.");
+                        return;
+                    };
+                    this.portal.replace(Some(portal));
+                    this.read_portal();
+
+                    while let Some(change) = changes.next().await {
+                        let Ok(args) = change.args() else {
+                            continue;
+                        };
+                        if args.namespace == APPEARANCE_NAMESPACE && args.key == COLOR_SCHEME_KEY {
+                            this.follow_portal(color_scheme_variant(&args.value));
+                        }
+                    }
+                }
+            ));
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ThemeSwitchService {
+        const NAME: &'static str = "BalladServicesThemeSwitchService";
+        type Type = super::ThemeSwitchService;
+    }
+
+    impl ObjectImpl for ThemeSwitchService {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            CONFIG_SERVICE.with(|service| {
+                self.config
+                    .replace(service.shell_config().theme.auto_switch);
+
+                service.connect_closure(
+                    "shell-theme-config-changed",
+                    false,
+                    closure_local!(
+                        #[weak(rename_to = this)]
+                        self,
+                        move |_: ConfigService, config: &ThemeConfig| {
+                            if *this.config.borrow() == config.auto_switch {
+                                return;
+                            }
+                            this.config.replace(config.auto_switch.clone());
+                            // Switch right away to what the new settings ask for.
+                            this.scheduled.set(None);
+                            this.check_schedule();
+                            this.read_portal();
+                        }
+                    ),
+                );
+            });
+
+            glib::timeout_add_seconds_local(
+                CHECK_INTERVAL_SECONDS,
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        this.check_schedule();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+            self.check_schedule();
+            self.watch_portal();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Switches between the light and dark theme of [`ballad_config::theme::schedule::ThemeSwitchConfig`] on a
+    /// schedule, at sunrise and sunset, or when the `org.freedesktop.appearance` color scheme changes.
+    pub struct ThemeSwitchService(ObjectSubclass<imp::ThemeSwitchService>);
+}
+impl ThemeSwitchService {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn config(&self) -> ThemeSwitchConfig {
+        self.imp().config.borrow().clone()
+    }
+
+    /// Checks the clock against the schedule now instead of at the next check, like after resuming from suspend.
+    pub fn check_schedule(&self) {
+        self.imp().check_schedule();
+    }
+}
+impl Default for ThemeSwitchService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    pub static THEME_SWITCH_SERVICE: LazyCell<ThemeSwitchService> =
+        LazyCell::new(ThemeSwitchService::new);
+}
@@ -0,0 +1,868 @@
+use std::{cell::LazyCell, collections::HashMap};
+
+use gtk::{
+    glib::{self, Object},
+    subclass::prelude::ObjectSubclassIsExt,
+};
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::DBUS_SESSION_CONNECTION;
+
+pub use item_imp::TrayItemStatus;
+
+pub const WATCHER_BUS_NAME: &str = "org.kde.StatusNotifierWatcher";
+pub const WATCHER_OBJECT_PATH: &str = "/StatusNotifierWatcher";
+/// The object path items are expected at when they register with only a bus name.
+pub const ITEM_OBJECT_PATH: &str = "/StatusNotifierItem";
+
+mod bus {
+    //! # D-Bus interface proxies for: `org.kde.StatusNotifierWatcher`, `org.kde.StatusNotifierItem` and `com.canonical.dbusmenu`
+    #![allow(clippy::type_complexity)]
+
+    use std::collections::HashMap;
+
+    use zbus::{proxy, zvariant::OwnedValue};
+
+    #[proxy(
+        interface = "org.kde.StatusNotifierWatcher",
+        default_service = "org.kde.StatusNotifierWatcher",
+        default_path = "/StatusNotifierWatcher"
+    )]
+    pub trait StatusNotifierWatcher {
+        /// RegisterStatusNotifierHost method
+        fn register_status_notifier_host(&self, service: &str) -> zbus::Result<()>;
+
+        /// RegisterStatusNotifierItem method
+        fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+
+        /// StatusNotifierItemRegistered signal
+        #[zbus(signal)]
+        fn status_notifier_item_registered(&self, service: &str) -> zbus::Result<()>;
+
+        /// StatusNotifierItemUnregistered signal
+        #[zbus(signal)]
+        fn status_notifier_item_unregistered(&self, service: &str) -> zbus::Result<()>;
+
+        /// RegisteredStatusNotifierItems property
+        #[zbus(property)]
+        fn registered_status_notifier_items(&self) -> zbus::Result<Vec<String>>;
+
+        /// IsStatusNotifierHostRegistered property
+        #[zbus(property)]
+        fn is_status_notifier_host_registered(&self) -> zbus::Result<bool>;
+    }
+
+    #[proxy(interface = "org.kde.StatusNotifierItem")]
+    pub trait StatusNotifierItem {
+        /// Activate method
+        fn activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+        /// ContextMenu method
+        fn context_menu(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+        /// Scroll method
+        fn scroll(&self, delta: i32, orientation: &str) -> zbus::Result<()>;
+
+        /// SecondaryActivate method
+        fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+        /// AttentionIconName property
+        #[zbus(property)]
+        fn attention_icon_name(&self) -> zbus::Result<String>;
+
+        /// AttentionIconPixmap property
+        #[zbus(property)]
+        fn attention_icon_pixmap(&self) -> zbus::Result<Vec<(i32, i32, Vec<u8>)>>;
+
+        /// Category property
+        #[zbus(property)]
+        fn category(&self) -> zbus::Result<String>;
+
+        /// IconName property
+        #[zbus(property)]
+        fn icon_name(&self) -> zbus::Result<String>;
+
+        /// IconPixmap property
+        #[zbus(property)]
+        fn icon_pixmap(&self) -> zbus::Result<Vec<(i32, i32, Vec<u8>)>>;
+
+        /// IconThemePath property
+        #[zbus(property)]
+        fn icon_theme_path(&self) -> zbus::Result<String>;
+
+        /// Id property
+        #[zbus(property)]
+        fn id(&self) -> zbus::Result<String>;
+
+        /// ItemIsMenu property
+        #[zbus(property)]
+        fn item_is_menu(&self) -> zbus::Result<bool>;
+
+        /// Menu property
+        #[zbus(property)]
+        fn menu(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+        /// Status property
+        #[zbus(property)]
+        fn status(&self) -> zbus::Result<String>;
+
+        /// Title property
+        #[zbus(property)]
+        fn title(&self) -> zbus::Result<String>;
+
+        /// ToolTip property
+        #[zbus(property)]
+        fn tool_tip(&self) -> zbus::Result<(String, Vec<(i32, i32, Vec<u8>)>, String, String)>;
+    }
+
+    #[proxy(interface = "com.canonical.dbusmenu")]
+    pub trait DBusMenu {
+        /// AboutToShow method
+        fn about_to_show(&self, id: i32) -> zbus::Result<bool>;
+
+        /// Event method
+        fn event(
+            &self,
+            id: i32,
+            event_id: &str,
+            data: &zbus::zvariant::Value<'_>,
+            timestamp: u32,
+        ) -> zbus::Result<()>;
+
+        /// GetLayout method
+        fn get_layout(
+            &self,
+            parent_id: i32,
+            recursion_depth: i32,
+            property_names: &[&str],
+        ) -> zbus::Result<(u32, (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>))>;
+
+        /// LayoutUpdated signal
+        #[zbus(signal)]
+        fn layout_updated(&self, revision: u32, parent: i32) -> zbus::Result<()>;
+    }
+}
+
+mod watcher {
+    //! Our own `org.kde.StatusNotifierWatcher`, used when no other watcher is running.
+
+    use smol::stream::StreamExt;
+    use zbus::{interface, message::Header, object_server::SignalEmitter};
+
+    use super::{ITEM_OBJECT_PATH, WATCHER_BUS_NAME, WATCHER_OBJECT_PATH};
+
+    #[derive(Debug, Default)]
+    pub struct StatusNotifierWatcher {
+        items: Vec<String>,
+        hosts: Vec<String>,
+    }
+
+    #[interface(name = "org.kde.StatusNotifierWatcher")]
+    impl StatusNotifierWatcher {
+        async fn register_status_notifier_item(
+            &mut self,
+            service: &str,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        ) {
+            // Some items register with their object path and expect us to use the sender's bus name.
+            let item = if service.starts_with('/') {
+                let Some(sender) = header.sender() else {
+                    return;
+                };
+                format!("{sender}{service}")
+            } else {
+                format!("{service}{ITEM_OBJECT_PATH}")
+            };
+
+            if self.items.contains(&item) {
+                return;
+            }
+            self.items.push(item.clone());
+            self.registered_status_notifier_items_changed(&emitter)
+                .await
+                .ok();
+            Self::status_notifier_item_registered(&emitter, &item)
+                .await
+                .ok();
+        }
+
+        async fn register_status_notifier_host(
+            &mut self,
+            service: &str,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        ) {
+            if self.hosts.iter().any(|host| host == service) {
+                return;
+            }
+            self.hosts.push(service.to_string());
+            self.is_status_notifier_host_registered_changed(&emitter)
+                .await
+                .ok();
+            Self::status_notifier_host_registered(&emitter).await.ok();
+        }
+
+        #[zbus(signal)]
+        async fn status_notifier_item_registered(
+            emitter: &SignalEmitter<'_>,
+            service: &str,
+        ) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn status_notifier_item_unregistered(
+            emitter: &SignalEmitter<'_>,
+            service: &str,
+        ) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn status_notifier_host_registered(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn status_notifier_host_unregistered(emitter: &SignalEmitter<'_>)
+        -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn registered_status_notifier_items(&self) -> Vec<String> {
+            self.items.clone()
+        }
+
+        #[zbus(property)]
+        fn is_status_notifier_host_registered(&self) -> bool {
+            !self.hosts.is_empty()
+        }
+
+        #[zbus(property)]
+        fn protocol_version(&self) -> i32 {
+            0
+        }
+    }
+
+    /// Forgets about items and hosts once the client that owned them disconnects.
+    async fn remove_vanished(connection: zbus::Connection) -> zbus::Result<()> {
+        let dbus_proxy = zbus::fdo::DBusProxy::new(&connection).await?;
+        let mut owner_changed_stream = dbus_proxy.receive_name_owner_changed().await?;
+        let watcher = connection
+            .object_server()
+            .interface::<_, StatusNotifierWatcher>(WATCHER_OBJECT_PATH)
+            .await?;
+
+        while let Some(signal) = owner_changed_stream.next().await {
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if args.new_owner().is_some() {
+                continue;
+            }
+            let name = args.name().as_str();
+            let emitter = watcher.signal_emitter();
+            let mut watcher = watcher.get_mut().await;
+
+            let (vanished, items) = watcher.items.drain(..).partition::<Vec<_>, _>(|item| {
+                item.split_once('/').map(|(bus_name, _)| bus_name) == Some(name)
+            });
+            watcher.items = items;
+            for item in vanished.iter() {
+                StatusNotifierWatcher::status_notifier_item_unregistered(emitter, item)
+                    .await
+                    .ok();
+            }
+            if !vanished.is_empty() {
+                watcher
+                    .registered_status_notifier_items_changed(emitter)
+                    .await
+                    .ok();
+            }
+
+            let host_count = watcher.hosts.len();
+            watcher.hosts.retain(|host| host != name);
+            if watcher.hosts.len() != host_count {
+                StatusNotifierWatcher::status_notifier_host_unregistered(emitter)
+                    .await
+                    .ok();
+                watcher
+                    .is_status_notifier_host_registered_changed(emitter)
+                    .await
+                    .ok();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serves the watcher on `connection`. Fails with [`zbus::Error::NameTaken`] if another watcher is already running.
+    pub async fn serve(connection: &zbus::Connection) -> zbus::Result<()> {
+        connection
+            .object_server()
+            .at(WATCHER_OBJECT_PATH, StatusNotifierWatcher::default())
+            .await?;
+        if let Err(err) = connection.request_name(WATCHER_BUS_NAME).await {
+            connection
+                .object_server()
+                .remove::<StatusNotifierWatcher, _>(WATCHER_OBJECT_PATH)
+                .await?;
+            return Err(err);
+        }
+
+        connection
+            .executor()
+            .spawn(
+                remove_vanished(connection.clone()),
+                "ballad status notifier watcher",
+            )
+            .detach();
+
+        Ok(())
+    }
+}
+
+/// Splits an item identifier as used by the watcher into its bus name and object path.
+fn split_item_id(id: &str) -> (&str, &str) {
+    match id.find('/') {
+        Some(index) => id.split_at(index),
+        None => (id, ITEM_OBJECT_PATH),
+    }
+}
+
+/// An icon sent as raw image data by a tray item.
+#[derive(Debug, Clone, PartialEq, Eq, glib::Boxed)]
+#[boxed_type(name = "BalladServicesTrayIconPixmap", nullable)]
+pub struct TrayIconPixmap {
+    pub width: i32,
+    pub height: i32,
+    /// ARGB32 pixels in network byte order.
+    pub data: Vec<u8>,
+}
+impl TrayIconPixmap {
+    /// Picks the smallest pixmap at least `size` pixels wide, or the largest one if none are big enough.
+    fn best(pixmaps: Vec<(i32, i32, Vec<u8>)>, size: i32) -> Option<Self> {
+        let pixmaps = pixmaps
+            .into_iter()
+            .filter(|(width, height, data)| {
+                *width > 0 && *height > 0 && data.len() == (*width * *height * 4) as usize
+            })
+            .collect::<Vec<_>>();
+
+        let best = pixmaps
+            .iter()
+            .filter(|(width, _, _)| *width >= size)
+            .min_by_key(|(width, _, _)| *width)
+            .or_else(|| pixmaps.iter().max_by_key(|(width, _, _)| *width))?;
+
+        Some(Self {
+            width: best.0,
+            height: best.1,
+            data: best.2.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayMenuToggle {
+    Checkmark(bool),
+    Radio(bool),
+}
+
+/// An entry in a tray item's menu, as exported over `com.canonical.dbusmenu`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayMenuItem {
+    pub id: i32,
+    pub label: String,
+    pub enabled: bool,
+    pub visible: bool,
+    pub separator: bool,
+    pub icon_name: Option<String>,
+    pub toggle: Option<TrayMenuToggle>,
+    pub children: Vec<TrayMenuItem>,
+}
+impl TrayMenuItem {
+    fn from_layout(
+        id: i32,
+        mut properties: HashMap<String, OwnedValue>,
+        children: Vec<OwnedValue>,
+    ) -> Self {
+        let mut take_string = |key: &str| {
+            properties
+                .remove(key)
+                .and_then(|value| String::try_from(value).ok())
+        };
+        let label = take_string("label").unwrap_or_default();
+        let separator = take_string("type").is_some_and(|kind| kind == "separator");
+        let icon_name = take_string("icon-name").filter(|name| !name.is_empty());
+        let toggle_type = take_string("toggle-type");
+
+        let mut take_bool = |key: &str| {
+            properties
+                .remove(key)
+                .and_then(|value| bool::try_from(value).ok())
+        };
+        let enabled = take_bool("enabled").unwrap_or(true);
+        let visible = take_bool("visible").unwrap_or(true);
+
+        let toggled = properties
+            .remove("toggle-state")
+            .and_then(|value| i32::try_from(value).ok())
+            .is_some_and(|state| state == 1);
+        let toggle = match toggle_type.as_deref() {
+            Some("checkmark") => Some(TrayMenuToggle::Checkmark(toggled)),
+            Some("radio") => Some(TrayMenuToggle::Radio(toggled)),
+            _ => None,
+        };
+
+        Self {
+            id,
+            label,
+            enabled,
+            visible,
+            separator,
+            icon_name,
+            toggle,
+            children: children.into_iter().filter_map(Self::from_value).collect(),
+        }
+    }
+
+    fn from_value(value: OwnedValue) -> Option<Self> {
+        // Children are sent wrapped in variants.
+        let value = match &*value {
+            Value::Value(inner) => inner.try_to_owned().ok()?,
+            _ => value,
+        };
+        let (id, properties, children) =
+            <(i32, HashMap<String, OwnedValue>, Vec<OwnedValue>)>::try_from(value).ok()?;
+        Some(Self::from_layout(id, properties, children))
+    }
+}
+
+mod item_imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use futures::join;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties, clone};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::lock::RwLock;
+    use smol::stream::StreamExt;
+
+    use super::TrayIconPixmap;
+    use super::bus::{DBusMenuProxy, StatusNotifierItemProxy};
+
+    /// The size pixmaps are picked for.
+    const ICON_SIZE: i32 = 24;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+    #[repr(u8)]
+    #[enum_type(name = "BalladServicesTrayItemStatus")]
+    pub enum TrayItemStatus {
+        Passive,
+        #[default]
+        Active,
+        NeedsAttention,
+    }
+    impl From<&str> for TrayItemStatus {
+        fn from(value: &str) -> Self {
+            match value {
+                "Passive" => Self::Passive,
+                "NeedsAttention" => Self::NeedsAttention,
+                _ => Self::Active,
+            }
+        }
+    }
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::TrayItem)]
+    pub struct TrayItem {
+        /// The identifier the watcher knows this item by: its bus name followed by its object path.
+        #[property(get)]
+        pub(super) id: RefCell<String>,
+        #[property(get)]
+        app_id: RefCell<String>,
+        #[property(get)]
+        category: RefCell<String>,
+        #[property(get)]
+        title: RefCell<String>,
+        #[property(get, builder(TrayItemStatus::Active))]
+        status: Cell<TrayItemStatus>,
+
+        #[property(get)]
+        icon_name: RefCell<Option<String>>,
+        #[property(get)]
+        icon_pixmap: RefCell<Option<TrayIconPixmap>>,
+        #[property(get)]
+        attention_icon_name: RefCell<Option<String>>,
+        #[property(get)]
+        attention_icon_pixmap: RefCell<Option<TrayIconPixmap>>,
+        /// An extra directory to look up `icon-name` in.
+        #[property(get)]
+        icon_theme_path: RefCell<Option<String>>,
+
+        #[property(get)]
+        tooltip_title: RefCell<String>,
+        #[property(get)]
+        tooltip_description: RefCell<String>,
+
+        /// Whether the item only supports showing its menu, not being activated.
+        #[property(get)]
+        item_is_menu: Cell<bool>,
+
+        pub(super) proxy: RwLock<Option<StatusNotifierItemProxy<'static>>>,
+        pub(super) menu_proxy: RwLock<Option<DBusMenuProxy<'static>>>,
+    }
+    impl TrayItem {
+        pub async fn update(&self) {
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+
+            let (app_id, category, title, status, tooltip) = join!(
+                proxy.id(),
+                proxy.category(),
+                proxy.title(),
+                proxy.status(),
+                proxy.tool_tip(),
+            );
+            let (
+                icon_name,
+                icon_pixmap,
+                attention_icon_name,
+                attention_icon_pixmap,
+                icon_theme_path,
+                item_is_menu,
+            ) = join!(
+                proxy.icon_name(),
+                proxy.icon_pixmap(),
+                proxy.attention_icon_name(),
+                proxy.attention_icon_pixmap(),
+                proxy.icon_theme_path(),
+                proxy.item_is_menu(),
+            );
+
+            fn none_if_empty(s: zbus::Result<String>) -> Option<String> {
+                s.ok().filter(|s| !s.is_empty())
+            }
+
+            self.app_id.replace(app_id.unwrap_or_default());
+            self.obj().notify_app_id();
+            self.category.replace(category.unwrap_or_default());
+            self.obj().notify_category();
+            self.title.replace(title.unwrap_or_default());
+            self.obj().notify_title();
+            self.status
+                .set(TrayItemStatus::from(status.unwrap_or_default().as_str()));
+            self.obj().notify_status();
+
+            self.icon_name.replace(none_if_empty(icon_name));
+            self.obj().notify_icon_name();
+            self.icon_pixmap.replace(TrayIconPixmap::best(
+                icon_pixmap.unwrap_or_default(),
+                ICON_SIZE,
+            ));
+            self.obj().notify_icon_pixmap();
+            self.attention_icon_name
+                .replace(none_if_empty(attention_icon_name));
+            self.obj().notify_attention_icon_name();
+            self.attention_icon_pixmap.replace(TrayIconPixmap::best(
+                attention_icon_pixmap.unwrap_or_default(),
+                ICON_SIZE,
+            ));
+            self.obj().notify_attention_icon_pixmap();
+            self.icon_theme_path.replace(none_if_empty(icon_theme_path));
+            self.obj().notify_icon_theme_path();
+
+            let (_, _, tooltip_title, tooltip_description) = tooltip.unwrap_or_default();
+            self.tooltip_title.replace(tooltip_title);
+            self.obj().notify_tooltip_title();
+            self.tooltip_description.replace(tooltip_description);
+            self.obj().notify_tooltip_description();
+
+            self.item_is_menu.set(item_is_menu.unwrap_or_default());
+            self.obj().notify_item_is_menu();
+
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        pub(super) fn subscribe_to_updates(&self) {
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    let proxy = this.proxy.read().await.clone().unwrap();
+                    // Items announce changes with `NewIcon`, `NewTitle` and similar signals instead of PropertiesChanged.
+                    let Ok(mut stream) = proxy.inner().receive_all_signals().await else {
+                        return;
+                    };
+                    while stream.next().await.is_some() {
+                        this.update().await;
+                    }
+                }
+            ));
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TrayItem {
+        const NAME: &'static str = "BalladServicesTrayItem";
+        type Type = super::TrayItem;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TrayItem {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+}
+
+mod imp {
+    use std::cell::Cell;
+
+    use futures::FutureExt;
+    use gtk::gio::ListStore;
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::stream::StreamExt;
+
+    use super::TrayItem;
+    use super::bus::StatusNotifierWatcherProxy;
+
+    #[derive(Properties)]
+    #[properties(wrapper_type = super::TrayService)]
+    pub struct TrayService {
+        #[property(get)]
+        available: Cell<bool>,
+        #[property(get)]
+        items: ListStore,
+    }
+    impl Default for TrayService {
+        fn default() -> Self {
+            Self {
+                available: Default::default(),
+                items: ListStore::with_type(TrayItem::static_type()),
+            }
+        }
+    }
+
+    impl TrayService {
+        fn item_position(&self, id: &str) -> Option<u32> {
+            self.items
+                .iter::<TrayItem>()
+                .filter_map(|item| item.ok())
+                .position(|item| item.id() == id)
+                .map(|position| position as u32)
+        }
+
+        async fn add_item(&self, connection: &zbus::Connection, id: &str) {
+            if self.item_position(id).is_some() {
+                return;
+            }
+            match TrayItem::with_id(connection, id).await {
+                Ok(item) => self.items.append(&item),
+                Err(err) => println!("Failed to connect to tray item {id}: {err}"),
+            }
+        }
+
+        fn remove_item(&self, id: &str) {
+            if let Some(position) = self.item_position(id) {
+                self.items.remove(position);
+            }
+        }
+
+        pub(super) async fn watch(&self, connection: zbus::Connection) -> zbus::Result<()> {
+            // Another bar may already be running a watcher, in which case we only act as a host.
+            match super::watcher::serve(&connection).await {
+                Ok(()) | Err(zbus::Error::NameTaken) => {}
+                Err(err) => return Err(err),
+            }
+
+            let host_name = format!("org.kde.StatusNotifierHost-{}", std::process::id());
+            connection.request_name(host_name.as_str()).await?;
+
+            let watcher = StatusNotifierWatcherProxy::new(&connection).await?;
+            let mut registered_stream = watcher.receive_status_notifier_item_registered().await?;
+            let mut unregistered_stream =
+                watcher.receive_status_notifier_item_unregistered().await?;
+
+            watcher.register_status_notifier_host(&host_name).await?;
+            for id in watcher.registered_status_notifier_items().await? {
+                self.add_item(&connection, &id).await;
+            }
+            self.available.set(true);
+            self.obj().notify_available();
+
+            loop {
+                futures::select! {
+                    registered = registered_stream.next().fuse() => {
+                        let Some(signal) = registered else {
+                            break;
+                        };
+                        if let Ok(args) = signal.args() {
+                            self.add_item(&connection, args.service()).await;
+                        }
+                    },
+                    unregistered = unregistered_stream.next().fuse() => {
+                        let Some(signal) = unregistered else {
+                            break;
+                        };
+                        if let Ok(args) = signal.args() {
+                            self.remove_item(args.service());
+                        }
+                    },
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TrayService {
+        const NAME: &'static str = "BalladServicesTrayService";
+        type Type = super::TrayService;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TrayService {}
+}
+
+glib::wrapper! {
+    pub struct TrayService(ObjectSubclass<imp::TrayService>);
+}
+impl TrayService {
+    pub fn new() -> Self {
+        Self::with_connection(DBUS_SESSION_CONNECTION.clone())
+    }
+
+    /// Hosts the tray on `connection` instead of the default session bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                if let Err(err) = this.imp().watch(connection).await {
+                    println!(
+                        "Failed to host the system tray: {err}. Tray service will not function!"
+                    );
+                }
+            }
+        ));
+
+        this
+    }
+}
+impl Default for TrayService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+glib::wrapper! {
+    pub struct TrayItem(ObjectSubclass<item_imp::TrayItem>);
+}
+impl TrayItem {
+    pub async fn with_id(connection: &zbus::Connection, id: &str) -> zbus::Result<Self> {
+        let (bus_name, path) = split_item_id(id);
+        let proxy = bus::StatusNotifierItemProxy::builder(connection)
+            .destination(bus_name.to_string())?
+            .path(path.to_string())?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await?;
+
+        let menu_proxy = match proxy.menu().await {
+            Ok(menu_path) => Some(
+                bus::DBusMenuProxy::builder(connection)
+                    .destination(bus_name.to_string())?
+                    .path(menu_path)?
+                    .build()
+                    .await?,
+            ),
+            Err(_) => None,
+        };
+
+        let item: Self = Object::builder().build();
+        item.imp().id.replace(id.to_string());
+        item.imp().proxy.write().await.replace(proxy);
+        *item.imp().menu_proxy.write().await = menu_proxy;
+
+        item.imp().update().await;
+        item.imp().subscribe_to_updates();
+
+        Ok(item)
+    }
+
+    /// Primary action, usually opening the application's window.
+    pub async fn activate(&self, x: i32, y: i32) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .activate(x, y)
+            .await
+    }
+    pub async fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .secondary_activate(x, y)
+            .await
+    }
+    /// Asks the item to show its own context menu. Only used for items that don't export a menu.
+    pub async fn context_menu(&self, x: i32, y: i32) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .context_menu(x, y)
+            .await
+    }
+    pub async fn scroll(&self, delta: i32, horizontal: bool) -> zbus::Result<()> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .scroll(delta, if horizontal { "horizontal" } else { "vertical" })
+            .await
+    }
+
+    pub async fn has_menu(&self) -> bool {
+        self.imp().menu_proxy.read().await.is_some()
+    }
+
+    /// Fetches the item's current menu. Returns the root item, whose children are the top level entries.
+    pub async fn menu(&self) -> Option<TrayMenuItem> {
+        let menu_proxy = self.imp().menu_proxy.read().await;
+        let menu_proxy = menu_proxy.as_ref()?;
+
+        // Gives the application a chance to update the menu before it is shown.
+        menu_proxy.about_to_show(0).await.ok();
+        let (_, (id, properties, children)) = menu_proxy.get_layout(0, -1, &[]).await.ok()?;
+
+        Some(TrayMenuItem::from_layout(id, properties, children))
+    }
+
+    pub async fn activate_menu_item(&self, id: i32) -> zbus::Result<()> {
+        let menu_proxy = self.imp().menu_proxy.read().await;
+        let Some(menu_proxy) = menu_proxy.as_ref() else {
+            return Ok(());
+        };
+
+        let timestamp = (glib::monotonic_time() / 1000) as u32;
+        menu_proxy
+            .event(id, "clicked", &Value::I32(0), timestamp)
+            .await
+    }
+}
+
+thread_local! {
+    pub static TRAY_SERVICE: LazyCell<TrayService> = LazyCell::new(TrayService::new);
+}
@@ -1,12 +1,375 @@
 use std::cell::LazyCell;
+use std::time::Duration;
 
 use gtk::glib::{self, Object};
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+/// The `Type` of a UPower device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesUPowerDeviceKind")]
+pub enum UPowerDeviceKind {
+    #[default]
+    Unknown,
+    LinePower,
+    Battery,
+    Ups,
+    Monitor,
+    Mouse,
+    Keyboard,
+    Pda,
+    Phone,
+    MediaPlayer,
+    Tablet,
+    Computer,
+    GamingInput,
+    Pen,
+    Touchpad,
+    Modem,
+    Network,
+    Headset,
+    Speakers,
+    Headphones,
+    Video,
+    OtherAudio,
+    RemoteControl,
+    Printer,
+    Scanner,
+    Camera,
+    Wearable,
+    Toy,
+    BluetoothGeneric,
+}
+impl From<u32> for UPowerDeviceKind {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::LinePower,
+            2 => Self::Battery,
+            3 => Self::Ups,
+            4 => Self::Monitor,
+            5 => Self::Mouse,
+            6 => Self::Keyboard,
+            7 => Self::Pda,
+            8 => Self::Phone,
+            9 => Self::MediaPlayer,
+            10 => Self::Tablet,
+            11 => Self::Computer,
+            12 => Self::GamingInput,
+            13 => Self::Pen,
+            14 => Self::Touchpad,
+            15 => Self::Modem,
+            16 => Self::Network,
+            17 => Self::Headset,
+            18 => Self::Speakers,
+            19 => Self::Headphones,
+            20 => Self::Video,
+            21 => Self::OtherAudio,
+            22 => Self::RemoteControl,
+            23 => Self::Printer,
+            24 => Self::Scanner,
+            25 => Self::Camera,
+            26 => Self::Wearable,
+            27 => Self::Toy,
+            28 => Self::BluetoothGeneric,
+            _ => Self::Unknown,
+        }
+    }
+}
+impl UPowerDeviceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "Device",
+            Self::LinePower => "Power supply",
+            Self::Battery => "Battery",
+            Self::Ups => "UPS",
+            Self::Monitor => "Monitor",
+            Self::Mouse => "Mouse",
+            Self::Keyboard => "Keyboard",
+            Self::Pda => "PDA",
+            Self::Phone => "Phone",
+            Self::MediaPlayer => "Media player",
+            Self::Tablet => "Tablet",
+            Self::Computer => "Computer",
+            Self::GamingInput => "Controller",
+            Self::Pen => "Pen",
+            Self::Touchpad => "Touchpad",
+            Self::Modem => "Modem",
+            Self::Network => "Network device",
+            Self::Headset => "Headset",
+            Self::Speakers => "Speakers",
+            Self::Headphones => "Headphones",
+            Self::Video => "Video device",
+            Self::OtherAudio => "Audio device",
+            Self::RemoteControl => "Remote control",
+            Self::Printer => "Printer",
+            Self::Scanner => "Scanner",
+            Self::Camera => "Camera",
+            Self::Wearable => "Wearable",
+            Self::Toy => "Toy",
+            Self::BluetoothGeneric => "Bluetooth device",
+        }
+    }
 
-mod imp {
-    use std::cell::Cell;
+    /// A freedesktop icon name for the kind of device.
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            Self::Battery | Self::Ups | Self::LinePower => "battery-symbolic",
+            Self::Mouse | Self::Touchpad => "input-mouse-symbolic",
+            Self::Keyboard => "input-keyboard-symbolic",
+            Self::GamingInput => "input-gaming-symbolic",
+            Self::Pen | Self::Tablet => "input-tablet-symbolic",
+            Self::Phone | Self::Pda => "phone-symbolic",
+            Self::Headset | Self::Headphones => "audio-headphones-symbolic",
+            Self::Speakers | Self::OtherAudio | Self::MediaPlayer => "audio-speakers-symbolic",
+            Self::Camera | Self::Video => "camera-web-symbolic",
+            Self::Printer => "printer-symbolic",
+            Self::Computer | Self::Monitor => "computer-symbolic",
+            _ => "bluetooth-symbolic",
+        }
+    }
+}
+
+/// The `State` of a UPower device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[repr(u8)]
+#[enum_type(name = "BalladServicesUPowerDeviceState")]
+pub enum UPowerDeviceState {
+    #[default]
+    Unknown,
+    Charging,
+    Discharging,
+    Empty,
+    FullyCharged,
+    PendingCharge,
+    PendingDischarge,
+}
+impl From<u32> for UPowerDeviceState {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Charging,
+            2 => Self::Discharging,
+            3 => Self::Empty,
+            4 => Self::FullyCharged,
+            5 => Self::PendingCharge,
+            6 => Self::PendingDischarge,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A series UPower keeps the history of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryHistoryKind {
+    /// The charge in percent.
+    Charge,
+    /// The rate of charge or discharge in W.
+    Rate,
+    /// The estimated time to full in seconds.
+    TimeToFull,
+    /// The estimated time to empty in seconds.
+    TimeToEmpty,
+}
+impl BatteryHistoryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Charge => "charge",
+            Self::Rate => "rate",
+            Self::TimeToFull => "time-full",
+            Self::TimeToEmpty => "time-empty",
+        }
+    }
+}
+
+/// One sample of a battery history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryHistoryPoint {
+    /// Seconds since the Unix epoch.
+    pub time: u32,
+    pub value: f64,
+    pub state: UPowerDeviceState,
+}
+
+/// Which statistics to ask UPower for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryStatisticsKind {
+    Charging,
+    Discharging,
+}
+impl BatteryStatisticsKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Charging => "charging",
+            Self::Discharging => "discharging",
+        }
+    }
+}
+
+/// How far off UPower's time estimates are at a charge level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryStatisticsPoint {
+    /// The charge in percent the point applies to.
+    pub percentage: f64,
+    /// How much the real time differs from the estimate, 1.0 meaning it was accurate.
+    pub value: f64,
+    /// How sure UPower is about `value`, from 0 to 100.
+    pub accuracy: f64,
+}
+
+mod device_imp {
+    use std::cell::{Cell, RefCell};
     use std::sync::OnceLock;
 
     use futures::join;
+    use gtk::glib::subclass::Signal;
+    use gtk::glib::{self, Properties};
+    use gtk::{prelude::*, subclass::prelude::*};
+    use smol::lock::RwLock;
+
+    use super::imp::UPowerDeviceProxy;
+    use super::{UPowerDeviceKind, UPowerDeviceState};
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::UPowerDevice)]
+    pub struct UPowerDevice {
+        #[property(get)]
+        pub(super) path: RefCell<String>,
+        #[property(get, builder(UPowerDeviceKind::Unknown))]
+        kind: Cell<UPowerDeviceKind>,
+        #[property(get)]
+        vendor: RefCell<String>,
+        #[property(get)]
+        model: RefCell<String>,
+        #[property(get)]
+        native_path: RefCell<String>,
+        #[property(get)]
+        percentage: Cell<f64>,
+        #[property(get, builder(UPowerDeviceState::Unknown))]
+        state: Cell<UPowerDeviceState>,
+        /// Whether the device powers the computer, as opposed to a peripheral.
+        #[property(get)]
+        power_supply: Cell<bool>,
+        #[property(get)]
+        is_present: Cell<bool>,
+        /// Seconds until the device is empty, or 0 if unknown.
+        #[property(get)]
+        time_to_empty: Cell<i64>,
+        /// Seconds until the device is fully charged, or 0 if unknown.
+        #[property(get)]
+        time_to_full: Cell<i64>,
+        /// Energy in Wh.
+        #[property(get)]
+        energy: Cell<f64>,
+        #[property(get)]
+        energy_full: Cell<f64>,
+        /// Rate of charge or discharge in W.
+        #[property(get)]
+        energy_rate: Cell<f64>,
+        #[property(get)]
+        has_history: Cell<bool>,
+        #[property(get)]
+        has_statistics: Cell<bool>,
+
+        pub(super) proxy: RwLock<Option<UPowerDeviceProxy<'static>>>,
+    }
+
+    impl UPowerDevice {
+        pub(super) async fn update(&self) {
+            let proxy = self.proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+
+            let (
+                kind,
+                vendor,
+                model,
+                native_path,
+                percentage,
+                state,
+                power_supply,
+                is_present,
+                time_to_empty,
+                time_to_full,
+                (energy, energy_full, energy_rate, has_history, has_statistics),
+            ) = join!(
+                proxy.type_(),
+                proxy.vendor(),
+                proxy.model(),
+                proxy.native_path(),
+                proxy.percentage(),
+                proxy.state(),
+                proxy.power_supply(),
+                proxy.is_present(),
+                proxy.time_to_empty(),
+                proxy.time_to_full(),
+                async {
+                    join!(
+                        proxy.energy(),
+                        proxy.energy_full(),
+                        proxy.energy_rate(),
+                        proxy.has_history(),
+                        proxy.has_statistics(),
+                    )
+                },
+            );
+
+            self.kind
+                .set(UPowerDeviceKind::from(kind.unwrap_or_default()));
+            self.obj().notify_kind();
+            self.vendor.replace(vendor.unwrap_or_default());
+            self.obj().notify_vendor();
+            self.model.replace(model.unwrap_or_default());
+            self.obj().notify_model();
+            self.native_path.replace(native_path.unwrap_or_default());
+            self.obj().notify_native_path();
+            self.percentage.set(percentage.unwrap_or_default());
+            self.obj().notify_percentage();
+            self.state
+                .set(UPowerDeviceState::from(state.unwrap_or_default()));
+            self.obj().notify_state();
+            self.power_supply.set(power_supply.unwrap_or_default());
+            self.obj().notify_power_supply();
+            self.is_present.set(is_present.unwrap_or_default());
+            self.obj().notify_is_present();
+            self.time_to_empty.set(time_to_empty.unwrap_or_default());
+            self.obj().notify_time_to_empty();
+            self.time_to_full.set(time_to_full.unwrap_or_default());
+            self.obj().notify_time_to_full();
+            self.energy.set(energy.unwrap_or_default());
+            self.obj().notify_energy();
+            self.energy_full.set(energy_full.unwrap_or_default());
+            self.obj().notify_energy_full();
+            self.energy_rate.set(energy_rate.unwrap_or_default());
+            self.obj().notify_energy_rate();
+            self.has_history.set(has_history.unwrap_or_default());
+            self.obj().notify_has_history();
+            self.has_statistics.set(has_statistics.unwrap_or_default());
+            self.obj().notify_has_statistics();
+
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for UPowerDevice {
+        const NAME: &'static str = "BalladServicesUPowerDevice";
+        type Type = super::UPowerDevice;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for UPowerDevice {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+}
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use futures::{FutureExt, join};
+    use gtk::gio::ListStore;
     use gtk::glib::clone;
     use gtk::glib::subclass::Signal;
 
@@ -15,15 +378,46 @@ mod imp {
     use smol::lock::RwLock;
     use smol::stream::StreamExt;
     use zbus::proxy;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+    use super::{UPowerDevice, UPowerDeviceKind};
 
-    use crate::DBUS_SYSTEM_CONNECTION;
+    #[proxy(
+        interface = "org.freedesktop.UPower",
+        default_service = "org.freedesktop.UPower",
+        default_path = "/org/freedesktop/UPower"
+    )]
+    trait UPower {
+        /// EnumerateDevices method
+        fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+        /// DeviceAdded signal
+        #[zbus(signal)]
+        fn device_added(&self, device: ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// DeviceRemoved signal
+        #[zbus(signal)]
+        fn device_removed(&self, device: ObjectPath<'_>) -> zbus::Result<()>;
+
+        /// LidIsClosed property
+        #[zbus(property)]
+        fn lid_is_closed(&self) -> zbus::Result<bool>;
+
+        /// LidIsPresent property
+        #[zbus(property)]
+        fn lid_is_present(&self) -> zbus::Result<bool>;
+
+        /// OnBattery property
+        #[zbus(property)]
+        fn on_battery(&self) -> zbus::Result<bool>;
+    }
 
     #[proxy(
         interface = "org.freedesktop.UPower.Device",
         default_service = "org.freedesktop.UPower",
         default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
     )]
-    trait UPowerDevice {
+    pub(super) trait UPowerDevice {
         /// GetHistory method
         fn get_history(
             &self,
@@ -159,7 +553,7 @@ mod imp {
         fn warning_level(&self) -> zbus::Result<u32>;
     }
 
-    #[derive(Properties, Default, Debug)]
+    #[derive(Properties, Debug)]
     #[properties(wrapper_type = super::UPowerService)]
     pub struct UPowerService {
         #[property(get, default_value = true)]
@@ -179,10 +573,48 @@ mod imp {
         #[property(get)]
         energy_rate: Cell<f64>,
 
+        /// Whether the computer is running on battery power.
+        #[property(get)]
+        on_battery: Cell<bool>,
+        #[property(get)]
+        lid_is_present: Cell<bool>,
+        #[property(get)]
+        lid_is_closed: Cell<bool>,
+        /// Every battery UPower knows about, including peripherals. Power supplies are left out.
+        #[property(get)]
+        devices: ListStore,
+
         proxy: RwLock<Option<UPowerDeviceProxy<'static>>>,
+        root_proxy: RwLock<Option<UPowerProxy<'static>>>,
+        pub(super) connection: RefCell<Option<zbus::Connection>>,
+    }
+    impl Default for UPowerService {
+        fn default() -> Self {
+            Self {
+                available: Default::default(),
+                percentage: Default::default(),
+                charging: Default::default(),
+                charged: Default::default(),
+                time_remaining: Default::default(),
+                energy: Default::default(),
+                energy_full: Default::default(),
+                energy_rate: Default::default(),
+                on_battery: Default::default(),
+                lid_is_present: Default::default(),
+                lid_is_closed: Default::default(),
+                devices: ListStore::with_type(UPowerDevice::static_type()),
+                proxy: Default::default(),
+                root_proxy: Default::default(),
+                connection: Default::default(),
+            }
+        }
     }
 
     impl UPowerService {
+        fn connection(&self) -> zbus::Connection {
+            self.connection.borrow().clone().unwrap()
+        }
+
         async fn update(&self) {
             let proxy = self.proxy.read().await;
             let proxy = proxy.as_ref().unwrap();
@@ -238,6 +670,95 @@ mod imp {
 
             self.obj().emit_by_name::<()>("battery-changed", &[]);
         }
+
+        async fn update_root(&self) {
+            let proxy = self.root_proxy.read().await;
+            let proxy = proxy.as_ref().unwrap();
+
+            let (on_battery, lid_is_present, lid_is_closed) = join!(
+                proxy.on_battery(),
+                proxy.lid_is_present(),
+                proxy.lid_is_closed(),
+            );
+            self.on_battery.set(on_battery.unwrap_or_default());
+            self.obj().notify_on_battery();
+            self.lid_is_present.set(lid_is_present.unwrap_or_default());
+            self.obj().notify_lid_is_present();
+            self.lid_is_closed.set(lid_is_closed.unwrap_or_default());
+            self.obj().notify_lid_is_closed();
+        }
+
+        fn device_position(&self, path: &str) -> Option<u32> {
+            self.devices
+                .iter::<UPowerDevice>()
+                .position(|device| device.is_ok_and(|device| device.path() == path))
+                .map(|position| position as u32)
+        }
+
+        async fn add_device(&self, path: OwnedObjectPath) {
+            if self.device_position(path.as_str()).is_some() {
+                return;
+            }
+            let Some(device) = UPowerDevice::with_path(self.connection(), path).await else {
+                return;
+            };
+            if device.kind() == UPowerDeviceKind::LinePower {
+                return;
+            }
+            self.devices.append(&device);
+        }
+
+        fn remove_device(&self, path: &str) {
+            if let Some(position) = self.device_position(path) {
+                self.devices.remove(position);
+            }
+        }
+
+        async fn watch_devices(&self) -> zbus::Result<()> {
+            let connection = self.connection();
+            let proxy = UPowerProxy::new(&connection).await?;
+            self.root_proxy.write().await.replace(proxy.clone());
+            self.update_root().await;
+
+            for path in proxy.enumerate_devices().await? {
+                self.add_device(path).await;
+            }
+
+            let mut added_stream = proxy.receive_device_added().await?;
+            let mut removed_stream = proxy.receive_device_removed().await?;
+            let properties_proxy = zbus::fdo::PropertiesProxy::new(
+                &connection,
+                "org.freedesktop.UPower",
+                proxy.inner().path(),
+            )
+            .await?;
+            let mut properties_stream = properties_proxy.receive_properties_changed().await?;
+
+            loop {
+                futures::select! {
+                    added = added_stream.next().fuse() => {
+                        let Some(added) = added else { break };
+                        if let Ok(args) = added.args() {
+                            self.add_device(args.device().to_owned().into()).await;
+                        }
+                    },
+                    removed = removed_stream.next().fuse() => {
+                        let Some(removed) = removed else { break };
+                        if let Ok(args) = removed.args() {
+                            self.remove_device(args.device().as_str());
+                        }
+                    },
+                    changed = properties_stream.next().fuse() => {
+                        if changed.is_none() {
+                            break;
+                        }
+                        self.update_root().await;
+                    },
+                }
+            }
+
+            Ok(())
+        }
     }
 
     #[glib::object_subclass]
@@ -246,16 +767,24 @@ mod imp {
         type Type = super::UPowerService;
     }
 
-    #[glib::derived_properties]
-    impl ObjectImpl for UPowerService {
-        fn constructed(&self) {
-            self.parent_constructed();
+    impl UPowerService {
+        pub(super) fn start(&self) {
+            gtk::glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    if let Err(err) = this.watch_devices().await {
+                        println!("Failed to watch UPower devices: {err}");
+                    }
+                }
+            ));
 
             gtk::glib::spawn_future_local(clone!(
                 #[weak(rename_to = this)]
                 self,
                 async move {
-                    let Ok(proxy) = UPowerDeviceProxy::new(&DBUS_SYSTEM_CONNECTION).await else {
+                    let connection = this.connection();
+                    let Ok(proxy) = UPowerDeviceProxy::new(&connection).await else {
                         println!(
                             "Failed to create UPowerDeviceProxy. Battery and brightness services will not function!"
                         );
@@ -266,7 +795,7 @@ mod imp {
                     this.update().await;
 
                     let properties_proxy = zbus::fdo::PropertiesProxy::new(
-                        &DBUS_SYSTEM_CONNECTION,
+                        &connection,
                         "org.freedesktop.UPower",
                         proxy.inner().path(),
                     )
@@ -281,7 +810,10 @@ mod imp {
                 }
             ));
         }
+    }
 
+    #[glib::derived_properties]
+    impl ObjectImpl for UPowerService {
         fn signals() -> &'static [Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
             SIGNALS.get_or_init(|| vec![Signal::builder("battery-changed").build()])
@@ -289,13 +821,188 @@ mod imp {
     }
 }
 
+glib::wrapper! {
+    pub struct UPowerDevice(ObjectSubclass<device_imp::UPowerDevice>);
+}
+impl UPowerDevice {
+    /// Creates a device and starts following its changes. Returns `None` if UPower doesn't know about `path`.
+    async fn with_path(
+        connection: zbus::Connection,
+        path: zbus::zvariant::OwnedObjectPath,
+    ) -> Option<Self> {
+        let proxy = imp::UPowerDeviceProxy::builder(&connection)
+            .path(path.clone())
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        proxy.type_().await.ok()?;
+
+        let this: Self = Object::builder().build();
+        this.imp().path.replace(path.to_string());
+        this.imp().proxy.write().await.replace(proxy.clone());
+        this.imp().update().await;
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            this,
+            async move {
+                let Ok(properties_proxy) = zbus::fdo::PropertiesProxy::new(
+                    &connection,
+                    "org.freedesktop.UPower",
+                    proxy.inner().path(),
+                )
+                .await
+                else {
+                    return;
+                };
+                let Ok(mut update_stream) = properties_proxy.receive_properties_changed().await
+                else {
+                    return;
+                };
+                while smol::stream::StreamExt::next(&mut update_stream)
+                    .await
+                    .is_some()
+                {
+                    this.imp().update().await;
+                }
+            }
+        ));
+
+        Some(this)
+    }
+
+    async fn proxy(&self) -> zbus::Result<imp::UPowerDeviceProxy<'static>> {
+        self.imp()
+            .proxy
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| zbus::Error::Failure("The device has no proxy".to_string()))
+    }
+
+    /// Samples of `kind` from the last `timespan`, oldest first. UPower returns at most `resolution` points.
+    pub async fn history(
+        &self,
+        kind: BatteryHistoryKind,
+        timespan: Duration,
+        resolution: u32,
+    ) -> zbus::Result<Vec<BatteryHistoryPoint>> {
+        let timespan = timespan.as_secs().try_into().unwrap_or(u32::MAX);
+        let mut points = self
+            .proxy()
+            .await?
+            .get_history(kind.as_str(), timespan, resolution)
+            .await?
+            .into_iter()
+            // UPower pads the history with empty samples when it doesn't have enough.
+            .filter(|(time, _, _)| *time != 0)
+            .map(|(time, value, state)| BatteryHistoryPoint {
+                time,
+                value,
+                state: UPowerDeviceState::from(state),
+            })
+            .collect::<Vec<_>>();
+        points.sort_by_key(|point| point.time);
+        Ok(points)
+    }
+
+    pub async fn statistics(
+        &self,
+        kind: BatteryStatisticsKind,
+    ) -> zbus::Result<Vec<BatteryStatisticsPoint>> {
+        Ok(self
+            .proxy()
+            .await?
+            .get_statistics(kind.as_str())
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(percentage, (value, accuracy))| BatteryStatisticsPoint {
+                percentage: percentage as f64,
+                value,
+                accuracy,
+            })
+            .collect())
+    }
+
+    /// Estimates the time until the device is empty or full from its average rate over `window`.
+    ///
+    /// UPower's own estimate follows the instantaneous rate, which jumps around with the load. Returns `None` when the
+    /// device is neither charging nor discharging or the rate is unknown.
+    pub async fn smoothed_time_remaining(&self, window: Duration) -> Option<Duration> {
+        let charging = match self.state() {
+            UPowerDeviceState::Charging | UPowerDeviceState::PendingCharge => true,
+            UPowerDeviceState::Discharging | UPowerDeviceState::PendingDischarge => false,
+            _ => return None,
+        };
+
+        let rates = self
+            .history(BatteryHistoryKind::Rate, window, 100)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|point| point.state == self.state() && point.value > 0.0)
+            .map(|point| point.value)
+            .chain([self.energy_rate()].into_iter().filter(|rate| *rate > 0.0))
+            .collect::<Vec<_>>();
+        if rates.is_empty() {
+            return None;
+        }
+        let rate = rates.iter().sum::<f64>() / rates.len() as f64;
+
+        let energy = if charging {
+            self.energy_full() - self.energy()
+        } else {
+            self.energy()
+        };
+        let hours = energy.max(0.0) / rate;
+        Duration::try_from_secs_f64(hours * 3600.0).ok()
+    }
+
+    /// A user facing name, the model if UPower knows it.
+    pub fn name(&self) -> String {
+        if !self.model().is_empty() {
+            self.model()
+        } else {
+            self.kind().name().to_string()
+        }
+    }
+}
+
 glib::wrapper! {
     pub struct UPowerService(ObjectSubclass<imp::UPowerService>);
 }
 
 impl UPowerService {
     pub fn new() -> Self {
-        Object::builder().build()
+        Self::with_connection(crate::DBUS_SYSTEM_CONNECTION.clone())
+    }
+
+    /// Talks to UPower on `connection` instead of the system bus.
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        let this: Self = Object::builder().build();
+        this.imp().connection.replace(Some(connection));
+        this.imp().start();
+        this
+    }
+
+    /// Batteries that power the computer.
+    pub fn batteries(&self) -> Vec<UPowerDevice> {
+        self.devices()
+            .iter::<UPowerDevice>()
+            .filter_map(|device| device.ok())
+            .filter(|device| device.power_supply() && device.kind() == UPowerDeviceKind::Battery)
+            .collect()
+    }
+
+    /// Batteries of peripherals like mice, keyboards and headsets.
+    pub fn peripherals(&self) -> Vec<UPowerDevice> {
+        self.devices()
+            .iter::<UPowerDevice>()
+            .filter_map(|device| device.ok())
+            .filter(|device| !device.power_supply() && device.is_present())
+            .collect()
     }
 }
 impl Default for UPowerService {
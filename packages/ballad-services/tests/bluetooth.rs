@@ -0,0 +1,401 @@
+//! Drives the Bluetooth service against a mock BlueZ on a private bus.
+//!
+//! The mock has one adapter with a paired mouse that reports its battery and a paired keyboard. Starting discovery
+//! makes a pair of headphones show up, and pairing with them asks the service's pairing agent to confirm a passkey.
+
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use ballad_services::bluetooth::{
+    BLUEZ_BUS_NAME, BLUEZ_OBJECT_PATH, BluetoothDevice, BluetoothRequestKind, BluetoothService,
+};
+use gtk::glib;
+use gtk::prelude::*;
+use zbus::{
+    fdo, interface,
+    message::Header,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath},
+};
+
+use common::{TestBus, run, wait_for};
+
+const ADAPTER_PATH: &str = "/org/bluez/hci0";
+const MOUSE_PATH: &str = "/org/bluez/hci0/dev_11_11_11_11_11_11";
+const KEYBOARD_PATH: &str = "/org/bluez/hci0/dev_22_22_22_22_22_22";
+const HEADPHONES_PATH: &str = "/org/bluez/hci0/dev_33_33_33_33_33_33";
+const PASSKEY: u32 = 123456;
+
+struct MockAgentManager;
+
+#[interface(name = "org.bluez.AgentManager1")]
+impl MockAgentManager {
+    async fn register_agent(
+        &self,
+        agent: OwnedObjectPath,
+        _capability: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(object_server)] server: &zbus::ObjectServer,
+    ) -> fdo::Result<()> {
+        let sender = header
+            .sender()
+            .map(|sender| sender.to_string())
+            .ok_or_else(|| fdo::Error::Failed("No sender".to_string()))?;
+        let adapter = server.interface::<_, MockAdapter>(ADAPTER_PATH).await?;
+        adapter.get_mut().await.agent = Some((sender, agent));
+        Ok(())
+    }
+
+    fn request_default_agent(&self, _agent: OwnedObjectPath) {}
+
+    fn unregister_agent(&self, _agent: OwnedObjectPath) {}
+}
+
+struct MockAdapter {
+    powered: bool,
+    discoverable: bool,
+    discovering: bool,
+    /// The bus name and path of the registered pairing agent.
+    agent: Option<(String, OwnedObjectPath)>,
+}
+
+#[interface(name = "org.bluez.Adapter1")]
+impl MockAdapter {
+    async fn start_discovery(
+        &mut self,
+        #[zbus(connection)] connection: &zbus::Connection,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.discovering = true;
+        self.discovering_changed(&emitter).await?;
+
+        // Discovered devices trickle in a little later.
+        let connection = connection.clone();
+        connection
+            .executor()
+            .spawn(
+                {
+                    let connection = connection.clone();
+                    async move {
+                        smol::Timer::after(Duration::from_secs(1)).await;
+                        connection
+                            .object_server()
+                            .at(
+                                HEADPHONES_PATH,
+                                MockDevice::new(
+                                    "33:33:33:33:33:33",
+                                    "Headphones",
+                                    "audio-headphones",
+                                ),
+                            )
+                            .await
+                            .ok();
+                    }
+                },
+                "mock-bluez-discovery",
+            )
+            .detach();
+        Ok(())
+    }
+
+    async fn stop_discovery(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.discovering = false;
+        self.discovering_changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn remove_device(
+        &self,
+        device: ObjectPath<'_>,
+        #[zbus(object_server)] server: &zbus::ObjectServer,
+    ) -> fdo::Result<()> {
+        server.remove::<MockBattery, _>(&device).await.ok();
+        server.remove::<MockDevice, _>(&device).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn address(&self) -> &str {
+        "00:00:00:00:00:00"
+    }
+    #[zbus(property)]
+    fn alias(&self) -> &str {
+        "ballad-mock"
+    }
+    #[zbus(property)]
+    fn powered(&self) -> bool {
+        self.powered
+    }
+    #[zbus(property)]
+    fn set_powered(&mut self, powered: bool) {
+        self.powered = powered;
+    }
+    #[zbus(property)]
+    fn discoverable(&self) -> bool {
+        self.discoverable
+    }
+    #[zbus(property)]
+    fn set_discoverable(&mut self, discoverable: bool) {
+        self.discoverable = discoverable;
+    }
+    #[zbus(property)]
+    fn discovering(&self) -> bool {
+        self.discovering
+    }
+}
+
+struct MockDevice {
+    address: String,
+    alias: String,
+    icon: String,
+    paired: bool,
+    trusted: bool,
+    connected: bool,
+}
+impl MockDevice {
+    fn new(address: &str, alias: &str, icon: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            alias: alias.to_string(),
+            icon: icon.to_string(),
+            paired: false,
+            trusted: false,
+            connected: false,
+        }
+    }
+
+    fn already_paired(self, connected: bool) -> Self {
+        Self {
+            paired: true,
+            trusted: true,
+            connected,
+            ..self
+        }
+    }
+}
+
+#[interface(name = "org.bluez.Device1")]
+impl MockDevice {
+    async fn connect(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.connected = true;
+        self.connected_changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn disconnect(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.connected = false;
+        self.connected_changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Asks the registered agent to confirm a passkey, like BlueZ does for devices with a display.
+    async fn pair(
+        &mut self,
+        #[zbus(connection)] connection: &zbus::Connection,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let adapter = connection
+            .object_server()
+            .interface::<_, MockAdapter>(ADAPTER_PATH)
+            .await?;
+        let Some((agent, agent_path)) = adapter.get().await.agent.clone() else {
+            return Err(fdo::Error::Failed("No agent registered".to_string()));
+        };
+
+        connection
+            .call_method(
+                Some(agent.as_str()),
+                agent_path.as_str(),
+                Some("org.bluez.Agent1"),
+                "RequestConfirmation",
+                &(emitter.path().to_owned(), PASSKEY),
+            )
+            .await
+            .map_err(|err| fdo::Error::AuthFailed(err.to_string()))?;
+
+        self.paired = true;
+        self.paired_changed(&emitter).await?;
+        Ok(())
+    }
+
+    fn cancel_pairing(&self) {}
+
+    #[zbus(property)]
+    fn adapter(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(ADAPTER_PATH).unwrap()
+    }
+    #[zbus(property)]
+    fn address(&self) -> &str {
+        &self.address
+    }
+    #[zbus(property)]
+    fn alias(&self) -> &str {
+        &self.alias
+    }
+    #[zbus(property)]
+    fn icon(&self) -> &str {
+        &self.icon
+    }
+    #[zbus(property)]
+    fn paired(&self) -> bool {
+        self.paired
+    }
+    #[zbus(property)]
+    fn trusted(&self) -> bool {
+        self.trusted
+    }
+    #[zbus(property)]
+    fn set_trusted(&mut self, trusted: bool) {
+        self.trusted = trusted;
+    }
+    #[zbus(property)]
+    fn connected(&self) -> bool {
+        self.connected
+    }
+}
+
+struct MockBattery {
+    percentage: u8,
+}
+
+#[interface(name = "org.bluez.Battery1")]
+impl MockBattery {
+    #[zbus(property)]
+    fn percentage(&self) -> u8 {
+        self.percentage
+    }
+}
+
+async fn serve_mock(bus: &TestBus) -> zbus::Result<zbus::Connection> {
+    bus.builder()
+        .serve_at("/", fdo::ObjectManager)?
+        .serve_at(BLUEZ_OBJECT_PATH, MockAgentManager)?
+        .serve_at(
+            ADAPTER_PATH,
+            MockAdapter {
+                powered: false,
+                discoverable: false,
+                discovering: false,
+                agent: None,
+            },
+        )?
+        .serve_at(
+            MOUSE_PATH,
+            MockDevice::new("11:11:11:11:11:11", "Mouse", "input-mouse").already_paired(true),
+        )?
+        .serve_at(MOUSE_PATH, MockBattery { percentage: 64 })?
+        .serve_at(
+            KEYBOARD_PATH,
+            MockDevice::new("22:22:22:22:22:22", "Keyboard", "input-keyboard")
+                .already_paired(false),
+        )?
+        .name(BLUEZ_BUS_NAME)?
+        .build()
+        .await
+}
+
+fn find_device(service: &BluetoothService, name: &str) -> Option<BluetoothDevice> {
+    service
+        .devices()
+        .iter::<BluetoothDevice>()
+        .flatten()
+        .find(|device| device.name() == name)
+}
+
+#[test]
+fn pair_trust_and_connect() {
+    let bus = TestBus::start();
+    run(async {
+        let _mock = serve_mock(&bus).await.unwrap();
+        let service = BluetoothService::with_connection(bus.connect().await);
+
+        // Stands in for the pairing prompt in the shell. The first request is rejected.
+        let requests = Rc::new(Cell::new(0));
+        service.connect_closure(
+            "agent-request",
+            false,
+            glib::closure_local!(
+                #[strong]
+                requests,
+                move |service: BluetoothService,
+                      id: u32,
+                      kind: BluetoothRequestKind,
+                      device: String,
+                      value: String| {
+                    assert_eq!(kind, BluetoothRequestKind::Confirmation);
+                    assert_eq!(device, "Headphones");
+                    assert_eq!(value, format!("{PASSKEY:06}"));
+                    requests.set(requests.get() + 1);
+                    if requests.get() == 1 {
+                        service.reject_request(id);
+                    } else {
+                        service.accept_request(id, None);
+                    }
+                }
+            ),
+        );
+
+        wait_for("the paired devices", || service.devices().n_items() == 2).await;
+        assert!(service.available());
+        assert!(!service.powered());
+        let mouse = find_device(&service, "Mouse").unwrap();
+        assert_eq!(mouse.address(), "11:11:11:11:11:11");
+        assert_eq!(mouse.icon().as_deref(), Some("input-mouse"));
+        assert!(mouse.paired() && mouse.trusted() && mouse.connected());
+        assert_eq!(mouse.battery_percentage(), 64);
+        let keyboard = find_device(&service, "Keyboard").unwrap();
+        assert!(keyboard.paired() && keyboard.trusted() && !keyboard.connected());
+        assert_eq!(keyboard.battery_percentage(), -1);
+
+        service.set_powered(true).await.unwrap();
+        wait_for("the adapter to power on", || service.powered()).await;
+
+        service.set_discovering(true).await.unwrap();
+        wait_for("discovery", || service.discovering()).await;
+        wait_for("the headphones to be discovered", || {
+            find_device(&service, "Headphones").is_some()
+        })
+        .await;
+        let headphones = find_device(&service, "Headphones").unwrap();
+        assert!(!headphones.paired() && !headphones.trusted() && !headphones.connected());
+
+        assert!(headphones.pair().await.is_err());
+        assert!(!headphones.paired());
+        headphones.pair().await.unwrap();
+        assert_eq!(requests.get(), 2);
+        wait_for("the headphones to be paired and trusted", || {
+            headphones.paired() && headphones.trusted()
+        })
+        .await;
+
+        headphones.connect().await.unwrap();
+        wait_for("the headphones to connect", || headphones.connected()).await;
+        mouse.disconnect().await.unwrap();
+        wait_for("the mouse to disconnect", || !mouse.connected()).await;
+
+        service.set_discovering(false).await.unwrap();
+        wait_for("discovery to stop", || !service.discovering()).await;
+
+        service.remove_device(&keyboard).await.unwrap();
+        wait_for("the keyboard to be forgotten", || {
+            find_device(&service, "Keyboard").is_none()
+        })
+        .await;
+        assert_eq!(service.devices().n_items(), 2);
+
+        service.set_powered(false).await.unwrap();
+        wait_for("the adapter to power off", || !service.powered()).await;
+    });
+}
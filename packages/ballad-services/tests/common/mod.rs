@@ -0,0 +1,69 @@
+//! A private D-Bus daemon for each test, so mocks can own the names of the real services.
+
+use std::future::Future;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use gtk::glib;
+
+/// How long [`wait_for`] waits for the services to catch up with the mocks.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TestBus {
+    daemon: Child,
+    address: String,
+}
+impl TestBus {
+    /// Starts `dbus-daemon`, which has to be installed.
+    pub fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start dbus-daemon");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .expect("Failed to read the address of dbus-daemon");
+        Self {
+            daemon,
+            address: address.trim().to_string(),
+        }
+    }
+
+    pub async fn connect(&self) -> zbus::Connection {
+        self.builder().build().await.unwrap()
+    }
+
+    pub fn builder(&self) -> zbus::connection::Builder<'static> {
+        zbus::connection::Builder::address(self.address.as_str()).unwrap()
+    }
+}
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        _ = self.daemon.kill();
+        _ = self.daemon.wait();
+    }
+}
+
+/// Runs `future` on a main context of its own, like the shell's main loop, so the tests can run in parallel.
+pub fn run<F: Future>(future: F) -> F::Output {
+    let context = glib::MainContext::new();
+    context
+        .with_thread_default(|| context.block_on(future))
+        .unwrap()
+}
+
+/// Waits until `condition` holds, letting the main context run in between.
+pub async fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(
+            start.elapsed() < WAIT_TIMEOUT,
+            "Timed out waiting for {what}"
+        );
+        glib::timeout_future(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,513 @@
+//! Drives the UPower service against a mock UPower on a private bus.
+//!
+//! The mock has a laptop battery with a few hours of history, a mains adapter and a wireless mouse. The tests unplug
+//! the adapter, connect a headset, close the lid and remove the mouse.
+
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ballad_services::upower::{
+    BatteryHistoryKind, BatteryStatisticsKind, UPowerDevice, UPowerDeviceKind, UPowerDeviceState,
+    UPowerService,
+};
+use gtk::prelude::*;
+use zbus::{
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath},
+};
+
+use common::{TestBus, run, wait_for};
+
+const UPOWER_BUS_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const BATTERY_PATH: &str = "/org/freedesktop/UPower/devices/battery_BAT0";
+const AC_PATH: &str = "/org/freedesktop/UPower/devices/line_power_AC";
+const MOUSE_PATH: &str = "/org/freedesktop/UPower/devices/mouse_dev_11_11_11_11_11_11";
+const HEADSET_PATH: &str = "/org/freedesktop/UPower/devices/headset_dev_33_33_33_33_33_33";
+
+// Device types and states as UPower reports them.
+const TYPE_LINE_POWER: u32 = 1;
+const TYPE_BATTERY: u32 = 2;
+const TYPE_MOUSE: u32 = 5;
+const TYPE_HEADSET: u32 = 17;
+const STATE_CHARGING: u32 = 1;
+const STATE_DISCHARGING: u32 = 2;
+
+fn object_path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).unwrap()
+}
+
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32
+}
+
+struct MockUPower {
+    on_battery: bool,
+    lid_is_closed: bool,
+    devices: Vec<OwnedObjectPath>,
+}
+
+#[interface(name = "org.freedesktop.UPower")]
+impl MockUPower {
+    fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+        self.devices.clone()
+    }
+
+    #[zbus(signal)]
+    async fn device_added(emitter: &SignalEmitter<'_>, device: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn device_removed(
+        emitter: &SignalEmitter<'_>,
+        device: ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn on_battery(&self) -> bool {
+        self.on_battery
+    }
+
+    #[zbus(property)]
+    fn lid_is_present(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn lid_is_closed(&self) -> bool {
+        self.lid_is_closed
+    }
+}
+
+#[derive(Default)]
+struct MockDevice {
+    kind: u32,
+    model: String,
+    native_path: String,
+    percentage: f64,
+    state: u32,
+    power_supply: bool,
+    online: bool,
+    energy_full: f64,
+    energy_rate: f64,
+    /// Samples of the charge and rate, newest first like UPower returns them.
+    charge_history: Vec<(u32, f64, u32)>,
+    rate_history: Vec<(u32, f64, u32)>,
+}
+impl MockDevice {
+    fn battery() -> Self {
+        // Six hours of slowly draining battery, sampled every two minutes, with the rate jumping around the load.
+        let now = now();
+        let mut charge_history = Vec::new();
+        let mut rate_history = Vec::new();
+        for sample in 0..180u32 {
+            let time = now - sample * 120;
+            let charge = 80.0 + sample as f64 * 0.1;
+            let rate = 9.0 + (sample % 7) as f64;
+            charge_history.push((time, charge.min(100.0), STATE_DISCHARGING));
+            rate_history.push((time, rate, STATE_DISCHARGING));
+        }
+        Self {
+            kind: TYPE_BATTERY,
+            model: "Laptop battery".to_string(),
+            native_path: "BAT0".to_string(),
+            percentage: 80.0,
+            state: STATE_CHARGING,
+            power_supply: true,
+            energy_full: 50.0,
+            energy_rate: 20.0,
+            charge_history,
+            rate_history,
+            ..Default::default()
+        }
+    }
+
+    fn display_device() -> Self {
+        Self {
+            native_path: String::new(),
+            ..Self::battery()
+        }
+    }
+
+    fn line_power() -> Self {
+        Self {
+            kind: TYPE_LINE_POWER,
+            native_path: "AC".to_string(),
+            power_supply: true,
+            online: true,
+            ..Default::default()
+        }
+    }
+
+    fn peripheral(kind: u32, model: &str, percentage: f64) -> Self {
+        Self {
+            kind,
+            model: model.to_string(),
+            native_path: format!("{}-battery", model.to_lowercase()),
+            percentage,
+            state: STATE_DISCHARGING,
+            ..Default::default()
+        }
+    }
+
+    fn current_energy(&self) -> f64 {
+        self.energy_full * self.percentage / 100.0
+    }
+}
+
+#[interface(name = "org.freedesktop.UPower.Device")]
+impl MockDevice {
+    fn get_history(&self, type_: &str, timespan: u32, resolution: u32) -> Vec<(u32, f64, u32)> {
+        let history = match type_ {
+            "charge" => &self.charge_history,
+            "rate" => &self.rate_history,
+            _ => return Vec::new(),
+        };
+        let since = now().saturating_sub(timespan);
+        let points = history
+            .iter()
+            .copied()
+            .filter(|(time, _, _)| *time >= since)
+            .collect::<Vec<_>>();
+        let step = points.len().div_ceil(resolution.max(1) as usize).max(1);
+        points.into_iter().step_by(step).collect()
+    }
+
+    fn get_statistics(&self, type_: &str) -> Vec<(f64, f64)> {
+        // One entry per percent, the estimate getting worse as the battery empties.
+        let skew = if type_ == "charging" { 0.001 } else { 0.002 };
+        (0..101)
+            .map(|percent| (1.0 + (100 - percent) as f64 * skew, 80.0))
+            .collect()
+    }
+
+    fn refresh(&self) {}
+
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> u32 {
+        self.kind
+    }
+
+    #[zbus(property)]
+    fn vendor(&self) -> String {
+        "Ballad".to_string()
+    }
+
+    #[zbus(property)]
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    #[zbus(property)]
+    fn native_path(&self) -> String {
+        self.native_path.clone()
+    }
+
+    #[zbus(property)]
+    fn percentage(&self) -> f64 {
+        self.percentage
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        self.state
+    }
+
+    #[zbus(property)]
+    fn power_supply(&self) -> bool {
+        self.power_supply
+    }
+
+    #[zbus(property)]
+    fn is_present(&self) -> bool {
+        self.kind != TYPE_LINE_POWER
+    }
+
+    #[zbus(property)]
+    fn online(&self) -> bool {
+        self.online
+    }
+
+    #[zbus(property)]
+    fn energy(&self) -> f64 {
+        self.current_energy()
+    }
+
+    #[zbus(property)]
+    fn energy_full(&self) -> f64 {
+        self.energy_full
+    }
+
+    #[zbus(property)]
+    fn energy_rate(&self) -> f64 {
+        self.energy_rate
+    }
+
+    #[zbus(property)]
+    fn time_to_empty(&self) -> i64 {
+        if self.state == STATE_DISCHARGING && self.energy_rate > 0.0 {
+            (self.current_energy() / self.energy_rate * 3600.0) as i64
+        } else {
+            0
+        }
+    }
+
+    #[zbus(property)]
+    fn time_to_full(&self) -> i64 {
+        if self.state == STATE_CHARGING && self.energy_rate > 0.0 {
+            ((self.energy_full - self.current_energy()) / self.energy_rate * 3600.0) as i64
+        } else {
+            0
+        }
+    }
+
+    #[zbus(property)]
+    fn has_history(&self) -> bool {
+        !self.charge_history.is_empty()
+    }
+
+    #[zbus(property)]
+    fn has_statistics(&self) -> bool {
+        self.kind == TYPE_BATTERY
+    }
+}
+
+async fn serve_mock(bus: &TestBus) -> zbus::Result<zbus::Connection> {
+    bus.builder()
+        .name(UPOWER_BUS_NAME)?
+        .serve_at(
+            UPOWER_PATH,
+            MockUPower {
+                on_battery: false,
+                lid_is_closed: false,
+                devices: [BATTERY_PATH, AC_PATH, MOUSE_PATH]
+                    .into_iter()
+                    .map(object_path)
+                    .collect(),
+            },
+        )?
+        .serve_at(DISPLAY_DEVICE_PATH, MockDevice::display_device())?
+        .serve_at(BATTERY_PATH, MockDevice::battery())?
+        .serve_at(AC_PATH, MockDevice::line_power())?
+        .serve_at(
+            MOUSE_PATH,
+            MockDevice::peripheral(TYPE_MOUSE, "Mouse", 64.0),
+        )?
+        .build()
+        .await
+}
+
+/// Unplugs the mains adapter, which makes both batteries start discharging.
+async fn unplug(connection: &zbus::Connection) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let upower = server.interface::<_, MockUPower>(UPOWER_PATH).await?;
+    upower.get_mut().await.on_battery = true;
+    upower
+        .get()
+        .await
+        .on_battery_changed(upower.signal_emitter())
+        .await?;
+
+    let ac = server.interface::<_, MockDevice>(AC_PATH).await?;
+    ac.get_mut().await.online = false;
+    ac.get().await.online_changed(ac.signal_emitter()).await?;
+
+    for path in [BATTERY_PATH, DISPLAY_DEVICE_PATH] {
+        let battery = server.interface::<_, MockDevice>(path).await?;
+        let mut device = battery.get_mut().await;
+        device.state = STATE_DISCHARGING;
+        device.energy_rate = 15.0;
+        device.state_changed(battery.signal_emitter()).await?;
+        device.energy_rate_changed(battery.signal_emitter()).await?;
+        device
+            .time_to_empty_changed(battery.signal_emitter())
+            .await?;
+    }
+    Ok(())
+}
+
+async fn add_headset(connection: &zbus::Connection) -> zbus::Result<()> {
+    let server = connection.object_server();
+    server
+        .at(
+            HEADSET_PATH,
+            MockDevice::peripheral(TYPE_HEADSET, "Headset", 30.0),
+        )
+        .await?;
+    let upower = server.interface::<_, MockUPower>(UPOWER_PATH).await?;
+    upower
+        .get_mut()
+        .await
+        .devices
+        .push(object_path(HEADSET_PATH));
+    MockUPower::device_added(
+        upower.signal_emitter(),
+        ObjectPath::from_static_str_unchecked(HEADSET_PATH),
+    )
+    .await
+}
+
+async fn close_lid(connection: &zbus::Connection) -> zbus::Result<()> {
+    let upower = connection
+        .object_server()
+        .interface::<_, MockUPower>(UPOWER_PATH)
+        .await?;
+    upower.get_mut().await.lid_is_closed = true;
+    upower
+        .get()
+        .await
+        .lid_is_closed_changed(upower.signal_emitter())
+        .await
+}
+
+async fn set_percentage(
+    connection: &zbus::Connection,
+    path: &str,
+    percentage: f64,
+) -> zbus::Result<()> {
+    let device = connection
+        .object_server()
+        .interface::<_, MockDevice>(path)
+        .await?;
+    device.get_mut().await.percentage = percentage;
+    device
+        .get()
+        .await
+        .percentage_changed(device.signal_emitter())
+        .await
+}
+
+async fn remove_mouse(connection: &zbus::Connection) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let upower = server.interface::<_, MockUPower>(UPOWER_PATH).await?;
+    upower
+        .get_mut()
+        .await
+        .devices
+        .retain(|path| path.as_str() != MOUSE_PATH);
+    server.remove::<MockDevice, _>(MOUSE_PATH).await?;
+    MockUPower::device_removed(
+        upower.signal_emitter(),
+        ObjectPath::from_static_str_unchecked(MOUSE_PATH),
+    )
+    .await
+}
+
+fn find_device(service: &UPowerService, name: &str) -> Option<UPowerDevice> {
+    service
+        .devices()
+        .iter::<UPowerDevice>()
+        .flatten()
+        .find(|device| device.name() == name)
+}
+
+#[test]
+fn devices_charge_and_state() {
+    let bus = TestBus::start();
+    run(async {
+        let mock = serve_mock(&bus).await.unwrap();
+        let service = UPowerService::with_connection(bus.connect().await);
+
+        // The mains adapter isn't a battery, so it is left out.
+        wait_for("the devices", || service.devices().n_items() == 2).await;
+        wait_for("the display device", || service.percentage() == 80.0).await;
+        assert!(service.available());
+        assert!(service.charging() && !service.charged());
+        assert!(!service.on_battery());
+        assert!(service.lid_is_present() && !service.lid_is_closed());
+
+        let batteries = service.batteries();
+        assert_eq!(batteries.len(), 1);
+        let battery = &batteries[0];
+        assert_eq!(battery.name(), "Laptop battery");
+        assert_eq!(battery.kind(), UPowerDeviceKind::Battery);
+        assert_eq!(battery.state(), UPowerDeviceState::Charging);
+        assert_eq!(battery.percentage(), 80.0);
+        assert_eq!(battery.energy(), 40.0);
+        assert_eq!(battery.energy_full(), 50.0);
+        assert_eq!(battery.time_to_full(), 1800);
+
+        let peripherals = service.peripherals();
+        assert_eq!(peripherals.len(), 1);
+        let mouse = &peripherals[0];
+        assert_eq!(mouse.name(), "Mouse");
+        assert_eq!(mouse.kind(), UPowerDeviceKind::Mouse);
+        assert_eq!(mouse.state(), UPowerDeviceState::Discharging);
+        assert_eq!(mouse.percentage(), 64.0);
+
+        unplug(&mock).await.unwrap();
+        wait_for("running on battery", || service.on_battery()).await;
+        wait_for("the display device to discharge", || !service.charging()).await;
+        wait_for("the battery to discharge", || {
+            battery.state() == UPowerDeviceState::Discharging
+        })
+        .await;
+        wait_for("the new rate", || battery.energy_rate() == 15.0).await;
+
+        set_percentage(&mock, MOUSE_PATH, 50.0).await.unwrap();
+        wait_for("the mouse to lose charge", || mouse.percentage() == 50.0).await;
+
+        add_headset(&mock).await.unwrap();
+        wait_for("the headset", || service.devices().n_items() == 3).await;
+        let headset = find_device(&service, "Headset").unwrap();
+        assert_eq!(headset.kind(), UPowerDeviceKind::Headset);
+        assert_eq!(headset.percentage(), 30.0);
+        assert_eq!(service.peripherals().len(), 2);
+        assert_eq!(service.batteries().len(), 1);
+
+        close_lid(&mock).await.unwrap();
+        wait_for("the lid to close", || service.lid_is_closed()).await;
+
+        remove_mouse(&mock).await.unwrap();
+        wait_for("the mouse to be removed", || {
+            find_device(&service, "Mouse").is_none()
+        })
+        .await;
+        assert_eq!(service.devices().n_items(), 2);
+        assert_eq!(service.peripherals().len(), 1);
+    });
+}
+
+#[test]
+fn history_and_statistics() {
+    let bus = TestBus::start();
+    run(async {
+        let _mock = serve_mock(&bus).await.unwrap();
+        let service = UPowerService::with_connection(bus.connect().await);
+        wait_for("the battery", || service.batteries().len() == 1).await;
+        let battery = service.batteries().remove(0);
+        assert!(battery.has_history() && battery.has_statistics());
+
+        let hour = Duration::from_secs(3600);
+        let points = battery
+            .history(BatteryHistoryKind::Charge, hour, 20)
+            .await
+            .unwrap();
+        assert!(!points.is_empty() && points.len() <= 20, "{points:?}");
+        // Oldest first, and the battery has been draining.
+        assert!(points.is_sorted_by_key(|point| point.time));
+        assert!(points.first().unwrap().value > points.last().unwrap().value);
+        assert_eq!(points.last().unwrap().value, 80.0);
+        let an_hour_ago = now() - hour.as_secs() as u32;
+        assert!(points.iter().all(|point| point.time >= an_hour_ago));
+
+        let statistics = battery
+            .statistics(BatteryStatisticsKind::Discharging)
+            .await
+            .unwrap();
+        assert_eq!(statistics.len(), 101);
+        assert_eq!(statistics[50].percentage, 50.0);
+        assert!((statistics[50].value - 1.1).abs() < 1e-9);
+        assert_eq!(statistics[50].accuracy, 80.0);
+
+        // The rate history is all from discharging, so only the current rate counts while charging: 10 Wh to go at
+        // 20 W.
+        let remaining = battery.smoothed_time_remaining(hour).await.unwrap();
+        assert_eq!(remaining.as_secs(), 1800);
+    });
+}
@@ -0,0 +1,119 @@
+use ballad_services::upower::BatteryHistoryPoint;
+use gtk::{glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::RefCell;
+
+    use gtk::{gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*};
+
+    /// How many horizontal guide lines to draw between the bottom and the top of the chart.
+    const GUIDES: u32 = 4;
+    const LINE_WIDTH: f32 = 2.0;
+
+    #[derive(Debug, Default)]
+    pub struct BatteryChart {
+        /// Points as fractions of the chart's width and height, measured from the bottom left.
+        pub(super) points: RefCell<Vec<(f64, f64)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BatteryChart {
+        const NAME: &'static str = "BalladSettingsBatteryChart";
+        type Type = super::BatteryChart;
+        type ParentType = gtk::Widget;
+
+        fn class_init(class: &mut Self::Class) {
+            class.set_css_name("battery-chart");
+        }
+    }
+
+    impl ObjectImpl for BatteryChart {}
+
+    impl WidgetImpl for BatteryChart {
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            match orientation {
+                gtk::Orientation::Horizontal => (120, 480, -1, -1),
+                _ => (80, 200, -1, -1),
+            }
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            let width = widget.width() as f32;
+            let height = widget.height() as f32;
+            let color = widget.color();
+            let faint = gdk::RGBA::new(color.red(), color.green(), color.blue(), 0.15);
+            let fill = gdk::RGBA::new(color.red(), color.green(), color.blue(), 0.25);
+
+            for guide in 0..=GUIDES {
+                let y = (height - 1.0) * guide as f32 / GUIDES as f32;
+                snapshot.append_color(&faint, &graphene::Rect::new(0.0, y, width, 1.0));
+            }
+
+            let points = self.points.borrow();
+            let Some(&(first_x, first_y)) = points.first() else {
+                return;
+            };
+            let to_widget = |(x, y): (f64, f64)| (x as f32 * width, (1.0 - y as f32) * height);
+
+            let line = gsk::PathBuilder::new();
+            let (x, y) = to_widget((first_x, first_y));
+            line.move_to(x, y);
+            for &point in points.iter().skip(1) {
+                let (x, y) = to_widget(point);
+                line.line_to(x, y);
+            }
+            let line = line.to_path();
+
+            let area = gsk::PathBuilder::new();
+            area.move_to(to_widget((first_x, 0.0)).0, height);
+            for &point in points.iter() {
+                let (x, y) = to_widget(point);
+                area.line_to(x, y);
+            }
+            let (last_x, _) = to_widget(*points.last().unwrap());
+            area.line_to(last_x, height);
+            area.close();
+
+            snapshot.append_fill(&area.to_path(), gsk::FillRule::Winding, &fill);
+            snapshot.append_stroke(&line, &gsk::Stroke::new(LINE_WIDTH), &color);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A line chart of a battery history, drawn in the widget's CSS `color`.
+    pub struct BatteryChart(ObjectSubclass<imp::BatteryChart>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+impl BatteryChart {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    /// Shows `points` between `start` and `end` (in seconds since the Unix epoch), scaled so `max` is the top of the
+    /// chart. The charge is a percentage, so it should use a `max` of 100.
+    pub fn set_history(&self, points: &[BatteryHistoryPoint], start: u32, end: u32, max: f64) {
+        let span = end.saturating_sub(start).max(1) as f64;
+        let max = if max > 0.0 { max } else { 1.0 };
+        self.imp().points.replace(
+            points
+                .iter()
+                .filter(|point| point.time >= start && point.time <= end)
+                .map(|point| {
+                    (
+                        (point.time - start) as f64 / span,
+                        (point.value / max).clamp(0.0, 1.0),
+                    )
+                })
+                .collect(),
+        );
+        self.queue_draw();
+    }
+}
+impl Default for BatteryChart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -11,6 +11,7 @@ use gtk::{
 };
 use gtk::{Orientation, Paned, Stack, prelude::*};
 
+mod battery_chart;
 mod pages;
 
 fn main() {
@@ -45,11 +46,26 @@ const PAGES: &[Page] = &[
         icon_name: "preferences-desktop-theme-symbolic",
         name: "shell",
     },
+    Page {
+        title: "Themes",
+        icon_name: "applications-graphics-symbolic",
+        name: "themes",
+    },
+    Page {
+        title: "Wallpaper",
+        icon_name: "preferences-desktop-wallpaper-symbolic",
+        name: "wallpaper",
+    },
     Page {
         title: "User",
         icon_name: "system-users-symbolic",
         name: "user",
     },
+    Page {
+        title: "Power",
+        icon_name: "battery-symbolic",
+        name: "power",
+    },
 ];
 
 fn page_switcher(stack: &Stack) -> gtk::Box {
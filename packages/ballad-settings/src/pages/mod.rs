@@ -1,14 +1,20 @@
 use gtk::{Align, Label, Widget, prelude::*};
 use typed_builder::TypedBuilder;
 
+pub mod power;
 pub mod shell;
+pub mod theme_editor;
 pub mod user;
+pub mod wallpaper;
 
 pub fn settings_stack() -> gtk::Stack {
     let stack = gtk::Stack::builder().name("settings-stack").build();
 
     stack.add_titled(&shell::shell_page(), Some("shell"), "Shell");
+    stack.add_titled(&theme_editor::theme_editor_page(), Some("themes"), "Themes");
+    stack.add_titled(&wallpaper::wallpaper_page(), Some("wallpaper"), "Wallpaper");
     stack.add_titled(&user::user_page(), Some("user"), "User");
+    stack.add_titled(&power::power_page(), Some("power"), "Power");
 
     stack
 }
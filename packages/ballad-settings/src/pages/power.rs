@@ -0,0 +1,148 @@
+use std::cell::LazyCell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ballad_services::upower::{BatteryHistoryKind, UPOWER_SERVICE, UPowerDevice, UPowerService};
+use gtk::{
+    DropDown, Label,
+    glib::{self, clone, closure_local},
+    prelude::*,
+};
+
+use super::{Page, option};
+use crate::battery_chart::BatteryChart;
+
+const TIMESPANS: &[(&str, Duration)] = &[
+    ("Last hour", Duration::from_secs(60 * 60)),
+    ("Last 6 hours", Duration::from_secs(6 * 60 * 60)),
+    ("Last day", Duration::from_secs(24 * 60 * 60)),
+    ("Last week", Duration::from_secs(7 * 24 * 60 * 60)),
+];
+const SERIES: &[(&str, BatteryHistoryKind)] = &[
+    ("Charge", BatteryHistoryKind::Charge),
+    ("Power usage", BatteryHistoryKind::Rate),
+];
+/// How many points to ask UPower for, roughly one every few pixels of the chart.
+const RESOLUTION: u32 = 150;
+/// The window the time remaining is averaged over.
+const ESTIMATE_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+async fn update_chart(
+    battery: UPowerDevice,
+    chart: BatteryChart,
+    estimate: Label,
+    timespan: Duration,
+    kind: BatteryHistoryKind,
+) {
+    let end = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as u32)
+        .unwrap_or_default();
+    let start = end.saturating_sub(timespan.as_secs() as u32);
+
+    let points = battery
+        .history(kind, timespan, RESOLUTION)
+        .await
+        .unwrap_or_default();
+    let max = match kind {
+        BatteryHistoryKind::Charge => 100.0,
+        _ => points.iter().map(|point| point.value).fold(0.0, f64::max),
+    };
+    chart.set_history(&points, start, end, max);
+
+    estimate.set_label(
+        &match battery.smoothed_time_remaining(ESTIMATE_WINDOW).await {
+            Some(remaining) => format_duration(remaining),
+            None => "Unknown".to_string(),
+        },
+    );
+}
+
+pub fn power_page() -> gtk::Box {
+    let service = UPOWER_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let timespan_selector =
+        DropDown::from_strings(&TIMESPANS.iter().map(|(name, _)| *name).collect::<Vec<_>>());
+    let series_selector =
+        DropDown::from_strings(&SERIES.iter().map(|(name, _)| *name).collect::<Vec<_>>());
+    let estimate = Label::builder()
+        .label("Unknown")
+        .css_classes(["battery-estimate"])
+        .build();
+    let chart = BatteryChart::new();
+    chart.set_hexpand(true);
+    chart.set_vexpand(true);
+
+    let refresh = clone!(
+        #[weak]
+        service,
+        #[weak]
+        timespan_selector,
+        #[weak]
+        series_selector,
+        #[weak]
+        chart,
+        #[weak]
+        estimate,
+        move || {
+            let Some(battery) = service.batteries().into_iter().next() else {
+                chart.set_history(&[], 0, 0, 0.0);
+                estimate.set_label("No battery");
+                return;
+            };
+            let (_, timespan) = TIMESPANS[timespan_selector.selected() as usize];
+            let (_, kind) = SERIES[series_selector.selected() as usize];
+            glib::spawn_future_local(update_chart(battery, chart, estimate, timespan, kind));
+        }
+    );
+    refresh();
+    timespan_selector.connect_selected_notify(clone!(
+        #[strong]
+        refresh,
+        move |_| refresh()
+    ));
+    series_selector.connect_selected_notify(clone!(
+        #[strong]
+        refresh,
+        move |_| refresh()
+    ));
+    service.devices().connect_items_changed(clone!(
+        #[strong]
+        refresh,
+        move |_, _, _, _| refresh()
+    ));
+    service.connect_closure(
+        "battery-changed",
+        false,
+        closure_local!(move |_: UPowerService| refresh()),
+    );
+
+    let page: gtk::Box = Page::builder()
+        .name("power-page")
+        .with_option(&option(
+            "Time remaining",
+            Some("Estimated from the average power usage over the last 15 minutes"),
+            &estimate,
+        ))
+        .with_option(&option(
+            "History",
+            Some("How far back the battery chart goes"),
+            &timespan_selector,
+        ))
+        .with_option(&option(
+            "Series",
+            Some("What the battery chart shows"),
+            &series_selector,
+        ))
+        .build();
+    page.append(&chart);
+
+    page
+}
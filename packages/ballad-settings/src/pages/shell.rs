@@ -1,35 +1,247 @@
-use std::cell::LazyCell;
+use std::cell::{Cell, LazyCell, RefCell};
+use std::rc::Rc;
 
 use super::{Page, option};
-use ballad_config::{ShellConfig, ThemeConfig, theme::get_or_init_all_theme_selections};
-use ballad_services::config::CONFIG_SERVICE;
-use gtk::DropDown;
+use ballad_config::{
+    ShellConfig, ThemeConfig, ThemeSelection, TimeOfDay,
+    theme::{
+        get_or_init_all_theme_selections,
+        schedule::{ThemeSwitchConfig, ThemeSwitchMode},
+    },
+};
+use ballad_services::config::{CONFIG_SERVICE, ConfigService};
+use gtk::{
+    Adjustment, DropDown, Entry, SpinButton, StringList,
+    glib::{self, clone, closure_local},
+    prelude::*,
+};
 
-pub fn shell_page() -> gtk::Box {
-    let theme_options = get_or_init_all_theme_selections().unwrap_or_default();
+const SWITCH_MODES: &[(&str, ThemeSwitchMode)] = &[
+    ("Never", ThemeSwitchMode::Manual),
+    ("On a schedule", ThemeSwitchMode::Schedule),
+    ("At sunrise and sunset", ThemeSwitchMode::Sun),
+    ("Follow the system", ThemeSwitchMode::Portal),
+];
+
+fn switch_mode_position(mode: ThemeSwitchMode) -> u32 {
+    SWITCH_MODES
+        .iter()
+        .position(|(_, option)| *option == mode)
+        .unwrap_or_default() as u32
+}
+
+/// The widgets that pick when the theme switches between light and dark.
+#[derive(Clone)]
+struct SwitchWidgets {
+    mode: DropDown,
+    light_at: Entry,
+    dark_at: Entry,
+    latitude: SpinButton,
+    longitude: SpinButton,
+}
+impl SwitchWidgets {
+    fn new() -> Self {
+        let time = || {
+            Entry::builder()
+                .placeholder_text("07:00")
+                .max_width_chars(5)
+                .build()
+        };
+        let degrees = |limit: f64| {
+            SpinButton::builder()
+                .adjustment(&Adjustment::new(0.0, -limit, limit, 0.01, 1.0, 0.0))
+                .digits(2)
+                .build()
+        };
+        Self {
+            mode: DropDown::from_strings(
+                &SWITCH_MODES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>(),
+            ),
+            light_at: time(),
+            dark_at: time(),
+            latitude: degrees(90.0),
+            longitude: degrees(180.0),
+        }
+    }
+
+    /// Shows `config`, and only lets the times or the location be edited when the mode uses them.
+    fn show(&self, config: &ThemeSwitchConfig) {
+        self.mode.set_selected(switch_mode_position(config.mode));
+        for (entry, time) in [
+            (&self.light_at, config.light_at),
+            (&self.dark_at, config.dark_at),
+        ] {
+            entry.set_text(&time.to_string());
+            entry.remove_css_class("error");
+            entry.set_sensitive(config.mode == ThemeSwitchMode::Schedule);
+        }
+        for (spin, degrees) in [
+            (&self.latitude, config.latitude),
+            (&self.longitude, config.longitude),
+        ] {
+            spin.set_value(degrees);
+            spin.set_sensitive(config.mode == ThemeSwitchMode::Sun);
+        }
+    }
+}
+
+/// Parses the time in `entry`, and marks it as an error if it isn't one.
+fn parse_time(entry: &Entry) -> Option<TimeOfDay> {
+    let time = entry.text().parse::<TimeOfDay>().ok();
+    if time.is_some() {
+        entry.remove_css_class("error");
+    } else {
+        entry.add_css_class("error");
+    }
+    time
+}
 
-    let theme_option_strings = theme_options
+/// Fills `names` with the themes there are now, and selects the selected theme.
+fn reload_themes(
+    selector: &DropDown,
+    names: &StringList,
+    theme_options: &RefCell<Vec<ThemeSelection>>,
+    selected: &ThemeSelection,
+) {
+    let options = get_or_init_all_theme_selections().unwrap_or_default();
+    let option_names = options
         .iter()
-        .map(|option| option.theme().unwrap().name.clone())
+        .map(|option| option.theme().map(|theme| theme.name).unwrap_or_default())
         .collect::<Vec<_>>();
-    let theme_selector = DropDown::from_strings(
-        &theme_option_strings
+    names.splice(
+        0,
+        names.n_items(),
+        &option_names.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+    selector.set_selected(
+        options
             .iter()
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>(),
+            .position(|option| option == selected)
+            .unwrap_or_default() as u32,
     );
+    theme_options.replace(options);
+}
+
+pub fn shell_page() -> gtk::Box {
+    let theme_names = StringList::new(&[]);
+    let theme_selector = DropDown::builder().model(&theme_names).build();
+    let theme_options = Rc::new(RefCell::new(Vec::new()));
+    // Set while the list is rebuilt, so selecting the theme again doesn't write the config.
+    let reloading = Rc::new(Cell::new(false));
+    let switch = SwitchWidgets::new();
 
     CONFIG_SERVICE.with(|service| {
         let service = LazyCell::force(service).clone();
-        let theme = service.shell_config().theme.selected_theme;
-        theme_selector.set_selected(theme_options.iter().position(|v| *v == theme).unwrap() as u32);
+        reloading.set(true);
+        reload_themes(
+            &theme_selector,
+            &theme_names,
+            &theme_options,
+            &service.shell_config().theme.selected_theme,
+        );
+        switch.show(&service.shell_config().theme.auto_switch);
+        reloading.set(false);
+
+        // Custom themes are added and removed on the themes page.
+        service.connect_closure(
+            "shell-theme-config-changed",
+            false,
+            closure_local!(
+                #[weak]
+                theme_selector,
+                #[weak]
+                theme_names,
+                #[strong]
+                switch,
+                #[strong]
+                theme_options,
+                #[strong]
+                reloading,
+                move |_: ConfigService, config: &ThemeConfig| {
+                    reloading.set(true);
+                    reload_themes(
+                        &theme_selector,
+                        &theme_names,
+                        &theme_options,
+                        &config.selected_theme,
+                    );
+                    switch.show(&config.auto_switch);
+                    reloading.set(false);
+                }
+            ),
+        );
+
+        // Writes an edit of the switch config, unless the widgets are being filled in from the config.
+        let update = Rc::new(clone!(
+            #[strong]
+            service,
+            #[strong]
+            reloading,
+            move |edit: &dyn Fn(&mut ThemeSwitchConfig)| {
+                if reloading.get() {
+                    return;
+                }
+                let mut config = service.shell_config();
+                edit(&mut config.theme.auto_switch);
+                service.set_shell_config(config);
+            }
+        ));
+
+        switch.mode.connect_selected_notify(clone!(
+            #[strong]
+            update,
+            move |combo| {
+                let Some((_, mode)) = SWITCH_MODES.get(combo.selected() as usize) else {
+                    return;
+                };
+                update(&|config| config.mode = *mode);
+            }
+        ));
+        switch.light_at.connect_activate(clone!(
+            #[strong]
+            update,
+            move |entry| {
+                if let Some(time) = parse_time(entry) {
+                    update(&|config| config.light_at = time);
+                }
+            }
+        ));
+        switch.dark_at.connect_activate(clone!(
+            #[strong]
+            update,
+            move |entry| {
+                if let Some(time) = parse_time(entry) {
+                    update(&|config| config.dark_at = time);
+                }
+            }
+        ));
+        switch.latitude.connect_value_changed(clone!(
+            #[strong]
+            update,
+            move |spin| update(&|config| config.latitude = spin.value())
+        ));
+        switch
+            .longitude
+            .connect_value_changed(move |spin| update(&|config| config.longitude = spin.value()));
 
         theme_selector.connect_selected_notify(move |combo| {
-            let theme = theme_options.get(combo.selected() as usize).unwrap();
+            if reloading.get() {
+                return;
+            }
+            let Some(theme) = theme_options
+                .borrow()
+                .get(combo.selected() as usize)
+                .cloned()
+            else {
+                return;
+            };
             let config = service.shell_config();
             service.set_shell_config(ShellConfig {
                 theme: ThemeConfig {
-                    selected_theme: theme.clone(),
+                    selected_theme: theme,
                     ..config.theme
                 },
                 ..config
@@ -44,5 +256,30 @@ pub fn shell_page() -> gtk::Box {
             Some("The color scheme Ballad applications and the system will use"),
             &theme_selector,
         ))
+        .with_option(&option(
+            "Switch between light and dark",
+            Some("Uses the light and dark themes last picked in the quick settings"),
+            &switch.mode,
+        ))
+        .with_option(&option(
+            "Light from",
+            Some("When the light theme starts on a schedule, like 07:00"),
+            &switch.light_at,
+        ))
+        .with_option(&option(
+            "Dark from",
+            Some("When the dark theme starts on a schedule, like 19:00"),
+            &switch.dark_at,
+        ))
+        .with_option(&option(
+            "Latitude",
+            Some("Where sunrise and sunset are taken, in degrees north"),
+            &switch.latitude,
+        ))
+        .with_option(&option(
+            "Longitude",
+            Some("Where sunrise and sunset are taken, in degrees east"),
+            &switch.longitude,
+        ))
         .build()
 }
@@ -0,0 +1,670 @@
+use std::cell::{Cell, LazyCell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use ballad_config::{
+    ShellConfig, ThemeSelection,
+    theme::{
+        Color, Theme, ThemeVariant,
+        files::{ThemeFileFormat, export_theme, import_themes},
+        palette::Palette,
+    },
+};
+use ballad_services::config::{CONFIG_SERVICE, ConfigService};
+use gtk::{
+    Align, Button, ColorDialog, ColorDialogButton, CssProvider, DropDown, Entry, FileDialog,
+    FileFilter, Grid, Label, PolicyType, STYLE_PROVIDER_PRIORITY_APPLICATION, ScrolledWindow,
+    StringList, Switch, Window,
+    gdk::{Display, RGBA},
+    gio::Cancellable,
+    glib::{self, clone, closure_local},
+    prelude::*,
+    style_context_add_provider_for_display,
+};
+
+use super::{Page, option};
+
+/// How many colors are in each row of the color grid.
+const COLOR_COLUMNS: i32 = 3;
+
+fn color_label(name: &str) -> String {
+    match name {
+        "bg_0" => "Background".to_string(),
+        "bg_1" => "Middle background".to_string(),
+        "bg_2" => "Top background".to_string(),
+        _ => {
+            let name = name.replace('_', " ");
+            let mut chars = name.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    }
+}
+
+fn rgba_to_color(rgba: &RGBA) -> Color {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::rgba(
+        channel(rgba.red()),
+        channel(rgba.green()),
+        channel(rgba.blue()),
+        channel(rgba.alpha()),
+    )
+}
+
+fn color_to_rgba(color: &Color) -> RGBA {
+    let channel = |value: u8| value as f32 / 255.0;
+    RGBA::new(
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a),
+    )
+}
+
+/// A name for a copy of `name` that no theme in `themes` has yet.
+fn unique_name(themes: &[Theme], name: &str) -> String {
+    let taken = |candidate: &str| themes.iter().any(|theme| theme.name == candidate);
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|number| format!("{name} {number}"))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+/// The image to take a palette from for a wallpaper path, which may be a slideshow folder.
+fn wallpaper_image(path: &Path) -> Option<PathBuf> {
+    if !path.is_dir() {
+        return Some(path.to_path_buf());
+    }
+    let mut images = std::fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    images.sort();
+    images.into_iter().next()
+}
+
+fn preview_css(theme: &Theme) -> String {
+    let colors = &theme.colors;
+    let mut css = format!(
+        "#theme-preview {{ background-color: {}; color: {}; }}\n#theme-preview .preview-subtext {{ color: {}; }}\n",
+        colors.bg_0, colors.text, colors.subtext_0
+    );
+    for (name, color) in colors.named() {
+        css.push_str(&format!(
+            "#theme-preview .preview-{name} {{ background-color: {color}; }}\n"
+        ));
+    }
+    css
+}
+
+/// The widgets of the page, which edit the custom theme picked in `themes`.
+#[derive(Clone)]
+struct ThemeEditor {
+    service: ConfigService,
+    themes: DropDown,
+    theme_names: StringList,
+    editor: gtk::Box,
+    name: Entry,
+    variant: DropDown,
+    gtk_theme: Entry,
+    active: Switch,
+    colors: Rc<Vec<(&'static str, ColorDialogButton)>>,
+    preview_provider: CssProvider,
+    delete: Button,
+    export: Button,
+    /// Set while the widgets are filled in from the config, so that doesn't write the config again.
+    updating: Rc<Cell<bool>>,
+    /// The theme that was picked before the list was rebuilt.
+    picked: Rc<RefCell<Option<String>>>,
+}
+impl ThemeEditor {
+    fn custom_themes(&self) -> Vec<Theme> {
+        self.service.shell_config().theme.custom_themes
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        self.theme_names
+            .string(self.themes.selected())
+            .map(|name| name.to_string())
+    }
+
+    fn selected_theme(&self) -> Option<Theme> {
+        let name = self.selected_name()?;
+        self.custom_themes()
+            .into_iter()
+            .find(|theme| theme.name == name)
+    }
+
+    /// Rebuilds the list of custom themes, picking `pick` or else the theme that was picked before.
+    fn reload(&self, pick: Option<&str>) {
+        let pick = pick.map(str::to_string).or_else(|| self.picked.take());
+        let names = self
+            .custom_themes()
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect::<Vec<_>>();
+
+        self.updating.set(true);
+        self.theme_names.splice(
+            0,
+            self.theme_names.n_items(),
+            &names.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        let position = pick
+            .and_then(|pick| names.iter().position(|name| *name == pick))
+            .unwrap_or_default();
+        self.themes.set_selected(position as u32);
+        self.updating.set(false);
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let theme = self.selected_theme();
+        *self.picked.borrow_mut() = theme.as_ref().map(|theme| theme.name.clone());
+        self.editor.set_sensitive(theme.is_some());
+        self.delete.set_sensitive(theme.is_some());
+        self.export.set_sensitive(theme.is_some());
+        let Some(theme) = theme else {
+            return;
+        };
+
+        self.updating.set(true);
+        self.name.set_text(&theme.name);
+        self.variant.set_selected(theme.is_light() as u32);
+        self.gtk_theme
+            .set_text(theme.gtk_theme.as_deref().unwrap_or_default());
+        self.active.set_active(
+            self.service.shell_config().theme.selected_theme
+                == ThemeSelection::Custom(theme.name.clone()),
+        );
+        for ((_, color), (_, button)) in theme.colors.named().iter().zip(self.colors.iter()) {
+            button.set_rgba(&color_to_rgba(color));
+        }
+        self.preview_provider.load_from_string(&preview_css(&theme));
+        self.updating.set(false);
+    }
+
+    fn write(&self, config: ShellConfig) {
+        self.service.set_shell_config(config);
+    }
+
+    /// Changes the picked theme. The shell restyles itself when it is the selected theme.
+    fn edit(&self, edit: impl FnOnce(&mut Theme)) {
+        if self.updating.get() {
+            return;
+        }
+        let Some(name) = self.selected_name() else {
+            return;
+        };
+        let mut config = self.service.shell_config();
+        let Some(theme) = config
+            .theme
+            .custom_themes
+            .iter_mut()
+            .find(|theme| theme.name == name)
+        else {
+            return;
+        };
+        edit(theme);
+        self.preview_provider.load_from_string(&preview_css(theme));
+        self.write(config);
+    }
+
+    fn rename(&self, new_name: &str) {
+        let new_name = new_name.trim();
+        let Some(old_name) = self.selected_name() else {
+            return;
+        };
+        let mut config = self.service.shell_config();
+        if new_name.is_empty()
+            || new_name == old_name
+            || config
+                .theme
+                .custom_themes
+                .iter()
+                .any(|theme| theme.name == new_name)
+        {
+            self.refresh();
+            return;
+        }
+        for theme in &mut config.theme.custom_themes {
+            if theme.name == old_name {
+                theme.name = new_name.to_string();
+            }
+        }
+        if config.theme.selected_theme == ThemeSelection::Custom(old_name) {
+            config.theme.selected_theme = ThemeSelection::Custom(new_name.to_string());
+        }
+        self.write(config);
+        self.reload(Some(new_name));
+    }
+
+    /// Adds a copy of the picked theme, or of the selected theme if no custom theme is picked.
+    fn duplicate(&self) {
+        let mut config = self.service.shell_config();
+        let Some(source) = self
+            .selected_theme()
+            .or_else(|| config.theme.selected_theme.theme())
+        else {
+            return;
+        };
+        let name = unique_name(
+            &config.theme.custom_themes,
+            &format!("{} copy", source.name),
+        );
+        config.theme.custom_themes.push(Theme {
+            name: name.clone(),
+            ..source
+        });
+        self.write(config);
+        self.reload(Some(&name));
+    }
+
+    fn delete(&self) {
+        let Some(name) = self.selected_name() else {
+            return;
+        };
+        let mut config = self.service.shell_config();
+        config
+            .theme
+            .custom_themes
+            .retain(|theme| theme.name != name);
+        if config.theme.selected_theme == ThemeSelection::Custom(name) {
+            config.theme.selected_theme = ThemeSelection::default();
+        }
+        self.write(config);
+        self.reload(None);
+    }
+
+    fn set_active(&self, active: bool) {
+        if self.updating.get() {
+            return;
+        }
+        let Some(name) = self.selected_name() else {
+            return;
+        };
+        let mut config = self.service.shell_config();
+        config.theme.selected_theme = if active {
+            ThemeSelection::Custom(name)
+        } else {
+            ThemeSelection::default()
+        };
+        self.write(config);
+    }
+
+    /// Adds themes, replacing custom themes with the same names, and picks the first one.
+    fn add_themes(&self, themes: Vec<Theme>) {
+        let Some(first) = themes.first().map(|theme| theme.name.clone()) else {
+            return;
+        };
+        let mut config = self.service.shell_config();
+        for theme in themes {
+            config.theme.save_custom_theme(theme);
+        }
+        self.write(config);
+        self.reload(Some(&first));
+    }
+
+    fn generate_from_wallpaper(&self) {
+        let config = self.service.shell_config();
+        let Some(image) = config.wallpaper.path.as_deref().and_then(wallpaper_image) else {
+            println!("There is no wallpaper to take a theme from");
+            return;
+        };
+        match Palette::load(&image) {
+            Ok(palette) => self.add_themes(vec![palette.theme("Wallpaper", None)]),
+            Err(err) => println!("Failed to take a theme from the wallpaper: {err}"),
+        }
+    }
+
+    fn import(&self) {
+        let filter = FileFilter::new();
+        filter.set_name(Some("Themes"));
+        for format in ThemeFileFormat::ALL {
+            filter.add_suffix(format.extension());
+        }
+        filter.add_suffix("yml");
+        let dialog = FileDialog::builder().default_filter(&filter).build();
+        let editor = self.clone();
+        dialog.open(None::<&Window>, Cancellable::NONE, move |response| {
+            let Some(path) = response.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match import_themes(&path) {
+                Ok(themes) => editor.add_themes(themes),
+                Err(err) => println!("Failed to import themes: {err}"),
+            }
+        });
+    }
+
+    fn export(&self) {
+        let Some(theme) = self.selected_theme() else {
+            return;
+        };
+        let dialog = FileDialog::builder()
+            .initial_name(format!("{}.toml", theme.name))
+            .build();
+        dialog.save(None::<&Window>, Cancellable::NONE, move |response| {
+            let Some(path) = response.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            // base16 and base24 share an extension, so YAML is always exported as base24, which keeps more colors.
+            let format = match ThemeFileFormat::from_path(&path) {
+                Some(ThemeFileFormat::Base16) => ThemeFileFormat::Base24,
+                Some(format) => format,
+                None => ThemeFileFormat::Ballad,
+            };
+            if let Err(err) = export_theme(&theme, &path, format) {
+                println!("Failed to export the theme: {err}");
+            }
+        });
+    }
+}
+
+fn color_grid(colors: &[(&'static str, ColorDialogButton)]) -> Grid {
+    let grid = Grid::builder()
+        .css_classes(["theme-colors"])
+        .column_spacing(12)
+        .row_spacing(6)
+        .build();
+    for (index, (name, button)) in colors.iter().enumerate() {
+        let index = index as i32;
+        let (row, column) = (index / COLOR_COLUMNS, index % COLOR_COLUMNS * 2);
+        grid.attach(
+            &Label::builder()
+                .label(color_label(name))
+                .halign(Align::Start)
+                .build(),
+            column,
+            row,
+            1,
+            1,
+        );
+        grid.attach(button, column + 1, row, 1, 1);
+    }
+    grid
+}
+
+fn preview() -> gtk::Box {
+    let preview = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("theme-preview")
+        .css_classes(["theme-preview"])
+        .spacing(6)
+        .build();
+    preview.append(
+        &Label::builder()
+            .label("The quick brown fox")
+            .halign(Align::Start)
+            .build(),
+    );
+    preview.append(
+        &Label::builder()
+            .label("jumps over the lazy dog")
+            .css_classes(["preview-subtext"])
+            .halign(Align::Start)
+            .build(),
+    );
+    for row in [
+        [
+            "bg_1",
+            "bg_2",
+            "surface_0",
+            "surface_1",
+            "surface_2",
+            "overlay_0",
+            "overlay_1",
+        ],
+        ["pink", "orange", "red", "yellow", "green", "blue", "purple"],
+    ] {
+        let chips = gtk::Box::builder().spacing(6).build();
+        for name in row {
+            chips.append(
+                &gtk::Box::builder()
+                    .css_classes(["preview-chip", &format!("preview-{name}")])
+                    .tooltip_text(color_label(name))
+                    .build(),
+            );
+        }
+        preview.append(&chips);
+    }
+    preview
+}
+
+pub fn theme_editor_page() -> gtk::Box {
+    let service = CONFIG_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let preview_provider = CssProvider::new();
+    style_context_add_provider_for_display(
+        &Display::default().unwrap(),
+        &preview_provider,
+        STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+
+    let theme_names = StringList::new(&[]);
+    let themes = DropDown::builder().model(&theme_names).build();
+    let duplicate = Button::builder()
+        .label("Duplicate")
+        .tooltip_text("Copy the theme, or the selected theme if there are no custom themes")
+        .build();
+    let delete = Button::builder().label("Delete").build();
+    let theme_controls = gtk::Box::builder().spacing(6).build();
+    theme_controls.append(&themes);
+    theme_controls.append(&duplicate);
+    theme_controls.append(&delete);
+
+    let from_wallpaper = Button::builder().label("From wallpaper").build();
+    let import = Button::builder().label("Import").build();
+    let export = Button::builder().label("Export").build();
+    let file_controls = gtk::Box::builder().spacing(6).build();
+    file_controls.append(&from_wallpaper);
+    file_controls.append(&import);
+    file_controls.append(&export);
+
+    let colors = ThemeSelection::default()
+        .theme()
+        .unwrap()
+        .colors
+        .named()
+        .map(|(name, _)| {
+            let dialog = ColorDialog::builder().with_alpha(false).build();
+            (name, ColorDialogButton::new(Some(dialog)))
+        });
+
+    let editor = ThemeEditor {
+        service: service.clone(),
+        themes,
+        theme_names,
+        editor: gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .build(),
+        name: Entry::new(),
+        variant: DropDown::from_strings(&["Dark", "Light"]),
+        gtk_theme: Entry::builder()
+            .placeholder_text("Default for the variant")
+            .build(),
+        active: Switch::new(),
+        colors: Rc::new(colors.into()),
+        preview_provider,
+        delete,
+        export,
+        updating: Rc::new(Cell::new(false)),
+        picked: Rc::default(),
+    };
+
+    editor.themes.connect_selected_notify(clone!(
+        #[strong]
+        editor,
+        move |_| {
+            if !editor.updating.get() {
+                editor.refresh();
+            }
+        }
+    ));
+    duplicate.connect_clicked(clone!(
+        #[strong]
+        editor,
+        move |_| editor.duplicate()
+    ));
+    editor.delete.connect_clicked(clone!(
+        #[strong]
+        editor,
+        move |_| editor.delete()
+    ));
+    from_wallpaper.connect_clicked(clone!(
+        #[strong]
+        editor,
+        move |_| editor.generate_from_wallpaper()
+    ));
+    import.connect_clicked(clone!(
+        #[strong]
+        editor,
+        move |_| editor.import()
+    ));
+    editor.export.connect_clicked(clone!(
+        #[strong]
+        editor,
+        move |_| editor.export()
+    ));
+    editor.name.connect_activate(clone!(
+        #[strong]
+        editor,
+        move |entry| editor.rename(&entry.text())
+    ));
+    editor.variant.connect_selected_notify(clone!(
+        #[strong]
+        editor,
+        move |dropdown| {
+            let variant = if dropdown.selected() == 1 {
+                ThemeVariant::Light
+            } else {
+                ThemeVariant::Dark
+            };
+            editor.edit(|theme| theme.variant = variant)
+        }
+    ));
+    editor.gtk_theme.connect_activate(clone!(
+        #[strong]
+        editor,
+        move |entry| {
+            let gtk_theme = Some(entry.text().trim().to_string()).filter(|name| !name.is_empty());
+            editor.edit(|theme| theme.gtk_theme = gtk_theme)
+        }
+    ));
+    editor.active.connect_active_notify(clone!(
+        #[strong]
+        editor,
+        move |switch| editor.set_active(switch.is_active())
+    ));
+    for (name, button) in editor.colors.iter() {
+        let name = *name;
+        button.connect_rgba_notify(clone!(
+            #[strong]
+            editor,
+            move |button| {
+                let color = rgba_to_color(&button.rgba());
+                editor.edit(|theme| {
+                    if let Some((_, value)) = theme
+                        .colors
+                        .named_mut()
+                        .into_iter()
+                        .find(|(color_name, _)| *color_name == name)
+                    {
+                        *value = color;
+                    }
+                })
+            }
+        ));
+    }
+
+    // Pick up themes added elsewhere, like by importing a file into the config by hand.
+    service.connect_closure(
+        "shell-theme-config-changed",
+        false,
+        closure_local!(
+            #[strong]
+            editor,
+            move |_: ConfigService, config: &ballad_config::ThemeConfig| {
+                let names = editor
+                    .theme_names
+                    .snapshot()
+                    .iter()
+                    .filter_map(|item| item.downcast_ref::<gtk::StringObject>().map(|s| s.string()))
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>();
+                let config_names = config
+                    .custom_themes
+                    .iter()
+                    .map(|theme| theme.name.clone())
+                    .collect::<Vec<_>>();
+                if names != config_names {
+                    editor.reload(None);
+                }
+            }
+        ),
+    );
+
+    for option in [
+        option(
+            "Name",
+            Some("Press Enter to rename the theme"),
+            &editor.name,
+        ),
+        option(
+            "Variant",
+            Some("Whether the theme is light or dark"),
+            &editor.variant,
+        ),
+        option(
+            "GTK theme",
+            Some("The GTK theme applications use with this theme. Press Enter to apply"),
+            &editor.gtk_theme,
+        ),
+        option(
+            "Use this theme",
+            Some("Apply the theme to the shell, which also previews every change"),
+            &editor.active,
+        ),
+        option("Preview", None, &preview()),
+    ] {
+        editor.editor.append(&option);
+    }
+    let colors = color_grid(&editor.colors);
+    colors.set_halign(Align::Start);
+    editor.editor.append(&colors);
+
+    let page = Page::builder()
+        .name("theme-editor-page")
+        .with_option(&option(
+            "Theme",
+            Some("The custom theme to edit"),
+            &theme_controls,
+        ))
+        .with_option(&option(
+            "Files",
+            Some("Take a theme from the wallpaper, or share themes as base16, base24, catppuccin or Ballad files"),
+            &file_controls,
+        ))
+        .build();
+    page.append(&editor.editor);
+    editor.reload(None);
+
+    let scrolled = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .vexpand(true)
+        .child(&page)
+        .build();
+    let container = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    container.append(&scrolled);
+    container
+}
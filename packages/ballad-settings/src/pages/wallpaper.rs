@@ -0,0 +1,305 @@
+use std::cell::{Cell, LazyCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use ballad_config::{
+    WallpaperConfig,
+    wallpaper::{MonitorWallpaper, WallpaperMode, WallpaperTransition},
+};
+use ballad_services::config::{CONFIG_SERVICE, ConfigService};
+use gtk::{
+    Adjustment, Button, DropDown, FileDialog, FileFilter, Label, SpinButton, Switch, Window,
+    gdk::{Display, Monitor},
+    gio::Cancellable,
+    glib::{self, clone},
+    prelude::*,
+};
+
+use super::{Page, option};
+
+/// The widgets of the page, which edit the wallpaper of every monitor or of the one picked in `monitor`.
+#[derive(Clone)]
+struct WallpaperPage {
+    service: ConfigService,
+    connectors: Rc<Vec<String>>,
+    monitor: DropDown,
+    enabled: Switch,
+    path: Label,
+    reset: Button,
+    mode: DropDown,
+    interval: SpinButton,
+    shuffle: Switch,
+    fade: Switch,
+    /// Set while the widgets are filled in from the config, so that doesn't write the config again.
+    updating: Rc<Cell<bool>>,
+}
+impl WallpaperPage {
+    /// The connector of the picked monitor, or `None` for every monitor.
+    fn connector(&self) -> Option<String> {
+        let index = self.monitor.selected() as usize;
+        index
+            .checked_sub(1)
+            .and_then(|index| self.connectors.get(index).cloned())
+    }
+
+    fn update(&self, edit: impl FnOnce(&mut WallpaperConfig, Option<&str>)) {
+        if self.updating.get() {
+            return;
+        }
+        let mut config = self.service.shell_config();
+        edit(&mut config.wallpaper, self.connector().as_deref());
+        self.service.set_shell_config(config);
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        self.updating.set(true);
+        let config = self.service.shell_config().wallpaper;
+        let connector = self.connector();
+        let monitor = connector.as_deref().and_then(|connector| {
+            config
+                .monitors
+                .iter()
+                .find(|monitor| monitor.connector == connector)
+        });
+
+        self.enabled.set_active(config.enabled);
+        let (path, mode) = match &connector {
+            Some(connector) => config.for_monitor(connector),
+            None => (config.path.clone(), config.mode),
+        };
+        self.path.set_label(
+            &path
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        );
+        self.reset.set_visible(monitor.is_some());
+        self.mode.set_selected(
+            WallpaperMode::ALL
+                .iter()
+                .position(|option| *option == mode)
+                .unwrap_or_default() as u32,
+        );
+        self.interval
+            .set_value((config.slideshow_interval / 60).max(1) as f64);
+        self.shuffle.set_active(config.shuffle);
+        self.fade
+            .set_active(config.transition == WallpaperTransition::Fade);
+        self.updating.set(false);
+    }
+
+    fn set_path(&self, path: PathBuf) {
+        self.update(|config, connector| match connector {
+            Some(connector) => monitor_override(config, connector).path = Some(path),
+            None => config.path = Some(path),
+        });
+    }
+
+    fn choose_path(&self, folder: bool) {
+        let filter = FileFilter::new();
+        filter.add_mime_type("image/*");
+        let dialog = FileDialog::builder().default_filter(&filter).build();
+        let page = self.clone();
+        let on_response = move |response: Result<gtk::gio::File, glib::Error>| {
+            if let Some(path) = response.ok().and_then(|file| file.path()) {
+                page.set_path(path);
+            }
+        };
+        if folder {
+            dialog.select_folder(None::<&Window>, Cancellable::NONE, on_response);
+        } else {
+            dialog.open(None::<&Window>, Cancellable::NONE, on_response);
+        }
+    }
+}
+
+/// The override for the monitor with `connector`, added if there is none yet.
+fn monitor_override<'a>(
+    config: &'a mut WallpaperConfig,
+    connector: &str,
+) -> &'a mut MonitorWallpaper {
+    let index = match config
+        .monitors
+        .iter()
+        .position(|monitor| monitor.connector == connector)
+    {
+        Some(index) => index,
+        None => {
+            config.monitors.push(MonitorWallpaper {
+                connector: connector.to_string(),
+                path: None,
+                mode: None,
+            });
+            config.monitors.len() - 1
+        }
+    };
+    &mut config.monitors[index]
+}
+
+fn connectors() -> Vec<String> {
+    Display::default()
+        .map(|display| {
+            display
+                .monitors()
+                .iter::<Monitor>()
+                .flatten()
+                .filter_map(|monitor| monitor.connector())
+                .map(|connector| connector.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn wallpaper_page() -> gtk::Box {
+    let connectors = connectors();
+    let mut monitor_names = vec!["All monitors"];
+    monitor_names.extend(connectors.iter().map(String::as_str));
+    let monitor = DropDown::from_strings(&monitor_names);
+
+    let path_controls = gtk::Box::builder().spacing(6).build();
+    let path = Label::builder()
+        .ellipsize(gtk::pango::EllipsizeMode::Start)
+        .max_width_chars(32)
+        .css_classes(["wallpaper-path"])
+        .build();
+    let choose_image = Button::builder().label("Image").build();
+    let choose_folder = Button::builder().label("Folder").build();
+    let reset = Button::builder()
+        .label("Reset")
+        .tooltip_text("Use the wallpaper of all monitors")
+        .build();
+    path_controls.append(&path);
+    path_controls.append(&choose_image);
+    path_controls.append(&choose_folder);
+    path_controls.append(&reset);
+
+    let page = WallpaperPage {
+        service: CONFIG_SERVICE.with(|service| LazyCell::force(service).clone()),
+        connectors: Rc::new(connectors),
+        monitor,
+        enabled: Switch::new(),
+        path,
+        reset: reset.clone(),
+        mode: DropDown::from_strings(
+            &WallpaperMode::ALL
+                .iter()
+                .map(WallpaperMode::name)
+                .collect::<Vec<_>>(),
+        ),
+        interval: SpinButton::builder()
+            .adjustment(&Adjustment::new(10.0, 1.0, 24.0 * 60.0, 1.0, 10.0, 0.0))
+            .digits(0)
+            .build(),
+        shuffle: Switch::new(),
+        fade: Switch::new(),
+        updating: Rc::new(Cell::new(false)),
+    };
+    page.refresh();
+
+    page.monitor.connect_selected_notify(clone!(
+        #[strong]
+        page,
+        move |_| page.refresh()
+    ));
+    page.enabled.connect_active_notify(clone!(
+        #[strong]
+        page,
+        move |switch| page.update(|config, _| config.enabled = switch.is_active())
+    ));
+    choose_image.connect_clicked(clone!(
+        #[strong]
+        page,
+        move |_| page.choose_path(false)
+    ));
+    choose_folder.connect_clicked(clone!(
+        #[strong]
+        page,
+        move |_| page.choose_path(true)
+    ));
+    reset.connect_clicked(clone!(
+        #[strong]
+        page,
+        move |_| {
+            page.update(|config, connector| {
+                config
+                    .monitors
+                    .retain(|monitor| Some(monitor.connector.as_str()) != connector)
+            })
+        }
+    ));
+    page.mode.connect_selected_notify(clone!(
+        #[strong]
+        page,
+        move |dropdown| {
+            let mode = WallpaperMode::ALL[dropdown.selected() as usize];
+            page.update(|config, connector| match connector {
+                Some(connector) => monitor_override(config, connector).mode = Some(mode),
+                None => config.mode = mode,
+            })
+        }
+    ));
+    page.interval.connect_value_changed(clone!(
+        #[strong]
+        page,
+        move |spin| {
+            page.update(|config, _| config.slideshow_interval = spin.value_as_int() as u32 * 60)
+        }
+    ));
+    page.shuffle.connect_active_notify(clone!(
+        #[strong]
+        page,
+        move |switch| page.update(|config, _| config.shuffle = switch.is_active())
+    ));
+    page.fade.connect_active_notify(clone!(
+        #[strong]
+        page,
+        move |switch| {
+            page.update(|config, _| {
+                config.transition = if switch.is_active() {
+                    WallpaperTransition::Fade
+                } else {
+                    WallpaperTransition::None
+                }
+            })
+        }
+    ));
+
+    Page::builder()
+        .name("wallpaper-page")
+        .with_option(&option(
+            "Monitor",
+            Some("Give a monitor its own wallpaper"),
+            &page.monitor,
+        ))
+        .with_option(&option(
+            "Wallpaper",
+            Some("Turn this off to use another program like swaybg"),
+            &page.enabled,
+        ))
+        .with_option(&option(
+            "Image",
+            Some("An image, or a folder of images to cycle through"),
+            &path_controls,
+        ))
+        .with_option(&option(
+            "Mode",
+            Some("How the image is fitted to the monitor"),
+            &page.mode,
+        ))
+        .with_option(&option(
+            "Slideshow interval",
+            Some("Minutes each image of a folder is shown"),
+            &page.interval,
+        ))
+        .with_option(&option(
+            "Shuffle",
+            Some("Show the images of a folder in a random order"),
+            &page.shuffle,
+        ))
+        .with_option(&option(
+            "Fade",
+            Some("Fade to the next image instead of switching at once"),
+            &page.fade,
+        ))
+        .build()
+}
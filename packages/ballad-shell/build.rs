@@ -28,6 +28,7 @@ fn bundle_scss(out_dir: &Path) {
             .canonicalize()
             .expect("Failed to canonicalize style file path.");
 
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
         let constant_name = path
             .file_stem()
             .unwrap()
@@ -36,19 +37,22 @@ fn bundle_scss(out_dir: &Path) {
             .replace("-", "_")
             .to_ascii_uppercase();
 
-        imports.push((constant_name, path.display().to_string()));
+        imports.push((constant_name, file_name, path.display().to_string()));
         imports
     });
 
+    // The file names are kept so errors can point at the file, and `--dev-styles` can read the same files from disk.
     let scss_list = format!(
-        "const SCSS_FILES: &[&str] = &[{}];",
+        "const SCSS_FILES: &[(&str, &str)] = &[{}];",
         style_constants
             .iter()
-            .fold(String::new(), |acc, (name, _)| acc + name + ", ")
+            .fold(String::new(), |acc, (name, file_name, _)| {
+                acc + &format!("(\"{file_name}\", {name}), ")
+            })
     );
     let includes = style_constants
         .iter()
-        .fold(String::new(), |acc, (name, path)| {
+        .fold(String::new(), |acc, (name, _, path)| {
             let include = format!("pub const {name}: &str = include_str!(\"{path}\");\n");
             acc + &include
         });
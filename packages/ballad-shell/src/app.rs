@@ -1,15 +1,24 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::OnceLock};
+use std::{
+    cell::{LazyCell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    sync::OnceLock,
+};
 
 use crate::widgets::{
     PerMonitorWidget,
     clock::clock_underlay,
+    lock::{lock_session, unlock_session},
+    logout_menu::LogoutMenu,
+    notifications::{center::NotificationCenter, popups::NotificationPopups},
     quick_settings::QuickSettings,
     sidebar::{screen_bevels::screen_bevels, sidebar},
+    wallpaper::wallpaper,
 };
 use gtk::{
     Application, ApplicationWindow, CssProvider, Window,
     gdk::{self, Display, Monitor},
-    glib::{ExitCode, closure_local},
+    glib::{ExitCode, clone, closure_local},
     prelude::*,
     style_context_add_provider_for_display, style_context_remove_provider_for_display,
 };
@@ -36,6 +45,7 @@ impl App {
                                     window.set_visible(!window.is_visible());
                                 }
                             }
+                            AppControl::Lock => lock_session(&this.app),
                         }
                     }
                 });
@@ -70,6 +80,7 @@ impl App {
 pub enum AppControl {
     Quit,
     ToggleWindow(String),
+    Lock,
 }
 
 thread_local! {
@@ -138,35 +149,144 @@ fn activate(app: &Application) {
         push_window_id(&screen_bevels);
         screen_bevels.present();
 
-        let clock_underlay = clock_underlay(properties);
+        let clock_underlay = clock_underlay(properties.clone());
         push_window_id(&clock_underlay);
         clock_underlay.present();
+
+        // The wallpaper is only shown when one is configured, so swaybg and the like keep working.
+        let wallpaper = wallpaper(properties);
+        push_window_id(&wallpaper);
     });
 
     let quick_settings = QuickSettings::builder().application(app).build();
     push_window_id(&quick_settings);
     quick_settings.present();
     quick_settings.set_visible(false);
+
+    let notification_center = NotificationCenter::builder().application(app).build();
+    push_window_id(&notification_center);
+    notification_center.present();
+    notification_center.set_visible(false);
+
+    let logout_menu = LogoutMenu::builder().application(app).build();
+    push_window_id(&logout_menu);
+    logout_menu.present();
+    logout_menu.set_visible(false);
+
+    // Popups show and hide themselves as notifications arrive and expire.
+    let notification_popups = NotificationPopups::builder().application(app).build();
+    push_window_id(&notification_popups);
 }
 
-fn startup(_app: &Application) {
+fn startup(app: &Application) {
     watch_theme_config();
+    // logind asks us to lock for `loginctl lock-session`, the power menu, and before sleeping if configured.
+    ballad_services::logind::LOGIND_SERVICE.with(|service| {
+        service.connect_closure(
+            "lock",
+            false,
+            closure_local!(
+                #[weak]
+                app,
+                move |_: ballad_services::logind::LogindService| lock_session(&app)
+            ),
+        );
+        service.connect_closure(
+            "unlock",
+            false,
+            closure_local!(move |_: ballad_services::logind::LogindService| unlock_session()),
+        );
+    });
+    // The battery policy, idle timeouts, theme outputs and theme switching work in the background, so nothing else
+    // would create them.
+    ballad_services::battery_policy::BATTERY_POLICY_SERVICE.with(|service| {
+        LazyCell::force(service);
+    });
+    ballad_services::idle::IDLE_SERVICE.with(|service| {
+        LazyCell::force(service);
+    });
+    ballad_services::theme_outputs::THEME_OUTPUTS_SERVICE.with(|service| {
+        LazyCell::force(service);
+    });
+    ballad_services::theme_switch::THEME_SWITCH_SERVICE.with(|service| {
+        LazyCell::force(service);
+    });
+    ballad_services::power_profiles::POWER_PROFILES_SERVICE
+        .with(|service| service.watch_hold_rules());
+}
+
+/// Compiles the styles with `config` and replaces the CSS provider with them. If they fail to compile, the old styles
+/// are kept.
+fn reload_styles(provider: &RefCell<CssProvider>, config: &ballad_config::ThemeConfig) {
+    let Some(new_css) = crate::style::compile_scss_for_config(config) else {
+        return;
+    };
+    let new_provider = CssProvider::new();
+    new_provider.load_from_string(&new_css);
+
+    style_context_remove_provider_for_display(&Display::default().unwrap(), &*provider.borrow());
+    style_context_add_provider_for_display(
+        &Display::default().unwrap(),
+        &new_provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+
+    provider.replace(new_provider);
+}
+
+thread_local! {
+    /// Watches the user stylesheet, and the styles directory with `--dev-styles`. Dropping a monitor stops it.
+    static STYLE_MONITORS: RefCell<Vec<gio::FileMonitor>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Recompiles the styles when the user stylesheet or, with `--dev-styles`, the styles directory changes.
+fn watch_style_files(provider: &Rc<RefCell<CssProvider>>) {
+    let user_style = gio::File::for_path(ballad_config::user_style_path())
+        .monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE);
+    let dev_styles = crate::style::DEV_STYLES_DIR.get().map(|dir| {
+        gio::File::for_path(dir)
+            .monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+    });
+
+    for monitor in std::iter::once(user_style).chain(dev_styles) {
+        let monitor = match monitor {
+            Ok(monitor) => monitor,
+            Err(err) => {
+                println!("Failed to watch the styles for changes: {err}");
+                continue;
+            }
+        };
+        monitor.connect_changed(clone!(
+            #[strong]
+            provider,
+            move |_, _, _, event| {
+                if matches!(
+                    event,
+                    gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Deleted
+                ) {
+                    let config = ballad_services::config::CONFIG_SERVICE
+                        .with(|service| service.shell_config());
+                    reload_styles(&provider, &config.theme);
+                }
+            }
+        ));
+        STYLE_MONITORS.with(|monitors| monitors.borrow_mut().push(monitor));
+    }
 }
 
 fn watch_theme_config() {
-    // The CSS provider is replaced when the theme changes.
+    // The CSS provider is replaced when the theme or the styles change.
     let provider = Rc::new(RefCell::new(CssProvider::new()));
-
-    let config = ballad_config::get_or_init_shell_config().unwrap_or_default();
-    let initial_css = crate::style::compile_scss_for_config(&config.theme).unwrap();
-    // Load the initial CSS.
-    provider.borrow().load_from_string(&initial_css);
     style_context_add_provider_for_display(
         &Display::default().unwrap(),
         &*provider.borrow(),
         gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
 
+    let config = ballad_config::get_or_init_shell_config().unwrap_or_default();
+    reload_styles(&provider, &config.theme);
+    watch_style_files(&provider);
+
     let theme_settings = gio::Settings::new("org.gnome.desktop.interface");
 
     ballad_services::config::CONFIG_SERVICE.with(|config_service| {
@@ -178,22 +298,7 @@ fn watch_theme_config() {
                 provider,
                 move |_: ballad_services::config::ConfigService,
                       config: &ballad_config::ThemeConfig| {
-                    if let Some(new_css) = crate::style::compile_scss_for_config(config) {
-                        let new_provider = CssProvider::new();
-                        new_provider.load_from_string(&new_css);
-
-                        style_context_remove_provider_for_display(
-                            &Display::default().unwrap(),
-                            &*provider.borrow(),
-                        );
-                        style_context_add_provider_for_display(
-                            &Display::default().unwrap(),
-                            &new_provider,
-                            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-                        );
-
-                        provider.replace(new_provider);
-                    }
+                    reload_styles(&provider, config);
                     // Set the system GTK theme based on our theme config.
                     let color = if config.selected_theme.is_dark().unwrap_or(true) {
                         "prefer-dark"
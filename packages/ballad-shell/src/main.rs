@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use app::{APP_CONTROL_SENDER, AppControl};
 use clap::Parser;
 use smol::block_on;
@@ -13,6 +15,10 @@ mod widgets;
 pub struct Args {
     #[command(subcommand)]
     command: Option<Command>,
+    /// Read the styles from a directory instead of the ones built in, and recompile them when they change. Defaults
+    /// to the styles in the source tree.
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = style::SOURCE_STYLES_DIR)]
+    dev_styles: Option<PathBuf>,
     #[arg(last = true)]
     gtk_args: Vec<String>,
 }
@@ -25,6 +31,9 @@ pub enum Command {
     Quit,
     #[command(visible_aliases = ["toggle", "t"])]
     ToggleWindow { window: String },
+    /// Lock the session.
+    #[command(visible_alias = "l")]
+    Lock,
 }
 
 pub struct BalladShell;
@@ -46,6 +55,11 @@ impl BalladShell {
             let _ = sender.try_send(AppControl::ToggleWindow(title.to_string()));
         }
     }
+    fn lock(&self) {
+        if let Some(sender) = APP_CONTROL_SENDER.get() {
+            let _ = sender.try_send(AppControl::Lock);
+        }
+    }
 }
 
 fn main() -> Result<(), Error> {
@@ -54,6 +68,9 @@ fn main() -> Result<(), Error> {
     block_on(smol::LocalExecutor::new().run(async {
         match command {
             Command::Run => {
+                if let Some(dir) = args.dev_styles {
+                    _ = style::DEV_STYLES_DIR.set(dir);
+                }
                 let _connection = zbus::connection::Builder::session()?
                     .name("com.gavinniederman.BalladShell")?
                     .serve_at("/com/gavinniederman/BalladShell", BalladShell)?
@@ -79,6 +96,7 @@ async fn send_app_command(command: Command) -> Result<(), Error> {
     let res = match command {
         Command::Quit => proxy.quit().await,
         Command::ToggleWindow { window } => proxy.toggle_window(&window).await,
+        Command::Lock => proxy.lock().await,
         _ => unreachable!(),
     };
     if res == Err(zbus::Error::InterfaceNotFound) {
@@ -1,21 +1,182 @@
-use ballad_config::ThemeConfig;
+use std::{path::PathBuf, sync::OnceLock};
+
+use ballad_config::{ThemeConfig, ThemeSelection, user_style_path};
+use snafu::Snafu;
 
 include!(concat!(env!("OUT_DIR"), "/style_files.rs"));
 
-fn scss_for_config(config: &ThemeConfig) -> Option<String> {
-    let flavor_scss = config.as_scss()?;
+/// The styles in the source tree, which `--dev-styles` reads by default.
+pub const SOURCE_STYLES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/styles");
+
+/// Set by `--dev-styles` to read the styles from this directory instead of the ones built into the binary, and
+/// recompile them when they change.
+pub static DEV_STYLES_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The name of the section holding the theme variables, which comes before the styles.
+const THEME_SECTION: &str = "theme";
+
+#[derive(Debug, Snafu)]
+pub enum StyleError {
+    #[snafu(display("{file}:{line}:{column}: {message}\n    {code}"))]
+    Compile {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+        /// The line the error is on.
+        code: String,
+    },
+    #[snafu(display("{message}"))]
+    Other { message: String },
+}
+impl StyleError {
+    fn file(&self) -> Option<&str> {
+        match self {
+            Self::Compile { file, .. } => Some(file),
+            Self::Other { .. } => None,
+        }
+    }
+}
+
+/// A file that is compiled into the shell styles.
+struct Section {
+    /// Where the SCSS came from, so errors can point at it.
+    file: String,
+    scss: String,
+}
+
+fn theme_section(config: &ThemeConfig) -> Section {
+    Section {
+        file: THEME_SECTION.to_string(),
+        scss: format!("@use \"sass:math\";\n{}", config.as_scss()),
+    }
+}
+
+/// The shell styles, from [`DEV_STYLES_DIR`] if it is set.
+fn style_sections() -> Vec<Section> {
+    let Some(dir) = DEV_STYLES_DIR.get() else {
+        return SCSS_FILES
+            .iter()
+            .map(|(file, scss)| Section {
+                file: file.to_string(),
+                scss: scss.to_string(),
+            })
+            .collect();
+    };
 
-    let real_styles = SCSS_FILES
+    // Keep the order of the built-in styles, and add files that were created since the shell was built.
+    let mut files = SCSS_FILES
         .iter()
-        .fold(String::new(), |acc, &scss| acc + &format!("{}\n", scss));
+        .map(|(file, _)| file.to_string())
+        .collect::<Vec<_>>();
+    let mut new_files = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|file| file.ends_with(".scss") && !files.contains(file))
+        .collect::<Vec<_>>();
+    new_files.sort();
+    files.extend(new_files);
 
-    Some(format!(
-        "@use \"sass:math\";\n{}\n{}",
-        flavor_scss, real_styles
-    ))
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let path = dir.join(&file);
+            match std::fs::read_to_string(&path) {
+                Ok(scss) => Some(Section {
+                    file: path.display().to_string(),
+                    scss,
+                }),
+                // A file that was removed from the source tree is left out.
+                Err(err) => {
+                    println!("Failed to read {}: {err}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
 }
 
-pub fn compile_scss_for_config(flavor: &ThemeConfig) -> Option<String> {
-    let scss = scss_for_config(flavor)?;
-    Some(grass::from_string(scss, &grass::Options::default()).unwrap())
+fn user_section() -> Option<Section> {
+    let path = user_style_path();
+    match std::fs::read_to_string(&path) {
+        Ok(scss) => Some(Section {
+            file: path.display().to_string(),
+            scss,
+        }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            println!("Failed to read {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Compiles the sections as one stylesheet, so they share variables and mixins.
+fn compile(sections: &[Section]) -> Result<String, StyleError> {
+    let mut scss = String::new();
+    // The line each section starts on, to find the file an error is in.
+    let mut starts = Vec::with_capacity(sections.len());
+    for section in sections {
+        starts.push(scss.matches('\n').count());
+        scss.push_str(&section.scss);
+        scss.push('\n');
+    }
+
+    grass::from_string(scss, &grass::Options::default()).map_err(|err| {
+        let message = err.to_string();
+        match err.kind() {
+            grass::ErrorKind::ParseError { message, loc, .. } => {
+                let index = starts
+                    .iter()
+                    .rposition(|start| *start <= loc.begin.line)
+                    .unwrap_or_default();
+                StyleError::Compile {
+                    file: sections[index].file.clone(),
+                    line: loc.begin.line - starts[index] + 1,
+                    column: loc.begin.column + 1,
+                    message,
+                    code: loc.file.source_line(loc.begin.line).trim().to_string(),
+                }
+            }
+            _ => StyleError::Other { message },
+        }
+    })
+}
+
+/// Compiles the shell styles with the theme, followed by the user stylesheet.
+///
+/// If they fail to compile, the error is printed and the part that broke them is left out: the user stylesheet, or the
+/// theme, which is replaced by the default one.
+pub fn compile_scss_for_config(config: &ThemeConfig) -> Option<String> {
+    let mut sections = vec![theme_section(config)];
+    sections.extend(style_sections());
+    sections.extend(user_section());
+
+    let mut default_theme = config.selected_theme == ThemeSelection::default();
+    loop {
+        let err = match compile(&sections) {
+            Ok(css) => return Some(css),
+            Err(err) => err,
+        };
+        println!("Failed to compile the shell styles: {err}");
+
+        let user_style = user_style_path().display().to_string();
+        match err.file() {
+            Some(file) if file == user_style => {
+                println!("Leaving out {user_style}");
+                sections.retain(|section| section.file != user_style);
+            }
+            Some(THEME_SECTION) if !default_theme => {
+                println!("Falling back to the default theme");
+                sections[0] = theme_section(&ThemeConfig {
+                    selected_theme: ThemeSelection::default(),
+                    ..config.clone()
+                });
+                default_theme = true;
+            }
+            _ => return None,
+        }
+    }
 }
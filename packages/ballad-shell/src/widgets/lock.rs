@@ -0,0 +1,275 @@
+use std::cell::{LazyCell, RefCell};
+
+use ballad_services::accounts::{ACCOUNTS_SERVICE, User};
+use ballad_services::logind::LOGIND_SERVICE;
+use gtk::{
+    Align, Application, ApplicationWindow, Image, Label, Orientation, Overflow, PasswordEntry,
+    gdk::{self, Monitor},
+    gio,
+    glib::{self, SignalHandlerId, clone},
+    prelude::*,
+};
+use gtk4_session_lock::Instance;
+
+use super::clock::{date, time};
+use super::quick_settings::info::user_icon;
+
+/// The PAM service used to check the password, configured in `/etc/pam.d/ballad-lock`.
+pub const PAM_SERVICE: &str = "ballad-lock";
+pub const LOCK_SCREEN_WINDOW_TITLE: &str = "lock-screen";
+
+struct SessionLock {
+    instance: Instance,
+    /// Looked up once when locking, before the lock surfaces are added.
+    user: Option<User>,
+    windows: Vec<ApplicationWindow>,
+    /// Adds lock surfaces to monitors plugged in while the session is locked.
+    monitors_handler: Option<SignalHandlerId>,
+}
+
+thread_local! {
+    static SESSION_LOCK: RefCell<Option<SessionLock>> = const { RefCell::new(None) };
+}
+
+pub fn is_locked() -> bool {
+    SESSION_LOCK.with(|lock| lock.borrow().is_some())
+}
+
+fn user_name(user: Option<&User>) -> String {
+    user.map(|user| user.user_name())
+        .filter(|name| !name.is_empty())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+/// Checks `password` with PAM. This blocks, so it should run off the main thread.
+fn authenticate(user_name: &str, password: &str) -> Result<(), pam::PamError> {
+    let mut authenticator = pam::Authenticator::with_password(PAM_SERVICE)?;
+    authenticator
+        .get_handler()
+        .set_credentials(user_name, password);
+    authenticator.authenticate()
+}
+
+fn set_locked_hint(locked: bool) {
+    glib::spawn_future_local(async move {
+        let service = LOGIND_SERVICE.with(|service| LazyCell::force(service).clone());
+        if let Err(err) = service.set_locked_hint(locked).await {
+            println!("Failed to set the locked hint: {err}");
+        }
+    });
+}
+
+/// Locks the session with a lock surface on every monitor. Does nothing if it is already locked.
+pub fn lock_session(application: &Application) {
+    if is_locked() {
+        return;
+    }
+    if !gtk4_session_lock::is_supported() {
+        println!(
+            "The compositor doesn't support ext-session-lock-v1. The session will not be locked!"
+        );
+        return;
+    }
+
+    let instance = Instance::new();
+    instance.connect_failed(|_| {
+        println!("Failed to lock the session. Is another screen locker running?");
+        release_session_lock();
+    });
+    instance.connect_locked(|_| set_locked_hint(true));
+    if !instance.lock() {
+        return;
+    }
+    SESSION_LOCK.with(|lock| {
+        lock.replace(Some(SessionLock {
+            instance: instance.clone(),
+            user: None,
+            windows: Vec::new(),
+            monitors_handler: None,
+        }))
+    });
+
+    // The compositor keeps the screen blank until the lock surfaces showing the user are added.
+    glib::spawn_future_local(clone!(
+        #[weak]
+        application,
+        async move {
+            let service = ACCOUNTS_SERVICE.with(|service| LazyCell::force(service).clone());
+            let user = service.current_user().await;
+
+            let monitors = gdk::Display::default().unwrap().monitors();
+            let added = SESSION_LOCK.with(|lock| {
+                let mut lock = lock.borrow_mut();
+                // The session may have been unlocked, or locked again, while looking up the user.
+                let Some(lock) = lock.as_mut().filter(|lock| lock.instance == instance) else {
+                    return false;
+                };
+                lock.user = user;
+                lock.monitors_handler = Some(monitors.connect_items_changed(clone!(
+                    #[weak]
+                    application,
+                    move |monitors, position, _, added| {
+                        for index in position..position + added {
+                            if let Some(monitor) = monitors.item(index).and_downcast::<Monitor>() {
+                                add_lock_surface(&application, &monitor);
+                            }
+                        }
+                    }
+                )));
+                true
+            });
+            if added {
+                for monitor in monitors.iter::<Monitor>().flatten() {
+                    add_lock_surface(&application, &monitor);
+                }
+            }
+        }
+    ));
+}
+
+fn add_lock_surface(application: &Application, monitor: &Monitor) {
+    SESSION_LOCK.with(|lock| {
+        let mut lock = lock.borrow_mut();
+        let Some(lock) = lock.as_mut() else {
+            return;
+        };
+        let window = lock_surface(application, lock.user.clone());
+        lock.instance.assign_window_to_monitor(&window, monitor);
+        window.present();
+        lock.windows.push(window);
+    });
+}
+
+/// Unlocks the session and destroys the lock surfaces.
+pub fn unlock_session() {
+    let instance =
+        SESSION_LOCK.with(|lock| lock.borrow().as_ref().map(|lock| lock.instance.clone()));
+    let Some(instance) = instance else {
+        return;
+    };
+    instance.unlock();
+    release_session_lock();
+    set_locked_hint(false);
+}
+
+fn release_session_lock() {
+    let Some(lock) = SESSION_LOCK.with(|lock| lock.take()) else {
+        return;
+    };
+    if let Some(handler) = lock.monitors_handler {
+        gdk::Display::default()
+            .unwrap()
+            .monitors()
+            .disconnect(handler);
+    }
+    for window in lock.windows {
+        window.destroy();
+    }
+}
+
+fn lock_surface(application: &Application, user: Option<User>) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(application)
+        .title(LOCK_SCREEN_WINDOW_TITLE)
+        .css_classes(["lock-screen"])
+        .build();
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["lock-screen-content"])
+        .halign(Align::Center)
+        .valign(Align::Center)
+        .build();
+
+    let clock = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["lock-screen-clock"])
+        .halign(Align::Center)
+        .build();
+    let date = date();
+    date.set_halign(Align::Center);
+    clock.append(&time());
+    clock.append(&date);
+
+    let icon = gtk::Box::builder()
+        .css_classes(["user-icon"])
+        .overflow(Overflow::Hidden)
+        .halign(Align::Center)
+        .build();
+    match user.clone() {
+        Some(user) => icon.append(&user_icon(96, user)),
+        None => icon.append(
+            &Image::builder()
+                .icon_name("avatar-default-symbolic")
+                .pixel_size(96)
+                .build(),
+        ),
+    }
+
+    let name = Label::builder()
+        .label(
+            user.as_ref()
+                .and_then(|user| user.real_name())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| user_name(user.as_ref())),
+        )
+        .css_classes(["lock-screen-name"])
+        .build();
+
+    let password = PasswordEntry::builder()
+        .placeholder_text("Password")
+        .show_peek_icon(true)
+        .css_classes(["lock-screen-password"])
+        .build();
+    let error = Label::builder()
+        .css_classes(["lock-screen-error"])
+        .visible(false)
+        .build();
+
+    password.connect_activate(clone!(
+        #[weak]
+        error,
+        move |password| {
+            let user_name = user_name(user.as_ref());
+            let secret = password.text().to_string();
+            password.set_sensitive(false);
+            error.set_visible(false);
+
+            glib::spawn_future_local(clone!(
+                #[weak]
+                password,
+                #[weak]
+                error,
+                async move {
+                    let result =
+                        gio::spawn_blocking(move || authenticate(&user_name, &secret)).await;
+                    match result {
+                        Ok(Ok(())) => unlock_session(),
+                        Ok(Err(err)) => {
+                            println!("Authentication failed: {err}");
+                            error.set_label("Wrong password");
+                            error.set_visible(true);
+                        }
+                        Err(_) => {
+                            error.set_label("Failed to check the password");
+                            error.set_visible(true);
+                        }
+                    }
+                    password.set_text("");
+                    password.set_sensitive(true);
+                    password.grab_focus();
+                }
+            ));
+        }
+    ));
+
+    content.append(&clock);
+    content.append(&icon);
+    content.append(&name);
+    content.append(&password);
+    content.append(&error);
+    window.set_child(Some(&content));
+
+    window
+}
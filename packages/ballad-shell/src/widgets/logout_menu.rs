@@ -0,0 +1,458 @@
+use std::cell::{Cell, LazyCell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use ballad_services::logind::{LOGIND_SERVICE, LogindService, PowerAction};
+use gtk::{
+    Align, ApplicationWindow, Box, Button, EventControllerKey, Label, Orientation, Stack,
+    gdk::Key,
+    glib::{self, SourceId, clone},
+    prelude::*,
+};
+use gtk4_layer_shell::{KeyboardMode, LayerShell};
+use typed_builder::TypedBuilder;
+
+use super::icon::symbolic_icon;
+use crate::widgets::window::{Layer, LayershellWindow};
+
+pub const LOGOUT_MENU_WINDOW_TITLE: &str = "logout-menu";
+
+/// How long the confirmation waits before performing the action on its own.
+const COUNTDOWN_SECONDS: u32 = 30;
+/// How far in the future "Later" schedules a restart or shutdown.
+const LATER: Duration = Duration::from_secs(10 * 60);
+
+fn action_icon(action: PowerAction) -> &'static str {
+    match action {
+        PowerAction::Lock => "lock-symbolic",
+        PowerAction::Logout => "logout-symbolic",
+        PowerAction::Suspend | PowerAction::HybridSleep => "suspend-symbolic",
+        PowerAction::Hibernate => "hibernate-symbolic",
+        PowerAction::Reboot => "restart-symbolic",
+        PowerAction::PowerOff => "power-symbolic",
+    }
+}
+
+fn format_remaining(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{seconds} s"),
+        (minutes, seconds) => format!("{minutes} min {seconds} s"),
+    }
+}
+
+fn perform(service: &LogindService, action: PowerAction) {
+    glib::spawn_future_local(clone!(
+        #[strong]
+        service,
+        async move {
+            if let Err(err) = service.perform(action).await {
+                println!("Failed to {}: {err}", action.name().to_lowercase());
+            }
+        }
+    ));
+}
+
+#[derive(Debug, Clone, TypedBuilder, PartialEq, Eq)]
+#[builder(build_method(into = ApplicationWindow))]
+pub struct LogoutMenu<'a> {
+    pub application: &'a gtk::Application,
+}
+impl From<LogoutMenu<'_>> for ApplicationWindow {
+    fn from(props: LogoutMenu) -> Self {
+        logout_menu(props)
+    }
+}
+
+/// The state of the confirmation page.
+#[derive(Default)]
+struct Confirmation {
+    action: Cell<Option<PowerAction>>,
+    remaining: Cell<u32>,
+    countdown: RefCell<Option<SourceId>>,
+}
+impl Confirmation {
+    fn stop(&self) {
+        self.action.set(None);
+        if let Some(countdown) = self.countdown.take() {
+            countdown.remove();
+        }
+    }
+}
+
+pub fn logout_menu(LogoutMenu { application }: LogoutMenu) -> ApplicationWindow {
+    let service = LOGIND_SERVICE.with(|service| LazyCell::force(service).clone());
+    let confirmation = Rc::new(Confirmation::default());
+
+    let window: ApplicationWindow = LayershellWindow::builder()
+        .layer(Layer::Overlay)
+        .application(application)
+        .title(LOGOUT_MENU_WINDOW_TITLE)
+        .build();
+    window.set_keyboard_mode(KeyboardMode::Exclusive);
+
+    let stack = Stack::builder()
+        .transition_type(gtk::StackTransitionType::Crossfade)
+        .halign(Align::Center)
+        .valign(Align::Center)
+        .build();
+
+    // The options page.
+    let options = Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["logout-menu-options"])
+        .build();
+    let buttons = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["logout-menu-buttons"])
+        .halign(Align::Center)
+        .build();
+    let scheduled = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["logout-menu-scheduled"])
+        .halign(Align::Center)
+        .build();
+    let scheduled_label = Label::new(None);
+    let cancel_scheduled = Button::builder()
+        .label("Cancel")
+        .css_classes(["logout-menu-cancel"])
+        .build();
+    cancel_scheduled.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| {
+            glib::spawn_future_local(async move {
+                if let Err(err) = service.cancel_scheduled().await {
+                    println!("Failed to cancel the scheduled shutdown: {err}");
+                }
+            });
+        }
+    ));
+    scheduled.append(&scheduled_label);
+    scheduled.append(&cancel_scheduled);
+    options.append(&buttons);
+    options.append(&scheduled);
+
+    // The confirmation page.
+    let confirm = Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["logout-menu-confirm"])
+        .build();
+    let confirm_title = Label::builder()
+        .css_classes(["logout-menu-confirm-title"])
+        .build();
+    let inhibitors = Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["logout-menu-inhibitors"])
+        .build();
+    let confirm_buttons = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["logout-menu-confirm-buttons"])
+        .halign(Align::Center)
+        .build();
+    let now_button = Button::builder()
+        .css_classes(["logout-menu-option", "logout-menu-now"])
+        .build();
+    let later_button = Button::builder()
+        .label("In 10 minutes")
+        .css_classes(["logout-menu-option"])
+        .build();
+    let cancel_button = Button::builder()
+        .label("Cancel")
+        .css_classes(["logout-menu-option"])
+        .build();
+    confirm_buttons.append(&cancel_button);
+    confirm_buttons.append(&later_button);
+    confirm_buttons.append(&now_button);
+    confirm.append(&confirm_title);
+    confirm.append(&inhibitors);
+    confirm.append(&confirm_buttons);
+
+    stack.add_named(&options, Some("options"));
+    stack.add_named(&confirm, Some("confirm"));
+
+    let update_countdown = clone!(
+        #[weak]
+        confirm_title,
+        #[weak]
+        now_button,
+        #[strong]
+        confirmation,
+        move || {
+            let Some(action) = confirmation.action.get() else {
+                return;
+            };
+            confirm_title.set_label(&format!(
+                "{} in {} seconds",
+                action.name(),
+                confirmation.remaining.get()
+            ));
+            now_button.set_label(&format!("{} now", action.name()));
+        }
+    );
+
+    let back_to_options = clone!(
+        #[weak]
+        stack,
+        #[strong]
+        confirmation,
+        move || {
+            confirmation.stop();
+            stack.set_visible_child_name("options");
+        }
+    );
+
+    let finish = clone!(
+        #[weak]
+        window,
+        #[weak]
+        service,
+        #[strong]
+        confirmation,
+        #[strong]
+        back_to_options,
+        move || {
+            let action = confirmation.action.get();
+            back_to_options();
+            window.set_visible(false);
+            if let Some(action) = action {
+                perform(&service, action);
+            }
+        }
+    );
+
+    let confirm_action = clone!(
+        #[weak]
+        stack,
+        #[weak]
+        service,
+        #[weak]
+        inhibitors,
+        #[weak]
+        later_button,
+        #[strong]
+        confirmation,
+        #[strong]
+        update_countdown,
+        #[strong]
+        finish,
+        move |action: PowerAction| {
+            confirmation.stop();
+            confirmation.action.set(Some(action));
+            confirmation.remaining.set(COUNTDOWN_SECONDS);
+            update_countdown();
+            later_button.set_visible(matches!(
+                action,
+                PowerAction::Reboot | PowerAction::PowerOff
+            ));
+
+            while let Some(child) = inhibitors.first_child() {
+                inhibitors.remove(&child);
+            }
+            glib::spawn_future_local(clone!(
+                #[weak]
+                inhibitors,
+                async move {
+                    let Ok(found) = service.inhibitors_for(action).await else {
+                        return;
+                    };
+                    for inhibitor in found {
+                        let verb = if inhibitor.blocks() {
+                            "Blocked"
+                        } else {
+                            "Delayed"
+                        };
+                        inhibitors.append(
+                            &Label::builder()
+                                .label(format!("{verb} by {}: {}", inhibitor.who, inhibitor.why))
+                                .css_classes(["logout-menu-inhibitor"])
+                                .wrap(true)
+                                .build(),
+                        );
+                    }
+                }
+            ));
+
+            let countdown = glib::timeout_add_seconds_local(
+                1,
+                clone!(
+                    #[strong]
+                    confirmation,
+                    #[strong]
+                    update_countdown,
+                    #[strong]
+                    finish,
+                    move || {
+                        let remaining = confirmation.remaining.get().saturating_sub(1);
+                        confirmation.remaining.set(remaining);
+                        if remaining == 0 {
+                            // The source is removed by returning `Break`, so it must not be removed again.
+                            confirmation.countdown.take();
+                            finish();
+                            return glib::ControlFlow::Break;
+                        }
+                        update_countdown();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+            confirmation.countdown.replace(Some(countdown));
+            stack.set_visible_child_name("confirm");
+        }
+    );
+
+    now_button.connect_clicked(clone!(
+        #[strong]
+        finish,
+        move |_| finish()
+    ));
+    cancel_button.connect_clicked(clone!(
+        #[strong]
+        back_to_options,
+        move |_| back_to_options()
+    ));
+    later_button.connect_clicked(clone!(
+        #[weak]
+        service,
+        #[strong]
+        confirmation,
+        #[strong]
+        back_to_options,
+        move |_| {
+            let Some(action) = confirmation.action.get() else {
+                return;
+            };
+            back_to_options();
+            glib::spawn_future_local(async move {
+                if let Err(err) = service.schedule(action, LATER).await {
+                    println!("Failed to schedule {}: {err}", action.name().to_lowercase());
+                }
+            });
+        }
+    ));
+
+    for action in PowerAction::ALL {
+        let button = Button::builder()
+            .css_classes(["logout-menu-option"])
+            .tooltip_text(action.name())
+            .build();
+        let content = Box::builder().orientation(Orientation::Vertical).build();
+        content.append(&symbolic_icon(action_icon(action), 48));
+        content.append(&Label::new(Some(action.name())));
+        button.set_child(Some(&content));
+        button.connect_clicked(clone!(
+            #[weak]
+            window,
+            #[weak]
+            service,
+            #[strong]
+            confirm_action,
+            move |_| {
+                // Locking is harmless and easy to undo, so it doesn't ask.
+                if action == PowerAction::Lock {
+                    window.set_visible(false);
+                    perform(&service, action);
+                } else {
+                    confirm_action(action);
+                }
+            }
+        ));
+        buttons.append(&button);
+
+        let update_visible = clone!(
+            #[weak]
+            button,
+            move |service: &LogindService| button.set_visible(service.can(action))
+        );
+        update_visible(&service);
+        service.connect_notify_local(None, move |service, _| update_visible(service));
+    }
+
+    let update_scheduled = clone!(
+        #[weak]
+        scheduled,
+        #[weak]
+        scheduled_label,
+        move |service: &LogindService| {
+            let kind = service.scheduled_shutdown_kind();
+            let action = match kind.as_str() {
+                "reboot" => PowerAction::Reboot.name(),
+                _ => PowerAction::PowerOff.name(),
+            };
+            match service.scheduled_shutdown_in() {
+                Some(remaining) if !kind.is_empty() => {
+                    scheduled_label.set_label(&format!(
+                        "{action} scheduled in {}",
+                        format_remaining(remaining)
+                    ));
+                    scheduled.set_visible(true);
+                }
+                _ => scheduled.set_visible(false),
+            }
+        }
+    );
+    update_scheduled(&service);
+    service.connect_scheduled_shutdown_time_notify(clone!(
+        #[strong]
+        update_scheduled,
+        move |service| update_scheduled(service)
+    ));
+
+    let kbd_exit = EventControllerKey::builder()
+        .name("close-logout-menu")
+        .build();
+    kbd_exit.connect_key_pressed(clone!(
+        #[weak]
+        window,
+        #[weak]
+        stack,
+        #[strong]
+        back_to_options,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, key, _, _| {
+            if key == Key::Escape || key == Key::q {
+                // Escape backs out of the confirmation before it closes the menu.
+                if stack.visible_child_name().as_deref() == Some("confirm") {
+                    back_to_options();
+                } else {
+                    window.set_visible(false);
+                }
+                return glib::Propagation::Stop;
+            }
+            glib::Propagation::Proceed
+        }
+    ));
+    window.add_controller(kbd_exit);
+
+    window.connect_visible_notify(clone!(
+        #[weak]
+        service,
+        #[strong]
+        back_to_options,
+        #[strong]
+        update_scheduled,
+        move |window| {
+            if window.is_visible() {
+                update_scheduled(&service);
+                glib::spawn_future_local(async move {
+                    _ = service.refresh().await;
+                });
+            } else {
+                back_to_options();
+            }
+        }
+    ));
+
+    let menu = Box::builder()
+        .name("logout-menu")
+        .hexpand(true)
+        .vexpand(true)
+        .css_classes(["logout-menu"])
+        .build();
+    stack.set_hexpand(true);
+    menu.append(&stack);
+    window.set_child(Some(&menu));
+
+    window
+}
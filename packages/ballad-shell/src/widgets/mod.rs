@@ -3,9 +3,13 @@ use typed_builder::TypedBuilder;
 
 pub mod clock;
 pub mod icon;
+pub mod lock;
+pub mod logout_menu;
+pub mod notifications;
 pub mod quick_settings;
 pub mod sidebar;
 pub mod volume;
+pub mod wallpaper;
 pub mod window;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,150 @@
+use std::cell::LazyCell;
+
+use ballad_services::notifications::{NOTIFICATIONS_SERVICE, Notification};
+use gtk::{
+    Align, ApplicationWindow, Box, Button, CustomSorter, EventControllerKey, GestureClick, Label,
+    ListBox, Orientation, Overlay, PolicyType, ScrolledWindow, SelectionMode, SortListModel,
+    gdk::Key,
+    glib::{self, clone},
+    prelude::*,
+};
+use gtk4_layer_shell::{KeyboardMode, LayerShell};
+use typed_builder::TypedBuilder;
+
+use super::notification_card;
+use crate::widgets::window::{Layer, LayershellWindow};
+
+pub const NOTIFICATION_CENTER_WINDOW_TITLE: &str = "notification-center";
+
+#[derive(Debug, Clone, TypedBuilder, PartialEq, Eq)]
+#[builder(build_method(into = ApplicationWindow))]
+pub struct NotificationCenter<'a> {
+    pub application: &'a gtk::Application,
+}
+impl From<NotificationCenter<'_>> for ApplicationWindow {
+    fn from(props: NotificationCenter) -> Self {
+        notification_center(props)
+    }
+}
+
+pub fn notification_center(
+    NotificationCenter { application }: NotificationCenter,
+) -> ApplicationWindow {
+    let service = NOTIFICATIONS_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let window: ApplicationWindow = LayershellWindow::builder()
+        .layer(Layer::Top)
+        .application(application)
+        .title(NOTIFICATION_CENTER_WINDOW_TITLE)
+        .build();
+    window.set_keyboard_mode(KeyboardMode::OnDemand);
+
+    let kbd_exit = EventControllerKey::builder()
+        .name("close-notification-center")
+        .build();
+    kbd_exit.connect_key_pressed(clone!(
+        #[weak]
+        window,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, key, _, _| {
+            if key == Key::Escape || key == Key::q {
+                window.set_visible(false);
+            }
+            glib::Propagation::Stop
+        }
+    ));
+    window.add_controller(kbd_exit);
+
+    let overlay = Overlay::builder().name("padding-container").build();
+
+    let click_screen = Box::builder()
+        .name("click-screen")
+        .hexpand(true)
+        .vexpand(true)
+        .focusable(false)
+        .build();
+    let click_exit = GestureClick::builder()
+        .name("close-notification-center")
+        .build();
+    click_exit.connect_pressed(clone!(
+        #[weak]
+        window,
+        move |_, _, _, _| {
+            window.set_visible(false);
+        }
+    ));
+    click_screen.add_controller(click_exit);
+
+    let notification_center = Box::builder()
+        .name("notification-center")
+        .orientation(Orientation::Vertical)
+        .halign(Align::Start)
+        .valign(Align::Start)
+        .css_classes(["notification-center"])
+        .build();
+
+    let header = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["notification-center-header"])
+        .build();
+    header.append(
+        &Label::builder()
+            .label("Notifications")
+            .css_classes(["notification-center-title"])
+            .hexpand(true)
+            .halign(Align::Start)
+            .build(),
+    );
+    let clear_button = Button::builder()
+        .label("Clear")
+        .css_classes(["notification-center-clear"])
+        .build();
+    clear_button.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| {
+            smol::block_on(service.clear());
+        }
+    ));
+    header.append(&clear_button);
+
+    // Newest notifications are shown first.
+    let sorter = CustomSorter::new(|a, b| {
+        let a = a.downcast_ref::<Notification>().unwrap();
+        let b = b.downcast_ref::<Notification>().unwrap();
+        b.timestamp().cmp(&a.timestamp()).into()
+    });
+    let sorted = SortListModel::new(Some(service.notifications()), Some(sorter));
+
+    let list = ListBox::builder()
+        .selection_mode(SelectionMode::None)
+        .css_classes(["notification-list"])
+        .build();
+    list.bind_model(Some(&sorted), |notification| {
+        notification_card(notification.downcast_ref::<Notification>().unwrap()).upcast()
+    });
+    list.set_placeholder(Some(
+        &Label::builder()
+            .label("No notifications")
+            .css_classes(["notification-center-placeholder"])
+            .build(),
+    ));
+
+    let scroller = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(600)
+        .child(&list)
+        .build();
+
+    notification_center.append(&header);
+    notification_center.append(&scroller);
+
+    overlay.set_child(Some(&click_screen));
+    overlay.add_overlay(&notification_center);
+
+    window.set_child(Some(&overlay));
+
+    window
+}
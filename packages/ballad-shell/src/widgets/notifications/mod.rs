@@ -0,0 +1,250 @@
+pub mod center;
+pub mod popups;
+
+use std::cell::LazyCell;
+
+use ballad_services::notifications::{NOTIFICATIONS_SERVICE, Notification, NotificationImage};
+use gtk::{
+    Align, Button, GestureClick, Image, Label, Orientation, Overflow,
+    gdk::{MemoryFormat, MemoryTexture},
+    glib::{self, clone, closure_local},
+    pango::{self, WrapMode},
+    prelude::*,
+};
+
+use crate::widgets::icon::symbolic_icon;
+
+/// Builds an image widget for a notification, falling back to the sending application's icon.
+fn notification_image(notification: &Notification, size: i32) -> Image {
+    let image = Image::builder()
+        .pixel_size(size)
+        .css_classes(["notification-image"])
+        .build();
+
+    match notification.image() {
+        Some(NotificationImage::Data {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            bits_per_sample: 8,
+            channels,
+            data,
+        }) if channels == if has_alpha { 4 } else { 3 } => {
+            let format = if has_alpha {
+                MemoryFormat::R8g8b8a8
+            } else {
+                MemoryFormat::R8g8b8
+            };
+            let texture = MemoryTexture::new(
+                width,
+                height,
+                format,
+                &glib::Bytes::from_owned(data),
+                rowstride as usize,
+            );
+            image.set_paintable(Some(&texture));
+        }
+        Some(NotificationImage::Path(path)) => image.set_from_file(Some(path)),
+        Some(NotificationImage::IconName(name)) => image.set_icon_name(Some(&name)),
+        _ => image.set_icon_name(Some(
+            notification
+                .desktop_entry()
+                .as_deref()
+                .unwrap_or("dialog-information-symbolic"),
+        )),
+    }
+
+    image
+}
+
+fn body_label(notification: &Notification) -> Label {
+    let label = Label::builder()
+        .css_classes(["notification-body"])
+        .halign(Align::Start)
+        .xalign(0.0)
+        .wrap(true)
+        .wrap_mode(WrapMode::WordChar)
+        .max_width_chars(40)
+        .lines(4)
+        .ellipsize(pango::EllipsizeMode::End)
+        .build();
+
+    // Clients are allowed to send a small subset of markup. Fall back to plain text if it doesn't parse.
+    let body = notification.body();
+    if pango::parse_markup(&body, '\0').is_ok() {
+        label.set_markup(&body);
+    } else {
+        label.set_text(&body);
+    }
+
+    label
+}
+
+/// A card displaying a single notification, used by both the popups and the notification center.
+pub fn notification_card(notification: &Notification) -> gtk::Box {
+    let service = NOTIFICATIONS_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let container = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .name("notification")
+        .css_classes(["notification", notification.urgency().as_class_name()])
+        .overflow(Overflow::Hidden)
+        .build();
+
+    let header = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["notification-header"])
+        .spacing(8)
+        .build();
+    let app_name = Label::builder()
+        .css_classes(["notification-app-name"])
+        .halign(Align::Start)
+        .hexpand(true)
+        .ellipsize(pango::EllipsizeMode::End)
+        .build();
+    let close_button = Button::builder()
+        .css_classes(["notification-close", "icon-container", "hoverable"])
+        .child(&symbolic_icon("window-close-symbolic", 16))
+        .valign(Align::Start)
+        .build();
+    header.append(&app_name);
+    header.append(&close_button);
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["notification-content"])
+        .spacing(12)
+        .build();
+    let text = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .hexpand(true)
+        .valign(Align::Center)
+        .build();
+    let summary = Label::builder()
+        .css_classes(["notification-summary"])
+        .halign(Align::Start)
+        .xalign(0.0)
+        .wrap(true)
+        .wrap_mode(WrapMode::WordChar)
+        .max_width_chars(40)
+        .build();
+    text.append(&summary);
+
+    let actions = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["notification-actions"])
+        .homogeneous(true)
+        .spacing(4)
+        .build();
+
+    container.append(&header);
+    container.append(&content);
+    container.append(&actions);
+
+    // Everything that depends on the notification's contents is rebuilt when the notification is replaced.
+    let build_contents = clone!(
+        #[weak]
+        content,
+        #[weak]
+        text,
+        #[weak]
+        summary,
+        #[weak]
+        actions,
+        #[weak]
+        app_name,
+        #[weak]
+        service,
+        move |notification: &Notification| {
+            while let Some(child) = content.first_child() {
+                content.remove(&child);
+            }
+            while let Some(child) = text.last_child() {
+                if child == summary {
+                    break;
+                }
+                text.remove(&child);
+            }
+            while let Some(child) = actions.first_child() {
+                actions.remove(&child);
+            }
+
+            app_name.set_label(&notification.app_name());
+            summary.set_label(&notification.summary());
+            if !notification.body().is_empty() {
+                text.append(&body_label(notification));
+            }
+
+            content.append(&notification_image(notification, 48));
+            content.append(&text);
+
+            // The client was told an expired notification is closed, so its actions do nothing anymore.
+            for action in notification
+                .actions()
+                .into_iter()
+                .filter(|action| action.key != "default" && !notification.expired())
+            {
+                let button = Button::builder()
+                    .css_classes(["notification-action"])
+                    .label(&action.label)
+                    .build();
+                button.connect_clicked(clone!(
+                    #[weak]
+                    service,
+                    #[weak]
+                    notification,
+                    move |_| {
+                        smol::block_on(service.invoke_action(notification.id(), &action.key));
+                    }
+                ));
+                actions.append(&button);
+            }
+            actions.set_visible(actions.first_child().is_some());
+        }
+    );
+    build_contents(notification);
+
+    notification.connect_closure(
+        "changed",
+        false,
+        closure_local!(
+            #[weak]
+            container,
+            move |notification: Notification| {
+                container
+                    .set_css_classes(&["notification", notification.urgency().as_class_name()]);
+                build_contents(&notification);
+            }
+        ),
+    );
+
+    close_button.connect_clicked(clone!(
+        #[weak]
+        service,
+        #[weak]
+        notification,
+        move |_| {
+            smol::block_on(service.dismiss(notification.id()));
+        }
+    ));
+
+    // Clicking the notification itself invokes its default action.
+    let click = GestureClick::builder()
+        .name("notification-default-action")
+        .build();
+    click.connect_released(clone!(
+        #[weak]
+        service,
+        #[weak]
+        notification,
+        move |_, _, _, _| {
+            if let Some(action) = notification.default_action() {
+                smol::block_on(service.invoke_action(notification.id(), &action.key));
+            }
+        }
+    ));
+    content.add_controller(click);
+
+    container
+}
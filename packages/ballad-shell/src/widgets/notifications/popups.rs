@@ -0,0 +1,140 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use ballad_services::{
+    do_not_disturb::DO_NOT_DISTURB_SERVICE,
+    notifications::{NOTIFICATIONS_SERVICE, Notification, NotificationsService},
+};
+use gtk::{
+    Align, ApplicationWindow, Orientation, Revealer, RevealerTransitionType,
+    glib::{self, clone, closure_local},
+    prelude::*,
+};
+use gtk4_layer_shell::{KeyboardMode, LayerShell};
+use typed_builder::TypedBuilder;
+
+use super::notification_card;
+use crate::widgets::window::{Anchor, Layer, LayershellWindow};
+
+pub const NOTIFICATION_POPUPS_WINDOW_TITLE: &str = "notification-popups";
+
+#[derive(Debug, Clone, TypedBuilder, PartialEq, Eq)]
+#[builder(build_method(into = ApplicationWindow))]
+pub struct NotificationPopups<'a> {
+    pub application: &'a gtk::Application,
+}
+impl From<NotificationPopups<'_>> for ApplicationWindow {
+    fn from(props: NotificationPopups) -> Self {
+        notification_popups(props)
+    }
+}
+
+/// Hides a popup and removes it once the transition has finished.
+fn remove_popup(container: &gtk::Box, window: &ApplicationWindow, revealer: Revealer) {
+    revealer.set_reveal_child(false);
+    revealer.connect_child_revealed_notify(clone!(
+        #[weak]
+        container,
+        #[weak]
+        window,
+        move |revealer| {
+            if !revealer.is_child_revealed() {
+                container.remove(revealer);
+                window.set_visible(container.first_child().is_some());
+            }
+        }
+    ));
+}
+
+pub fn notification_popups(
+    NotificationPopups { application }: NotificationPopups,
+) -> ApplicationWindow {
+    let window: ApplicationWindow = LayershellWindow::builder()
+        .anchors(&[Anchor::Top, Anchor::Right])
+        .layer(Layer::Overlay)
+        .application(application)
+        .title(NOTIFICATION_POPUPS_WINDOW_TITLE)
+        .build();
+    window.set_keyboard_mode(KeyboardMode::None);
+
+    let container = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .name("notification-popups")
+        .css_classes(["notification-popups"])
+        .valign(Align::Start)
+        .build();
+    window.set_child(Some(&container));
+
+    let popups: Rc<RefCell<HashMap<u32, Revealer>>> = Default::default();
+
+    NOTIFICATIONS_SERVICE.with(|service| {
+        service.connect_closure(
+            "notified",
+            false,
+            closure_local!(
+                #[weak]
+                container,
+                #[weak]
+                window,
+                #[strong]
+                popups,
+                move |_: NotificationsService, notification: Notification, _replaced: bool| {
+                    // Replaced notifications update their existing card in place.
+                    if let Some(revealer) = popups.borrow().get(&notification.id()) {
+                        revealer.set_reveal_child(true);
+                        return;
+                    }
+                    // Suppressed notifications are still available from the notification center.
+                    if !DO_NOT_DISTURB_SERVICE.with(|dnd| dnd.should_popup(&notification)) {
+                        return;
+                    }
+
+                    let revealer = Revealer::builder()
+                        .transition_type(RevealerTransitionType::SlideDown)
+                        .child(&notification_card(&notification))
+                        .build();
+                    container.prepend(&revealer);
+                    window.set_visible(true);
+                    revealer.set_reveal_child(true);
+
+                    popups.borrow_mut().insert(notification.id(), revealer);
+                }
+            ),
+        );
+        service.connect_closure(
+            "expired",
+            false,
+            closure_local!(
+                #[weak]
+                container,
+                #[weak]
+                window,
+                #[strong]
+                popups,
+                move |_: NotificationsService, notification: Notification| {
+                    if let Some(revealer) = popups.borrow_mut().remove(&notification.id()) {
+                        remove_popup(&container, &window, revealer);
+                    }
+                }
+            ),
+        );
+        service.connect_closure(
+            "closed",
+            false,
+            closure_local!(
+                #[weak]
+                container,
+                #[weak]
+                window,
+                #[strong]
+                popups,
+                move |_: NotificationsService, id: u32, _reason: u32| {
+                    if let Some(revealer) = popups.borrow_mut().remove(&id) {
+                        remove_popup(&container, &window, revealer);
+                    }
+                }
+            ),
+        );
+    });
+
+    window
+}
@@ -0,0 +1,378 @@
+use std::cell::{Cell, LazyCell};
+use std::rc::Rc;
+
+use ballad_services::{
+    bluetooth::{BLUETOOTH_SERVICE, BluetoothDevice, BluetoothRequestKind, BluetoothService},
+    reactive::Reactive,
+};
+use gtk::{
+    Align, Button, Entry, Image, InputPurpose, Label, Orientation, Revealer, ScrolledWindow, gio,
+    glib::{self, clone, closure_local},
+    pango,
+    prelude::*,
+};
+
+use crate::{utils::set_class_on_widget, widgets::icon::symbolic_icon};
+
+use super::dropdown_button::DropdownButton;
+
+fn report(result: zbus::Result<()>) {
+    if let Err(err) = result {
+        println!("Bluetooth request failed: {err}");
+    }
+}
+
+fn request_message(kind: BluetoothRequestKind, device: &str, value: &str) -> String {
+    match kind {
+        BluetoothRequestKind::PinCode => format!("Enter the PIN code for {device}"),
+        BluetoothRequestKind::Passkey => format!("Enter the passkey shown on {device}"),
+        BluetoothRequestKind::Confirmation => {
+            format!("Does {device} show the passkey {value}?")
+        }
+        BluetoothRequestKind::Authorization => format!("Allow {device} to pair?"),
+        BluetoothRequestKind::ServiceAuthorization => {
+            format!("Allow {device} to use service {value}?")
+        }
+        BluetoothRequestKind::DisplayPinCode | BluetoothRequestKind::DisplayPasskey => {
+            format!("Type {value} on {device}")
+        }
+    }
+}
+
+/// Connects, disconnects or pairs with `device` depending on its state.
+fn toggle_device(device: BluetoothDevice) {
+    // Pairing waits on our own agent, which is answered from the main loop, so nothing here may block it.
+    glib::spawn_future_local(async move {
+        let result = if device.connected() {
+            device.disconnect().await
+        } else if device.paired() {
+            device.connect().await
+        } else {
+            match device.pair().await {
+                Ok(()) => device.connect().await,
+                Err(err) => Err(err),
+            }
+        };
+        report(result);
+    });
+}
+
+fn update_device_row(button: &Button, name: &Label, battery: &Label, device: &BluetoothDevice) {
+    name.set_label(&device.name());
+    battery.set_label(&format!("{}%", device.battery_percentage()));
+    battery.set_visible(device.battery_percentage() >= 0);
+    set_class_on_widget(device.connected(), button, "active");
+    set_class_on_widget(!device.paired(), button, "unpaired");
+    button.set_tooltip_text(Some(if device.connected() {
+        "Disconnect"
+    } else if device.paired() {
+        "Connect"
+    } else {
+        "Pair"
+    }));
+}
+
+fn device_row(device: &BluetoothDevice) -> Button {
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    let icon = Image::builder()
+        .icon_name(device.icon().unwrap_or("bluetooth-symbolic".to_string()))
+        .pixel_size(16)
+        .build();
+    let name = Label::builder()
+        .label(device.name())
+        .hexpand(true)
+        .halign(Align::Start)
+        .ellipsize(pango::EllipsizeMode::End)
+        .max_width_chars(20)
+        .build();
+    let battery = Label::builder()
+        .css_classes(["bluetooth-device-battery"])
+        .build();
+    content.append(&icon);
+    content.append(&name);
+    content.append(&battery);
+
+    let button = Button::builder()
+        .css_classes(["toggle-button-dropdown-option", "bluetooth-device"])
+        .hexpand(true)
+        .child(&content)
+        .build();
+
+    update_device_row(&button, &name, &battery, device);
+    device.connect_closure(
+        "changed",
+        false,
+        closure_local!(
+            #[weak]
+            button,
+            #[weak]
+            name,
+            #[weak]
+            battery,
+            move |device: BluetoothDevice| update_device_row(&button, &name, &battery, &device)
+        ),
+    );
+
+    button.connect_clicked(clone!(
+        #[weak]
+        device,
+        move |_| toggle_device(device)
+    ));
+    button
+}
+
+/// Shown while the pairing agent is waiting for the user.
+fn agent_prompt(service: &BluetoothService) -> Revealer {
+    let prompt = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .css_classes(["bluetooth-agent-prompt"])
+        .build();
+    let message = Label::builder()
+        .halign(Align::Start)
+        .wrap(true)
+        .max_width_chars(28)
+        .build();
+    let input = Entry::builder()
+        .input_purpose(InputPurpose::Digits)
+        .css_classes(["bluetooth-agent-input"])
+        .build();
+    let buttons = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(Align::End)
+        .spacing(4)
+        .build();
+    let reject = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .label("Reject")
+        .build();
+    let accept = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .label("Accept")
+        .build();
+    buttons.append(&reject);
+    buttons.append(&accept);
+    prompt.append(&message);
+    prompt.append(&input);
+    prompt.append(&buttons);
+
+    let revealer = Revealer::builder().child(&prompt).build();
+    // The request being answered, if it needs an answer.
+    let request = Rc::new(Cell::new(None::<u32>));
+
+    let answer = clone!(
+        #[weak]
+        service,
+        #[weak]
+        revealer,
+        #[weak]
+        input,
+        #[strong]
+        request,
+        move |accepted: bool| {
+            if let Some(id) = request.take() {
+                if accepted {
+                    service.accept_request(id, Some(input.text().to_string()));
+                } else {
+                    service.reject_request(id);
+                }
+            }
+            input.set_text("");
+            revealer.set_reveal_child(false);
+        }
+    );
+    accept.connect_clicked(clone!(
+        #[strong]
+        answer,
+        move |_| answer(true)
+    ));
+    input.connect_activate(clone!(
+        #[strong]
+        answer,
+        move |_| answer(true)
+    ));
+    reject.connect_clicked(move |_| answer(false));
+
+    service.connect_closure(
+        "agent-request",
+        false,
+        closure_local!(
+            #[weak]
+            revealer,
+            #[weak]
+            message,
+            #[weak]
+            input,
+            #[weak]
+            reject,
+            #[weak]
+            accept,
+            #[strong]
+            request,
+            move |service: BluetoothService,
+                  id: u32,
+                  kind: BluetoothRequestKind,
+                  device: String,
+                  value: String| {
+                if let Some(previous) = request.take() {
+                    service.reject_request(previous);
+                }
+                if !kind.is_display() {
+                    request.set(Some(id));
+                }
+                message.set_label(&request_message(kind, &device, &value));
+                input.set_text("");
+                input.set_visible(kind.needs_input());
+                reject.set_visible(!kind.is_display());
+                accept.set_label(if kind.is_display() { "Done" } else { "Accept" });
+                revealer.set_reveal_child(true);
+                if kind.needs_input() {
+                    input.grab_focus();
+                }
+            }
+        ),
+    );
+    service.connect_closure(
+        "agent-request-cancelled",
+        false,
+        closure_local!(
+            #[weak]
+            revealer,
+            #[strong]
+            request,
+            move |_: BluetoothService, id: u32| {
+                if request.get() == Some(id) {
+                    request.set(None);
+                    revealer.set_reveal_child(false);
+                }
+            }
+        ),
+    );
+
+    revealer
+}
+
+pub fn bluetooth_selector() -> gtk::Box {
+    let service = BLUETOOTH_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let powered = Reactive::new(service.powered());
+    service.connect_powered_notify(clone!(
+        #[strong]
+        powered,
+        move |service| {
+            powered.set_blocking(service.powered());
+        }
+    ));
+
+    let button_content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(Align::Start)
+        .spacing(8)
+        .build();
+    button_content.append(&symbolic_icon("bluetooth-symbolic", 24));
+    button_content.append(
+        &Label::builder()
+            .label("Bluetooth")
+            .vexpand(true)
+            .valign(Align::Center)
+            .build(),
+    );
+
+    let options_content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .name("bluetooth-options")
+        .css_classes(["bluetooth-options"])
+        .build();
+    options_content.append(&agent_prompt(&service));
+
+    let devices = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .build();
+    let rebuild_devices = clone!(
+        #[weak]
+        devices,
+        move |store: &gio::ListStore| {
+            while let Some(child) = devices.first_child() {
+                devices.remove(&child);
+            }
+            // Connected devices first, then the ones that can be connected to without pairing.
+            let mut sorted = store
+                .iter::<BluetoothDevice>()
+                .filter_map(|device| device.ok())
+                .collect::<Vec<_>>();
+            sorted.sort_by_key(|device| (!device.connected(), !device.paired(), device.name()));
+            for device in sorted {
+                devices.append(&device_row(&device));
+            }
+        }
+    );
+    rebuild_devices(&service.devices());
+    service
+        .devices()
+        .connect_items_changed(move |store, _, _, _| rebuild_devices(store));
+    options_content.append(
+        &ScrolledWindow::builder()
+            .min_content_height(64)
+            .max_content_height(240)
+            .propagate_natural_height(true)
+            .child(&devices)
+            .build(),
+    );
+
+    let discover = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .halign(Align::Start)
+        .build();
+    let update_discover = clone!(
+        #[weak]
+        discover,
+        move |service: &BluetoothService| {
+            discover.set_label(if service.discovering() {
+                "Stop searching"
+            } else {
+                "Search for devices"
+            });
+            discover.set_sensitive(service.powered());
+        }
+    );
+    update_discover(&service);
+    service.connect_discovering_notify(clone!(
+        #[strong]
+        update_discover,
+        move |service| update_discover(service)
+    ));
+    service.connect_powered_notify(move |service| update_discover(service));
+    discover.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| report(smol::block_on(
+            service.set_discovering(!service.discovering())
+        ))
+    ));
+    options_content.append(&discover);
+
+    let selector = DropdownButton::builder()
+        .on_toggle(clone!(
+            #[weak]
+            service,
+            move |powered| report(smol::block_on(service.set_powered(powered)))
+        ))
+        .toggled(powered)
+        .button_content(button_content)
+        .dropdown_content(options_content)
+        .build();
+
+    selector.set_visible(service.adapter().is_some());
+    service.connect_adapter_notify(clone!(
+        #[weak]
+        selector,
+        move |service| selector.set_visible(service.adapter().is_some())
+    ));
+
+    selector
+}
@@ -0,0 +1,118 @@
+use std::cell::LazyCell;
+
+use ballad_config::idle::IdleTimeouts;
+use ballad_services::{idle::IDLE_SERVICE, reactive::Reactive, upower::UPOWER_SERVICE};
+use gtk::{
+    Align, Image, Label, Orientation,
+    glib::{self, clone},
+    prelude::*,
+};
+
+use super::dropdown_button::DropdownButton;
+
+fn format_timeout(seconds: u32) -> String {
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{seconds} s"),
+        (minutes, 0) => format!("{minutes} min"),
+        (minutes, seconds) => format!("{minutes} min {seconds} s"),
+    }
+}
+
+fn timeouts_label(caffeine: bool) -> String {
+    if caffeine {
+        return "Staying awake until turned off".to_string();
+    }
+    let config = IDLE_SERVICE.with(|service| service.config());
+    if !config.enabled {
+        return "Idle actions are turned off".to_string();
+    }
+    let on_battery = UPOWER_SERVICE.with(|service| service.available() && service.on_battery());
+    let IdleTimeouts {
+        dim,
+        lock,
+        dpms_off,
+        suspend,
+    } = config.timeouts(on_battery);
+    let stages = [
+        ("Dims", dim),
+        ("Locks", lock),
+        ("Turns the screen off", dpms_off),
+        ("Suspends", suspend),
+    ]
+    .into_iter()
+    .filter_map(|(stage, timeout)| Some(format!("{stage} after {}", format_timeout(timeout?))))
+    .collect::<Vec<_>>();
+    if stages.is_empty() {
+        "No idle actions are set".to_string()
+    } else {
+        stages.join("\n")
+    }
+}
+
+pub fn caffeine_toggle() -> gtk::Box {
+    let service = IDLE_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let enabled = Reactive::new(service.caffeine());
+    service.connect_caffeine_notify(clone!(
+        #[strong]
+        enabled,
+        move |service| {
+            enabled.set_blocking(service.caffeine());
+        }
+    ));
+
+    let button_content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(Align::Start)
+        .spacing(8)
+        .build();
+    button_content.append(
+        &Image::builder()
+            .icon_name("coffee-symbolic")
+            .pixel_size(24)
+            .build(),
+    );
+    button_content.append(
+        &Label::builder()
+            .label("Caffeine")
+            .vexpand(true)
+            .valign(Align::Center)
+            .build(),
+    );
+
+    let options_content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .halign(Align::Start)
+        .name("caffeine-options")
+        .css_classes(["caffeine-options"])
+        .build();
+    let timeouts = Label::builder()
+        .label(timeouts_label(service.caffeine()))
+        .halign(Align::Start)
+        .css_classes(["caffeine-timeouts"])
+        .build();
+    let update_timeouts = clone!(
+        #[weak]
+        timeouts,
+        #[weak]
+        service,
+        move || timeouts.set_label(&timeouts_label(service.caffeine()))
+    );
+    service.connect_caffeine_notify(clone!(
+        #[strong]
+        update_timeouts,
+        move |_| update_timeouts()
+    ));
+    UPOWER_SERVICE.with(|upower| {
+        upower.connect_on_battery_notify(move |_| update_timeouts());
+    });
+    options_content.append(&timeouts);
+
+    DropdownButton::builder()
+        .on_toggle(move |enabled| service.set_caffeine(enabled))
+        .toggled(enabled)
+        .button_content(button_content)
+        .dropdown_content(options_content)
+        .build()
+}
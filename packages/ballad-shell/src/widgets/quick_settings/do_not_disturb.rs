@@ -0,0 +1,105 @@
+use std::cell::LazyCell;
+
+use ballad_services::{
+    do_not_disturb::DO_NOT_DISTURB_SERVICE, notifications::NOTIFICATIONS_SERVICE,
+    reactive::Reactive,
+};
+use gtk::{
+    Align, Button, Image, Label, Orientation,
+    glib::{self, clone},
+    prelude::*,
+};
+
+use super::dropdown_button::DropdownButton;
+
+fn quiet_hours_label(quiet_hours_active: bool) -> String {
+    let config = DO_NOT_DISTURB_SERVICE.with(|service| service.config());
+    match config.quiet_hours {
+        Some(_) if quiet_hours_active => "Quiet hours are active".to_string(),
+        Some(quiet_hours) => format!(
+            "Quiet hours from {} to {}",
+            quiet_hours.start, quiet_hours.end
+        ),
+        None => "No quiet hours set".to_string(),
+    }
+}
+
+pub fn do_not_disturb_toggle() -> gtk::Box {
+    let service = DO_NOT_DISTURB_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let enabled = Reactive::new(service.enabled());
+    service.connect_enabled_notify(clone!(
+        #[strong]
+        enabled,
+        move |service| {
+            enabled.set_blocking(service.enabled());
+        }
+    ));
+
+    let button_content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(Align::Start)
+        .spacing(8)
+        .build();
+    let icon = Image::builder().pixel_size(24).build();
+    let set_icon = clone!(
+        #[weak]
+        icon,
+        move |active: bool| {
+            icon.set_icon_name(Some(if active {
+                "bell-off-symbolic"
+            } else {
+                "bell-symbolic"
+            }));
+        }
+    );
+    set_icon(service.active());
+    service.connect_active_notify(move |service| set_icon(service.active()));
+    button_content.append(&icon);
+    button_content.append(
+        &Label::builder()
+            .label("Do Not Disturb")
+            .vexpand(true)
+            .valign(Align::Center)
+            .build(),
+    );
+
+    let options_content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .halign(Align::Start)
+        .name("do-not-disturb-options")
+        .css_classes(["do-not-disturb-options"])
+        .build();
+
+    let quiet_hours = Label::builder()
+        .label(quiet_hours_label(service.quiet_hours_active()))
+        .halign(Align::Start)
+        .css_classes(["do-not-disturb-quiet-hours"])
+        .build();
+    service.connect_quiet_hours_active_notify(clone!(
+        #[weak]
+        quiet_hours,
+        move |service| {
+            quiet_hours.set_label(&quiet_hours_label(service.quiet_hours_active()));
+        }
+    ));
+    options_content.append(&quiet_hours);
+
+    let clear_history = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .halign(Align::Start)
+        .label("Clear notification history")
+        .build();
+    clear_history.connect_clicked(|_| {
+        NOTIFICATIONS_SERVICE.with(|service| smol::block_on(service.clear()));
+    });
+    options_content.append(&clear_history);
+
+    DropdownButton::builder()
+        .on_toggle(move |enabled| service.set_enabled(enabled))
+        .toggled(enabled)
+        .button_content(button_content)
+        .dropdown_content(options_content)
+        .build()
+}
@@ -98,6 +98,7 @@ fn theme_option_button(
         .build();
 
     let theme_selection2 = theme_selection.clone();
+    let variant = theme.variant;
     CONFIG_SERVICE.with(|service| {
         let service = LazyCell::force(service).clone();
 
@@ -111,12 +112,20 @@ fn theme_option_button(
             move |_| {
                 retained_selection.set_blocking(theme_selection2.clone());
 
+                let config = service.shell_config();
+                // Switching between light and dark automatically uses the themes picked here.
+                let mut auto_switch = config.theme.auto_switch.clone();
+                match variant {
+                    ThemeVariant::Light => auto_switch.light_theme = theme_selection2.clone(),
+                    ThemeVariant::Dark => auto_switch.dark_theme = theme_selection2.clone(),
+                }
                 service.set_shell_config(ShellConfig {
                     theme: ThemeConfig {
                         selected_theme: theme_selection2.clone(),
-                        ..service.shell_config().theme
+                        auto_switch,
+                        ..config.theme
                     },
-                    ..service.shell_config()
+                    ..config
                 });
             }
         ));
@@ -204,7 +213,7 @@ pub fn flavor_selector() -> Box {
         if config.theme.selected_theme.is_dark().unwrap_or(true) {
             config.theme.selected_theme
         } else {
-            ThemeSelection::default()
+            config.theme.auto_switch.dark_theme
         }
     });
     let retained_light_flavor = Reactive::new({
@@ -212,7 +221,7 @@ pub fn flavor_selector() -> Box {
         if config.theme.selected_theme.is_light().unwrap_or(false) {
             config.theme.selected_theme
         } else {
-            ThemeSelection::CatppuccinLatte
+            config.theme.auto_switch.light_theme
         }
     });
 
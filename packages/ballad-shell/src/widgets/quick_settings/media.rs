@@ -0,0 +1,253 @@
+use std::{
+    cell::{LazyCell, RefCell},
+    rc::Rc,
+};
+
+use ballad_services::mpris::{MPRIS_SERVICE, MediaPlayer, PlaybackStatus};
+use gtk::{
+    Align, Button, ContentFit, Label, Orientation, Overflow, Picture, Scale, ScrollType, gio,
+    glib::{self, SignalHandlerId, clone, closure_local},
+    pango,
+    prelude::*,
+};
+
+use crate::widgets::icon::symbolic_icon;
+
+fn format_time(microseconds: i64) -> String {
+    let seconds = microseconds.max(0) / 1_000_000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+struct MediaCardWidgets {
+    art: Picture,
+    title: Label,
+    artist: Label,
+    position: Label,
+    length: Label,
+    seek_bar: Scale,
+    previous: Button,
+    play_pause: Button,
+    next: Button,
+}
+impl MediaCardWidgets {
+    fn update(&self, player: &MediaPlayer) {
+        let art_url = player.art_url();
+        if self.art.file().map(|file| file.uri().to_string()) != art_url {
+            self.art
+                .set_file(art_url.as_deref().map(gio::File::for_uri).as_ref());
+        }
+        self.art.set_visible(art_url.is_some());
+
+        self.title.set_label(&player.title());
+        self.artist.set_label(&player.artist());
+        self.artist.set_visible(!player.artist().is_empty());
+
+        self.length.set_label(&format_time(player.length()));
+        self.seek_bar.set_range(0.0, player.length().max(1) as f64);
+        self.seek_bar
+            .set_sensitive(player.can_seek() && player.length() > 0);
+        self.update_position(player);
+
+        self.previous.set_sensitive(player.can_go_previous());
+        self.next.set_sensitive(player.can_go_next());
+        self.play_pause
+            .set_sensitive(player.can_play() || player.can_pause());
+        self.play_pause.set_child(Some(&symbolic_icon(
+            if player.playback_status() == PlaybackStatus::Playing {
+                "pause-symbolic"
+            } else {
+                "play-symbolic"
+            },
+            24,
+        )));
+    }
+
+    fn update_position(&self, player: &MediaPlayer) {
+        self.position.set_label(&format_time(player.position()));
+        self.seek_bar.set_value(player.position() as f64);
+    }
+}
+
+/// The player currently shown on the card and the handlers connected to it.
+type BoundPlayer = Option<(MediaPlayer, Vec<SignalHandlerId>)>;
+
+fn media_button(icon_name: &str) -> Button {
+    Button::builder()
+        .css_classes(["media-control", "icon-container", "hoverable"])
+        .child(&symbolic_icon(icon_name, 24))
+        .build()
+}
+
+/// A card controlling the most recently active media player. Hidden while there are no players.
+pub fn media_card() -> gtk::Box {
+    let service = MPRIS_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let container = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .name("media-card")
+        .css_classes(["media-card"])
+        .spacing(12)
+        .overflow(Overflow::Hidden)
+        .build();
+
+    let art = Picture::builder()
+        .css_classes(["media-art"])
+        .content_fit(ContentFit::Cover)
+        .width_request(96)
+        .height_request(96)
+        .valign(Align::Center)
+        .build();
+
+    let details = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .hexpand(true)
+        .valign(Align::Center)
+        .build();
+    let title = Label::builder()
+        .css_classes(["media-title"])
+        .halign(Align::Start)
+        .ellipsize(pango::EllipsizeMode::End)
+        .max_width_chars(24)
+        .build();
+    let artist = Label::builder()
+        .css_classes(["media-artist"])
+        .halign(Align::Start)
+        .ellipsize(pango::EllipsizeMode::End)
+        .max_width_chars(24)
+        .build();
+
+    let seek_row = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["media-seek-row"])
+        .spacing(4)
+        .build();
+    let position = Label::builder().css_classes(["media-time"]).build();
+    let length = Label::builder().css_classes(["media-time"]).build();
+    let seek_bar = Scale::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["media-seek-bar", "horizontal"])
+        .hexpand(true)
+        .build();
+    seek_row.append(&position);
+    seek_row.append(&seek_bar);
+    seek_row.append(&length);
+
+    let controls = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["media-controls"])
+        .halign(Align::Center)
+        .spacing(8)
+        .build();
+    let previous = media_button("previous-symbolic");
+    let play_pause = media_button("play-symbolic");
+    let next = media_button("next-symbolic");
+    controls.append(&previous);
+    controls.append(&play_pause);
+    controls.append(&next);
+
+    details.append(&title);
+    details.append(&artist);
+    details.append(&seek_row);
+    details.append(&controls);
+
+    container.append(&art);
+    container.append(&details);
+
+    let widgets = Rc::new(MediaCardWidgets {
+        art,
+        title,
+        artist,
+        position,
+        length,
+        seek_bar: seek_bar.clone(),
+        previous: previous.clone(),
+        play_pause: play_pause.clone(),
+        next: next.clone(),
+    });
+
+    previous.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| {
+            if let Some(player) = service.active_player() {
+                smol::block_on(player.previous()).ok();
+            }
+        }
+    ));
+    play_pause.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| {
+            if let Some(player) = service.active_player() {
+                smol::block_on(player.play_pause()).ok();
+            }
+        }
+    ));
+    next.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| {
+            if let Some(player) = service.active_player() {
+                smol::block_on(player.next()).ok();
+            }
+        }
+    ));
+    // Only user interaction triggers `change-value`, so position updates from the player don't seek.
+    seek_bar.connect_change_value(clone!(
+        #[weak]
+        service,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, _: ScrollType, value| {
+            if let Some(player) = service.active_player() {
+                smol::block_on(player.set_position(value as i64)).ok();
+            }
+            glib::Propagation::Proceed
+        }
+    ));
+
+    // Handlers on the previously active player, disconnected once another player becomes active.
+    let handlers: Rc<RefCell<BoundPlayer>> = Default::default();
+
+    let bind_player = clone!(
+        #[weak]
+        container,
+        #[strong]
+        widgets,
+        #[strong]
+        handlers,
+        move |player: Option<MediaPlayer>| {
+            if let Some((previous_player, ids)) = handlers.take() {
+                for id in ids {
+                    previous_player.disconnect(id);
+                }
+            }
+
+            container.set_visible(player.is_some());
+            let Some(player) = player else {
+                return;
+            };
+            widgets.update(&player);
+
+            let changed = player.connect_closure(
+                "changed",
+                false,
+                closure_local!(
+                    #[strong]
+                    widgets,
+                    move |player: MediaPlayer| widgets.update(&player)
+                ),
+            );
+            let position = player.connect_position_notify(clone!(
+                #[strong]
+                widgets,
+                move |player| widgets.update_position(player)
+            ));
+            handlers.replace(Some((player, vec![changed, position])));
+        }
+    );
+    bind_player(service.active_player());
+    service.connect_active_player_notify(move |service| bind_player(service.active_player()));
+
+    container
+}
@@ -1,12 +1,21 @@
+mod bluetooth;
 mod brightness;
+mod caffeine;
+mod do_not_disturb;
 mod dropdown_button;
 mod flavor;
-mod info;
+pub mod info;
+mod media;
+mod network;
 mod power_profile;
 
 use super::volume::Volume;
 use super::window::{Layer, LayershellWindow};
 use ballad_services::brightness::BRIGHTNESS_SERVICE;
+use ballad_services::idle::IDLE_SERVICE;
+use bluetooth::bluetooth_selector;
+use caffeine::caffeine_toggle;
+use do_not_disturb::do_not_disturb_toggle;
 use flavor::flavor_selector;
 use gtk::gdk::Key;
 use gtk::glib;
@@ -16,6 +25,8 @@ use gtk::{
 };
 use gtk4_layer_shell::{KeyboardMode, LayerShell};
 use info::info_block;
+use media::media_card;
+use network::wifi_selector;
 use power_profile::power_profile_selector;
 use typed_builder::TypedBuilder;
 
@@ -94,10 +105,25 @@ pub fn quick_settings(QuickSettings { application }: QuickSettings) -> Applicati
     if BRIGHTNESS_SERVICE.with(|service| service.available_blocking()) {
         quick_settings.append(&brightness::brightness());
     }
-    let dropdowns_top_row = Box::builder().orientation(Orientation::Horizontal).spacing(8).build();
+    quick_settings.append(&media_card());
+    let dropdowns_top_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
     dropdowns_top_row.append(&flavor_selector());
     dropdowns_top_row.append(&power_profile_selector());
+    dropdowns_top_row.append(&bluetooth_selector());
     quick_settings.append(&dropdowns_top_row);
+    let dropdowns_bottom_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    dropdowns_bottom_row.append(&wifi_selector());
+    dropdowns_bottom_row.append(&do_not_disturb_toggle());
+    if IDLE_SERVICE.with(|service| service.available()) {
+        dropdowns_bottom_row.append(&caffeine_toggle());
+    }
+    quick_settings.append(&dropdowns_bottom_row);
 
     overlay.set_child(Some(&click_screen));
     overlay.add_overlay(&quick_settings);
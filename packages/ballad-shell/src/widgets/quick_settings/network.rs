@@ -0,0 +1,409 @@
+use std::cell::{Cell, LazyCell};
+use std::rc::Rc;
+
+use ballad_services::{
+    network::{AccessPoint, NETWORK_SERVICE, NetworkService, VpnConnection, WifiSecurity},
+    reactive::Reactive,
+};
+use gtk::{
+    Align, Button, Image, Label, Orientation, PasswordEntry, Revealer, ScrolledWindow, gio,
+    glib::{self, clone, closure_local},
+    pango,
+    prelude::*,
+};
+
+use crate::{utils::set_class_on_widget, widgets::icon::symbolic_icon};
+
+use super::dropdown_button::DropdownButton;
+
+fn wifi_icon_name(strength: u8) -> &'static str {
+    match strength {
+        0..25 => "wifi-1-symbolic",
+        25..50 => "wifi-2-symbolic",
+        50..75 => "wifi-3-symbolic",
+        _ => "wifi-4-symbolic",
+    }
+}
+
+/// Networks we can't connect to without asking for a password first.
+fn needs_password(access_point: &AccessPoint) -> bool {
+    access_point.secured()
+        && access_point.security() != WifiSecurity::Enterprise
+        && access_point.known_connection().is_none()
+}
+
+fn report(result: zbus::Result<()>) {
+    if let Err(err) = result {
+        println!("Network request failed: {err}");
+    }
+}
+
+fn access_point_row(service: &NetworkService, access_point: &AccessPoint) -> gtk::Box {
+    let row = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .css_classes(["wifi-network"])
+        .build();
+
+    let button_content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    button_content.append(&symbolic_icon(wifi_icon_name(access_point.strength()), 16));
+    button_content.append(
+        &Label::builder()
+            .label(access_point.ssid())
+            .hexpand(true)
+            .halign(Align::Start)
+            .ellipsize(pango::EllipsizeMode::End)
+            .max_width_chars(20)
+            .build(),
+    );
+    if access_point.secured() {
+        let lock = symbolic_icon("lock-symbolic", 16);
+        lock.set_tooltip_text(Some(access_point.security().as_str()));
+        button_content.append(&lock);
+    }
+
+    let button = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .hexpand(true)
+        .child(&button_content)
+        .build();
+    set_class_on_widget(access_point.active(), &button, "active");
+
+    let password = PasswordEntry::builder()
+        .placeholder_text("Password")
+        .show_peek_icon(true)
+        .css_classes(["wifi-password"])
+        .build();
+    let password_revealer = Revealer::builder().child(&password).build();
+
+    button.connect_clicked(clone!(
+        #[weak]
+        service,
+        #[weak]
+        access_point,
+        #[weak]
+        password_revealer,
+        #[weak]
+        password,
+        move |_| {
+            if access_point.active() {
+                report(smol::block_on(service.disconnect_wifi()));
+            } else if needs_password(&access_point) {
+                password_revealer.set_reveal_child(!password_revealer.reveals_child());
+                password.grab_focus();
+            } else {
+                report(smol::block_on(
+                    service.connect_to_access_point(&access_point, None),
+                ));
+            }
+        }
+    ));
+    password.connect_activate(clone!(
+        #[weak]
+        service,
+        #[weak]
+        access_point,
+        move |password| {
+            report(smol::block_on(service.connect_to_access_point(
+                &access_point,
+                Some(password.text().to_string()),
+            )));
+        }
+    ));
+
+    row.append(&button);
+    row.append(&password_revealer);
+    row
+}
+
+fn vpn_row(service: &NetworkService, vpn: &VpnConnection) -> Button {
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    content.append(&symbolic_icon("shield-symbolic", 16));
+    content.append(
+        &Label::builder()
+            .label(vpn.name())
+            .halign(Align::Start)
+            .ellipsize(pango::EllipsizeMode::End)
+            .max_width_chars(20)
+            .build(),
+    );
+
+    let button = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .hexpand(true)
+        .child(&content)
+        .build();
+    set_class_on_widget(vpn.active(), &button, "active");
+    button.connect_clicked(clone!(
+        #[weak]
+        service,
+        #[weak]
+        vpn,
+        move |_| {
+            report(smol::block_on(service.set_vpn_active(&vpn, !vpn.active())));
+        }
+    ));
+    button
+}
+
+/// Shown when NetworkManager asks for the password of a saved network.
+fn secret_prompt(service: &NetworkService) -> Revealer {
+    let prompt = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .css_classes(["wifi-secret-prompt"])
+        .build();
+    let title = Label::builder()
+        .halign(Align::Start)
+        .ellipsize(pango::EllipsizeMode::End)
+        .max_width_chars(24)
+        .build();
+    let password = PasswordEntry::builder()
+        .placeholder_text("Password")
+        .show_peek_icon(true)
+        .css_classes(["wifi-password"])
+        .build();
+    let cancel = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .halign(Align::End)
+        .label("Cancel")
+        .build();
+    prompt.append(&title);
+    prompt.append(&password);
+    prompt.append(&cancel);
+
+    let revealer = Revealer::builder().child(&prompt).build();
+    // The request currently being answered.
+    let request = Rc::new(Cell::new(None::<u32>));
+
+    let answer = clone!(
+        #[weak]
+        service,
+        #[weak]
+        revealer,
+        #[weak]
+        password,
+        #[strong]
+        request,
+        move |secret: Option<String>| {
+            if let Some(id) = request.take() {
+                service.provide_secret(id, secret);
+            }
+            password.set_text("");
+            revealer.set_reveal_child(false);
+        }
+    );
+    password.connect_activate(clone!(
+        #[strong]
+        answer,
+        move |password| answer(Some(password.text().to_string()))
+    ));
+    cancel.connect_clicked(move |_| answer(None));
+
+    service.connect_closure(
+        "secret-requested",
+        false,
+        closure_local!(
+            #[weak]
+            revealer,
+            #[weak]
+            title,
+            #[weak]
+            password,
+            #[strong]
+            request,
+            move |service: NetworkService, id: u32, name: String| {
+                // Only one prompt is shown at a time, so an unanswered request is cancelled.
+                if let Some(previous) = request.replace(Some(id)) {
+                    service.provide_secret(previous, None);
+                }
+                title.set_label(&format!("Password for {name}"));
+                password.set_text("");
+                revealer.set_reveal_child(true);
+                password.grab_focus();
+            }
+        ),
+    );
+    service.connect_closure(
+        "secret-request-cancelled",
+        false,
+        closure_local!(
+            #[weak]
+            revealer,
+            #[strong]
+            request,
+            move |_: NetworkService, id: u32| {
+                if request.get() == Some(id) {
+                    request.set(None);
+                    revealer.set_reveal_child(false);
+                }
+            }
+        ),
+    );
+
+    revealer
+}
+
+pub fn wifi_selector() -> gtk::Box {
+    let service = NETWORK_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let enabled = Reactive::new(service.wifi_enabled());
+    service.connect_wifi_enabled_notify(clone!(
+        #[strong]
+        enabled,
+        move |service| {
+            enabled.set_blocking(service.wifi_enabled());
+        }
+    ));
+
+    let button_content = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(Align::Start)
+        .spacing(8)
+        .build();
+    let icon = Image::builder().pixel_size(24).build();
+    let label = Label::builder()
+        .vexpand(true)
+        .valign(Align::Center)
+        .ellipsize(pango::EllipsizeMode::End)
+        .max_width_chars(12)
+        .build();
+    button_content.append(&icon);
+    button_content.append(&label);
+
+    let update_button = clone!(
+        #[weak]
+        icon,
+        #[weak]
+        label,
+        move |service: &NetworkService| match service.active_access_point() {
+            Some(access_point) if service.wifi_enabled() => {
+                icon.set_icon_name(Some(wifi_icon_name(access_point.strength())));
+                label.set_label(&access_point.ssid());
+            }
+            _ => {
+                icon.set_icon_name(Some("wifi-off-symbolic"));
+                label.set_label("Wi-Fi");
+            }
+        }
+    );
+    update_button(&service);
+    service.connect_active_access_point_notify(clone!(
+        #[strong]
+        update_button,
+        move |service| update_button(service)
+    ));
+    service.connect_wifi_enabled_notify(move |service| update_button(service));
+
+    let options_content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .name("wifi-options")
+        .css_classes(["wifi-options"])
+        .build();
+    options_content.append(&secret_prompt(&service));
+
+    let networks = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .build();
+    let rebuild_networks = clone!(
+        #[weak]
+        service,
+        #[weak]
+        networks,
+        move |access_points: &gio::ListStore| {
+            while let Some(child) = networks.first_child() {
+                networks.remove(&child);
+            }
+            for access_point in access_points.iter::<AccessPoint>().filter_map(|ap| ap.ok()) {
+                networks.append(&access_point_row(&service, &access_point));
+            }
+        }
+    );
+    rebuild_networks(&service.access_points());
+    service
+        .access_points()
+        .connect_items_changed(move |access_points, _, _, _| rebuild_networks(access_points));
+    options_content.append(
+        &ScrolledWindow::builder()
+            .min_content_height(64)
+            .max_content_height(240)
+            .propagate_natural_height(true)
+            .child(&networks)
+            .build(),
+    );
+
+    let scan = Button::builder()
+        .css_classes(["toggle-button-dropdown-option"])
+        .halign(Align::Start)
+        .label("Scan for networks")
+        .build();
+    scan.connect_clicked(clone!(
+        #[weak]
+        service,
+        move |_| report(smol::block_on(service.request_scan()))
+    ));
+    options_content.append(&scan);
+
+    let vpns = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .css_classes(["vpn-connections"])
+        .build();
+    let rebuild_vpns = clone!(
+        #[weak]
+        service,
+        #[weak]
+        vpns,
+        move |vpn_connections: &gio::ListStore| {
+            while let Some(child) = vpns.first_child() {
+                vpns.remove(&child);
+            }
+            vpns.append(
+                &Label::builder()
+                    .label("VPN")
+                    .halign(Align::Start)
+                    .css_classes(["network-section-title"])
+                    .build(),
+            );
+            for vpn in vpn_connections
+                .iter::<VpnConnection>()
+                .filter_map(|vpn| vpn.ok())
+            {
+                vpns.append(&vpn_row(&service, &vpn));
+            }
+            vpns.set_visible(vpn_connections.n_items() > 0);
+        }
+    );
+    rebuild_vpns(&service.vpn_connections());
+    service
+        .vpn_connections()
+        .connect_items_changed(move |vpn_connections, _, _, _| rebuild_vpns(vpn_connections));
+    options_content.append(&vpns);
+
+    let selector = DropdownButton::builder()
+        .on_toggle(clone!(
+            #[weak]
+            service,
+            move |enabled| report(smol::block_on(service.set_wifi_enabled(enabled)))
+        ))
+        .toggled(enabled)
+        .button_content(button_content)
+        .dropdown_content(options_content)
+        .build();
+
+    selector.set_visible(service.wifi_available());
+    service.connect_wifi_available_notify(clone!(
+        #[weak]
+        selector,
+        move |service| selector.set_visible(service.wifi_available())
+    ));
+
+    selector
+}
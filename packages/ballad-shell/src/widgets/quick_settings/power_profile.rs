@@ -1,9 +1,14 @@
 use std::cell::LazyCell;
 
-use ballad_services::{power_profiles::POWER_PROFILES_SERVICE, reactive::Reactive};
+use ballad_config::ShellConfig;
+use ballad_services::{
+    config::{CONFIG_SERVICE, ConfigService},
+    power_profiles::{POWER_PROFILES_SERVICE, ProfileHold},
+    reactive::Reactive,
+};
 use gtk::{Align, glib};
-use gtk::{Button, Orientation, glib::clone};
-use gtk::{Image, Label, ScrolledWindow, prelude::*};
+use gtk::{Button, Orientation, glib::clone, glib::closure_local};
+use gtk::{Image, Label, ScrolledWindow, pango, prelude::*};
 
 use crate::utils::set_class_on_widget;
 
@@ -36,6 +41,43 @@ fn profile_option(name: String, retained_profile: Reactive<String>) -> Button {
     button
 }
 
+fn update_profiles(container: &gtk::Box, profiles: &[String], retained_profile: &Reactive<String>) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    for profile in profiles {
+        container.append(&profile_option(profile.clone(), retained_profile.clone()));
+    }
+}
+
+fn update_holds(container: &gtk::Box, holds: &[ProfileHold]) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    for hold in holds {
+        let application = if hold.application_id.is_empty() {
+            "An application"
+        } else {
+            hold.application_id.as_str()
+        };
+        let mut text = format!("{application} holds {}", hold.profile);
+        if !hold.reason.is_empty() {
+            text.push_str(&format!(": {}", hold.reason));
+        }
+        container.append(
+            &Label::builder()
+                .label(text)
+                .halign(Align::Start)
+                .wrap(true)
+                .wrap_mode(pango::WrapMode::WordChar)
+                .max_width_chars(28)
+                .css_classes(["power-profile-hold"])
+                .build(),
+        );
+    }
+    container.set_visible(!holds.is_empty());
+}
+
 pub fn power_profile_selector() -> gtk::Box {
     let service = POWER_PROFILES_SERVICE.with(|service| LazyCell::force(service).clone());
 
@@ -49,7 +91,11 @@ pub fn power_profile_selector() -> gtk::Box {
         pp_enabled,
         move |_, profile| {
             if pp_enabled.get_blocking() {
-                service.set_active_profile(profile);
+                glib::spawn_future_local(async move {
+                    if let Err(err) = service.switch_profile(&profile).await {
+                        println!("Failed to switch to the {profile} power profile: {err}");
+                    }
+                });
             }
         }
     ));
@@ -58,7 +104,11 @@ pub fn power_profile_selector() -> gtk::Box {
         service,
         move |_, enabled| {
             if !enabled {
-                service.set_active_profile("balanced".to_string());
+                glib::spawn_future_local(async move {
+                    if let Err(err) = service.switch_profile("balanced").await {
+                        println!("Failed to switch to the balanced power profile: {err}");
+                    }
+                });
             }
         }
     ));
@@ -90,19 +140,76 @@ pub fn power_profile_selector() -> gtk::Box {
         .css_classes(["power-profile-options"])
         .build();
 
-    for option in service.profiles_blocking() {
-        pp_options_content.append(&profile_option(option, retained_profile.clone()));
-    }
+    let profiles = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(4)
+        .build();
+    update_profiles(&profiles, &service.profiles_blocking(), &retained_profile);
+    service.connect_profiles(clone!(
+        #[weak]
+        profiles,
+        #[strong]
+        retained_profile,
+        move |_, options| update_profiles(&profiles, &options, &retained_profile)
+    ));
+    pp_options_content.append(&profiles);
+
+    let holds = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .css_classes(["power-profile-holds"])
+        .build();
+    update_holds(&holds, &service.holds_blocking());
+    service.connect_holds(clone!(
+        #[weak]
+        holds,
+        move |_, profile_holds| update_holds(&holds, &profile_holds)
+    ));
+    pp_options_content.append(&holds);
 
     let pp_options_scroller = ScrolledWindow::builder()
         .min_content_height(64)
         .child(&pp_options_content)
         .build();
 
-    DropdownButton::builder()
+    let selector = DropdownButton::builder()
         .on_toggle(|_| {})
         .toggled(pp_enabled)
         .button_content(pp_button_content)
         .dropdown_content(pp_options_scroller)
-        .build()
+        .build();
+
+    // Hidden while power-profiles-daemon isn't running, or when the config turns the selector off.
+    CONFIG_SERVICE.with(|config_service| {
+        selector.set_visible(
+            service.available_blocking()
+                && config_service.shell_config().power_profiles.show_selector,
+        );
+        config_service.connect_closure(
+            "shell-config-changed",
+            false,
+            closure_local!(
+                #[weak]
+                selector,
+                #[weak]
+                service,
+                move |_: ConfigService, config: &ShellConfig| {
+                    selector.set_visible(
+                        service.available_blocking() && config.power_profiles.show_selector,
+                    );
+                }
+            ),
+        );
+    });
+    service.connect_available(clone!(
+        #[weak]
+        selector,
+        move |_, available| {
+            let enabled =
+                CONFIG_SERVICE.with(|service| service.shell_config().power_profiles.show_selector);
+            selector.set_visible(available && enabled);
+        }
+    ));
+
+    selector
 }
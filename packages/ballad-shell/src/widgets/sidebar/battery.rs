@@ -1,6 +1,7 @@
-use ballad_services::upower::{UPOWER_SERVICE, UPowerService};
+use ballad_services::upower::{UPOWER_SERVICE, UPowerDevice, UPowerService};
 use gtk::glib::{clone, closure_local};
-use gtk::prelude::{BoxExt, WidgetExt};
+use gtk::prelude::{BoxExt, PopoverExt, WidgetExt};
+use gtk::{Align, GestureClick, Image, Orientation, Popover, PositionType, pango};
 use gtk::{Box, Stack, StackTransitionType, prelude::ObjectExt};
 use gtk::{Label, LevelBar, glib};
 use typed_builder::TypedBuilder;
@@ -40,6 +41,98 @@ impl BatteryLevel {
     }
 }
 
+fn device_row(device: &UPowerDevice) -> Box {
+    let row = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .css_classes(["battery-device"])
+        .build();
+    let header = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    header.append(
+        &Image::builder()
+            .icon_name(device.kind().icon_name())
+            .pixel_size(16)
+            .build(),
+    );
+    header.append(
+        &Label::builder()
+            .label(device.name())
+            .hexpand(true)
+            .halign(Align::Start)
+            .ellipsize(pango::EllipsizeMode::End)
+            .max_width_chars(24)
+            .build(),
+    );
+    let percent_label = Label::builder().css_classes(["percent-display"]).build();
+    header.append(&percent_label);
+
+    let level = BatteryLevel::from_percent(device.percentage());
+    let bar = LevelBar::builder()
+        .css_classes(["battery-bar", "horizontal", level.as_class_name()])
+        .mode(gtk::LevelBarMode::Continuous)
+        .build();
+
+    device
+        .bind_property("percentage", &percent_label, "label")
+        .transform_to(|_, percent: f64| Some(format!("{:.0}%", percent)))
+        .sync_create()
+        .build();
+    device
+        .bind_property("percentage", &bar, "value")
+        .transform_to(|_, percent: f64| Some(percent / 100.0))
+        .sync_create()
+        .build();
+
+    row.append(&header);
+    row.append(&bar);
+    row
+}
+
+/// Lists the peripheral batteries, along with whether the computer is plugged in.
+fn devices_popover(service: &UPowerService, position: PositionType) -> Popover {
+    let content = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(8)
+        .css_classes(["battery-devices"])
+        .build();
+
+    let status = if service.on_battery() {
+        "On battery"
+    } else {
+        "Plugged in"
+    };
+    content.append(
+        &Label::builder()
+            .label(status)
+            .halign(Align::Start)
+            .css_classes(["battery-status"])
+            .build(),
+    );
+
+    let peripherals = service.peripherals();
+    if peripherals.is_empty() {
+        content.append(
+            &Label::builder()
+                .label("No other devices")
+                .halign(Align::Start)
+                .css_classes(["battery-devices-empty"])
+                .build(),
+        );
+    }
+    for device in peripherals {
+        content.append(&device_row(&device));
+    }
+
+    Popover::builder()
+        .position(position)
+        .has_arrow(false)
+        .child(&content)
+        .build()
+}
+
 #[derive(Debug, TypedBuilder, Clone, PartialEq, Eq)]
 #[builder(build_method(into = Box))]
 pub struct Battery {
@@ -184,5 +277,32 @@ pub fn battery(Battery { orientation }: Battery) -> Box {
     container.append(&percent_label);
     container.append(&battery_bar);
 
+    let popover_position = if orientation == crate::widgets::Orientation::Horizontal {
+        PositionType::Bottom
+    } else {
+        PositionType::Right
+    };
+    let click = GestureClick::builder()
+        .button(gtk::gdk::BUTTON_PRIMARY)
+        .build();
+    click.connect_released(clone!(
+        #[weak]
+        container,
+        move |_, _, _, _| {
+            let popover = UPOWER_SERVICE.with(|service| devices_popover(service, popover_position));
+            popover.set_parent(&container);
+            popover.connect_closed(|popover| {
+                // Unparenting while the popover is still closing upsets GTK, so wait until it's done.
+                glib::idle_add_local_once(clone!(
+                    #[weak]
+                    popover,
+                    move || popover.unparent()
+                ));
+            });
+            popover.popup();
+        }
+    ));
+    container.add_controller(click);
+
     container
 }
@@ -1,6 +1,7 @@
 pub mod battery;
 pub mod niri;
 pub mod screen_bevels;
+pub mod tray;
 
 use std::cell::Cell;
 
@@ -17,32 +18,50 @@ use super::volume::Volume;
 use super::{
     PerMonitorWidget,
     icon::symbolic_icon,
+    notifications::center::NOTIFICATION_CENTER_WINDOW_TITLE,
     quick_settings::QUICK_SETTINGS_WINDOW_TITLE,
     window::{Anchor, LayershellWindow},
 };
 
-pub fn quick_settings_toggle() -> Button {
+/// A button that toggles the visibility of the window with the given title.
+fn window_toggle(name: &str, icon_name: &str, window_title: &'static str) -> Button {
     let button = Button::builder()
-        .name("quick-settings-toggle")
+        .name(name)
         .css_classes(["icon-container", "hoverable"])
         .build();
 
     button.connect_clicked(move |_| {
-        if let Some(quick_settings_window) = APP.with(|app| {
+        if let Some(window) = APP.with(|app| {
             app.borrow()
                 .as_ref()
-                .map(|app| app.window_by_title(QUICK_SETTINGS_WINDOW_TITLE).unwrap())
+                .map(|app| app.window_by_title(window_title).unwrap())
         }) {
-            quick_settings_window.set_visible(!quick_settings_window.is_visible())
+            window.set_visible(!window.is_visible())
         }
     });
 
-    let icon = symbolic_icon("settings-symbolic", 24);
+    let icon = symbolic_icon(icon_name, 24);
     button.set_child(Some(&icon));
 
     button
 }
 
+pub fn quick_settings_toggle() -> Button {
+    window_toggle(
+        "quick-settings-toggle",
+        "settings-symbolic",
+        QUICK_SETTINGS_WINDOW_TITLE,
+    )
+}
+
+pub fn notification_center_toggle() -> Button {
+    window_toggle(
+        "notification-center-toggle",
+        "bell-symbolic",
+        NOTIFICATION_CENTER_WINDOW_TITLE,
+    )
+}
+
 pub fn sidebar(
     PerMonitorWidget {
         monitor,
@@ -87,10 +106,14 @@ pub fn sidebar(
         .valign(Align::End)
         .build();
 
+    let tray = tray::Tray::builder().build();
+    let notification_center_toggle = notification_center_toggle();
     let quick_settings_toggle = quick_settings_toggle();
     let battery = battery::Battery::builder().build();
     let volume = Volume::builder().build();
 
+    lower_section.append(&tray);
+    lower_section.append(&notification_center_toggle);
     lower_section.append(&quick_settings_toggle);
     lower_section.append(
         &Separator::builder()
@@ -0,0 +1,280 @@
+use std::cell::LazyCell;
+
+use ballad_services::tray::{
+    TRAY_SERVICE, TrayIconPixmap, TrayItem, TrayItemStatus, TrayMenuItem, TrayMenuToggle,
+};
+use gtk::{
+    Box, Button, EventControllerScroll, EventControllerScrollFlags, GestureClick, IconTheme, Image,
+    PopoverMenu, PositionType,
+    gdk::{self, BUTTON_PRIMARY, BUTTON_SECONDARY, MemoryFormat, MemoryTexture},
+    gio::{self, SimpleAction, SimpleActionGroup},
+    glib::{self, clone, closure_local},
+    prelude::*,
+};
+use typed_builder::TypedBuilder;
+
+use crate::utils::set_class_on_widget;
+
+const TRAY_ICON_SIZE: i32 = 20;
+const MENU_ACTION_GROUP: &str = "tray";
+
+#[derive(Debug, TypedBuilder, Clone, PartialEq, Eq)]
+#[builder(build_method(into = Box))]
+pub struct Tray {
+    #[builder(default = crate::widgets::Orientation::Vertical)]
+    pub orientation: crate::widgets::Orientation,
+}
+impl From<Tray> for Box {
+    fn from(props: Tray) -> Self {
+        tray(props)
+    }
+}
+
+fn pixmap_texture(pixmap: TrayIconPixmap) -> MemoryTexture {
+    MemoryTexture::new(
+        pixmap.width,
+        pixmap.height,
+        MemoryFormat::A8r8g8b8,
+        &glib::Bytes::from_owned(pixmap.data),
+        pixmap.width as usize * 4,
+    )
+}
+
+fn update_icon(image: &Image, item: &TrayItem) {
+    if let Some(path) = item.icon_theme_path() {
+        let theme = IconTheme::for_display(&image.display());
+        if !theme
+            .search_path()
+            .iter()
+            .any(|search_path| search_path.to_str() == Some(path.as_str()))
+        {
+            theme.add_search_path(&path);
+        }
+    }
+
+    let (icon_name, pixmap) = if item.status() == TrayItemStatus::NeedsAttention
+        && (item.attention_icon_name().is_some() || item.attention_icon_pixmap().is_some())
+    {
+        (item.attention_icon_name(), item.attention_icon_pixmap())
+    } else {
+        (item.icon_name(), item.icon_pixmap())
+    };
+
+    match (icon_name, pixmap) {
+        (Some(icon_name), _) => image.set_icon_name(Some(&icon_name)),
+        (None, Some(pixmap)) => image.set_paintable(Some(&pixmap_texture(pixmap))),
+        (None, None) => image.set_icon_name(Some("application-x-executable")),
+    }
+}
+
+fn tooltip(item: &TrayItem) -> String {
+    let title = if item.tooltip_title().is_empty() {
+        item.title()
+    } else {
+        item.tooltip_title()
+    };
+    if item.tooltip_description().is_empty() {
+        title
+    } else {
+        format!("{title}\n{}", item.tooltip_description())
+    }
+}
+
+/// Converts a dbusmenu layout into a menu model, adding an action to `actions` for every entry.
+fn menu_model(item: &TrayItem, parent: &TrayMenuItem, actions: &SimpleActionGroup) -> gio::Menu {
+    let menu = gio::Menu::new();
+    // Separators are represented as sections, since menu models have no separator entries.
+    let mut section = gio::Menu::new();
+
+    for entry in parent.children.iter().filter(|entry| entry.visible) {
+        if entry.separator {
+            if section.n_items() > 0 {
+                menu.append_section(None, &section);
+                section = gio::Menu::new();
+            }
+            continue;
+        }
+
+        let action_name = format!("item-{}", entry.id);
+        let menu_item = gio::MenuItem::new(
+            Some(&entry.label),
+            Some(&format!("{MENU_ACTION_GROUP}.{action_name}")),
+        );
+        if let Some(icon_name) = entry.icon_name.as_deref() {
+            menu_item.set_icon(&gio::ThemedIcon::new(icon_name));
+        }
+
+        if !entry.children.is_empty() {
+            let submenu = menu_model(item, entry, actions);
+            section.append_submenu(Some(&entry.label), &submenu);
+            continue;
+        }
+
+        let action = match entry.toggle {
+            Some(TrayMenuToggle::Checkmark(toggled) | TrayMenuToggle::Radio(toggled)) => {
+                SimpleAction::new_stateful(&action_name, None, &toggled.to_variant())
+            }
+            None => SimpleAction::new(&action_name, None),
+        };
+        action.set_enabled(entry.enabled);
+        let id = entry.id;
+        action.connect_activate(clone!(
+            #[weak]
+            item,
+            move |_, _| {
+                smol::block_on(item.activate_menu_item(id)).ok();
+            }
+        ));
+        actions.add_action(&action);
+
+        section.append_item(&menu_item);
+    }
+    if section.n_items() > 0 {
+        menu.append_section(None, &section);
+    }
+
+    menu
+}
+
+fn show_menu(button: &Button, item: &TrayItem, position: PositionType) {
+    let Some(root) = smol::block_on(item.menu()) else {
+        smol::block_on(item.context_menu(0, 0)).ok();
+        return;
+    };
+
+    let actions = SimpleActionGroup::new();
+    let model = menu_model(item, &root, &actions);
+    button.insert_action_group(MENU_ACTION_GROUP, Some(&actions));
+
+    let popover = PopoverMenu::builder()
+        .menu_model(&model)
+        .position(position)
+        .has_arrow(false)
+        .css_classes(["tray-menu"])
+        .build();
+    popover.set_parent(button);
+    popover.connect_closed(|popover| {
+        // Unparenting while the popover is still closing upsets GTK, so wait until it's done.
+        glib::idle_add_local_once(clone!(
+            #[weak]
+            popover,
+            move || popover.unparent()
+        ));
+    });
+    popover.popup();
+}
+
+fn tray_item(item: &TrayItem, menu_position: PositionType) -> Button {
+    let image = Image::builder()
+        .pixel_size(TRAY_ICON_SIZE)
+        .css_classes(["tray-icon"])
+        .build();
+    let button = Button::builder()
+        .css_classes(["tray-item", "icon-container", "hoverable"])
+        .child(&image)
+        .build();
+
+    let update = clone!(
+        #[weak]
+        image,
+        #[weak]
+        button,
+        move |item: &TrayItem| {
+            update_icon(&image, item);
+            button.set_tooltip_text(Some(&tooltip(item)));
+            button.set_visible(item.status() != TrayItemStatus::Passive);
+            set_class_on_widget(
+                item.status() == TrayItemStatus::NeedsAttention,
+                &button,
+                "needs-attention",
+            );
+        }
+    );
+    update(item);
+    item.connect_closure(
+        "changed",
+        false,
+        closure_local!(move |item: TrayItem| update(&item)),
+    );
+
+    // Clicks are handled by a gesture so the secondary button can be told apart.
+    let click = GestureClick::builder().button(0).build();
+    click.connect_released(clone!(
+        #[weak]
+        item,
+        #[weak]
+        button,
+        move |gesture, _, _, _| {
+            let has_menu = smol::block_on(item.has_menu());
+            match gesture.current_button() {
+                BUTTON_PRIMARY if item.item_is_menu() && has_menu => {
+                    show_menu(&button, &item, menu_position)
+                }
+                BUTTON_PRIMARY => {
+                    smol::block_on(item.activate(0, 0)).ok();
+                }
+                BUTTON_SECONDARY => show_menu(&button, &item, menu_position),
+                gdk::BUTTON_MIDDLE => {
+                    smol::block_on(item.secondary_activate(0, 0)).ok();
+                }
+                _ => {}
+            }
+        }
+    ));
+    button.add_controller(click);
+
+    let scroll = EventControllerScroll::new(EventControllerScrollFlags::BOTH_AXES);
+    scroll.connect_scroll(clone!(
+        #[weak]
+        item,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, dx, dy| {
+            if dy != 0.0 {
+                smol::block_on(item.scroll(dy as i32, false)).ok();
+            } else if dx != 0.0 {
+                smol::block_on(item.scroll(dx as i32, true)).ok();
+            }
+            glib::Propagation::Stop
+        }
+    ));
+    button.add_controller(scroll);
+
+    button
+}
+
+pub fn tray(Tray { orientation }: Tray) -> Box {
+    let service = TRAY_SERVICE.with(|service| LazyCell::force(service).clone());
+
+    let container = Box::builder()
+        .orientation(orientation.into())
+        .name("tray")
+        .css_classes(["tray"])
+        .build();
+
+    // Menus open away from the screen edge the sidebar is attached to.
+    let menu_position = match orientation {
+        crate::widgets::Orientation::Vertical => PositionType::Right,
+        crate::widgets::Orientation::Horizontal => PositionType::Bottom,
+    };
+
+    let rebuild = clone!(
+        #[weak]
+        container,
+        move |items: &gio::ListStore| {
+            while let Some(child) = container.first_child() {
+                container.remove(&child);
+            }
+            for item in items.iter::<TrayItem>().filter_map(|item| item.ok()) {
+                container.append(&tray_item(&item, menu_position));
+            }
+            container.set_visible(items.n_items() > 0);
+        }
+    );
+    rebuild(&service.items());
+    service
+        .items()
+        .connect_items_changed(move |items, _, _, _| rebuild(items));
+
+    container
+}
@@ -0,0 +1,261 @@
+use std::{
+    cell::{LazyCell, RefCell},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use ballad_config::{
+    WallpaperConfig,
+    wallpaper::{WallpaperMode, WallpaperTransition},
+};
+use ballad_services::config::{CONFIG_SERVICE, ConfigService};
+use gtk::{
+    ApplicationWindow, CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION,
+    gdk::Display,
+    gio,
+    glib::{self, SourceId, clone, closure_local},
+    prelude::*,
+    style_context_add_provider_for_display, style_context_remove_provider_for_display,
+};
+use gtk4_layer_shell::{KeyboardMode, LayerShell};
+
+use crate::widgets::{
+    PerMonitorWidget,
+    window::{Layer, LayershellWindow},
+};
+
+/// The images a slideshow folder can contain.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "avif", "jxl", "tif", "tiff", "bmp", "gif", "svg",
+];
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// The images to show for `path`: the image itself, or the images in a folder sorted by name.
+fn images(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+    let mut images = std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && is_image(path))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    images.sort();
+    images
+}
+
+fn wallpaper_css(
+    name: &str,
+    image: Option<&Path>,
+    mode: WallpaperMode,
+    config: &WallpaperConfig,
+) -> String {
+    let image = match image {
+        Some(image) => format!("url(\"{}\")", gio::File::for_path(image).uri()),
+        None => "none".to_string(),
+    };
+    let (size, repeat) = match mode {
+        WallpaperMode::Fill => ("cover", "no-repeat"),
+        WallpaperMode::Fit => ("contain", "no-repeat"),
+        WallpaperMode::Stretch => ("100% 100%", "no-repeat"),
+        WallpaperMode::Center => ("auto", "no-repeat"),
+        WallpaperMode::Tile => ("auto", "repeat"),
+    };
+    let transition = match config.transition {
+        WallpaperTransition::Fade => {
+            format!(
+                "background-image {}s ease-in-out",
+                config.transition_duration
+            )
+        }
+        WallpaperTransition::None => "none".to_string(),
+    };
+    format!(
+        "#{name} {{
+    background-color: {};
+    background-image: {image};
+    background-size: {size};
+    background-repeat: {repeat};
+    background-position: center;
+    transition: {transition};
+}}",
+        config.background_color
+    )
+}
+
+/// The image shown on one monitor, and the slideshow cycling through a folder.
+struct Wallpaper {
+    connector: String,
+    window: glib::WeakRef<ApplicationWindow>,
+    provider: CssProvider,
+    config: WallpaperConfig,
+    images: Vec<PathBuf>,
+    index: usize,
+    slideshow: Option<SourceId>,
+}
+impl Wallpaper {
+    fn name(&self) -> String {
+        format!("wallpaper-{}", self.connector)
+    }
+
+    fn mode(&self) -> WallpaperMode {
+        self.config.for_monitor(&self.connector).1
+    }
+
+    fn show_current(&self) {
+        let image = self.images.get(self.index).map(PathBuf::as_path);
+        self.provider.load_from_string(&wallpaper_css(
+            &self.name(),
+            image,
+            self.mode(),
+            &self.config,
+        ));
+        if let Some(window) = self.window.upgrade() {
+            window.set_visible(self.config.enabled && image.is_some());
+        }
+    }
+
+    fn next(&mut self) {
+        let Some(path) = self.config.for_monitor(&self.connector).0 else {
+            return;
+        };
+        // The folder is read again every time around, so new images show up.
+        if self.index + 1 >= self.images.len() {
+            self.images = images(&path);
+        }
+        self.index = if self.config.shuffle && self.images.len() > 1 {
+            let offset = glib::random_int_range(1, self.images.len() as i32) as usize;
+            (self.index + offset) % self.images.len()
+        } else {
+            (self.index + 1) % self.images.len().max(1)
+        };
+        self.show_current();
+    }
+
+    fn stop_slideshow(&mut self) {
+        if let Some(source) = self.slideshow.take() {
+            source.remove();
+        }
+    }
+}
+
+fn apply_config(wallpaper: &Rc<RefCell<Wallpaper>>, config: WallpaperConfig) {
+    let mut this = wallpaper.borrow_mut();
+    this.stop_slideshow();
+    let (path, _) = config.for_monitor(&this.connector);
+    this.images = path.as_deref().map(images).unwrap_or_default();
+    this.index = if config.shuffle && !this.images.is_empty() {
+        glib::random_int_range(0, this.images.len() as i32) as usize
+    } else {
+        0
+    };
+    this.config = config;
+    this.show_current();
+
+    if this.config.enabled && this.images.len() > 1 {
+        let interval = Duration::from_secs(this.config.slideshow_interval.max(1) as u64);
+        this.slideshow = Some(glib::timeout_add_local(
+            interval,
+            clone!(
+                #[weak]
+                wallpaper,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    wallpaper.borrow_mut().next();
+                    glib::ControlFlow::Continue
+                }
+            ),
+        ));
+    }
+}
+
+/// Draws the configured wallpaper behind everything else on a monitor. The window shows and hides itself depending on
+/// whether there is a wallpaper to draw.
+pub fn wallpaper(
+    PerMonitorWidget {
+        monitor,
+        application,
+    }: PerMonitorWidget,
+) -> ApplicationWindow {
+    let connector = monitor.connector().unwrap().to_string();
+    let window: ApplicationWindow = LayershellWindow::builder()
+        .application(application)
+        .title(&format!("wallpaper-{connector}"))
+        .monitor(monitor)
+        .layer(Layer::Background)
+        .build();
+    // Stretch under the exclusive zones of the sidebar and other panels.
+    window.set_exclusive_zone(-1);
+    window.set_keyboard_mode(KeyboardMode::None);
+
+    let provider = CssProvider::new();
+    style_context_add_provider_for_display(
+        &Display::default().unwrap(),
+        &provider,
+        STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+    let wallpaper = Rc::new(RefCell::new(Wallpaper {
+        connector,
+        window: window.downgrade(),
+        provider,
+        config: WallpaperConfig::default(),
+        images: Vec::new(),
+        index: 0,
+        slideshow: None,
+    }));
+
+    let content = gtk::Box::builder()
+        .name(wallpaper.borrow().name())
+        .css_classes(["wallpaper"])
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    window.set_child(Some(&content));
+
+    CONFIG_SERVICE.with(|service| {
+        let service = LazyCell::force(service).clone();
+        apply_config(&wallpaper, service.shell_config().wallpaper);
+        let handler = RefCell::new(Some(service.connect_closure(
+            "shell-wallpaper-config-changed",
+            false,
+            closure_local!(
+                #[weak]
+                wallpaper,
+                move |_: ConfigService, config: &WallpaperConfig| {
+                    apply_config(&wallpaper, config.clone());
+                }
+            ),
+        )));
+
+        window.connect_destroy(clone!(
+            #[strong]
+            wallpaper,
+            #[strong]
+            service,
+            move |_| {
+                let mut wallpaper = wallpaper.borrow_mut();
+                wallpaper.stop_slideshow();
+                style_context_remove_provider_for_display(
+                    &Display::default().unwrap(),
+                    &wallpaper.provider,
+                );
+                if let Some(handler) = handler.take() {
+                    service.disconnect(handler);
+                }
+            }
+        ));
+    });
+
+    window
+}